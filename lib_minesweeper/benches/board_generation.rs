@@ -0,0 +1,53 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use lib_minesweeper::solver::{probabilities, probabilities_with};
+use lib_minesweeper::MapElement::Number;
+use lib_minesweeper::{create_seeded_board, numbers_on_board, Adjacency, Board, BoardState, Point};
+
+fn cells(board: &Board) -> impl Iterator<Item = Point> + '_ {
+    (0..board.width * board.height).map(move |i| Point::new(i % board.width, i / board.width))
+}
+
+// Expert sized boards are where neighbor iteration dominates: every cell looks
+// at up to eight neighbors to get its count.
+fn board_generation(c: &mut Criterion) {
+    c.bench_function("numbers_on_board expert", |b| {
+        let board = create_seeded_board(30, 16, 99, 42).unwrap();
+        b.iter(|| numbers_on_board(black_box(board.clone())))
+    });
+    c.bench_function("numbers_on_board 200x200", |b| {
+        let board = create_seeded_board(200, 200, 8000, 42).unwrap();
+        b.iter(|| numbers_on_board(black_box(board.clone())))
+    });
+}
+
+// A sparse board, so opening a zero floods most of it.
+fn flood_fill(c: &mut Criterion) {
+    c.bench_function("cascade_open_item 200x200", |b| {
+        let board = numbers_on_board(create_seeded_board(200, 200, 400, 42).unwrap());
+        let zero = cells(&board)
+            .find(|p| matches!(board.at(p), Some(Number { count: 0, .. })))
+            .unwrap();
+        b.iter(|| black_box(&board).cascade_open_item(&zero))
+    });
+}
+
+// The assists solve the same board after every move, which is what
+// probabilities_with and a shared Adjacency are for.
+fn solving(c: &mut Criterion) {
+    let board = numbers_on_board(create_seeded_board(30, 16, 99, 42).unwrap());
+    let board = cells(&board)
+        .filter_map(|p| board.cascade_open_item(&p).ok())
+        .find(|b| b.state == BoardState::Playing)
+        .unwrap();
+    c.bench_function("probabilities expert", |b| {
+        b.iter(|| probabilities(black_box(&board)))
+    });
+    c.bench_function("probabilities_with expert", |b| {
+        let adjacency = Adjacency::for_board(&board);
+        b.iter(|| probabilities_with(black_box(&board), &adjacency))
+    });
+}
+
+criterion_group!(benches, board_generation, flood_fill, solving);
+criterion_main!(benches);
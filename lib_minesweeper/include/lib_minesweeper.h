@@ -0,0 +1,120 @@
+#ifndef LIB_MINESWEEPER_H
+#define LIB_MINESWEEPER_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define CELL_CLOSED -1
+
+#define CELL_FLAGGED -2
+
+#define CELL_MINE -3
+
+#define CELL_OUT_OF_BOUNDS -4
+
+typedef enum MinesweeperHaptic {
+  MinesweeperHaptic_None = 0,
+  MinesweeperHaptic_Short,
+  MinesweeperHaptic_Medium,
+  MinesweeperHaptic_Long,
+} MinesweeperHaptic;
+
+typedef enum MinesweeperResult {
+  MinesweeperResult_Ok = 0,
+  MinesweeperResult_OutOfBounds,
+  MinesweeperResult_CellAlreadyOpen,
+  MinesweeperResult_CellFlagged,
+  MinesweeperResult_GameOver,
+  MinesweeperResult_InvalidConfig,
+  MinesweeperResult_ParseError,
+  MinesweeperResult_NullGame,
+  MinesweeperResult_FlagsDisabled,
+  MinesweeperResult_StorageError,
+  MinesweeperResult_OutOfReach,
+  MinesweeperResult_InconsistentWithRevealed,
+} MinesweeperResult;
+
+typedef enum MinesweeperState {
+  MinesweeperState_NotReady = 0,
+  MinesweeperState_Ready,
+  MinesweeperState_Playing,
+  MinesweeperState_Won,
+  MinesweeperState_Failed,
+} MinesweeperState;
+
+typedef struct MinesweeperGame MinesweeperGame;
+
+/**
+ * Creates a new game, or returns null if the configuration is invalid. The
+ * same seed always produces the same board.
+ */
+struct MinesweeperGame *game_new(uintptr_t width, uintptr_t height, uintptr_t mines, uint64_t seed);
+
+/**
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new` that was not freed.
+ */
+enum MinesweeperResult game_open(struct MinesweeperGame *game, int32_t x, int32_t y);
+
+/**
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new` that was not freed.
+ */
+enum MinesweeperResult game_flag(struct MinesweeperGame *game, int32_t x, int32_t y);
+
+/**
+ * Returns the count of an open cell (0 to 8) or one of the `CELL_*`
+ * constants. Mines are only revealed once the game is over.
+ *
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new` that was not freed.
+ */
+int32_t game_cell_at(const struct MinesweeperGame *game, int32_t x, int32_t y);
+
+/**
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new` that was not freed.
+ */
+enum MinesweeperState game_state(const struct MinesweeperGame *game);
+
+/**
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new` that was not freed.
+ */
+uintptr_t game_width(const struct MinesweeperGame *game);
+
+/**
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new` that was not freed.
+ */
+uintptr_t game_height(const struct MinesweeperGame *game);
+
+/**
+ * The vibration to play for the last `game_open` or `game_flag` call, or
+ * `MinesweeperHaptic_None` if it failed or there was none.
+ *
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new` that was not freed.
+ */
+enum MinesweeperHaptic game_last_haptic(const struct MinesweeperGame *game);
+
+/**
+ * # Safety
+ *
+ * `game` must be null or a pointer returned by `game_new`, and must not be
+ * used again after this call.
+ */
+void game_free(struct MinesweeperGame *game);
+
+#endif  /* LIB_MINESWEEPER_H */
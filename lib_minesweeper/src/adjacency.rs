@@ -0,0 +1,108 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::topology::Topology;
+use crate::{Board, Point};
+
+// The in-bounds neighbors of every cell of a board, as row-major cell indices,
+// worked out once so hot loops don't redo the bounds checks. All neighbor
+// lists live in one Vec; `starts[i]..starts[i + 1]` is the slice of cell i.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Adjacency {
+    pub width: usize,
+    pub height: usize,
+    starts: Vec<usize>,
+    neighbors: Vec<usize>,
+}
+
+impl Adjacency {
+    pub fn new(width: usize, height: usize) -> Adjacency {
+        let mut starts = Vec::with_capacity(width * height + 1);
+        let mut neighbors = Vec::with_capacity(width * height * 8);
+        for y in 0..height {
+            for x in 0..width {
+                starts.push(neighbors.len());
+                neighbors.extend(
+                    Point::new(x, y)
+                        .neighbors()
+                        .filter(|n| {
+                            n.x >= 0
+                                && n.y >= 0
+                                && (n.x as usize) < width
+                                && (n.y as usize) < height
+                        })
+                        .map(|n| n.y as usize * width + n.x as usize),
+                );
+            }
+        }
+        starts.push(neighbors.len());
+        Adjacency {
+            width,
+            height,
+            starts,
+            neighbors,
+        }
+    }
+
+    // The neighbors of each cell as the topology defines them instead of the
+    // eight around it.
+    pub fn for_topology(topology: &impl Topology) -> Adjacency {
+        let (width, height) = (topology.width(), topology.height());
+        let mut starts = Vec::with_capacity(width * height + 1);
+        let mut neighbors = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                starts.push(neighbors.len());
+                neighbors.extend(
+                    topology
+                        .neighbors(&Point::new(x, y))
+                        .iter()
+                        .map(|n| n.y as usize * width + n.x as usize),
+                );
+            }
+        }
+        starts.push(neighbors.len());
+        Adjacency {
+            width,
+            height,
+            starts,
+            neighbors,
+        }
+    }
+
+    pub fn for_board(board: &Board) -> Adjacency {
+        Adjacency::new(board.width, board.height)
+    }
+
+    // Same order as Board::neighbors.
+    pub fn neighbors(&self, cell: usize) -> &[usize] {
+        &self.neighbors[self.starts[cell]..self.starts[cell + 1]]
+    }
+
+    pub fn cell(&self, p: &Point) -> usize {
+        p.y as usize * self.width + p.x as usize
+    }
+
+    pub fn point(&self, cell: usize) -> Point {
+        Point::new(cell % self.width, cell / self.width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::five_by_two_board;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_matches_board_neighbors() {
+        let board = five_by_two_board();
+        let adjacency = Adjacency::for_board(&board);
+        for cell in 0..board.width * board.height {
+            let p = adjacency.point(cell);
+            let expected: Vec<usize> = board.neighbors(&p).map(|n| adjacency.cell(&n)).collect();
+            assert_eq!(adjacency.neighbors(cell), &expected[..]);
+        }
+        assert_eq!(adjacency.neighbors(0), &[5, 1, 6]);
+    }
+}
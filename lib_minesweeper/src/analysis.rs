@@ -0,0 +1,648 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::game::Game;
+use crate::random::{RandomSource, SplitMix64};
+use crate::replay::Replay;
+use crate::solver::{
+    constraints, deductions_with, probabilities_with, relative_layouts, LayoutSampler,
+};
+use crate::tablebase::Tablebase;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Open;
+use crate::{numbers_on_board, Adjacency, Board, BoardState, Error, Move, Point, Result};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GuessEvaluation {
+    pub point: Point,
+    pub mine_probability: f64,
+    // Cells that become certain (safe or mine) once the guess is revealed,
+    // averaged over the numbers it may show, and counting nothing when it's a
+    // mine. A guess with more of it leaves less guessing for later.
+    pub expected_progress: f64,
+    // Chance the guess is safe and its number settles at least one more cell,
+    // so play can go on without guessing again.
+    pub progress_probability: f64,
+}
+
+impl GuessEvaluation {
+    // Safer guesses first; among equally safe ones, the more informative.
+    pub fn is_better_than(&self, other: &GuessEvaluation) -> bool {
+        if (self.mine_probability - other.mine_probability).abs() > 1e-9 {
+            self.mine_probability < other.mine_probability
+        } else {
+            self.expected_progress > other.expected_progress
+        }
+    }
+}
+
+fn too_large() -> Error {
+    Error::InvalidConfig(String::from(
+        "the frontier is too large to evaluate guesses exactly",
+    ))
+}
+
+// Works through each number the cell could show: the board with that number
+// revealed is solved, and weighted by how many mine layouts agree with it.
+pub fn evaluate_guess(board: &Board, point: &Point) -> Result<GuessEvaluation> {
+    match board.at(point) {
+        None => {
+            return Err(Error::OutOfBounds {
+                x: point.x,
+                y: point.y,
+            })
+        }
+        Some(Number { state: Open, .. }) | Some(Mine { state: Open }) => {
+            return Err(Error::CellAlreadyOpen {
+                x: point.x,
+                y: point.y,
+            })
+        }
+        _ => (),
+    }
+    let adjacency = Adjacency::for_board(board);
+    let probabilities = probabilities_with(board, &adjacency);
+    if !probabilities.exact {
+        return Err(too_large());
+    }
+    let mine_probability = probabilities.at(point).unwrap_or(0.0);
+    let known: Vec<Point> = deductions_with(board, &adjacency)
+        .iter()
+        .map(|d| d.point)
+        .collect();
+
+    let mut weights = Vec::new();
+    let mut progress = Vec::new();
+    for count in 0..=board.neighbors(point).count() {
+        let mut revealed = board.clone();
+        revealed.map[point.y as usize][point.x as usize] = Number {
+            state: Open,
+            count: count as i32,
+        };
+        let weight = relative_layouts(&revealed, &adjacency).ok_or_else(too_large)?;
+        if weight == 0.0 {
+            continue;
+        }
+        // Only cells that weren't already certain, and not the guess itself.
+        let settled = deductions_with(&revealed, &adjacency)
+            .iter()
+            .filter(|d| d.point != *point && !known.contains(&d.point))
+            .count();
+        weights.push(weight);
+        progress.push(settled as f64);
+    }
+    let total: f64 = weights.iter().sum();
+    let safe = 1.0 - mine_probability;
+    let (expected_progress, progress_probability) = if total > 0.0 {
+        weights
+            .iter()
+            .zip(&progress)
+            .fold((0.0, 0.0), |(expected, chance), (w, cells)| {
+                let p = safe * w / total;
+                (
+                    expected + p * cells,
+                    chance + if *cells > 0.0 { p } else { 0.0 },
+                )
+            })
+    } else {
+        (0.0, 0.0)
+    };
+    Ok(GuessEvaluation {
+        point: *point,
+        mine_probability,
+        expected_progress,
+        progress_probability,
+    })
+}
+
+// The best closed cell to guess, or None if there's nothing left to open or
+// the board is too large to evaluate.
+pub fn best_guess(board: &Board) -> Option<GuessEvaluation> {
+    let mut best: Option<GuessEvaluation> = None;
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let p = Point::new(x, y);
+            if !matches!(
+                board.at(&p),
+                Some(Number { state: Closed, .. }) | Some(Mine { state: Closed })
+            ) {
+                continue;
+            }
+            let evaluation = evaluate_guess(board, &p).ok()?;
+            if best.as_ref().map_or(true, |b| evaluation.is_better_than(b)) {
+                best = Some(evaluation);
+            }
+        }
+    }
+    best
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MoveKind {
+    // A guess with nothing certain left to play, the first click included.
+    Forced,
+    // Settled by a single number: all its mines or all its safe cells found.
+    Safe,
+    // Certain, but only by combining numbers or counting the mines left.
+    Deducible,
+    // A guess while some cell was certainly safe.
+    Guess,
+    // Opening a certain mine or flagging a certainly safe cell.
+    Blunder,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnnotatedMove {
+    // Index into the replay's moves.
+    pub index: usize,
+    pub action: Move,
+    pub kind: MoveKind,
+    // For opens, the chance the cell was a mine when it was opened.
+    pub mine_probability: Option<f64>,
+    // For guesses, the cells that were certainly safe instead.
+    pub missed: Vec<Point>,
+    // For opens in endgames small enough for the tablebase, the chance of
+    // winning given up against the best move.
+    pub win_probability_lost: Option<f64>,
+    // The evaluation of the game before and after the move, when it was
+    // played back by analyze_replay_with_evaluation.
+    pub win_probability: Option<(f64, f64)>,
+}
+
+// One point of a game's eval graph, see evaluate_position.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct WinProbability {
+    // Index into the replay's moves.
+    pub index: usize,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl WinProbability {
+    // Negative for moves that made winning less likely.
+    pub fn change(&self) -> f64 {
+        self.after - self.before
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnalysisReport {
+    // Moves the game rejected (opening an open cell, say) are left out.
+    pub moves: Vec<AnnotatedMove>,
+    pub won: bool,
+    // Opens that were not certain, forced or not.
+    pub guesses: usize,
+    // The chance of surviving every guess the player took. The lower it is
+    // for a won game, the luckier the player was.
+    pub survival_chance: f64,
+}
+
+impl AnalysisReport {
+    // Guesses taken while a certainly safe cell was there to open.
+    pub fn missed_deductions(&self) -> impl Iterator<Item = &AnnotatedMove> {
+        self.moves.iter().filter(|m| !m.missed.is_empty())
+    }
+
+    // The evaluation around every move, in order, to plot the game with.
+    // Empty unless the positions were evaluated.
+    pub fn win_probability_series(&self) -> impl Iterator<Item = WinProbability> + '_ {
+        self.moves.iter().filter_map(|m| {
+            m.win_probability.map(|(before, after)| WinProbability {
+                index: m.index,
+                before,
+                after,
+            })
+        })
+    }
+
+    // The guess that cost the most chance of winning: the one to highlight.
+    pub fn losing_guess(&self) -> Option<WinProbability> {
+        self.moves
+            .iter()
+            .filter(|m| matches!(m.kind, MoveKind::Forced | MoveKind::Guess))
+            .filter_map(|m| {
+                m.win_probability.map(|(before, after)| WinProbability {
+                    index: m.index,
+                    before,
+                    after,
+                })
+            })
+            .filter(|w| w.change() < 0.0)
+            .fold(None, |worst: Option<WinProbability>, w| match worst {
+                Some(v) if v.change() <= w.change() => worst,
+                _ => Some(w),
+            })
+    }
+}
+
+pub(crate) fn annotate(board: &Board, index: usize, action: &Move) -> AnnotatedMove {
+    let adjacency = Adjacency::for_board(board);
+    let (point, flag) = match action {
+        Move::Open(p) | Move::Power(_, p) => (p, false),
+        Move::Flag(p) => (p, true),
+    };
+    let deductions = deductions_with(board, &adjacency);
+    let cell = adjacency.cell(point);
+    let single_number = constraints(board, &adjacency).iter().any(|c| {
+        c.cells.contains(&cell)
+            && if flag {
+                c.mines == c.cells.len()
+            } else {
+                c.mines == 0
+            }
+    });
+    let safe_cells: Vec<Point> = deductions
+        .iter()
+        .filter(|d| !d.mine)
+        .map(|d| d.point)
+        .collect();
+    let (kind, missed) = match deductions.iter().find(|d| d.point == *point) {
+        Some(d) if d.mine != flag => (MoveKind::Blunder, vec![]),
+        Some(_) if single_number => (MoveKind::Safe, vec![]),
+        Some(_) => (MoveKind::Deducible, vec![]),
+        None if flag || !safe_cells.is_empty() => (MoveKind::Guess, safe_cells),
+        None => (MoveKind::Forced, vec![]),
+    };
+    let mine_probability = if flag {
+        None
+    } else {
+        probabilities_with(board, &adjacency).at(point)
+    };
+    AnnotatedMove {
+        index,
+        action: *action,
+        kind,
+        mine_probability,
+        missed,
+        win_probability_lost: None,
+        win_probability: None,
+    }
+}
+
+// Plays the replay back and annotates each move against the board the player
+// saw, like a chess engine's game review. Boards too large to solve exactly
+// have no deductions, so every open on them counts as forced. Positions
+// aren't evaluated, see analyze_replay_with_evaluation.
+pub fn analyze_replay(replay: &Replay) -> AnalysisReport {
+    analyze(replay, None)
+}
+
+// Like analyze_replay, with every position evaluated too, from `playouts`
+// playouts and the board's seed, for the win probability of each move. It
+// takes far longer, every position being played out that many times, so
+// it's left to callers that plot the game.
+pub fn analyze_replay_with_evaluation(replay: &Replay, playouts: usize) -> AnalysisReport {
+    analyze(replay, Some(playouts))
+}
+
+fn analyze(replay: &Replay, playouts: Option<usize>) -> AnalysisReport {
+    let mut game = Game::new(replay.board.clone(), replay.options.clone());
+    let mut tablebase = Tablebase::default();
+    let seed = replay.board.seed.unwrap_or(0);
+    let evaluate = |board: &Board, tablebase: &mut Tablebase| {
+        playouts.map(|playouts| evaluate_position_with(board, tablebase, playouts, seed))
+    };
+    let mut current = evaluate(game.board(), &mut tablebase);
+    let mut moves = vec![];
+    for (index, logged) in replay.moves.iter().enumerate() {
+        // Power-ups aren't the player's deductions, good or bad.
+        if let Move::Power(..) = logged.action {
+            if game.play(&logged.action, logged.at).is_ok() {
+                current = evaluate(game.board(), &mut tablebase);
+            }
+            continue;
+        }
+        let mut annotated = annotate(game.board(), index, &logged.action);
+        if let Move::Open(p) = &logged.action {
+            annotated.win_probability_lost =
+                tablebase.evaluate(game.board()).and_then(|e| e.loss(p));
+        }
+        if game.play(&logged.action, logged.at).is_ok() {
+            let before = current;
+            current = evaluate(game.board(), &mut tablebase);
+            annotated.win_probability = before.zip(current);
+            moves.push(annotated);
+        }
+    }
+    let guesses: Vec<f64> = moves
+        .iter()
+        .filter(|m| matches!(m.kind, MoveKind::Forced | MoveKind::Guess))
+        .filter_map(|m| m.mine_probability)
+        .collect();
+    AnalysisReport {
+        won: game.board().state == BoardState::Won,
+        guesses: guesses.len(),
+        survival_chance: guesses.iter().map(|p| 1.0 - p).product(),
+        moves,
+    }
+}
+
+// Playouts evaluate_position averages over, when the tablebase can't say.
+pub const EVALUATION_PLAYOUTS: usize = 64;
+
+// The chance a strong player wins from here, for an eval bar over a game.
+// Exact once the closed cells fit in the tablebase. Until then it averages
+// playouts on mine layouts drawn from those agreeing with the board: each
+// opens safe cells first, then the cell least likely a mine, and plays
+// perfectly once the tablebase takes over, so it's a little short of
+// optimal play. The mine count is taken as known.
+pub fn evaluate_position(board: &Board) -> f64 {
+    evaluate_position_with(
+        board,
+        &mut Tablebase::default(),
+        EVALUATION_PLAYOUTS,
+        board.seed.unwrap_or(0),
+    )
+}
+
+// For evaluating every position of a game with one tablebase. The same seed
+// gives the same estimate. Frontiers with too many layouts to draw from are
+// played out on mines drawn cell by cell from their probabilities.
+pub fn evaluate_position_with(
+    board: &Board,
+    tablebase: &mut Tablebase,
+    playouts: usize,
+    seed: u64,
+) -> f64 {
+    match board.state {
+        BoardState::Won => return 1.0,
+        BoardState::Failed => return 0.0,
+        _ => (),
+    }
+    if let Some(endgame) = tablebase.evaluate(board) {
+        return endgame.win_probability;
+    }
+    let adjacency = Adjacency::for_board(board);
+    let sampler = LayoutSampler::new(board, &adjacency);
+    let mut rng = SplitMix64::new(seed);
+    // Without a sampler each cell is drawn on its own, from its chance of
+    // being a mine: the board's own mines would give the position away.
+    let probabilities = match sampler {
+        Some(_) => None,
+        None => Some(probabilities_with(board, &adjacency)),
+    };
+    let wins: f64 = (0..playouts)
+        .map(|_| {
+            let mines = match (&sampler, &probabilities) {
+                (Some(sampler), _) => sampler.sample(&mut rng),
+                (None, Some(probabilities)) => probabilities
+                    .cells
+                    .iter()
+                    .map(|p| p.is_some_and(|p| rng.next_f64() < p))
+                    .collect(),
+                (None, None) => unreachable!(),
+            };
+            playout(redeal(board, &mines), &adjacency, tablebase)
+        })
+        .sum();
+    wins / playouts.max(1) as f64
+}
+
+// The board with its closed cells holding `mines` instead, and no flags.
+fn redeal(board: &Board, mines: &[bool]) -> Board {
+    let mut redealt = board.clone();
+    for (i, el) in redealt.map.iter_mut().flatten().enumerate() {
+        *el = match el {
+            Number { state: Open, .. } | Mine { state: Open } => *el,
+            _ if mines[i] => Mine { state: Closed },
+            _ => Number {
+                state: Closed,
+                count: 0,
+            },
+        };
+    }
+    redealt.missing_points = redealt
+        .map
+        .iter()
+        .flatten()
+        .filter(|el| matches!(el, Number { state: Closed, .. }))
+        .count() as i32;
+    let state = match board.state {
+        BoardState::NotReady => BoardState::Ready,
+        state => state,
+    };
+    Board {
+        state,
+        ..numbers_on_board(redealt)
+    }
+}
+
+fn playout(mut board: Board, adjacency: &Adjacency, tablebase: &mut Tablebase) -> f64 {
+    loop {
+        match board.state {
+            BoardState::Won => return 1.0,
+            BoardState::Failed => return 0.0,
+            _ => (),
+        }
+        if let Some(endgame) = tablebase.evaluate(&board) {
+            return endgame.win_probability;
+        }
+        let next = deductions_with(&board, adjacency)
+            .into_iter()
+            .find(|d| !d.mine)
+            .map(|d| d.point)
+            .or_else(|| {
+                let probabilities = probabilities_with(&board, adjacency);
+                let safest = probabilities
+                    .cells
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, p)| p.map(|p| (i, p)))
+                    .fold(None, |best: Option<(usize, f64)>, (i, p)| match best {
+                        Some((_, q)) if q <= p => best,
+                        _ => Some((i, p)),
+                    });
+                safest.map(|(i, _)| adjacency.point(i))
+            });
+        match next.and_then(|p| board.cascade_open_item(&p).ok()) {
+            Some(opened) => board = opened,
+            None => return 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameOptions;
+    use crate::scenario::Scenario;
+    use pretty_assertions::assert_eq;
+
+    fn close_to(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_evaluate_guess() {
+        // A 1 with its mine on either side and one cell out of its reach. The
+        // far cell is safe by the mine count and its number tells which side
+        // the mine is on, settling both cells; opening next to the 1 is a
+        // coin flip.
+        let board = Scenario::from_ascii("*1..\n").into_board();
+        let far = evaluate_guess(&board, &Point::new(3, 0)).unwrap();
+        assert!(close_to(far.mine_probability, 0.0));
+        assert!(close_to(far.expected_progress, 2.0));
+
+        let near = evaluate_guess(&board, &Point::new(2, 0)).unwrap();
+        assert!(close_to(near.mine_probability, 0.5));
+        assert!(close_to(near.progress_probability, 0.5));
+        assert!(close_to(near.expected_progress, 0.5));
+        assert!(far.is_better_than(&near));
+        assert_eq!(best_guess(&board).map(|b| b.point), Some(Point::new(3, 0)));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let kind = |ascii: &str, action: Move| {
+            annotate(&Scenario::from_ascii(ascii).into_board(), 0, &action).kind
+        };
+        assert_eq!(
+            kind("*...\n", Move::Open(Point::new(3, 0))),
+            MoveKind::Forced
+        );
+        // The 0 makes D1 safe and the 1 has a single closed neighbor.
+        assert_eq!(kind("*1 .\n", Move::Open(Point::new(3, 0))), MoveKind::Safe);
+        assert_eq!(kind("*1 .\n", Move::Flag(Point::new(0, 0))), MoveKind::Safe);
+        assert_eq!(
+            kind("*1 .\n", Move::Open(Point::new(0, 0))),
+            MoveKind::Blunder
+        );
+        // Only the mine count makes D1 safe.
+        assert_eq!(
+            kind("*1..\n", Move::Open(Point::new(3, 0))),
+            MoveKind::Deducible
+        );
+        let guess = annotate(
+            &Scenario::from_ascii("*1..\n").into_board(),
+            0,
+            &Move::Open(Point::new(2, 0)),
+        );
+        assert_eq!(guess.kind, MoveKind::Guess);
+        assert_eq!(guess.missed, vec![Point::new(3, 0)]);
+        assert_eq!(guess.mine_probability, Some(0.5));
+    }
+
+    #[test]
+    fn test_analyze_replay() {
+        // *2100
+        // 2*100
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        for m in &[
+            Move::Open(Point::new(4, 0)),
+            Move::Flag(Point::new(0, 0)),
+            Move::Open(Point::new(0, 1)),
+            Move::Open(Point::new(0, 1)),
+            Move::Open(Point::new(1, 0)),
+        ] {
+            let _ = game.apply(m);
+        }
+        let report = analyze_replay(&game.replay());
+        let kinds: Vec<(usize, MoveKind)> =
+            report.moves.iter().map(|m| (m.index, m.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0, MoveKind::Forced),
+                (1, MoveKind::Guess),
+                (2, MoveKind::Forced),
+                (4, MoveKind::Forced),
+            ]
+        );
+        assert!(report.won);
+        assert_eq!(report.guesses, 3);
+        assert!(close_to(report.survival_chance, 0.8 * 0.5 * 0.5));
+        // Flagging A1 was a guess, but there was nothing certain to do instead.
+        assert_eq!(report.missed_deductions().count(), 0);
+    }
+
+    #[test]
+    fn test_win_probability_lost() {
+        // Opening an end settles the mine two times in three, the middle
+        // never does.
+        let board = Scenario::from_ascii("..*\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        game.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        let report = analyze_replay(&game.replay());
+        let lost = report.moves[0].win_probability_lost.unwrap();
+        assert!(close_to(lost, 1.0 / 3.0));
+    }
+
+    #[test]
+    fn test_win_probability_series() {
+        // The middle shows a 1 and leaves a coin flip, which the player
+        // loses.
+        let board = Scenario::from_ascii("..*\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        game.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(2, 0))).unwrap();
+        assert_eq!(analyze_replay(&game.replay()).losing_guess(), None);
+        let report = analyze_replay_with_evaluation(&game.replay(), EVALUATION_PLAYOUTS);
+        let series: Vec<WinProbability> = report.win_probability_series().collect();
+        assert_eq!(series.len(), 2);
+        assert!(close_to(series[0].before, 2.0 / 3.0));
+        assert!(close_to(series[0].after, 0.5));
+        assert_eq!((series[1].index, series[1].after), (1, 0.0));
+        assert_eq!(report.losing_guess(), Some(series[1]));
+    }
+
+    #[test]
+    fn test_evaluate_position() {
+        let board = Scenario::from_ascii("..*\n").into_board();
+        assert!(close_to(evaluate_position(&board), 2.0 / 3.0));
+
+        // Too many closed cells for the tablebase: one mine in sixteen is
+        // nearly always found.
+        let board = Scenario::from_ascii("....\n....\n..*.\n....\n").into_board();
+        let mut tablebase = Tablebase::default();
+        let eval = evaluate_position_with(&board, &mut tablebase, 16, 7);
+        assert!(eval > 0.8 && eval <= 1.0);
+        assert_eq!(eval, evaluate_position_with(&board, &mut tablebase, 16, 7));
+
+        let won = Scenario::from_ascii("*1\n1.\n").open("B2").into_board();
+        assert_eq!(evaluate_position(&won), 1.0);
+    }
+
+    #[test]
+    fn test_evaluate_position_hides_the_mines() {
+        // Seventeen closed columns of two with a mine each, between open
+        // columns that only tell there are two mines on either side: more
+        // layouts than the sampler lists. Where the mines are can't matter.
+        let board = |top: &dyn Fn(usize) -> bool| {
+            let rows: Vec<String> = [true, false]
+                .iter()
+                .map(|&row| {
+                    (0..33)
+                        .map(|x| match x % 2 {
+                            1 => ' ',
+                            _ if top(x) == row => '*',
+                            _ => '.',
+                        })
+                        .collect()
+                })
+                .collect();
+            Scenario::from_ascii(&rows.join("\n")).into_board()
+        };
+        let on_top = board(&|_| true);
+        let below = board(&|_| false);
+        let adjacency = Adjacency::for_board(&on_top);
+        assert!(LayoutSampler::new(&on_top, &adjacency).is_none());
+        let mut tablebase = Tablebase::default();
+        assert_eq!(
+            evaluate_position_with(&on_top, &mut tablebase, 4, 3),
+            evaluate_position_with(&below, &mut tablebase, 4, 3)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_open_cell_fails() {
+        let board = Scenario::from_ascii("*1..\n").into_board();
+        assert_eq!(
+            evaluate_guess(&board, &Point::new(1, 0)),
+            Err(Error::CellAlreadyOpen { x: 1, y: 0 })
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::view::CellView;
+use crate::{Error, Point, Result};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum ColorTag {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+// What a player marked a cell with, for their own use: nothing here changes
+// how the game plays.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default, Serialize, Deserialize)]
+pub struct Annotation {
+    pub color: Option<ColorTag>,
+    pub note: Option<String>,
+    // The player's own estimate of a mine there, in percent.
+    pub pin: Option<u8>,
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        *self == Annotation::default()
+    }
+}
+
+// The annotated cells of a board, in the order they were first annotated.
+// A list rather than a map so it saves to JSON, whose keys are strings.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default, Serialize, Deserialize)]
+pub struct Annotations {
+    cells: Vec<(Point, Annotation)>,
+}
+
+impl Annotations {
+    pub fn get(&self, p: &Point) -> Option<&Annotation> {
+        self.cells.iter().find(|(q, _)| q == p).map(|(_, a)| a)
+    }
+
+    // An empty annotation takes the cell's off.
+    pub fn set(&mut self, p: &Point, annotation: Annotation) {
+        match self.cells.iter().position(|(q, _)| q == p) {
+            Some(i) if annotation.is_empty() => {
+                self.cells.remove(i);
+            }
+            Some(i) => self.cells[i].1 = annotation,
+            None if annotation.is_empty() => (),
+            None => self.cells.push((*p, annotation)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Point, Annotation)> {
+        self.cells.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+// A cell as the player sees it, with their annotation.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct AnnotatedCell {
+    pub view: CellView,
+    pub annotation: Option<Annotation>,
+}
+
+impl Game {
+    // Replaces the cell's annotation. Annotations are kept in saves but not
+    // in replays, and can be changed once the game is over.
+    pub fn annotate(&mut self, p: &Point, annotation: Annotation) -> Result<()> {
+        if self.board().at(p).is_none() {
+            return Err(Error::OutOfBounds { x: p.x, y: p.y });
+        }
+        if annotation.pin.is_some_and(|pin| pin > 100) {
+            return Err(Error::InvalidConfig(String::from(
+                "a pinned probability is at most 100 percent",
+            )));
+        }
+        let annotations = self.annotations.get_or_insert_with(Annotations::default);
+        annotations.set(p, annotation);
+        if annotations.is_empty() {
+            self.annotations = None;
+        }
+        Ok(())
+    }
+
+    pub fn annotation(&self, p: &Point) -> Option<&Annotation> {
+        self.annotations.as_ref()?.get(p)
+    }
+
+    pub fn annotations(&self) -> Option<&Annotations> {
+        self.annotations.as_ref()
+    }
+
+    // Row by row, like Game::view.
+    pub fn annotated_view(&self) -> Vec<Vec<AnnotatedCell>> {
+        let view = self.view();
+        view.into_iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.into_iter()
+                    .enumerate()
+                    .map(|(x, view)| AnnotatedCell {
+                        view,
+                        annotation: self.annotation(&Point::new(x, y)).cloned(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameOptions;
+    use crate::numbers_on_board;
+    use crate::tests::five_by_two_board;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_annotations() {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        let suspect = Annotation {
+            color: Some(ColorTag::Red),
+            note: Some(String::from("looks like a 50/50")),
+            pin: Some(50),
+        };
+        game.annotate(&Point::new(1, 1), suspect.clone()).unwrap();
+        game.annotate(
+            &Point::new(0, 0),
+            Annotation {
+                pin: Some(50),
+                ..Annotation::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            game.annotate(&Point::new(5, 0), suspect.clone()),
+            Err(Error::OutOfBounds { x: 5, y: 0 })
+        );
+        assert!(game
+            .annotate(
+                &Point::new(0, 0),
+                Annotation {
+                    pin: Some(101),
+                    ..Annotation::default()
+                }
+            )
+            .is_err());
+        let view = game.annotated_view();
+        assert_eq!(view[1][1].view, CellView::Hidden);
+        assert_eq!(view[1][1].annotation, Some(suspect));
+        assert_eq!(view[0][1].annotation, None);
+
+        game.annotate(&Point::new(0, 0), Annotation::default())
+            .unwrap();
+        assert_eq!(game.annotation(&Point::new(0, 0)), None);
+        assert_eq!(game.annotations().unwrap().iter().count(), 1);
+        game.annotate(&Point::new(1, 1), Annotation::default())
+            .unwrap();
+        assert_eq!(game.annotations(), None);
+    }
+
+    #[cfg(feature = "storage")]
+    #[test]
+    fn test_annotations_are_saved() {
+        use crate::migrations::{save_from_json, save_to_json};
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        game.annotate(
+            &Point::new(1, 1),
+            Annotation {
+                color: Some(ColorTag::Blue),
+                ..Annotation::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(save_from_json(&save_to_json(&game).unwrap()), Ok(game));
+    }
+}
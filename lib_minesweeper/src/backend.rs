@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+
+use crate::fixed::FixedBoard;
+use crate::view::{self, CellView};
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::{Board, BoardState, Error, MapElement, Point, Result};
+
+// What solvers and renderers need to look at a board, whichever way it's
+// kept. Cells off the board are None.
+pub trait BoardRead {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn mines(&self) -> usize;
+    fn state(&self) -> BoardState;
+    fn cell(&self, p: &Point) -> Option<MapElement>;
+
+    // Only Board keeps treasures.
+    fn is_treasure(&self, _p: &Point) -> bool {
+        false
+    }
+
+    fn cell_view(&self, p: &Point) -> Option<CellView> {
+        view::cell_view(self, p)
+    }
+
+    // Row by row, like Board's map.
+    fn view(&self) -> Vec<Vec<CellView>> {
+        view::view(self)
+    }
+}
+
+// Playing on a board, and the little numbers_on_board needs to build one.
+pub trait BoardWrite: BoardRead {
+    // Opens the cell, and around it when it's a zero.
+    fn open(&mut self, p: &Point) -> Result<()>;
+    // Flags a closed cell, or takes the flag off a flagged one.
+    fn flag(&mut self, p: &Point) -> Result<()>;
+    // Changes the number a cell shows, nothing else. Mines have none.
+    fn set_count(&mut self, p: &Point, count: i32) -> Result<()>;
+    fn set_state(&mut self, state: BoardState);
+}
+
+fn count_of<'a>(el: &'a mut MapElement, p: &Point) -> Result<&'a mut i32> {
+    match el {
+        Number { count, .. } => Ok(count),
+        Mine { .. } => Err(Error::InvalidConfig(alloc::format!(
+            "({}, {}) is a mine and shows no number",
+            p.x,
+            p.y
+        ))),
+    }
+}
+
+impl BoardRead for Board {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn mines(&self) -> usize {
+        self.mines
+    }
+
+    fn state(&self) -> BoardState {
+        self.state
+    }
+
+    fn cell(&self, p: &Point) -> Option<MapElement> {
+        self.at(p).copied()
+    }
+
+    fn is_treasure(&self, p: &Point) -> bool {
+        Board::is_treasure(self, p)
+    }
+}
+
+impl BoardWrite for Board {
+    fn open(&mut self, p: &Point) -> Result<()> {
+        *self = self.cascade_open_item(p)?;
+        Ok(())
+    }
+
+    fn flag(&mut self, p: &Point) -> Result<()> {
+        *self = self.flag_item(p)?;
+        Ok(())
+    }
+
+    fn set_count(&mut self, p: &Point, count: i32) -> Result<()> {
+        if self.at(p).is_none() {
+            return Err(Error::OutOfBounds { x: p.x, y: p.y });
+        }
+        *count_of(&mut self.map[p.y as usize][p.x as usize], p)? = count;
+        Ok(())
+    }
+
+    fn set_state(&mut self, state: BoardState) {
+        self.state = state;
+    }
+}
+
+impl<const W: usize, const H: usize> BoardRead for FixedBoard<W, H> {
+    fn width(&self) -> usize {
+        W
+    }
+
+    fn height(&self) -> usize {
+        H
+    }
+
+    fn mines(&self) -> usize {
+        FixedBoard::mines(self)
+    }
+
+    fn state(&self) -> BoardState {
+        FixedBoard::state(self)
+    }
+
+    fn cell(&self, p: &Point) -> Option<MapElement> {
+        self.at(p).copied()
+    }
+}
+
+impl<const W: usize, const H: usize> BoardWrite for FixedBoard<W, H> {
+    fn open(&mut self, p: &Point) -> Result<()> {
+        FixedBoard::open(self, p)
+    }
+
+    fn flag(&mut self, p: &Point) -> Result<()> {
+        FixedBoard::flag(self, p)
+    }
+
+    fn set_count(&mut self, p: &Point, count: i32) -> Result<()> {
+        *count_of(self.at_mut(p)?, p)? = count;
+        Ok(())
+    }
+
+    fn set_state(&mut self, state: BoardState) {
+        FixedBoard::set_state(self, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers_on_board;
+    use crate::render::board_text;
+    use crate::solver::deductions;
+    use crate::tests::five_by_two_board;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_backends_agree() {
+        let mut board = numbers_on_board(five_by_two_board());
+        let mut fixed =
+            numbers_on_board(FixedBoard::<5, 2>::from_board(&five_by_two_board()).unwrap());
+        for b in [&mut board as &mut dyn BoardWrite, &mut fixed] {
+            b.open(&Point::new(4, 0)).unwrap();
+            b.flag(&Point::new(0, 0)).unwrap();
+        }
+        assert_eq!(board_text(&fixed), board_text(&board));
+        assert_eq!(deductions(&fixed), deductions(&board));
+        assert_eq!(fixed.to_board(), board);
+        assert!(fixed.set_count(&Point::new(0, 0), 1).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{board_with_mines, numbers_on_board, Board, Error, Point, Result};
+
+// One board of a benchmark set, as published: its size and where its mines
+// are. Nothing is checked until it's made into a Board.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct BenchmarkEntry {
+    pub name: Option<String>,
+    pub width: usize,
+    pub height: usize,
+    pub mines: Vec<Point>,
+}
+
+impl BenchmarkEntry {
+    // Closed, with its numbers worked out. Fails on mines off the board.
+    pub fn board(&self) -> Result<Board> {
+        if let Some(p) = self.mines.iter().find(|p| {
+            p.x < 0 || p.y < 0 || p.x as usize >= self.width || p.y as usize >= self.height
+        }) {
+            return Err(Error::OutOfBounds { x: p.x, y: p.y });
+        }
+        board_with_mines(self.width, self.height, &self.mines).map(numbers_on_board)
+    }
+}
+
+// A corpus of boards to run solvers against, read from the files benchmark
+// sets are published as.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default, Serialize, Deserialize)]
+pub struct BenchmarkSet {
+    pub entries: Vec<BenchmarkEntry>,
+}
+
+fn parse_error(line: usize, what: impl core::fmt::Display) -> Error {
+    Error::ParseError(format!("line {}: {}", line, what))
+}
+
+const TOO_FAR: &str = "mine coordinates too large for a point";
+
+// Any point a Point can hold, on the board or off it: boards check their
+// mines when they're made.
+fn point(x: usize, y: usize) -> Option<Point> {
+    Point::checked(x, y, (usize::MAX, usize::MAX))
+}
+
+impl BenchmarkSet {
+    // One board per line: width, height, then the x and y of every mine,
+    // all separated by commas. Blank lines and lines starting with '#' are
+    // skipped, and so is a first line naming the columns.
+    pub fn from_csv(text: &str) -> Result<BenchmarkSet> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let numbers: core::result::Result<Vec<usize>, _> =
+                fields.iter().map(|f| f.parse::<usize>()).collect();
+            let numbers = match numbers {
+                Ok(numbers) => numbers,
+                Err(_) if entries.is_empty() && i == 0 => continue,
+                Err(e) => return Err(parse_error(i + 1, e)),
+            };
+            if numbers.len() < 2 || numbers.len() % 2 != 0 {
+                return Err(parse_error(
+                    i + 1,
+                    "expected width, height and pairs of mine coordinates",
+                ));
+            }
+            let mines = numbers[2..]
+                .chunks(2)
+                .map(|xy| point(xy[0], xy[1]).ok_or_else(|| parse_error(i + 1, TOO_FAR)))
+                .collect::<Result<Vec<Point>>>()?;
+            entries.push(BenchmarkEntry {
+                name: None,
+                width: numbers[0],
+                height: numbers[1],
+                mines,
+            });
+        }
+        Ok(BenchmarkSet { entries })
+    }
+
+    // A JSON array of boards, each an object with "width", "height",
+    // "mines" as [x, y] pairs and optionally a "name".
+    #[cfg(feature = "json")]
+    pub fn from_json(text: &str) -> Result<BenchmarkSet> {
+        #[derive(Deserialize)]
+        struct Published {
+            name: Option<String>,
+            width: usize,
+            height: usize,
+            mines: Vec<(usize, usize)>,
+        }
+        let published: Vec<Published> =
+            serde_json::from_str(text).map_err(|e| Error::ParseError(e.to_string()))?;
+        let entries = published
+            .into_iter()
+            .map(|p| {
+                let mines = p
+                    .mines
+                    .into_iter()
+                    .map(|(x, y)| {
+                        point(x, y).ok_or_else(|| Error::ParseError(String::from(TOO_FAR)))
+                    })
+                    .collect::<Result<Vec<Point>>>()?;
+                Ok(BenchmarkEntry {
+                    name: p.name,
+                    width: p.width,
+                    height: p.height,
+                    mines,
+                })
+            })
+            .collect::<Result<Vec<BenchmarkEntry>>>()?;
+        Ok(BenchmarkSet { entries })
+    }
+
+    // Every board in the set, in order, made as it's reached.
+    pub fn boards(&self) -> impl Iterator<Item = Result<Board>> + '_ {
+        self.entries.iter().map(BenchmarkEntry::board)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::five_by_two_board;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_benchmark_csv() {
+        let set = BenchmarkSet::from_csv(
+            "width,height,mines\n# the fixture board\n5, 2, 0, 0, 1, 1\n\n3,3\n4,1,9,0\n",
+        )
+        .unwrap();
+        assert_eq!(set.len(), 3);
+        let boards: Vec<Result<Board>> = set.boards().collect();
+        assert_eq!(boards[0], Ok(numbers_on_board(five_by_two_board())));
+        assert_eq!(boards[1].as_ref().map(|b| b.mines), Ok(0));
+        assert_eq!(boards[2], Err(Error::OutOfBounds { x: 9, y: 0 }));
+
+        assert!(BenchmarkSet::from_csv("5,2,0\n").is_err());
+        assert!(BenchmarkSet::from_csv("5,2\nfive,2\n").is_err());
+        assert!(matches!(
+            BenchmarkSet::from_csv("5,2,4294967296,0\n"),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_benchmark_json() {
+        let set = BenchmarkSet::from_json(
+            r#"[{"name": "fixture", "width": 5, "height": 2, "mines": [[0, 0], [1, 1]]}]"#,
+        )
+        .unwrap();
+        assert_eq!(set.entries[0].name.as_deref(), Some("fixture"));
+        assert_eq!(
+            set.boards().collect::<Vec<_>>(),
+            vec![Ok(numbers_on_board(five_by_two_board()))]
+        );
+        assert!(BenchmarkSet::from_json("{}").is_err());
+        assert!(matches!(
+            BenchmarkSet::from_json(r#"[{"width": 5, "height": 2, "mines": [[0, 2147483648]]}]"#),
+            Err(Error::ParseError(_))
+        ));
+    }
+}
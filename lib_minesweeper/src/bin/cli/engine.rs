@@ -0,0 +1,184 @@
+use std::io::{self, BufRead, Write};
+
+use serde_derive::Deserialize;
+use serde_json::{json, Value};
+
+use lib_minesweeper::analysis::best_guess;
+use lib_minesweeper::game::{Game, GameConfig, GameOptions};
+use lib_minesweeper::random::{OsEntropy, RandomSource};
+use lib_minesweeper::render::game_text;
+use lib_minesweeper::solver::{deductions, probabilities_grid};
+use lib_minesweeper::{Error, Move, Point, Result};
+
+// One command per line, as a JSON object naming it in "cmd":
+//
+//     {"cmd": "newboard", "width": 9, "height": 9, "mines": 10, "seed": 1}
+//     {"cmd": "open", "x": 4, "y": 4}
+//     {"cmd": "flag", "x": 0, "y": 0}
+//     {"cmd": "hint"}
+//     {"cmd": "analyze"}
+//     {"cmd": "quit"}
+//
+// Every command gets one line back, an object with "ok": true and the
+// command's results, or "ok": false and an "error".
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum Command {
+    // Mines are placed on the first open, never under it. Without a seed
+    // the engine picks one.
+    NewBoard {
+        width: usize,
+        height: usize,
+        mines: usize,
+        seed: Option<u64>,
+    },
+    Open {
+        x: usize,
+        y: usize,
+    },
+    Flag {
+        x: usize,
+        y: usize,
+    },
+    Hint,
+    Analyze,
+    Quit,
+}
+
+fn no_game() -> Error {
+    Error::InvalidConfig(String::from("no board yet, send newboard first"))
+}
+
+fn position(game: &Game) -> Value {
+    json!({
+        "state": game.board().state,
+        "board": game_text(game).lines().collect::<Vec<_>>(),
+    })
+}
+
+fn with(mut value: Value, key: &str, extra: Value) -> Value {
+    value[key] = extra;
+    value
+}
+
+// A cell the solver proves safe, or the best guess when there is none.
+fn hint(game: &Game) -> Value {
+    let board = game.board();
+    if let Some(d) = deductions(board)
+        .into_iter()
+        .find(|d| !d.mine && game.is_legal(&Move::Open(d.point)))
+    {
+        return json!({"x": d.point.x, "y": d.point.y, "mine_probability": 0.0});
+    }
+    match best_guess(board) {
+        Some(guess) => json!({
+            "x": guess.point.x,
+            "y": guess.point.y,
+            "mine_probability": guess.mine_probability,
+        }),
+        None => Value::Null,
+    }
+}
+
+fn play(game: &mut Option<Game>, m: Move) -> Result<Value> {
+    let game = game.as_mut().ok_or_else(no_game)?;
+    let events = game.apply(&m)?;
+    Ok(with(position(game), "events", json!(events)))
+}
+
+fn handle(game: &mut Option<Game>, command: Command) -> Result<Value> {
+    match command {
+        Command::NewBoard {
+            width,
+            height,
+            mines,
+            seed,
+        } => {
+            let new = Game::new_deferred(GameConfig {
+                width,
+                height,
+                mines,
+                seed: seed.unwrap_or_else(|| OsEntropy.next_u64()),
+                options: GameOptions::default(),
+            })?;
+            Ok(position(game.insert(new)))
+        }
+        Command::Open { x, y } => play(game, Move::Open(Point::new(x, y))),
+        Command::Flag { x, y } => play(game, Move::Flag(Point::new(x, y))),
+        Command::Hint => Ok(json!({"hint": hint(game.as_ref().ok_or_else(no_game)?)})),
+        Command::Analyze => {
+            let game = game.as_ref().ok_or_else(no_game)?;
+            Ok(json!({"probabilities": probabilities_grid(game.board())}))
+        }
+        Command::Quit => Ok(json!({})),
+    }
+}
+
+// Answers commands from `input` on `output` until quit or the end of the
+// input. Blank lines are skipped; bad ones get an error and are otherwise
+// ignored.
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut game = None;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command =
+            serde_json::from_str::<Command>(&line).map_err(|e| Error::ParseError(e.to_string()));
+        let quit = matches!(command, Ok(Command::Quit));
+        let response = match command.and_then(|command| handle(&mut game, command)) {
+            Ok(response) => with(response, "ok", json!(true)),
+            Err(e) => json!({"ok": false, "error": e.to_string()}),
+        };
+        writeln!(output, "{}", response)?;
+        output.flush()?;
+        if quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn responses(input: &str) -> Vec<Value> {
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_engine_session() {
+        let responses = responses(
+            r#"{"cmd": "open", "x": 0, "y": 0}
+{"cmd": "newboard", "width": 9, "height": 9, "mines": 10, "seed": 3}
+
+{"cmd": "open", "x": 4, "y": 4}
+{"cmd": "hint"}
+{"cmd": "analyze"}
+not json
+{"cmd": "quit"}
+{"cmd": "hint"}"#,
+        );
+        assert_eq!(responses.len(), 7);
+        assert_eq!(responses[0]["ok"], json!(false));
+        assert_eq!(responses[1]["state"], json!("Ready"));
+        assert_eq!(responses[1]["board"][0], json!("........."));
+        assert_eq!(responses[2]["ok"], json!(true));
+        assert!(responses[2]["events"].as_array().unwrap().contains(
+            &json!({"Opened": {"point": {"x": 4, "y": 4}, "count": 0, "source": "User"}})
+        ));
+        assert!(responses[3]["hint"]["mine_probability"].is_number());
+        assert_eq!(responses[4]["probabilities"][4][4], Value::Null);
+        assert_eq!(responses[5]["ok"], json!(false));
+        assert_eq!(responses[6], json!({"ok": true}));
+    }
+}
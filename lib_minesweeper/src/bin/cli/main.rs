@@ -0,0 +1,301 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+use lib_minesweeper::editor::EditorBoard;
+use lib_minesweeper::render::{board_ansi, board_svg, board_text};
+use lib_minesweeper::simulate::{
+    simulate_batch, BasicSolverPolicy, BatchConfig, Policy, RandomPolicy,
+};
+use lib_minesweeper::solver::{deductions, Deduction};
+use lib_minesweeper::stats::three_bv;
+use lib_minesweeper::MapElement::Mine;
+use lib_minesweeper::MapElement::Number;
+use lib_minesweeper::MapElementCellState::Closed;
+use lib_minesweeper::{Board, BoardBuilder, BoardState, Error, Move, Point, Result};
+
+mod engine;
+
+const USAGE: &str = "usage: minesweeper-cli <command> [options]
+
+commands:
+  generate --width W --height H --mines M [--seed S] [--out FILE]
+  solve [FILE] [--out FILE]
+  grade [FILE]
+  render [FILE] [--format text|ansi|svg] [--out FILE]
+  simulate --width W --height H --mines M [--games N] [--seed S]
+           [--policy basic|random] [--threads T]
+  engine   JSON commands on stdin, one per line, answered on stdout
+
+Boards are read and written as ASCII, one character per cell: '.' closed,
+'*' mine, 'F' flagged mine, digits and ' ' open. FILE defaults to stdin.";
+
+// The command line after the command: `--name value` options and at most one
+// file.
+#[derive(Debug, PartialEq, Eq, Default)]
+struct Args {
+    options: Vec<(String, String)>,
+    file: Option<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Args> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| parse_error(format!("--{} needs a value", name)))?;
+                    parsed.options.push((name.to_string(), value.clone()));
+                }
+                None if parsed.file.is_none() => parsed.file = Some(arg.clone()),
+                None => return Err(parse_error(format!("unexpected argument {:?}", arg))),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.get(name)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| parse_error(format!("--{} expects a number, got {:?}", name, v)))
+            })
+            .transpose()
+    }
+
+    fn required<T: std::str::FromStr>(&self, name: &str) -> Result<T> {
+        self.number(name)?
+            .ok_or_else(|| parse_error(format!("--{} is required", name)))
+    }
+
+    // The board in the file, or on stdin without one.
+    fn board(&self) -> Result<Board> {
+        let ascii = match self.file.as_deref() {
+            None | Some("-") => {
+                let mut ascii = String::new();
+                io::stdin()
+                    .read_to_string(&mut ascii)
+                    .map_err(|e| Error::Storage(e.to_string()))?;
+                ascii
+            }
+            Some(path) => fs::read_to_string(path).map_err(|e| Error::Storage(e.to_string()))?,
+        };
+        EditorBoard::from_ascii(&ascii)?.to_board()
+    }
+}
+
+fn parse_error(message: String) -> Error {
+    Error::ParseError(message)
+}
+
+fn is_closed(board: &Board, p: &Point) -> bool {
+    matches!(
+        board.at(p),
+        Some(Mine { state: Closed }) | Some(Number { state: Closed, .. })
+    )
+}
+
+// Plays the board as far as certain deductions go, starting from the first
+// zero when nothing is open yet. Returns the board reached and the number of
+// rounds of deductions it took.
+fn solve(board: &Board) -> Result<(Board, usize)> {
+    let cells = || (0..board.height).flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)));
+    let mut board = if cells().all(|p| is_closed(board, &p)) {
+        let start = cells()
+            .find(|p| matches!(board.at(p), Some(Number { count: 0, .. })))
+            .ok_or_else(|| {
+                Error::InvalidConfig(String::from("the board has no zero to start from"))
+            })?;
+        board.cascade_open_item(&start)?
+    } else {
+        board.clone()
+    };
+    let mut rounds = 0;
+    while board.state == BoardState::Playing {
+        let found: Vec<Deduction> = deductions(&board)
+            .into_iter()
+            .filter(|d| is_closed(&board, &d.point))
+            .collect();
+        if found.is_empty() {
+            break;
+        }
+        for d in found {
+            // Earlier opens of the round may have cascaded over the cell, or
+            // won the game.
+            if board.state != BoardState::Playing || !is_closed(&board, &d.point) {
+                continue;
+            }
+            board = board.apply_move(&match d.mine {
+                true => Move::Flag(d.point),
+                false => Move::Open(d.point),
+            })?;
+        }
+        rounds += 1;
+    }
+    Ok((board, rounds))
+}
+
+fn generate(args: &Args) -> Result<String> {
+    let mut builder = BoardBuilder::new(
+        args.required("width")?,
+        args.required("height")?,
+        args.required("mines")?,
+    );
+    if let Some(seed) = args.number("seed")? {
+        builder = builder.seed(seed);
+    }
+    Ok(EditorBoard::from_board(&builder.build()?).to_ascii())
+}
+
+fn grade(args: &Args) -> Result<String> {
+    let board = args.board()?;
+    let (solved, rounds) = solve(&board)?;
+    Ok(format!(
+        "size: {}x{}\nmines: {}\n3bv: {}\nsolvable without guessing: {}\nrounds of deductions: {}\n",
+        board.width,
+        board.height,
+        board.mines,
+        three_bv(&board),
+        if solved.state == BoardState::Won { "yes" } else { "no" },
+        rounds
+    ))
+}
+
+fn render(args: &Args) -> Result<String> {
+    let board = args.board()?;
+    match args.get("format").unwrap_or("text") {
+        "text" => Ok(board_text(&board)),
+        "ansi" => Ok(board_ansi(&board)),
+        "svg" => Ok(board_svg(&board, 16)),
+        format => Err(parse_error(format!("unknown format {:?}", format))),
+    }
+}
+
+fn simulate(args: &Args) -> Result<String> {
+    let config = BatchConfig {
+        width: args.required("width")?,
+        height: args.required("height")?,
+        mines: args.required("mines")?,
+        games: args.number("games")?.unwrap_or(1000),
+        seed: args.number("seed")?.unwrap_or(0),
+    };
+    let policy: &dyn Policy = match args.get("policy").unwrap_or("basic") {
+        "basic" => &BasicSolverPolicy,
+        "random" => &RandomPolicy,
+        policy => return Err(parse_error(format!("unknown policy {:?}", policy))),
+    };
+    let threads = args.number("threads")?.unwrap_or(1);
+    let report = simulate_batch(&[config], policy, threads)?.remove(0);
+    Ok(format!(
+        "games: {}\nwins: {}\nfailures: {}\nstalls: {}\nwin rate: {:.3}\n",
+        report.games.len(),
+        report.wins,
+        report.failures,
+        report.stalls,
+        report.win_rate()
+    ))
+}
+
+fn run(args: &[String]) -> Result<String> {
+    let (command, rest) = args
+        .split_first()
+        .ok_or_else(|| parse_error(String::from("no command given")))?;
+    let args = Args::parse(rest)?;
+    match command.as_str() {
+        "generate" => generate(&args),
+        "solve" => Ok(EditorBoard::from_board(&solve(&args.board()?)?.0).to_ascii()),
+        "grade" => grade(&args),
+        "render" => render(&args),
+        "simulate" => simulate(&args),
+        command => Err(parse_error(format!("unknown command {:?}", command))),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args[0] == "--help" || args[0] == "help" {
+        println!("{}", USAGE);
+        return;
+    }
+    if args[0] == "engine" {
+        if let Err(e) = engine::run(io::stdin().lock(), io::stdout().lock()) {
+            eprintln!("minesweeper-cli: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    let out = Args::parse(&args[1..])
+        .ok()
+        .and_then(|parsed| parsed.get("out").map(String::from));
+    let written = run(&args).and_then(|text| match out {
+        Some(path) => fs::write(path, text).map_err(|e| Error::Storage(e.to_string())),
+        None => io::stdout()
+            .write_all(text.as_bytes())
+            .map_err(|e| Error::Storage(e.to_string())),
+    });
+    if let Err(e) = written {
+        eprintln!("minesweeper-cli: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            Args::parse(&args("board.txt --format svg")),
+            Ok(Args {
+                options: vec![(String::from("format"), String::from("svg"))],
+                file: Some(String::from("board.txt")),
+            })
+        );
+        assert!(Args::parse(&args("--seed")).is_err());
+        assert!(Args::parse(&args("a.txt b.txt")).is_err());
+        assert!(run(&args("generate --width 8 --height 8")).is_err());
+    }
+
+    #[test]
+    fn test_generate_and_solve() {
+        let ascii = run(&args("generate --width 8 --height 8 --mines 10 --seed 3")).unwrap();
+        let board = EditorBoard::from_ascii(&ascii).unwrap().to_board().unwrap();
+        assert_eq!(board.mines, 10);
+        assert_eq!(ascii.matches('*').count(), 10);
+
+        let solve_ascii = |ascii| {
+            let board = EditorBoard::from_ascii(ascii).unwrap().to_board().unwrap();
+            solve(&board).unwrap()
+        };
+        // *2100
+        // 2*100
+        // The 1s can't tell which of the left cells hide the mines.
+        let (stuck, rounds) = solve_ascii("*....\n.*...\n");
+        assert_eq!(stuck.state, BoardState::Playing);
+        assert_eq!(rounds, 0);
+        // *100
+        // 1111
+        // 001*
+        let (solved, rounds) = solve_ascii("*...\n....\n...*\n");
+        assert_eq!(solved.state, BoardState::Won);
+        assert_eq!(rounds, 1);
+    }
+}
@@ -0,0 +1,342 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::render::board_text;
+use crate::solver::{exact_probabilities, Constraint};
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardState, Error, MapElement, Result};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct Point3D {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Point3D {
+    pub fn new(x: usize, y: usize, z: usize) -> Point3D {
+        Point3D {
+            x: x as i32,
+            y: y as i32,
+            z: z as i32,
+        }
+    }
+}
+
+// A stack of layers, z = 0 on top. Every cell touches the 26 cells around it
+// in its own layer and the ones above and below, so counts go up to 26.
+//
+// Each layer is kept as a Board sharing the stack's state, so it can be drawn
+// by anything that draws boards. Only the counts and the game logic differ.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Board3D {
+    layers: Vec<Board>,
+    missing_points: usize,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub mines: usize,
+    pub state: BoardState,
+}
+
+pub fn create_board3d(
+    width: usize,
+    height: usize,
+    depth: usize,
+    mines: usize,
+    mut rand: impl FnMut(usize, usize) -> usize,
+) -> Result<Board3D> {
+    if width == 0 || height == 0 || depth == 0 {
+        return Err(Error::InvalidConfig(String::from(
+            "board must have at least one cell",
+        )));
+    }
+    if mines >= width * height * depth {
+        return Err(Error::InvalidConfig(format!(
+            "{} mines do not fit in a {}x{}x{} board",
+            mines, width, height, depth
+        )));
+    }
+    let mut points: Vec<Point3D> = Vec::with_capacity(mines);
+    for _ in 0..mines {
+        loop {
+            let p = Point3D::new(rand(0, width), rand(0, height), rand(0, depth));
+            if !points.contains(&p) {
+                points.push(p);
+                break;
+            }
+        }
+    }
+    let layers = (0..depth)
+        .map(|z| {
+            let map = (0..height)
+                .map(|y| {
+                    (0..width)
+                        .map(|x| {
+                            if points.contains(&Point3D::new(x, y, z)) {
+                                Mine { state: Closed }
+                            } else {
+                                Number {
+                                    state: Closed,
+                                    count: 0,
+                                }
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            Board::new(map)
+        })
+        .collect::<Result<Vec<Board>>>()?;
+    let mut board = Board3D {
+        layers,
+        missing_points: width * height * depth - mines,
+        width,
+        height,
+        depth,
+        mines,
+        state: BoardState::Ready,
+    };
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                let p = Point3D::new(x, y, z);
+                let mines = board.mines_around(&p);
+                if let Some(Number { count, .. }) = board.at_mut(&p) {
+                    *count = mines;
+                }
+            }
+        }
+    }
+    Ok(board)
+}
+
+impl Board3D {
+    fn contains(&self, p: &Point3D) -> bool {
+        p.x >= 0
+            && p.y >= 0
+            && p.z >= 0
+            && (p.x as usize) < self.width
+            && (p.y as usize) < self.height
+            && (p.z as usize) < self.depth
+    }
+
+    pub fn at(&self, p: &Point3D) -> Option<&MapElement> {
+        if !self.contains(p) {
+            return None;
+        }
+        Some(&self.layers[p.z as usize].map[p.y as usize][p.x as usize])
+    }
+
+    fn at_mut(&mut self, p: &Point3D) -> Option<&mut MapElement> {
+        if !self.contains(p) {
+            return None;
+        }
+        Some(&mut self.layers[p.z as usize].map[p.y as usize][p.x as usize])
+    }
+
+    // The in-bounds neighbors of a point, layer by layer and then row by row.
+    pub fn neighbors(&self, p: &Point3D) -> Vec<Point3D> {
+        let mut neighbors = Vec::with_capacity(26);
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let n = Point3D {
+                        x: p.x + dx,
+                        y: p.y + dy,
+                        z: p.z + dz,
+                    };
+                    if (dx, dy, dz) != (0, 0, 0) && self.contains(&n) {
+                        neighbors.push(n);
+                    }
+                }
+            }
+        }
+        neighbors
+    }
+
+    fn mines_around(&self, p: &Point3D) -> i32 {
+        self.neighbors(p)
+            .iter()
+            .filter(|n| matches!(self.at(n), Some(Mine { .. })))
+            .count() as i32
+    }
+
+    // One layer as a 2D board in the stack's state, for the renderers and
+    // views. Its counts include the layers above and below.
+    pub fn layer(&self, z: usize) -> Option<Board> {
+        let layer = self.layers.get(z)?;
+        Some(Board {
+            state: self.state,
+            ..layer.clone()
+        })
+    }
+
+    // Every layer drawn as for render::board_text, counts from 10 up shown as
+    // letters, with a blank line between layers.
+    pub fn to_text(&self) -> String {
+        (0..self.depth)
+            .filter_map(|z| self.layer(z))
+            .map(|layer| board_text(&layer))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn checked_at(&self, p: &Point3D) -> Result<&MapElement> {
+        if matches!(self.state, BoardState::Won | BoardState::Failed) {
+            return Err(Error::GameOver);
+        }
+        self.at(p).ok_or(Error::OutOfBounds { x: p.x, y: p.y })
+    }
+
+    pub fn flag_item(&self, p: &Point3D) -> Result<Board3D> {
+        let mut board = self.clone();
+        match board.checked_at(p)? {
+            Number { state: Open, .. } | Mine { state: Open } => {
+                return Err(Error::CellAlreadyOpen { x: p.x, y: p.y })
+            }
+            _ => (),
+        }
+        if let Some(Mine { state } | Number { state, .. }) = board.at_mut(p) {
+            *state = if *state == Flagged { Closed } else { Flagged };
+        }
+        Ok(board)
+    }
+
+    // Like Board::cascade_open_item: zeros open their neighbors, here in all
+    // three dimensions.
+    pub fn cascade_open_item(&self, p: &Point3D) -> Result<Board3D> {
+        match self.checked_at(p)? {
+            Number { state: Open, .. } => return Err(Error::CellAlreadyOpen { x: p.x, y: p.y }),
+            Mine { state: Flagged, .. } | Number { state: Flagged, .. } => {
+                return Err(Error::CellFlagged { x: p.x, y: p.y })
+            }
+            _ => (),
+        }
+        let mut board = self.clone();
+        if let Some(Mine { .. }) = board.at(p) {
+            *board.at_mut(p).unwrap() = Mine { state: Open };
+            board.state = BoardState::Failed;
+            return Ok(board);
+        }
+        let mut stack = vec![*p];
+        while let Some(p) = stack.pop() {
+            let zero = match board.at_mut(&p) {
+                Some(Number { state, count }) if *state == Closed => {
+                    *state = Open;
+                    *count == 0
+                }
+                _ => continue,
+            };
+            board.missing_points -= 1;
+            if zero {
+                stack.extend(board.neighbors(&p));
+            }
+        }
+        board.state = if board.missing_points == 0 {
+            BoardState::Won
+        } else {
+            BoardState::Playing
+        };
+        Ok(board)
+    }
+
+    // Mine probability of every closed cell, indexed [z][y][x]. None when
+    // the frontier is too large to solve exactly.
+    pub fn probabilities(&self) -> Option<Vec<Vec<Vec<Option<f64>>>>> {
+        let (width, height) = (self.width, self.height);
+        let index = |p: &Point3D| (p.z as usize * height + p.y as usize) * width + p.x as usize;
+        let points: Vec<Point3D> = (0..self.depth)
+            .flat_map(|z| {
+                (0..height).flat_map(move |y| (0..width).map(move |x| Point3D::new(x, y, z)))
+            })
+            .collect();
+        let unknown: Vec<bool> = points
+            .iter()
+            .map(|p| !matches!(self.at(p), Some(Number { state: Open, .. })))
+            .collect();
+        let constraints: Vec<Constraint> = points
+            .iter()
+            .filter_map(|p| match self.at(p) {
+                Some(Number { state: Open, count }) => {
+                    let cells: Vec<usize> = self
+                        .neighbors(p)
+                        .iter()
+                        .map(index)
+                        .filter(|&i| unknown[i])
+                        .collect();
+                    if cells.is_empty() {
+                        None
+                    } else {
+                        Some(Constraint {
+                            cells,
+                            mines: *count as usize,
+                        })
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+        let cells = exact_probabilities(&unknown, &constraints, self.mines)?;
+        Some(
+            cells
+                .chunks(width * height)
+                .map(|layer| layer.chunks(width).map(|row| row.to_vec()).collect())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::seeded_rand;
+    use pretty_assertions::assert_eq;
+
+    // A 2x2x2 cube with one mine in the far corner: every other cell
+    // touches it.
+    fn cube() -> Board3D {
+        let mut rand = vec![1, 1, 1].into_iter();
+        create_board3d(2, 2, 2, 1, |_, _| rand.next().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_counts_span_layers() {
+        let board = cube();
+        assert_eq!(board.neighbors(&Point3D::new(0, 0, 0)).len(), 7);
+        assert_eq!(board.to_text(), "..\n..\n\n..\n..\n");
+        let board = board.cascade_open_item(&Point3D::new(0, 0, 0)).unwrap();
+        assert_eq!(board.to_text(), "1.\n..\n\n..\n..\n");
+        let board = board.flag_item(&Point3D::new(1, 1, 1)).unwrap();
+        assert_eq!(board.to_text(), "1.\n..\n\n..\n.F\n");
+    }
+
+    #[test]
+    fn test_cascade_and_win() {
+        let board = create_board3d(4, 4, 3, 0, seeded_rand(0)).unwrap();
+        let board = board.cascade_open_item(&Point3D::new(0, 0, 2)).unwrap();
+        assert_eq!(board.state, BoardState::Won);
+
+        let board = cube().cascade_open_item(&Point3D::new(1, 1, 1)).unwrap();
+        assert_eq!(board.state, BoardState::Failed);
+        // The rest of the cube is revealed.
+        assert_eq!(board.to_text(), "11\n11\n\n11\n1X\n");
+    }
+
+    #[test]
+    fn test_probabilities() {
+        let board = cube().cascade_open_item(&Point3D::new(0, 0, 0)).unwrap();
+        let probabilities = board.probabilities().unwrap();
+        assert_eq!(probabilities[0][0][0], None);
+        for p in probabilities.iter().flatten().flatten().flatten() {
+            assert!((p - 1.0 / 7.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Open;
+use crate::{Board, Error, MapElement, Result};
+
+// Chunks are this many cells on a side, but for those on the right and
+// bottom edges, cut short by the board.
+pub const CHUNK_SIZE: usize = 32;
+
+// A square of the board's cells, the chunk at (x, y) starting at cell
+// (x * CHUNK_SIZE, y * CHUNK_SIZE).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    pub x: i32,
+    pub y: i32,
+    pub rows: Vec<Vec<MapElement>>,
+}
+
+// Where chunks are kept between sessions, so a huge board can be saved a
+// few chunks at a time.
+pub trait ChunkStore {
+    // None for chunks never saved.
+    fn load(&self, x: i32, y: i32) -> Result<Option<Chunk>>;
+    fn save(&mut self, chunk: &Chunk) -> Result<()>;
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MemoryChunkStore {
+    chunks: BTreeMap<(i32, i32), Chunk>,
+}
+
+impl MemoryChunkStore {
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+impl ChunkStore for MemoryChunkStore {
+    fn load(&self, x: i32, y: i32) -> Result<Option<Chunk>> {
+        Ok(self.chunks.get(&(x, y)).cloned())
+    }
+
+    fn save(&mut self, chunk: &Chunk) -> Result<()> {
+        self.chunks.insert((chunk.x, chunk.y), chunk.clone());
+        Ok(())
+    }
+}
+
+// One JSON file per chunk in a directory.
+#[cfg(feature = "storage")]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileChunkStore {
+    root: std::path::PathBuf,
+}
+
+#[cfg(feature = "storage")]
+impl FileChunkStore {
+    pub fn open(root: impl Into<std::path::PathBuf>) -> Result<FileChunkStore> {
+        let root = root.into();
+        std::fs::create_dir_all(&root).map_err(|e| crate::storage::io_error(&root, e))?;
+        Ok(FileChunkStore { root })
+    }
+
+    fn path(&self, x: i32, y: i32) -> std::path::PathBuf {
+        self.root.join(format!("chunk_{}_{}.json", x, y))
+    }
+}
+
+#[cfg(feature = "storage")]
+impl ChunkStore for FileChunkStore {
+    fn load(&self, x: i32, y: i32) -> Result<Option<Chunk>> {
+        let path = self.path(x, y);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(crate::storage::io_error(&path, e)),
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| Error::CorruptSave {
+                name: path.display().to_string(),
+                reason: e.to_string(),
+            })
+    }
+
+    fn save(&mut self, chunk: &Chunk) -> Result<()> {
+        let json = serde_json::to_vec(chunk).map_err(|e| Error::Storage(e.to_string()))?;
+        crate::storage::write_atomic(&self.path(chunk.x, chunk.y), &json)
+    }
+}
+
+impl Board {
+    pub(crate) fn chunk_bounds(
+        &self,
+        x: usize,
+        y: usize,
+    ) -> (core::ops::Range<usize>, core::ops::Range<usize>) {
+        let (x0, y0) = (x * CHUNK_SIZE, y * CHUNK_SIZE);
+        (
+            x0..(x0 + CHUNK_SIZE).min(self.width),
+            y0..(y0 + CHUNK_SIZE).min(self.height),
+        )
+    }
+
+    pub(crate) fn chunk_grid(&self) -> impl Iterator<Item = (usize, usize)> {
+        let across = self.width.div_ceil(CHUNK_SIZE);
+        (0..self.height.div_ceil(CHUNK_SIZE)).flat_map(move |y| (0..across).map(move |x| (x, y)))
+    }
+
+    // Whether the chunk at (x, y) has a cell that isn't the same on `before`,
+    // a board of the same size.
+    pub(crate) fn chunk_differs(&self, before: &Board, x: usize, y: usize) -> bool {
+        let (xs, ys) = self.chunk_bounds(x, y);
+        ys.into_iter()
+            .any(|row| before.map[row][xs.clone()] != self.map[row][xs.clone()])
+    }
+
+    // Writes the chunks whose cells differ from `before`'s, or all of them
+    // without it, and returns how many that was. `before` is the board as it
+    // was last saved.
+    pub fn save_chunks(&self, before: Option<&Board>, store: &mut dyn ChunkStore) -> Result<usize> {
+        if let Some(before) = before {
+            if (before.width, before.height) != (self.width, self.height) {
+                return Err(Error::InvalidConfig(format!(
+                    "a {}x{} board can't be compared with a {}x{} one",
+                    self.width, self.height, before.width, before.height
+                )));
+            }
+        }
+        let mut written = 0;
+        for (x, y) in self.chunk_grid() {
+            let (xs, ys) = self.chunk_bounds(x, y);
+            if before.map_or(true, |before| self.chunk_differs(before, x, y)) {
+                store.save(&Chunk {
+                    x: x as i32,
+                    y: y as i32,
+                    rows: ys.map(|row| self.map[row][xs.clone()].to_vec()).collect(),
+                })?;
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    // The board with every chunk the store has put back in place. Size,
+    // state, seed and treasures are kept with the rest of the game and come
+    // from this board; the mine count is worked out again.
+    pub fn load_chunks(&self, store: &dyn ChunkStore) -> Result<Board> {
+        let mut map = self.map.clone();
+        for (x, y) in self.chunk_grid() {
+            let chunk = match store.load(x as i32, y as i32)? {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            let (xs, ys) = self.chunk_bounds(x, y);
+            if chunk.rows.len() != ys.len() || chunk.rows.iter().any(|row| row.len() != xs.len()) {
+                return Err(Error::ParseError(format!(
+                    "chunk ({}, {}) doesn't fit a {}x{} board",
+                    x, y, self.width, self.height
+                )));
+            }
+            for (row, cells) in ys.zip(chunk.rows) {
+                map[row][xs.clone()].copy_from_slice(&cells);
+            }
+        }
+        let cells = map.iter().flatten();
+        let mines = cells.clone().filter(|el| matches!(el, Mine { .. })).count();
+        let missing_points = cells
+            .filter(|el| matches!(el, Number { state, .. } if *state != Open))
+            .count();
+        Ok(Board {
+            map,
+            mines,
+            missing_points: missing_points as i32,
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_seeded_board, numbers_on_board, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_only_touched_chunks_are_saved() {
+        let board = numbers_on_board(create_seeded_board(70, 40, 300, 2).unwrap());
+        let mut store = MemoryChunkStore::default();
+        // 3 chunks across, 2 down.
+        assert_eq!(board.save_chunks(None, &mut store), Ok(6));
+        let corner = (64..70)
+            .flat_map(|x| (32..40).map(move |y| Point::new(x, y)))
+            .find(|p| matches!(board.at(p), Some(Number { count, .. }) if *count > 0))
+            .unwrap();
+        let opened = board.cascade_open_item(&corner).unwrap();
+        assert_eq!(opened.save_chunks(Some(&board), &mut store), Ok(1));
+        assert_eq!(store.len(), 6);
+        // The state is saved with the rest of the game.
+        let header = Board {
+            state: opened.state,
+            ..board.clone()
+        };
+        assert_eq!(header.load_chunks(&store), Ok(opened.clone()));
+
+        let mut partial = MemoryChunkStore::default();
+        partial.save(&store.load(2, 1).unwrap().unwrap()).unwrap();
+        assert_eq!(header.load_chunks(&partial), Ok(opened));
+        let wrong_size = numbers_on_board(create_seeded_board(10, 10, 5, 2).unwrap());
+        assert!(wrong_size.load_chunks(&store).is_err());
+    }
+
+    #[cfg(feature = "storage")]
+    #[test]
+    fn test_file_chunk_store() {
+        let root =
+            std::env::temp_dir().join(format!("lib_minesweeper-chunks-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut store = FileChunkStore::open(&root).unwrap();
+        let board = numbers_on_board(create_seeded_board(40, 10, 20, 5).unwrap());
+        assert_eq!(board.save_chunks(None, &mut store), Ok(2));
+        assert_eq!(store.load(5, 5), Ok(None));
+        let store = FileChunkStore::open(&root).unwrap();
+        assert_eq!(board.load_chunks(&store), Ok(board));
+    }
+}
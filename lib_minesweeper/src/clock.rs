@@ -0,0 +1,127 @@
+use core::cell::Cell;
+use core::time::Duration;
+use serde_derive::{Deserialize, Serialize};
+
+// A monotonic source of time. Readings are measured from an arbitrary origin
+// fixed for the clock's lifetime, so only differences between them matter.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct SystemClock {
+    origin: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            origin: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+// A clock that only moves when told to, for deterministic tests.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Cell<Duration>,
+}
+
+impl MockClock {
+    pub fn new() -> MockClock {
+        MockClock::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+// A stopwatch that only counts while running. Readings of the clock are only
+// kept while running, so pause a timer before saving it and resume it against
+// the new process's clock after loading.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct Timer {
+    // Time counted in the stretches that already ended.
+    banked: Duration,
+    // Clock reading when the current stretch started.
+    running_since: Option<Duration>,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    pub fn resume(&mut self, clock: &impl Clock) {
+        if self.running_since.is_none() {
+            self.running_since = Some(clock.now());
+        }
+    }
+
+    pub fn pause(&mut self, clock: &impl Clock) {
+        self.banked = self.elapsed(clock);
+        self.running_since = None;
+    }
+
+    pub fn elapsed(&self, clock: &impl Clock) -> Duration {
+        match self.running_since {
+            Some(since) => self.banked + clock.now().saturating_sub(since),
+            None => self.banked,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_timer_pause_resume() {
+        let clock = MockClock::new();
+        let mut timer = Timer::new();
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(timer.elapsed(&clock), Duration::ZERO);
+
+        timer.resume(&clock);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(timer.elapsed(&clock), Duration::from_secs(3));
+
+        timer.pause(&clock);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(timer.elapsed(&clock), Duration::from_secs(3));
+        assert!(!timer.is_running());
+
+        timer.resume(&clock);
+        timer.resume(&clock);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(timer.elapsed(&clock), Duration::from_millis(3500));
+    }
+}
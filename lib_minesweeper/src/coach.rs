@@ -0,0 +1,303 @@
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::analysis::{annotate, MoveKind};
+use crate::solver::deductions;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardState, Move, Point};
+
+// Which kinds of advice a coached game gives, so frontends can offer each as
+// its own toggle.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct CoachOptions {
+    // Point out certainly safe cells left on the board after each move.
+    pub missed_safe_cells: bool,
+    // Call out guesses taken while a safe cell was there to open.
+    pub unnecessary_guesses: bool,
+    // Call out flags placed before the mine under them was certain.
+    pub flag_order: bool,
+    // Warn when the flags stop adding up, see ConsistencyWarning. None in
+    // saves from before it existed, and off.
+    pub consistency: Option<bool>,
+}
+
+impl CoachOptions {
+    pub fn all() -> CoachOptions {
+        CoachOptions {
+            missed_safe_cells: true,
+            unnecessary_guesses: true,
+            flag_order: true,
+            consistency: Some(true),
+        }
+    }
+}
+
+// Ways the player's flags can disagree with the numbers they flagged
+// around. Only what's on screen is used, never where the mines really are.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum ConsistencyWarning {
+    TooManyFlags {
+        flags: usize,
+        mines: usize,
+    },
+    // A number with more flags around it than it counts.
+    OverFlagged {
+        point: Point,
+        count: i32,
+        flags: usize,
+    },
+    // Taking the flags as right, the numbers they complete make their other
+    // closed neighbors safe, and this number is left with too few cells for
+    // its mines: `available` closed cells for `count - flags` of them.
+    Starved {
+        point: Point,
+        count: i32,
+        flags: usize,
+        available: usize,
+    },
+}
+
+// Empty once the game is over.
+pub fn consistency_warnings(board: &Board) -> Vec<ConsistencyWarning> {
+    let mut warnings = Vec::new();
+    if !matches!(board.state, BoardState::Ready | BoardState::Playing) {
+        return warnings;
+    }
+    let flagged = |p: &Point| {
+        matches!(
+            board.at(p),
+            Some(Mine { state: Flagged }) | Some(Number { state: Flagged, .. })
+        )
+    };
+    let closed = |p: &Point| {
+        matches!(
+            board.at(p),
+            Some(Mine { state: Closed }) | Some(Number { state: Closed, .. })
+        )
+    };
+    let points: Vec<Point> = (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+        .collect();
+    let flags = points.iter().filter(|p| flagged(p)).count();
+    if flags > board.mines {
+        warnings.push(ConsistencyWarning::TooManyFlags {
+            flags,
+            mines: board.mines,
+        });
+    }
+    let open_count = |p: &Point| match board.at(p) {
+        Some(Number { state: Open, count }) => Some(*count),
+        _ => None,
+    };
+    let flags_around = |p: &Point| board.neighbors(p).filter(|n| flagged(n)).count();
+    // Closed cells next to a number its flags already complete.
+    let ruled_out = |p: &Point| {
+        board
+            .neighbors(p)
+            .any(|n| open_count(&n).is_some_and(|count| count as usize == flags_around(&n)))
+    };
+    for p in &points {
+        let count = match open_count(p) {
+            Some(count) => count,
+            None => continue,
+        };
+        let flags = flags_around(p);
+        if flags > count as usize {
+            warnings.push(ConsistencyWarning::OverFlagged {
+                point: *p,
+                count,
+                flags,
+            });
+            continue;
+        }
+        let available = board
+            .neighbors(p)
+            .filter(|n| closed(n) && !ruled_out(n))
+            .count();
+        if flags + available < count as usize {
+            warnings.push(ConsistencyWarning::Starved {
+                point: *p,
+                count,
+                flags,
+                available,
+            });
+        }
+    }
+    warnings
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Advice {
+    SafeCellsAvailable {
+        points: Vec<Point>,
+    },
+    UnnecessaryGuess {
+        point: Point,
+        mine_probability: f64,
+        safe: Vec<Point>,
+    },
+    OpenedKnownMine {
+        point: Point,
+    },
+    // Flagged before the numbers proved a mine there; `safe` lists cells that
+    // could have been opened first to find out.
+    PrematureFlag {
+        point: Point,
+        safe: Vec<Point>,
+    },
+    FlaggedSafeCell {
+        point: Point,
+    },
+    // Only the warnings the move brought about.
+    Inconsistent(ConsistencyWarning),
+}
+
+// Advice on a move, from the board the player saw before it and the board it
+// left. Nothing here changes the game.
+pub fn advise(options: &CoachOptions, before: &Board, m: &Move, after: &Board) -> Vec<Advice> {
+    let mut advice = Vec::new();
+    if options.unnecessary_guesses || options.flag_order {
+        let annotated = annotate(before, 0, m);
+        match (m, annotated.kind) {
+            (Move::Open(p), MoveKind::Guess) if options.unnecessary_guesses => {
+                advice.push(Advice::UnnecessaryGuess {
+                    point: *p,
+                    mine_probability: annotated.mine_probability.unwrap_or(0.0),
+                    safe: annotated.missed,
+                })
+            }
+            (Move::Open(p), MoveKind::Blunder) if options.unnecessary_guesses => {
+                advice.push(Advice::OpenedKnownMine { point: *p })
+            }
+            (Move::Flag(p), MoveKind::Guess) if options.flag_order => {
+                advice.push(Advice::PrematureFlag {
+                    point: *p,
+                    safe: annotated.missed,
+                })
+            }
+            (Move::Flag(p), MoveKind::Blunder) if options.flag_order => {
+                advice.push(Advice::FlaggedSafeCell { point: *p })
+            }
+            _ => (),
+        }
+    }
+    if options.consistency == Some(true) {
+        let earlier = consistency_warnings(before);
+        advice.extend(
+            consistency_warnings(after)
+                .into_iter()
+                .filter(|w| !earlier.contains(w))
+                .map(Advice::Inconsistent),
+        );
+    }
+    if options.missed_safe_cells && after.state == BoardState::Playing {
+        let points: Vec<Point> = deductions(after)
+            .into_iter()
+            .filter(|d| !d.mine)
+            .map(|d| d.point)
+            .collect();
+        if !points.is_empty() {
+            advice.push(Advice::SafeCellsAvailable { points });
+        }
+    }
+    advice
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use alloc::vec;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_advise() {
+        // The 0 makes D1 safe, so opening E1 was a needless guess. Its 1 then
+        // shows F1 is a mine, and with both mines known G1 is safe too.
+        let before = Scenario::from_ascii("*1 ..*.\n").into_board();
+        let m = Move::Open(Point::new(4, 0));
+        let after = before.apply_move(&m).unwrap();
+        let advice = advise(&CoachOptions::all(), &before, &m, &after);
+        match &advice[0] {
+            Advice::UnnecessaryGuess {
+                point,
+                mine_probability,
+                safe,
+            } => {
+                assert_eq!(point, &Point::new(4, 0));
+                assert!((mine_probability - 1.0 / 3.0).abs() < 1e-9);
+                assert_eq!(safe, &vec![Point::new(3, 0)]);
+            }
+            other => panic!("unexpected advice {:?}", other),
+        }
+        assert_eq!(
+            &advice[1..],
+            &[Advice::SafeCellsAvailable {
+                points: vec![Point::new(3, 0), Point::new(6, 0)],
+            }]
+        );
+        assert_eq!(
+            advise(&CoachOptions::default(), &before, &m, &after),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_premature_flag() {
+        let before = Scenario::from_ascii("*1 ..*.\n").into_board();
+        let m = Move::Flag(Point::new(5, 0));
+        let after = before.apply_move(&m).unwrap();
+        let options = CoachOptions {
+            flag_order: true,
+            ..CoachOptions::default()
+        };
+        assert_eq!(
+            advise(&options, &before, &m, &after),
+            vec![Advice::PrematureFlag {
+                point: Point::new(5, 0),
+                safe: vec![Point::new(3, 0)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_consistency_warnings() {
+        // The flag on A1 completes B1's 1, which leaves D1's 1 nowhere to go.
+        let board = Scenario::from_ascii(".1*1 .\n").into_board();
+        let m = Move::Flag(Point::new(0, 0));
+        let flagged = board.apply_move(&m).unwrap();
+        let starved = ConsistencyWarning::Starved {
+            point: Point::new(3, 0),
+            count: 1,
+            flags: 0,
+            available: 0,
+        };
+        assert_eq!(consistency_warnings(&board), vec![]);
+        assert_eq!(consistency_warnings(&flagged), vec![starved.clone()]);
+        let options = CoachOptions {
+            consistency: Some(true),
+            ..CoachOptions::default()
+        };
+        assert_eq!(
+            advise(&options, &board, &m, &flagged),
+            vec![Advice::Inconsistent(starved)]
+        );
+
+        let both = flagged.flag_item(&Point::new(2, 0)).unwrap();
+        assert_eq!(
+            consistency_warnings(&both),
+            vec![
+                ConsistencyWarning::TooManyFlags { flags: 2, mines: 1 },
+                ConsistencyWarning::OverFlagged {
+                    point: Point::new(1, 0),
+                    count: 1,
+                    flags: 2,
+                },
+            ]
+        );
+    }
+}
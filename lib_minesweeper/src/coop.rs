@@ -0,0 +1,205 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::{Event, Game};
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::{Error, Move, Point, Result};
+
+// What happened in a co-op game, on top of the game's own events.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum CoopEvent {
+    Game(Event),
+    // A flag waiting for the other player to agree.
+    Proposed { point: Point },
+    // Taken back by the player who proposed it, or dropped when its cell
+    // was opened.
+    Withdrawn { point: Point },
+}
+
+// An event and the player whose action caused it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PlayerEvent {
+    pub player: usize,
+    pub event: CoopEvent,
+}
+
+// Two players on one game, for pair play. Either can open any cell, but a
+// flag only goes on the board once both agree on it: the first player to
+// flag a cell proposes it, the other confirms it by flagging it too.
+// Proposed flags guard their cell from opens like real ones.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct CoopGame {
+    game: Game,
+    // Proposed flags and who proposed them.
+    proposed: Vec<(Point, usize)>,
+    events: Vec<PlayerEvent>,
+}
+
+impl CoopGame {
+    pub fn new(game: Game) -> CoopGame {
+        CoopGame {
+            game,
+            proposed: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn proposed_flags(&self) -> &[(Point, usize)] {
+        &self.proposed
+    }
+
+    // Every event so far, oldest first.
+    pub fn events(&self) -> &[PlayerEvent] {
+        &self.events
+    }
+
+    fn check_player(player: usize) -> Result<()> {
+        if player > 1 {
+            return Err(Error::InvalidConfig(format!(
+                "player {} is not one of the two",
+                player
+            )));
+        }
+        Ok(())
+    }
+
+    fn proposal(&self, p: &Point) -> Option<usize> {
+        self.proposed
+            .iter()
+            .find(|(q, _)| q == p)
+            .map(|(_, player)| *player)
+    }
+
+    fn record(&mut self, player: usize, events: Vec<CoopEvent>) -> Vec<PlayerEvent> {
+        let events: Vec<PlayerEvent> = events
+            .into_iter()
+            .map(|event| PlayerEvent { player, event })
+            .collect();
+        self.events.extend(events.iter().cloned());
+        events
+    }
+
+    pub fn open(&mut self, player: usize, p: &Point) -> Result<Vec<PlayerEvent>> {
+        CoopGame::check_player(player)?;
+        if self.proposal(p).is_some() {
+            return Err(Error::CellFlagged { x: p.x, y: p.y });
+        }
+        let mut events: Vec<CoopEvent> = self
+            .game
+            .apply(&Move::Open(*p))?
+            .into_iter()
+            .map(CoopEvent::Game)
+            .collect();
+        // Proposals on cells the open cascaded over are moot.
+        let board = self.game.board();
+        let (kept, opened): (Vec<_>, Vec<_>) = self.proposed.drain(..).partition(|(q, _)| {
+            matches!(
+                board.at(q),
+                Some(Mine { .. } | Number { state: Closed, .. })
+            )
+        });
+        self.proposed = kept;
+        events.extend(
+            opened
+                .into_iter()
+                .map(|(point, _)| CoopEvent::Withdrawn { point }),
+        );
+        Ok(self.record(player, events))
+    }
+
+    // Proposes a flag, confirms the other player's proposal or takes back
+    // one's own. Flags already on the board come off at once.
+    pub fn flag(&mut self, player: usize, p: &Point) -> Result<Vec<PlayerEvent>> {
+        CoopGame::check_player(player)?;
+        let events = match self.proposal(p) {
+            Some(by) if by == player => {
+                self.proposed.retain(|(q, _)| q != p);
+                vec![CoopEvent::Withdrawn { point: *p }]
+            }
+            Some(_) => {
+                let events = self.game.apply(&Move::Flag(*p))?;
+                self.proposed.retain(|(q, _)| q != p);
+                events.into_iter().map(CoopEvent::Game).collect()
+            }
+            None => match self.game.board().at(p) {
+                Some(Mine { state: Closed } | Number { state: Closed, .. })
+                    if self.game.is_legal(&Move::Flag(*p)) =>
+                {
+                    self.proposed.push((*p, player));
+                    vec![CoopEvent::Proposed { point: *p }]
+                }
+                _ => self
+                    .game
+                    .apply(&Move::Flag(*p))?
+                    .into_iter()
+                    .map(CoopEvent::Game)
+                    .collect(),
+            },
+        };
+        Ok(self.record(player, events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameOptions, Source};
+    use crate::scenario::Scenario;
+    use crate::MapElementCellState::Flagged;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_coop_flags_need_both_players() {
+        // *2100
+        // 2*100
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut coop = CoopGame::new(Game::new(board, GameOptions::default()));
+        let mine = Point::new(0, 0);
+        assert_eq!(
+            coop.flag(0, &mine).unwrap(),
+            vec![PlayerEvent {
+                player: 0,
+                event: CoopEvent::Proposed { point: mine }
+            }]
+        );
+        assert_eq!(coop.game().board().at(&mine), Some(&Mine { state: Closed }));
+        assert_eq!(coop.open(1, &mine), Err(Error::CellFlagged { x: 0, y: 0 }));
+        let events = coop.flag(1, &mine).unwrap();
+        assert_eq!(
+            events[0],
+            PlayerEvent {
+                player: 1,
+                event: CoopEvent::Game(Event::Flagged {
+                    point: mine,
+                    source: Source::User
+                })
+            }
+        );
+        assert_eq!(
+            coop.game().board().at(&mine),
+            Some(&Mine { state: Flagged })
+        );
+        assert_eq!(coop.proposed_flags(), &[]);
+
+        // A proposal the open cascades over is dropped.
+        coop.flag(1, &Point::new(3, 1)).unwrap();
+        let events = coop.open(0, &Point::new(4, 0)).unwrap();
+        assert!(events.iter().all(|e| e.player == 0));
+        assert_eq!(
+            events.last().map(|e| &e.event),
+            Some(&CoopEvent::Withdrawn {
+                point: Point::new(3, 1)
+            })
+        );
+        assert!(coop.flag(2, &Point::new(1, 0)).is_err());
+        assert_eq!(coop.events().len(), 3 + events.len());
+    }
+}
@@ -0,0 +1,287 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::game::GameOptions;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardState, Error, MapElement, Point, Result};
+
+// A board being designed by hand. Every edit keeps the numbers up to date, and
+// nothing is checked until the board is exported, so designers can pass
+// through invalid layouts on the way to a valid one.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EditorBoard {
+    board: Board,
+}
+
+impl EditorBoard {
+    pub fn new(width: usize, height: usize) -> Result<EditorBoard> {
+        let map = vec![
+            vec![
+                Number {
+                    state: Closed,
+                    count: 0
+                };
+                width
+            ];
+            height
+        ];
+        Ok(EditorBoard {
+            board: Board::new(map)?,
+        })
+    }
+
+    pub fn from_board(board: &Board) -> EditorBoard {
+        let mut board = board.clone();
+        board.seed = None;
+        EditorBoard { board }
+    }
+
+    // One character per cell: '.' closed, '*' mine, 'F' flagged mine, and
+    // the count (or ' ' for zero) for open cells. The counts are worked out
+    // again, so any digit marks an open cell.
+    pub fn from_ascii(ascii: &str) -> Result<EditorBoard> {
+        let rows: Vec<&str> = ascii
+            .lines()
+            .map(|l| l.trim_end_matches('\r'))
+            .filter(|l| !l.is_empty())
+            .collect();
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        let mut editor = EditorBoard::new(width, rows.len())?;
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(Error::ParseError(format!(
+                    "row {} is {} cells wide, expected {}",
+                    y,
+                    row.chars().count(),
+                    width
+                )));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let el = match c {
+                    '.' => continue,
+                    '*' => Mine { state: Closed },
+                    'F' => Mine { state: Flagged },
+                    ' ' | '0'..='8' => Number {
+                        state: Open,
+                        count: 0,
+                    },
+                    c => {
+                        return Err(Error::ParseError(format!(
+                            "unknown cell {:?} at ({}, {})",
+                            c, x, y
+                        )))
+                    }
+                };
+                editor.board.map[y][x] = el;
+            }
+        }
+        let all: Vec<Point> = (0..editor.board.height)
+            .flat_map(|y| (0..editor.board.width).map(move |x| Point::new(x, y)))
+            .collect();
+        editor.refresh(&all);
+        Ok(editor)
+    }
+
+    pub fn width(&self) -> usize {
+        self.board.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.board.height
+    }
+
+    pub fn mines(&self) -> usize {
+        self.board.mines
+    }
+
+    pub fn at(&self, p: &Point) -> Option<&MapElement> {
+        self.board.at(p)
+    }
+
+    fn cell_mut(&mut self, p: &Point) -> Result<&mut MapElement> {
+        if self.board.at(p).is_none() {
+            return Err(Error::OutOfBounds { x: p.x, y: p.y });
+        }
+        Ok(&mut self.board.map[p.y as usize][p.x as usize])
+    }
+
+    // Recounts mines and closed cells and updates the numbers around the
+    // edited points.
+    fn refresh(&mut self, edited: &[Point]) {
+        let cells = self.board.map.iter().flatten();
+        self.board.mines = cells.clone().filter(|el| matches!(el, Mine { .. })).count();
+        self.board.missing_points = cells
+            .filter(|el| matches!(el, Number { state, .. } if *state != Open))
+            .count() as i32;
+        self.board.recompute_numbers_around(edited);
+    }
+
+    // Turns a safe cell into a mine or back. Flags stay, an open cell is closed
+    // when it becomes a mine.
+    pub fn toggle_mine(&mut self, p: &Point) -> Result<()> {
+        let cell = self.cell_mut(p)?;
+        *cell = match cell {
+            Mine { state } => Number {
+                state: *state,
+                count: 0,
+            },
+            Number { state: Flagged, .. } => Mine { state: Flagged },
+            Number { .. } => Mine { state: Closed },
+        };
+        self.refresh(core::slice::from_ref(p));
+        Ok(())
+    }
+
+    pub fn set_open(&mut self, p: &Point, open: bool) -> Result<()> {
+        match self.cell_mut(p)? {
+            Number { state, .. } => *state = if open { Open } else { Closed },
+            Mine { .. } if open => {
+                return Err(Error::InvalidConfig(format!(
+                    "({}, {}) is a mine and can't be open",
+                    p.x, p.y
+                )))
+            }
+            Mine { .. } => (),
+        }
+        self.refresh(&[]);
+        Ok(())
+    }
+
+    pub fn place_flag(&mut self, p: &Point, flagged: bool) -> Result<()> {
+        match self.cell_mut(p)? {
+            Number { state: Open, .. } => return Err(Error::CellAlreadyOpen { x: p.x, y: p.y }),
+            Mine { state } | Number { state, .. } => {
+                *state = if flagged { Flagged } else { Closed }
+            }
+        }
+        self.refresh(&[]);
+        Ok(())
+    }
+
+    // Everything that would make the board unplayable as a puzzle.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.board.mines == 0 {
+            problems.push(String::from("the board has no mines"));
+        }
+        if self.board.missing_points == 0 {
+            problems.push(String::from("every safe cell is already open"));
+        }
+        for y in 0..self.board.height {
+            for x in 0..self.board.width {
+                if let Number { state: Flagged, .. } = self.board.map[y][x] {
+                    problems.push(format!("({}, {}) is flagged but isn't a mine", x, y));
+                }
+            }
+        }
+        problems
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self.problems().into_iter().next() {
+            Some(problem) => Err(Error::InvalidConfig(problem)),
+            None => Ok(()),
+        }
+    }
+
+    // A board ready to play, picking up where the design left off: already
+    // open cells count as played.
+    pub fn to_board(&self) -> Result<Board> {
+        self.validate()?;
+        let started = self
+            .board
+            .map
+            .iter()
+            .flatten()
+            .any(|el| matches!(el, Number { state: Open, .. }));
+        Ok(Board {
+            state: if started {
+                BoardState::Playing
+            } else {
+                BoardState::Ready
+            },
+            ..self.board.clone()
+        })
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut out = String::new();
+        for row in &self.board.map {
+            for el in row {
+                out.push(match el {
+                    Mine { state: Flagged } => 'F',
+                    Mine { .. } => '*',
+                    Number {
+                        state: Open,
+                        count: 0,
+                    } => ' ',
+                    Number { state: Open, count } => {
+                        core::char::from_digit(*count as u32, 10).unwrap_or('?')
+                    }
+                    Number { .. } => '.',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn share_code(&self, options: &GameOptions) -> Result<String> {
+        self.to_board()?.share_code(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_edit_and_export() {
+        let mut editor = EditorBoard::new(4, 3).unwrap();
+        assert!(matches!(editor.to_board(), Err(Error::InvalidConfig(_))));
+        editor.toggle_mine(&Point::new(0, 0)).unwrap();
+        editor.toggle_mine(&Point::new(3, 2)).unwrap();
+        editor.set_open(&Point::new(2, 0), true).unwrap();
+        editor.place_flag(&Point::new(0, 0), true).unwrap();
+        assert!(editor.set_open(&Point::new(3, 2), true).is_err());
+        assert_eq!(editor.to_ascii(), "F. .\n....\n...*\n");
+        assert_eq!(editor.mines(), 2);
+
+        let board = editor.to_board().unwrap();
+        assert_eq!(board.state, BoardState::Playing);
+        assert_eq!(
+            board.at(&Point::new(1, 1)),
+            Some(&Number {
+                state: Closed,
+                count: 1
+            })
+        );
+        let opened = board.cascade_open_item(&Point::new(1, 0)).unwrap();
+        assert_eq!(opened.state, BoardState::Playing);
+
+        editor.place_flag(&Point::new(1, 1), true).unwrap();
+        assert_eq!(
+            editor.problems(),
+            vec![String::from("(1, 1) is flagged but isn't a mine")]
+        );
+    }
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let ascii = "*1 .\n111.\n...*\n";
+        let editor = EditorBoard::from_ascii(ascii).unwrap();
+        assert_eq!(editor.to_ascii(), ascii);
+        assert_eq!(editor.mines(), 2);
+
+        let (shared, _) =
+            Board::from_share_code(&editor.share_code(&GameOptions::default()).unwrap()).unwrap();
+        assert_eq!(shared, editor.to_board().unwrap());
+    }
+}
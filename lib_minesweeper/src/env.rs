@@ -0,0 +1,181 @@
+use alloc::vec::Vec;
+
+use crate::random::seeded_rand;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{create_board, numbers_on_board, Board, BoardState, Point, Result};
+
+pub const OBS_CLOSED: i32 = -1;
+pub const OBS_FLAGGED: i32 = -2;
+pub const OBS_MINE: i32 = -3;
+
+// Row-major player view: 0 to 8 for open cells, `OBS_*` for everything else.
+pub type Observation = Vec<i32>;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Action {
+    Open { x: usize, y: usize },
+    Flag { x: usize, y: usize },
+}
+
+impl Action {
+    // Discrete action space of size `2 * width * height`: the first half opens
+    // cells, the second half flags them.
+    pub fn from_index(index: usize, width: usize, height: usize) -> Option<Action> {
+        let cells = width * height;
+        if index >= 2 * cells {
+            return None;
+        }
+        let cell = index % cells;
+        let (x, y) = (cell % width, cell / width);
+        if index < cells {
+            Some(Action::Open { x, y })
+        } else {
+            Some(Action::Flag { x, y })
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rewards {
+    pub win: f64,
+    pub loss: f64,
+    pub per_opened_cell: f64,
+    pub invalid_move: f64,
+}
+
+impl Default for Rewards {
+    fn default() -> Rewards {
+        Rewards {
+            win: 1.0,
+            loss: -1.0,
+            per_opened_cell: 0.01,
+            invalid_move: -0.05,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Env {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub rewards: Rewards,
+    board: Board,
+}
+
+impl Env {
+    pub fn new(width: usize, height: usize, mines: usize) -> Result<Env> {
+        let board = numbers_on_board(create_board(width, height, mines, seeded_rand(0))?);
+        Ok(Env {
+            width,
+            height,
+            mines,
+            rewards: Rewards::default(),
+            board,
+        })
+    }
+
+    pub fn action_count(&self) -> usize {
+        2 * self.width * self.height
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        // The config was validated in `new`, so this can't fail.
+        if let Ok(board) = create_board(self.width, self.height, self.mines, seeded_rand(seed)) {
+            self.board = numbers_on_board(board);
+        }
+        self.observation()
+    }
+
+    pub fn step(&mut self, action: Action) -> (Observation, f64, bool) {
+        let opened_before = self.opened_count();
+        let result = match action {
+            Action::Open { x, y } => self.board.cascade_open_item(&Point::new(x, y)),
+            Action::Flag { x, y } => self.board.flag_item(&Point::new(x, y)),
+        };
+        let reward = match result {
+            Ok(board) => {
+                self.board = board;
+                match self.board.state {
+                    BoardState::Won => self.rewards.win,
+                    BoardState::Failed => self.rewards.loss,
+                    _ => {
+                        (self.opened_count() - opened_before) as f64 * self.rewards.per_opened_cell
+                    }
+                }
+            }
+            Err(_) => self.rewards.invalid_move,
+        };
+        (self.observation(), reward, self.is_done())
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.board.state, BoardState::Won | BoardState::Failed)
+    }
+
+    pub fn observation(&self) -> Observation {
+        let is_done = self.is_done();
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point::new(x, y)))
+            .map(|p| match self.board.at(&p) {
+                Some(Mine { .. }) if is_done => OBS_MINE,
+                Some(Number { state: Open, count }) => *count,
+                Some(Number { state: Flagged, .. }) | Some(Mine { state: Flagged }) => OBS_FLAGGED,
+                Some(Number { state: Closed, .. }) | Some(Mine { .. }) | None => OBS_CLOSED,
+            })
+            .collect()
+    }
+
+    fn opened_count(&self) -> usize {
+        self.observation().iter().filter(|&&c| c >= 0).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_action_from_index() {
+        assert_eq!(
+            Action::from_index(7, 5, 2),
+            Some(Action::Open { x: 2, y: 1 })
+        );
+        assert_eq!(
+            Action::from_index(12, 5, 2),
+            Some(Action::Flag { x: 2, y: 0 })
+        );
+        assert_eq!(Action::from_index(20, 5, 2), None);
+    }
+
+    #[test]
+    fn test_reset_is_deterministic() {
+        let mut a = Env::new(8, 8, 10).unwrap();
+        let mut b = Env::new(8, 8, 10).unwrap();
+        a.reset(3);
+        b.reset(3);
+        for index in 0..64 {
+            let action = Action::from_index(index, 8, 8).unwrap();
+            assert_eq!(a.step(action), b.step(action));
+        }
+    }
+
+    #[test]
+    fn test_step_rewards() {
+        let mut env = Env::new(8, 8, 10).unwrap();
+        let obs = env.reset(1);
+        assert_eq!(obs, vec![OBS_CLOSED; 64]);
+        let (obs, reward, done) = env.step(Action::Flag { x: 0, y: 0 });
+        assert_eq!(obs[0], OBS_FLAGGED);
+        assert_eq!(reward, 0.0);
+        assert!(!done);
+        let (_, reward, _) = env.step(Action::Open { x: 0, y: 0 });
+        assert_eq!(reward, env.rewards.invalid_move);
+    }
+}
@@ -0,0 +1,30 @@
+use alloc::string::String;
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum Error {
+    #[error("point ({x}, {y}) is outside of the board")]
+    OutOfBounds { x: i32, y: i32 },
+    #[error("cell ({x}, {y}) is already open")]
+    CellAlreadyOpen { x: i32, y: i32 },
+    #[error("cell ({x}, {y}) is flagged")]
+    CellFlagged { x: i32, y: i32 },
+    #[error("the game is already over")]
+    GameOver,
+    #[error("flags are disabled in no-flag mode")]
+    FlagsDisabled,
+    #[error("cell ({x}, {y}) is too far from the open cells")]
+    OutOfReach { x: i32, y: i32 },
+    #[error("moving the mine would change the open number at ({x}, {y})")]
+    InconsistentWithRevealed { x: i32, y: i32 },
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("could not parse input: {0}")]
+    ParseError(String),
+    #[error("storage error: {0}")]
+    Storage(String),
+    #[error("save {name:?} is corrupt: {reason}")]
+    CorruptSave { name: String, reason: String },
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
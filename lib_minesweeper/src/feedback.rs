@@ -0,0 +1,164 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::Event;
+
+// Opening at least this many cells with one move makes a BigCascade.
+pub const BIG_CASCADE_CELLS: usize = 10;
+
+// Abstract sounds for frontends to map to their own audio, derived from the
+// events of one move so every frontend plays the same thing.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum SoundCue {
+    // Placing or removing a flag.
+    Tick,
+    Reveal { cells: usize },
+    BigCascade { cells: usize },
+    Explosion,
+    Win,
+}
+
+impl SoundCue {
+    // From 0 to 1, for volume or pitch: cascades get louder with their size,
+    // up to a full expert board's worth.
+    pub fn intensity(&self) -> f64 {
+        match self {
+            SoundCue::Tick => 0.2,
+            SoundCue::Reveal { cells } | SoundCue::BigCascade { cells } => {
+                (0.3 + 0.7 * *cells as f64 / 480.0).min(1.0)
+            }
+            SoundCue::Explosion | SoundCue::Win => 1.0,
+        }
+    }
+}
+
+// The cues for the events Game::apply returned for one move, at most one of
+// each kind: a loss only sounds the explosion, a win follows its reveal.
+pub fn sound_cues(events: &[Event]) -> Vec<SoundCue> {
+    let opened = events
+        .iter()
+        .filter(|e| matches!(e, Event::Opened { .. }))
+        .count();
+    let ticks = events
+        .iter()
+        .any(|e| matches!(e, Event::Flagged { .. } | Event::Unflagged { .. }));
+    if events.iter().any(|e| matches!(e, Event::Exploded { .. })) {
+        return vec![SoundCue::Explosion];
+    }
+    let mut cues = vec![];
+    if ticks {
+        cues.push(SoundCue::Tick);
+    }
+    if opened >= BIG_CASCADE_CELLS {
+        cues.push(SoundCue::BigCascade { cells: opened });
+    } else if opened > 0 {
+        cues.push(SoundCue::Reveal { cells: opened });
+    }
+    if events.contains(&Event::Won) {
+        cues.push(SoundCue::Win);
+    }
+    cues
+}
+
+// Vibration patterns for touch frontends, defined here so every wrapper
+// buzzes the same way for the same move.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Haptic {
+    // Placing or removing a flag.
+    Short,
+    // Opening cells, however many.
+    Medium,
+    // Stepping on a mine.
+    Long,
+}
+
+impl Haptic {
+    pub fn duration_ms(&self) -> u32 {
+        match self {
+            Haptic::Short => 10,
+            Haptic::Medium => 25,
+            Haptic::Long => 400,
+        }
+    }
+}
+
+// The single pulse for the events Game::apply returned for one move, the
+// strongest one if the move did several things. Assisted flags and opens
+// count too, they happen as part of the move.
+pub fn haptic_hint(events: &[Event]) -> Option<Haptic> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Flagged { .. } | Event::Unflagged { .. } => Some(Haptic::Short),
+            Event::Opened { .. } => Some(Haptic::Medium),
+            Event::Exploded { .. } => Some(Haptic::Long),
+            _ => None,
+        })
+        .max_by_key(|h| h.duration_ms())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game, GameOptions};
+    use crate::scenario::Scenario;
+    use crate::{Move, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_sound_cues() {
+        // *2100
+        // 2*100
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(board.clone(), GameOptions::default());
+        let cues = |game: &mut Game, m| sound_cues(&game.apply(&m).unwrap());
+        assert_eq!(
+            cues(&mut game, Move::Open(Point::new(4, 0))),
+            vec![SoundCue::Reveal { cells: 6 }]
+        );
+        assert_eq!(
+            cues(&mut game, Move::Flag(Point::new(0, 0))),
+            vec![SoundCue::Tick]
+        );
+        assert_eq!(
+            cues(&mut game, Move::Open(Point::new(1, 0))),
+            vec![SoundCue::Reveal { cells: 1 }]
+        );
+        assert_eq!(
+            cues(&mut game, Move::Open(Point::new(0, 1))),
+            vec![SoundCue::Reveal { cells: 1 }, SoundCue::Win]
+        );
+
+        let mut game = Game::new(board, GameOptions::default());
+        assert_eq!(
+            cues(&mut game, Move::Open(Point::new(1, 1))),
+            vec![SoundCue::Explosion]
+        );
+        assert!(SoundCue::BigCascade { cells: 480 }.intensity() > 0.99);
+    }
+
+    #[test]
+    fn test_haptic_hint() {
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(board.clone(), GameOptions::default());
+        let hint = |game: &mut Game, m| haptic_hint(&game.apply(&m).unwrap());
+        assert_eq!(
+            hint(&mut game, Move::Open(Point::new(4, 0))),
+            Some(Haptic::Medium)
+        );
+        assert_eq!(
+            hint(&mut game, Move::Flag(Point::new(0, 0))),
+            Some(Haptic::Short)
+        );
+        assert_eq!(
+            hint(&mut game, Move::Flag(Point::new(0, 0))),
+            Some(Haptic::Short)
+        );
+        assert_eq!(
+            hint(&mut game, Move::Open(Point::new(1, 1))),
+            Some(Haptic::Long)
+        );
+        assert_eq!(haptic_hint(&[Event::Won]), None);
+    }
+}
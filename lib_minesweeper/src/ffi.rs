@@ -0,0 +1,257 @@
+use alloc::boxed::Box;
+use core::ptr;
+
+use crate::feedback::{haptic_hint, Haptic};
+use crate::game::move_events;
+use crate::view::CellView;
+use crate::{create_seeded_board, numbers_on_board, Board, BoardState, Error, Move, Point};
+
+pub const CELL_CLOSED: i32 = -1;
+pub const CELL_FLAGGED: i32 = -2;
+pub const CELL_MINE: i32 = -3;
+pub const CELL_OUT_OF_BOUNDS: i32 = -4;
+
+pub struct MinesweeperGame {
+    board: Board,
+    last_haptic: MinesweeperHaptic,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MinesweeperResult {
+    Ok = 0,
+    OutOfBounds,
+    CellAlreadyOpen,
+    CellFlagged,
+    GameOver,
+    InvalidConfig,
+    ParseError,
+    NullGame,
+    FlagsDisabled,
+    StorageError,
+    OutOfReach,
+    InconsistentWithRevealed,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MinesweeperState {
+    NotReady = 0,
+    Ready,
+    Playing,
+    Won,
+    Failed,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MinesweeperHaptic {
+    None = 0,
+    Short,
+    Medium,
+    Long,
+}
+
+impl From<Option<Haptic>> for MinesweeperHaptic {
+    fn from(h: Option<Haptic>) -> MinesweeperHaptic {
+        match h {
+            Some(Haptic::Short) => MinesweeperHaptic::Short,
+            Some(Haptic::Medium) => MinesweeperHaptic::Medium,
+            Some(Haptic::Long) => MinesweeperHaptic::Long,
+            None => MinesweeperHaptic::None,
+        }
+    }
+}
+
+impl From<Error> for MinesweeperResult {
+    fn from(e: Error) -> MinesweeperResult {
+        match e {
+            Error::OutOfBounds { .. } => MinesweeperResult::OutOfBounds,
+            Error::CellAlreadyOpen { .. } => MinesweeperResult::CellAlreadyOpen,
+            Error::CellFlagged { .. } => MinesweeperResult::CellFlagged,
+            Error::GameOver => MinesweeperResult::GameOver,
+            Error::FlagsDisabled => MinesweeperResult::FlagsDisabled,
+            Error::OutOfReach { .. } => MinesweeperResult::OutOfReach,
+            Error::InconsistentWithRevealed { .. } => MinesweeperResult::InconsistentWithRevealed,
+            Error::InvalidConfig(_) => MinesweeperResult::InvalidConfig,
+            Error::ParseError(_) => MinesweeperResult::ParseError,
+            Error::Storage(_) | Error::CorruptSave { .. } => MinesweeperResult::StorageError,
+        }
+    }
+}
+
+/// Creates a new game, or returns null if the configuration is invalid. The
+/// same seed always produces the same board.
+#[no_mangle]
+pub extern "C" fn game_new(
+    width: usize,
+    height: usize,
+    mines: usize,
+    seed: u64,
+) -> *mut MinesweeperGame {
+    match create_seeded_board(width, height, mines, seed) {
+        Ok(board) => Box::into_raw(Box::new(MinesweeperGame {
+            board: numbers_on_board(board),
+            last_haptic: MinesweeperHaptic::None,
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+unsafe fn apply(game: *mut MinesweeperGame, m: Move) -> MinesweeperResult {
+    let game = match game.as_mut() {
+        Some(game) => game,
+        None => return MinesweeperResult::NullGame,
+    };
+    match game.board.apply_move(&m) {
+        Ok(board) => {
+            game.last_haptic = haptic_hint(&move_events(&game.board, &board, &m)).into();
+            game.board = board;
+            MinesweeperResult::Ok
+        }
+        Err(e) => {
+            game.last_haptic = MinesweeperHaptic::None;
+            e.into()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn game_open(
+    game: *mut MinesweeperGame,
+    x: i32,
+    y: i32,
+) -> MinesweeperResult {
+    apply(game, Move::Open(Point { x, y }))
+}
+
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn game_flag(
+    game: *mut MinesweeperGame,
+    x: i32,
+    y: i32,
+) -> MinesweeperResult {
+    apply(game, Move::Flag(Point { x, y }))
+}
+
+/// Returns the count of an open cell (0 to 8) or one of the `CELL_*`
+/// constants. Mines are only revealed once the game is over.
+///
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn game_cell_at(game: *const MinesweeperGame, x: i32, y: i32) -> i32 {
+    let board = match game.as_ref() {
+        Some(game) => &game.board,
+        None => return CELL_OUT_OF_BOUNDS,
+    };
+    match board.cell_view(&Point { x, y }) {
+        Some(CellView::Mine) | Some(CellView::ExplodedMine) => CELL_MINE,
+        Some(CellView::Open(count)) | Some(CellView::Treasure(count)) => i32::from(count),
+        Some(CellView::Flagged) | Some(CellView::WrongFlag) => CELL_FLAGGED,
+        Some(CellView::Hidden) | Some(CellView::Question) | Some(CellView::OutOfReach) => {
+            CELL_CLOSED
+        }
+        None => CELL_OUT_OF_BOUNDS,
+    }
+}
+
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn game_state(game: *const MinesweeperGame) -> MinesweeperState {
+    match game.as_ref().map(|game| &game.board.state) {
+        Some(BoardState::Ready) => MinesweeperState::Ready,
+        Some(BoardState::Playing) => MinesweeperState::Playing,
+        Some(BoardState::Won) => MinesweeperState::Won,
+        Some(BoardState::Failed) => MinesweeperState::Failed,
+        Some(BoardState::NotReady) | None => MinesweeperState::NotReady,
+    }
+}
+
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn game_width(game: *const MinesweeperGame) -> usize {
+    game.as_ref().map_or(0, |game| game.board.width)
+}
+
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn game_height(game: *const MinesweeperGame) -> usize {
+    game.as_ref().map_or(0, |game| game.board.height)
+}
+
+/// The vibration to play for the last `game_open` or `game_flag` call, or
+/// `MinesweeperHaptic_None` if it failed or there was none.
+///
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new` that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn game_last_haptic(game: *const MinesweeperGame) -> MinesweeperHaptic {
+    game.as_ref()
+        .map_or(MinesweeperHaptic::None, |game| game.last_haptic)
+}
+
+/// # Safety
+///
+/// `game` must be null or a pointer returned by `game_new`, and must not be
+/// used again after this call.
+#[no_mangle]
+pub unsafe extern "C" fn game_free(game: *mut MinesweeperGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_game_lifecycle() {
+        let game = game_new(5, 4, 3, 7);
+        assert!(!game.is_null());
+        unsafe {
+            assert_eq!(game_width(game), 5);
+            assert_eq!(game_height(game), 4);
+            assert_eq!(game_state(game), MinesweeperState::Ready);
+            assert_eq!(game_cell_at(game, 0, 0), CELL_CLOSED);
+            assert_eq!(game_cell_at(game, 5, 0), CELL_OUT_OF_BOUNDS);
+            assert_eq!(game_flag(game, 0, 0), MinesweeperResult::Ok);
+            assert_eq!(game_last_haptic(game), MinesweeperHaptic::Short);
+            assert_eq!(game_cell_at(game, 0, 0), CELL_FLAGGED);
+            assert_eq!(game_open(game, 0, 0), MinesweeperResult::CellFlagged);
+            assert_eq!(game_last_haptic(game), MinesweeperHaptic::None);
+            assert_eq!(game_open(game, 9, 9), MinesweeperResult::OutOfBounds);
+            game_free(game);
+        }
+    }
+
+    #[test]
+    fn test_invalid_config_and_null_game() {
+        assert!(game_new(2, 2, 4, 0).is_null());
+        assert!(game_new(usize::MAX, 2, 1, 0).is_null());
+        assert!(game_new(2, 2, usize::MAX, 0).is_null());
+        unsafe {
+            assert_eq!(
+                game_open(ptr::null_mut(), 0, 0),
+                MinesweeperResult::NullGame
+            );
+            game_free(ptr::null_mut());
+        }
+    }
+}
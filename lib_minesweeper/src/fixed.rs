@@ -0,0 +1,338 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::random::RandomSource;
+use crate::topology::Topology;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{check_mines_fit, Board, BoardState, Error, MapElement, Move, Point, Result};
+
+// A board whose size is part of its type, kept in arrays: building, opening
+// and flagging never touch the heap, for embedded targets and simulations
+// that can't allocate per frame. Only errors and the conversions to and from
+// Board allocate. Plays by the same rules as Board.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct FixedBoard<const W: usize, const H: usize> {
+    map: [[MapElement; W]; H],
+    mines: usize,
+    // Safe cells still to open.
+    missing_points: usize,
+    state: BoardState,
+}
+
+impl<const W: usize, const H: usize> FixedBoard<W, H> {
+    // The board with mines on the given points, numbers worked out and every
+    // cell closed.
+    pub fn new(mines: &[Point]) -> Result<FixedBoard<W, H>> {
+        let mut board = FixedBoard::empty()?;
+        for p in mines {
+            board.index(p)?;
+            board.place_mine(p);
+        }
+        Ok(board)
+    }
+
+    // Mines placed at random, none of them on the safe points.
+    pub fn generate(
+        mines: usize,
+        source: &mut dyn RandomSource,
+        safe: &[Point],
+    ) -> Result<FixedBoard<W, H>> {
+        check_mines_fit(W, H, mines, safe)?;
+        let mut board = FixedBoard::empty()?;
+        while board.mines < mines {
+            let cell = source.gen_range(0, W * H);
+            let p = Point::new(cell % W, cell / W);
+            if !safe.contains(&p) {
+                board.place_mine(&p);
+            }
+        }
+        Ok(board)
+    }
+
+    fn empty() -> Result<FixedBoard<W, H>> {
+        if W == 0 || H == 0 {
+            return Err(Error::InvalidConfig(String::from(
+                "board must have at least one cell",
+            )));
+        }
+        Ok(FixedBoard {
+            map: [[Number {
+                state: Closed,
+                count: 0,
+            }; W]; H],
+            mines: 0,
+            missing_points: W * H,
+            state: BoardState::Ready,
+        })
+    }
+
+    // Does nothing on cells that are mines already.
+    fn place_mine(&mut self, p: &Point) {
+        let (x, y) = (p.x as usize, p.y as usize);
+        if let Mine { .. } = self.map[y][x] {
+            return;
+        }
+        self.map[y][x] = Mine { state: Closed };
+        self.mines += 1;
+        self.missing_points -= 1;
+        for n in p.neighbors() {
+            if let Ok((x, y)) = self.index(&n) {
+                if let Number { count, .. } = &mut self.map[y][x] {
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    fn index(&self, p: &Point) -> Result<(usize, usize)> {
+        if p.x < 0 || p.y < 0 || p.x as usize >= W || p.y as usize >= H {
+            return Err(Error::OutOfBounds { x: p.x, y: p.y });
+        }
+        Ok((p.x as usize, p.y as usize))
+    }
+
+    pub fn width(&self) -> usize {
+        W
+    }
+
+    pub fn height(&self) -> usize {
+        H
+    }
+
+    pub fn mines(&self) -> usize {
+        self.mines
+    }
+
+    pub fn state(&self) -> BoardState {
+        self.state
+    }
+
+    pub fn at(&self, p: &Point) -> Option<&MapElement> {
+        let (x, y) = self.index(p).ok()?;
+        Some(&self.map[y][x])
+    }
+
+    pub(crate) fn at_mut(&mut self, p: &Point) -> Result<&mut MapElement> {
+        let (x, y) = self.index(p)?;
+        Ok(&mut self.map[y][x])
+    }
+
+    pub(crate) fn set_state(&mut self, state: BoardState) {
+        self.state = state;
+    }
+
+    fn checked_index(&self, p: &Point) -> Result<(usize, usize)> {
+        if matches!(self.state, BoardState::Won | BoardState::Failed) {
+            return Err(Error::GameOver);
+        }
+        self.index(p)
+    }
+
+    fn reveal(&mut self, x: usize, y: usize) {
+        if let Number { state, .. } = &mut self.map[y][x] {
+            *state = Open;
+            self.missing_points -= 1;
+        }
+    }
+
+    // Opens the cell, and around it as Board::cascade_open_item does when
+    // it's a zero.
+    pub fn open(&mut self, p: &Point) -> Result<()> {
+        let (x, y) = self.checked_index(p)?;
+        match self.map[y][x] {
+            Number { state: Open, .. } | Mine { state: Open } => {
+                return Err(Error::CellAlreadyOpen { x: p.x, y: p.y })
+            }
+            Number { state: Flagged, .. } | Mine { state: Flagged } => {
+                return Err(Error::CellFlagged { x: p.x, y: p.y })
+            }
+            Mine { state: Closed } => {
+                self.map[y][x] = Mine { state: Open };
+                self.state = BoardState::Failed;
+                return Ok(());
+            }
+            Number { count, .. } => {
+                self.reveal(x, y);
+                if count == 0 {
+                    self.flood();
+                }
+            }
+        }
+        self.state = match self.missing_points {
+            0 => BoardState::Won,
+            _ => BoardState::Playing,
+        };
+        Ok(())
+    }
+
+    // Opens the closed neighbors of every open zero until there are none
+    // left. Sweeping the board over again instead of keeping a stack of
+    // cells to visit is slower on big openings, but needs no memory.
+    fn flood(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for y in 0..H {
+                for x in 0..W {
+                    if !matches!(
+                        self.map[y][x],
+                        Number {
+                            state: Open,
+                            count: 0
+                        }
+                    ) {
+                        continue;
+                    }
+                    for n in Point::new(x, y).neighbors() {
+                        if let Ok((nx, ny)) = self.index(&n) {
+                            if let Number { state: Closed, .. } = self.map[ny][nx] {
+                                self.reveal(nx, ny);
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Flags a closed cell, or takes the flag off a flagged one.
+    pub fn flag(&mut self, p: &Point) -> Result<()> {
+        let (x, y) = self.checked_index(p)?;
+        let toggled = |state| match state {
+            Closed => Ok(Flagged),
+            Flagged => Ok(Closed),
+            Open => Err(Error::CellAlreadyOpen { x: p.x, y: p.y }),
+        };
+        self.map[y][x] = match self.map[y][x] {
+            Mine { state } => Mine {
+                state: toggled(state)?,
+            },
+            Number { state, count } => Number {
+                state: toggled(state)?,
+                count,
+            },
+        };
+        if self.state == BoardState::Ready {
+            self.state = BoardState::Playing;
+        }
+        Ok(())
+    }
+
+    // Power-ups need a Board, and are refused.
+    pub fn apply_move(&mut self, m: &Move) -> Result<()> {
+        match m {
+            Move::Open(p) => self.open(p),
+            Move::Flag(p) => self.flag(p),
+            Move::Power(..) => Err(Error::InvalidConfig(String::from(
+                "power-ups can't be used on a fixed-size board",
+            ))),
+        }
+    }
+
+    pub fn to_board(&self) -> Board {
+        let map: Vec<Vec<MapElement>> = self.map.iter().map(|row| row.to_vec()).collect();
+        Board {
+            map,
+            missing_points: self.missing_points as i32,
+            width: W,
+            height: H,
+            mines: self.mines,
+            state: self.state,
+            seed: None,
+            treasures: None,
+        }
+    }
+
+    // Fails when the board isn't W by H.
+    pub fn from_board(board: &Board) -> Result<FixedBoard<W, H>> {
+        if (board.width, board.height) != (W, H) {
+            return Err(Error::InvalidConfig(alloc::format!(
+                "a {}x{} board doesn't fit in a {}x{} one",
+                board.width,
+                board.height,
+                W,
+                H
+            )));
+        }
+        let mut fixed = FixedBoard::empty()?;
+        for (y, row) in fixed.map.iter_mut().enumerate() {
+            row.copy_from_slice(&board.map[y]);
+        }
+        fixed.mines = board.mines;
+        fixed.missing_points = board.missing_points.max(0) as usize;
+        fixed.state = board.state;
+        Ok(fixed)
+    }
+}
+
+impl<const W: usize, const H: usize> Topology for FixedBoard<W, H> {
+    fn width(&self) -> usize {
+        W
+    }
+
+    fn height(&self) -> usize {
+        H
+    }
+
+    fn neighbors(&self, p: &Point) -> Vec<Point> {
+        p.neighbors().filter(|n| self.contains(n)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers_on_board;
+    use crate::random::SplitMix64;
+    use crate::tests::five_by_two_board;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_fixed_board_plays_like_board() {
+        // *2100
+        // 2*100
+        let mut fixed = FixedBoard::<5, 2>::new(&[Point::new(0, 0), Point::new(1, 1)]).unwrap();
+        let board = numbers_on_board(five_by_two_board());
+        assert_eq!(fixed.to_board(), board);
+
+        fixed.open(&Point::new(4, 0)).unwrap();
+        let board = board.cascade_open_item(&Point::new(4, 0)).unwrap();
+        assert_eq!(fixed.to_board(), board);
+        assert_eq!(
+            fixed.open(&Point::new(3, 1)),
+            Err(Error::CellAlreadyOpen { x: 3, y: 1 })
+        );
+        fixed.flag(&Point::new(0, 0)).unwrap();
+        assert_eq!(
+            fixed.open(&Point::new(0, 0)),
+            Err(Error::CellFlagged { x: 0, y: 0 })
+        );
+        fixed.open(&Point::new(1, 0)).unwrap();
+        fixed.open(&Point::new(0, 1)).unwrap();
+        assert_eq!(fixed.state(), BoardState::Won);
+        assert_eq!(fixed.open(&Point::new(1, 1)), Err(Error::GameOver));
+        assert_eq!(FixedBoard::<5, 2>::from_board(&fixed.to_board()), Ok(fixed));
+        assert!(FixedBoard::<2, 5>::from_board(&board).is_err());
+    }
+
+    #[test]
+    fn test_generate_fixed_board() {
+        let safe = [Point::new(0, 0)];
+        let board = FixedBoard::<9, 9>::generate(10, &mut SplitMix64::new(3), &safe).unwrap();
+        assert_eq!(board.mines(), 10);
+        assert_eq!(
+            board
+                .to_board()
+                .cascade_open_item(&safe[0])
+                .map(|b| b.state),
+            Ok(BoardState::Playing)
+        );
+        assert!(FixedBoard::<3, 3>::generate(9, &mut SplitMix64::new(3), &[]).is_err());
+        assert!(FixedBoard::<0, 3>::new(&[]).is_err());
+    }
+}
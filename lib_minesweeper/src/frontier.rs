@@ -0,0 +1,116 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::solver::{constraints, is_unknown};
+use crate::MapElement::Mine;
+use crate::MapElementCellState::Open;
+use crate::{Adjacency, Board, Point};
+
+// Exactly `mines` of `cells` hold a mine.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct FrontierConstraint {
+    pub cells: Vec<Point>,
+    pub mines: usize,
+}
+
+// What the player knows about the board, as a system of constraints for
+// solvers of one's own. Every revealed number with closed neighbors gives one
+// constraint; on top of those, the frontier cells (the ones in any constraint)
+// and the interior cells together hold exactly `mines_left` mines. Flags are
+// not trusted, so flagged cells are unknown like closed ones.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Frontier {
+    pub width: usize,
+    pub height: usize,
+    pub constraints: Vec<FrontierConstraint>,
+    // Unknown cells next to no revealed number.
+    pub interior: Vec<Point>,
+    pub mines_left: usize,
+}
+
+impl Frontier {
+    // The unknown cells in at least one constraint, row by row.
+    pub fn cells(&self) -> Vec<Point> {
+        let mut in_frontier = vec![false; self.width * self.height];
+        for c in &self.constraints {
+            for p in &c.cells {
+                in_frontier[p.y as usize * self.width + p.x as usize] = true;
+            }
+        }
+        (0..self.width * self.height)
+            .filter(|&i| in_frontier[i])
+            .map(|i| Point::new(i % self.width, i / self.width))
+            .collect()
+    }
+}
+
+impl Board {
+    pub fn frontier(&self) -> Frontier {
+        let adjacency = Adjacency::for_board(self);
+        let constraints = constraints(self, &adjacency);
+        let mut in_frontier = vec![false; self.width * self.height];
+        for c in &constraints {
+            for &cell in &c.cells {
+                in_frontier[cell] = true;
+            }
+        }
+        let interior = (0..self.width * self.height)
+            .filter(|&i| !in_frontier[i] && is_unknown(self, i % self.width, i / self.width))
+            .map(|i| adjacency.point(i))
+            .collect();
+        let known_mines = self
+            .map
+            .iter()
+            .flatten()
+            .filter(|el| matches!(el, Mine { state: Open }))
+            .count();
+        Frontier {
+            width: self.width,
+            height: self.height,
+            constraints: constraints
+                .into_iter()
+                .map(|c| FrontierConstraint {
+                    cells: c.cells.into_iter().map(|i| adjacency.point(i)).collect(),
+                    mines: c.mines,
+                })
+                .collect(),
+            interior,
+            mines_left: self.mines.saturating_sub(known_mines),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_frontier() {
+        // *....
+        // .*...
+        // .....
+        let board = Scenario::from_ascii("*....\n.*...\n.....\n")
+            .open("E1")
+            .into_board();
+        let frontier = board.frontier();
+        assert_eq!(frontier.mines_left, 2);
+        assert_eq!(
+            frontier.constraints[0],
+            FrontierConstraint {
+                cells: vec![Point::new(1, 0), Point::new(1, 1)],
+                mines: 1,
+            }
+        );
+        assert_eq!(
+            frontier.cells(),
+            vec![Point::new(1, 0), Point::new(1, 1), Point::new(1, 2),]
+        );
+        assert_eq!(
+            frontier.interior,
+            vec![Point::new(0, 0), Point::new(0, 1), Point::new(0, 2)]
+        );
+    }
+}
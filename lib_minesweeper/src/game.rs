@@ -0,0 +1,1636 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::annotations::Annotations;
+use crate::clock::{Clock, Timer};
+use crate::coach::{advise, consistency_warnings, Advice, CoachOptions, ConsistencyWarning};
+use crate::power::PowerUp;
+use crate::random::{seeded_rand, RngAudit, SplitMix64};
+use crate::replay::Replay;
+use crate::solver::{
+    deductions_with, layout_with_mine, probabilities_with, probabilities_with_density,
+};
+use crate::splits::{Split, SplitDefinition};
+use crate::stats::{solved_three_bv, three_bv, GameSummary, Progress};
+use crate::view::CellView;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{
+    border_cells, check_mines_fit, create_board, numbers_on_board, Adjacency, Board, BoardState,
+    Error, MapElement, MapElementCellState, Move, Point, Result,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct GameOptions {
+    // Flag every cell the solver proves is a mine after each open.
+    pub auto_flag: bool,
+    // Open every cell the solver proves is safe after each move, repeating
+    // until nothing new can be deduced.
+    pub auto_open_safe: bool,
+    // NF play: flag moves are rejected and auto flagging is off.
+    pub no_flag: bool,
+    // Opening a mine in a forced 50/50, with no safe cell left to find, moves
+    // the mine elsewhere instead of losing. See Event::Relocated.
+    pub kind: bool,
+    // The opposite: every open that isn't certainly safe finds a mine, by
+    // rearranging the closed cells into a layout that agrees with everything
+    // revealed. The first open is spared, or no game could be won. Kind mode
+    // does nothing in cruel games. See Event::Rearranged.
+    pub cruel: bool,
+    // Moving mines: after every this many moves that change the board,
+    // mines that touch no open cell may move. See Event::Shifted.
+    pub moving_mines: Option<usize>,
+    // Fog of war: only cells within this many cells of an open one can be
+    // opened or flagged. Before the first open any cell can.
+    pub fog: Option<usize>,
+    // Evens out a match between players of different strength, see
+    // tournament::Tournament::handicap.
+    pub handicap: Option<Handicap>,
+    // The player isn't told how many mines there are: see Game::mines_left.
+    // Games are won by opening every safe cell either way.
+    #[serde(default)]
+    pub hidden_mine_count: bool,
+    // For boards from BoardBuilder::density, in thousandths: the chance each
+    // cell had of being a mine. The count is hidden, and Game::mines_left
+    // gives the expected one instead.
+    pub mine_density: Option<u32>,
+    // Deferred games place no mine on the outer ring of cells, see
+    // BoardBuilder::border_free.
+    #[serde(default)]
+    pub border_free: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct Handicap {
+    // Safe cells opened for the player, picked from the board's seed, right
+    // after the first open.
+    pub reveals: usize,
+    // Mines fewer than the match's board has, taken off before the deal.
+    pub fewer_mines: usize,
+    // Taken off the player's time in the game's summary.
+    pub time_bonus: Duration,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Source {
+    User,
+    Auto,
+    // Opened by a row or column shortcut, see Game::open_row_safe.
+    Assisted,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Event {
+    Opened {
+        point: Point,
+        count: i32,
+        source: Source,
+    },
+    Flagged {
+        point: Point,
+        source: Source,
+    },
+    Unflagged {
+        point: Point,
+    },
+    Exploded {
+        point: Point,
+    },
+    Won,
+    Lost,
+    // Kind mode moved the mine the player was about to open. The revealed
+    // numbers are unchanged, only closed cells differ.
+    Relocated {
+        from: Point,
+        to: Point,
+    },
+    // Cruel mode moved mines around so the cell the player opened is one.
+    // The revealed numbers are unchanged, only closed cells differ.
+    Rearranged {
+        point: Point,
+    },
+    // Only from coached games, see Game::coaching.
+    Advice(Advice),
+    // Only from games with splits, see Game::splits.
+    Split(Split),
+    // Moving mines moved this many mines, all away from the open cells.
+    Shifted {
+        mines: usize,
+    },
+    // A treasure cell was opened, see Board::treasures.
+    TreasureFound {
+        point: Point,
+    },
+    // Comes before the events of what the power-up did.
+    PowerUsed {
+        power: PowerUp,
+        point: Point,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct LoggedMove {
+    pub action: Move,
+    // False when the move was rejected and left the board as it was.
+    pub changed: bool,
+    // Game time when the move was made, not counting pauses. Always zero for
+    // untimed games.
+    pub at: Duration,
+}
+
+// A game whose mines are placed by the first open, see Game::new_deferred.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub seed: u64,
+    pub options: GameOptions,
+}
+
+// Autosave after this many moves that changed the board.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct AutosaveEvery(pub usize);
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Game {
+    board: Board,
+    pub options: GameOptions,
+    auto_flags: usize,
+    user_flags: usize,
+    moves: Vec<LoggedMove>,
+    timer: Timer,
+    autosave: Option<AutosaveEvery>,
+    unsaved_moves: usize,
+    coach: Option<CoachOptions>,
+    // Set until the first open places the mines.
+    deferred_seed: Option<u64>,
+    // How the first open placed them.
+    rng_audit: Option<RngAudit>,
+    splits: Option<SplitDefinition>,
+    splits_reached: usize,
+    // Game time of every move that solved more of the 3BV, with the total
+    // solved after it.
+    solved: Vec<(Duration, usize)>,
+    // The board before mines first moved, by kind or cruel mode, moving
+    // mines or a defuse kit.
+    dealt: Option<Board>,
+    // See Game::annotate.
+    pub(crate) annotations: Option<Annotations>,
+}
+
+impl Game {
+    pub fn new(board: Board, options: GameOptions) -> Game {
+        Game {
+            board,
+            options,
+            auto_flags: 0,
+            user_flags: 0,
+            moves: vec![],
+            timer: Timer::new(),
+            autosave: None,
+            unsaved_moves: 0,
+            coach: None,
+            deferred_seed: None,
+            rng_audit: None,
+            splits: None,
+            splits_reached: 0,
+            solved: vec![],
+            dealt: None,
+            annotations: None,
+        }
+    }
+
+    // Starts on a board with no mines yet: they are placed from the seed when
+    // the first cell is opened, never on it or its neighbors, so the first
+    // open is always a zero. Boards too crowded for that only spare the cell
+    // itself, and border-free boards whose inside is all mines not even that.
+    // Flags placed before then are kept.
+    pub fn new_deferred(config: GameConfig) -> Result<Game> {
+        let (width, height, mines) = (config.width, config.height, config.mines);
+        // The same limit as BoardBuilder's, the border counted as safe.
+        let border = if config.options.border_free {
+            border_cells(width, height)
+        } else {
+            vec![]
+        };
+        check_mines_fit(width, height, mines, &border)?;
+        let board = numbers_on_board(create_board(width, height, 0, seeded_rand(config.seed))?);
+        let mut game = Game::new(
+            Board {
+                mines,
+                missing_points: (width * height - mines) as i32,
+                ..board
+            },
+            config.options,
+        );
+        game.deferred_seed = Some(config.seed);
+        Ok(game)
+    }
+
+    // False until the mines of a deferred game are placed.
+    pub fn is_materialized(&self) -> bool {
+        self.deferred_seed.is_none()
+    }
+
+    // When p is a mine, no cell is certainly safe and p is exactly as likely
+    // a mine as not, moves the mine to a closed cell that touches the same
+    // open numbers, so every number stays right. Returns where it went.
+    fn relocate_fifty_fifty(&mut self, p: &Point) -> Option<Point> {
+        if !matches!(self.board.at(p), Some(Mine { state: Closed })) {
+            return None;
+        }
+        let adjacency = Adjacency::for_board(&self.board);
+        if deductions_with(&self.board, &adjacency)
+            .iter()
+            .any(|d| !d.mine)
+        {
+            return None;
+        }
+        let probabilities = probabilities_with(&self.board, &adjacency);
+        match probabilities.at(p) {
+            Some(chance) if probabilities.exact && (chance - 0.5).abs() < 1e-9 => (),
+            _ => return None,
+        }
+        let border = self.border();
+        let to = (0..self.board.height)
+            .flat_map(|y| (0..self.board.width).map(move |x| Point::new(x, y)))
+            .find(|q| self.board.can_move_mine(p, q) && !border.contains(q))?;
+        self.keep_dealt();
+        self.board.move_mine(p, &to);
+        Some(to)
+    }
+
+    // Moving mines: every closed mine that touches no open cell moves, half
+    // the time, to a closed safe cell that touches none either. The numbers
+    // come from the game so far, so replays move the same mines. Returns how
+    // many moved.
+    fn shift_mines(&mut self) -> usize {
+        let mut rng = SplitMix64::new(self.board.seed.unwrap_or(0) ^ self.moves.len() as u64);
+        let cells: Vec<Point> = (0..self.board.height)
+            .flat_map(|y| (0..self.board.width).map(move |x| Point::new(x, y)))
+            .collect();
+        let movable: Vec<Point> = cells
+            .iter()
+            .filter(|p| {
+                matches!(self.board.at(p), Some(Mine { state: Closed }))
+                    && self.board.open_neighbors(p).is_empty()
+            })
+            .cloned()
+            .collect();
+        let border = self.border();
+        let mut moved = 0;
+        for from in movable {
+            if rng.next_u64() % 2 == 0 {
+                continue;
+            }
+            let free: Vec<&Point> = cells
+                .iter()
+                .filter(|q| self.board.can_move_mine(&from, q) && !border.contains(q))
+                .collect();
+            if free.is_empty() {
+                continue;
+            }
+            let to = *free[rng.gen_range(0, free.len())];
+            self.keep_dealt();
+            self.board.move_mine(&from, &to);
+            moved += 1;
+        }
+        moved
+    }
+
+    // Cells no mine may move to on border-free games.
+    fn border(&self) -> Vec<Point> {
+        if self.options.border_free {
+            border_cells(self.board.width, self.board.height)
+        } else {
+            vec![]
+        }
+    }
+
+    // Replays start from the mines as dealt, so they're kept before any mine
+    // moves.
+    fn keep_dealt(&mut self) {
+        if self.dealt.is_none() {
+            self.dealt = Some(self.board.clone());
+        }
+    }
+
+    // The board with the mines where they were first placed.
+    pub(crate) fn dealt(&self) -> &Board {
+        self.dealt.as_ref().unwrap_or(&self.board)
+    }
+
+    fn spring_trap(&mut self, p: &Point) -> bool {
+        if self.board.state != BoardState::Playing
+            || !matches!(self.board.at(p), Some(Number { state: Closed, .. }))
+        {
+            return false;
+        }
+        let adjacency = Adjacency::for_board(&self.board);
+        let treasures = self.board.treasures.iter().flatten();
+        let safe: Vec<usize> = self
+            .border()
+            .iter()
+            .chain(treasures)
+            .map(|q| adjacency.cell(q))
+            .collect();
+        let layout = match layout_with_mine(&self.board, &adjacency, adjacency.cell(p), &safe) {
+            Some(layout) => layout,
+            None => return false,
+        };
+        self.keep_dealt();
+        let width = self.board.width;
+        for (i, &mine) in layout.iter().enumerate() {
+            let el = &mut self.board.map[i / width][i % width];
+            *el = match el {
+                Mine { state } | Number { state, .. } if *state != Open => {
+                    if mine {
+                        Mine { state: *state }
+                    } else {
+                        Number {
+                            state: *state,
+                            count: 0,
+                        }
+                    }
+                }
+                _ => continue,
+            };
+        }
+        let all: Vec<Point> = (0..layout.len()).map(|i| adjacency.point(i)).collect();
+        self.board.recompute_numbers_around(&all);
+        true
+    }
+
+    fn materialize(&mut self, p: &Point) -> Result<()> {
+        let seed = match self.deferred_seed {
+            Some(seed) => seed,
+            None => return Ok(()),
+        };
+        if !matches!(self.board.at(p), Some(Number { state: Closed, .. })) {
+            // Let the move fail as it would on any board.
+            return Ok(());
+        }
+        let (width, height, mines) = (self.board.width, self.board.height, self.board.mines);
+        let border = self.border();
+        let mut safe: Vec<Point> = self.board.neighbors(p).chain(border.clone()).collect();
+        safe.push(*p);
+        let (board, audit) = RngAudit::generate(width, height, mines, seed, &safe)
+            .or_else(|_| {
+                let safe: Vec<Point> = border.iter().cloned().chain([*p]).collect();
+                RngAudit::generate(width, height, mines, seed, &safe)
+            })
+            .or_else(|_| RngAudit::generate(width, height, mines, seed, &border))?;
+        let mut board = numbers_on_board(board);
+        for (row, placeholder) in board.map.iter_mut().zip(&self.board.map) {
+            for (el, old) in row.iter_mut().zip(placeholder) {
+                if let (Mine { state } | Number { state, .. }, Number { state: Flagged, .. }) =
+                    (el, old)
+                {
+                    *state = Flagged;
+                }
+            }
+        }
+        self.board = board;
+        self.deferred_seed = None;
+        self.rng_audit = Some(audit);
+        Ok(())
+    }
+
+    pub fn autosave_policy(mut self, policy: AutosaveEvery) -> Game {
+        self.autosave = Some(policy);
+        self
+    }
+
+    // After each move that changes the board, advice on it is added to the
+    // events. The board is never touched, so a training mode can toggle this
+    // at any time.
+    pub fn coaching(mut self, options: CoachOptions) -> Game {
+        self.coach = Some(options);
+        self
+    }
+
+    pub fn set_coaching(&mut self, options: Option<CoachOptions>) {
+        self.coach = options;
+    }
+
+    // Adds a split event each time the solved 3BV passes one of the
+    // definition's percents, timed like the move that got there.
+    pub fn splits(mut self, definition: SplitDefinition) -> Game {
+        self.splits = Some(definition);
+        self
+    }
+
+    // True once the autosave policy's move count has been reached since the
+    // last save.
+    pub fn autosave_due(&self) -> bool {
+        match self.autosave {
+            Some(AutosaveEvery(n)) => self.unsaved_moves >= n.max(1),
+            None => false,
+        }
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.unsaved_moves = 0;
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    // Where the flags disagree with the numbers, for assist modes to point
+    // out. Coached games with consistency on get them as advice events.
+    pub fn consistency_warnings(&self) -> Vec<ConsistencyWarning> {
+        consistency_warnings(&self.board)
+    }
+
+    // Every click the player made while the game was running, including the
+    // ones that were rejected.
+    pub fn moves(&self) -> &[LoggedMove] {
+        &self.moves
+    }
+
+    pub fn replay(&self) -> Replay {
+        Replay::from_game(self)
+    }
+
+    // A copy of the game to try moves on. Nothing played on it reaches this
+    // game or its replay unless the branch is promoted, and it never asks
+    // for an autosave.
+    pub fn branch(&self) -> Branch {
+        Branch {
+            game: Game {
+                autosave: None,
+                ..self.clone()
+            },
+            from: self.moves.len(),
+        }
+    }
+
+    pub fn hides_mine_count(&self) -> bool {
+        self.options.hidden_mine_count || self.options.mine_density.is_some()
+    }
+
+    fn density(&self) -> Option<f64> {
+        self.options.mine_density.map(|d| d as f64 / 1000.0)
+    }
+
+    // Mines minus flags, the counter players see. In density games the mines
+    // are the number the density makes likeliest; None when the game hides
+    // the mine count.
+    pub fn mines_left(&self) -> Option<i64> {
+        let mines = match self.density() {
+            Some(density) => {
+                (density * (self.board.width * self.board.height) as f64).round() as i64
+            }
+            None if self.hides_mine_count() => return None,
+            None => self.board.mines as i64,
+        };
+        let flags = self
+            .board
+            .view()
+            .iter()
+            .flatten()
+            .filter(|c| **c == CellView::Flagged)
+            .count();
+        Some(mines - flags as i64)
+    }
+
+    pub fn summary(&self, elapsed_seconds: f64) -> GameSummary {
+        GameSummary::new(self, elapsed_seconds)
+    }
+
+    pub fn progress(&self, clock: &impl Clock) -> Progress {
+        Progress::new(self, self.elapsed(clock))
+    }
+
+    // The random numbers that placed the mines of a deferred game, once
+    // they're placed.
+    pub fn rng_audit(&self) -> Option<&RngAudit> {
+        self.rng_audit.as_ref()
+    }
+
+    // For saves rebuilt by replaying their moves, see recovery: the parts
+    // replaying doesn't bring back.
+    #[cfg(feature = "storage")]
+    pub(crate) fn restore(&mut self, timer: Timer, rng_audit: Option<RngAudit>) {
+        self.timer = timer;
+        self.rng_audit = rng_audit;
+    }
+
+    pub(crate) fn solved_history(&self) -> &[(Duration, usize)] {
+        &self.solved
+    }
+
+    pub fn elapsed(&self, clock: &impl Clock) -> Duration {
+        self.timer.elapsed(clock)
+    }
+
+    pub fn pause(&mut self, clock: &impl Clock) {
+        self.timer.pause(clock);
+    }
+
+    pub fn resume(&mut self, clock: &impl Clock) {
+        if self.board.state == BoardState::Playing {
+            self.timer.resume(clock);
+        }
+    }
+
+    pub fn auto_flags(&self) -> usize {
+        self.auto_flags
+    }
+
+    // True when no flag was ever placed, by the player or the assist. Games in
+    // no-flag mode always are.
+    pub fn is_no_flag(&self) -> bool {
+        self.user_flags == 0 && self.auto_flags == 0
+    }
+
+    // Whether the cell can be played in a game with fog of war: within the
+    // fog radius of an open cell, or anywhere before the first open. Always
+    // true in games without fog.
+    pub fn in_reach(&self, p: &Point) -> bool {
+        let radius = match self.options.fog {
+            Some(radius) => radius,
+            None => return true,
+        };
+        let opened = self
+            .moves
+            .iter()
+            .any(|m| m.changed && matches!(m.action, Move::Open(_)));
+        if !opened {
+            return true;
+        }
+        // The open cells within the radius, on the board: a radius past the
+        // board's size reaches no further.
+        let radius = radius.min(self.board.width.max(self.board.height)) as i64;
+        let span = |at: i32, len: usize| {
+            let from = (at as i64 - radius).max(0);
+            let to = (at as i64 + radius).min(len as i64 - 1);
+            from..=to
+        };
+        span(p.y, self.board.height).any(|y| {
+            span(p.x, self.board.width).any(|x| {
+                matches!(
+                    self.board.map[y as usize][x as usize],
+                    Number { state: Open, .. }
+                )
+            })
+        })
+    }
+
+    // Whether apply would take the move and change the board with it.
+    pub fn is_legal(&self, m: &Move) -> bool {
+        let (Move::Open(p) | Move::Flag(p) | Move::Power(_, p)) = m;
+        if matches!(self.board.state, BoardState::Won | BoardState::Failed)
+            || (self.options.no_flag && matches!(m, Move::Flag(_)))
+        {
+            return false;
+        }
+        match (m, self.board.at(p)) {
+            (_, None) => false,
+            (Move::Power(PowerUp::RevealRadar(_), _), _) => self.in_reach(p),
+            (_, Some(Number { state: Open, .. } | Mine { state: Open })) => false,
+            (Move::Open(_) | Move::Power(..), Some(Number { state: Flagged, .. })) => false,
+            (Move::Open(_) | Move::Power(PowerUp::SafeStep, _), Some(Mine { state: Flagged })) => {
+                false
+            }
+            _ => self.in_reach(p),
+        }
+    }
+
+    // Like Board::cell_view, but hidden cells out of reach in a game with fog
+    // of war show as such while the game goes on.
+    pub fn cell_view(&self, p: &Point) -> Option<CellView> {
+        let over = matches!(self.board.state, BoardState::Won | BoardState::Failed);
+        match self.board.cell_view(p)? {
+            CellView::Hidden if !over && !self.in_reach(p) => Some(CellView::OutOfReach),
+            view => Some(view),
+        }
+    }
+
+    // Row by row, like Board::view.
+    pub fn view(&self) -> Vec<Vec<CellView>> {
+        (0..self.board.height)
+            .map(|y| {
+                (0..self.board.width)
+                    .filter_map(|x| self.cell_view(&Point::new(x, y)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Same as applying Move::Power.
+    pub fn use_power(&mut self, power: PowerUp, p: Point) -> Result<Vec<Event>> {
+        self.apply(&Move::Power(power, p))
+    }
+
+    pub fn apply(&mut self, m: &Move) -> Result<Vec<Event>> {
+        self.play(m, Duration::ZERO)
+    }
+
+    // Like apply, but keeps the game timer: the first move starts it, a move
+    // made while paused resumes it and the end of the game stops it.
+    pub fn apply_timed(&mut self, m: &Move, clock: &impl Clock) -> Result<Vec<Event>> {
+        self.timed(clock, |game, at| game.play(m, at))
+    }
+
+    fn timed(
+        &mut self,
+        clock: &impl Clock,
+        play: impl FnOnce(&mut Game, Duration) -> Result<Vec<Event>>,
+    ) -> Result<Vec<Event>> {
+        if matches!(self.board.state, BoardState::Ready | BoardState::Playing) {
+            self.timer.resume(clock);
+        }
+        let result = play(self, self.timer.elapsed(clock));
+        if matches!(self.board.state, BoardState::Won | BoardState::Failed) {
+            self.timer.pause(clock);
+        }
+        result
+    }
+
+    // Opens every closed cell of row y the solver proves safe, for players
+    // who find aiming at single cells hard. Each is logged as an open, and
+    // the cells it reveals come out as Source::Assisted. Cells the game
+    // refuses, out of reach say, are skipped.
+    pub fn open_row_safe(&mut self, y: usize) -> Result<Vec<Event>> {
+        self.open_safe(self.row(y)?, Duration::ZERO)
+    }
+
+    // Like open_row_safe, for column x.
+    pub fn open_col_safe(&mut self, x: usize) -> Result<Vec<Event>> {
+        self.open_safe(self.column(x)?, Duration::ZERO)
+    }
+
+    // Like open_row_safe, keeping the game timer as apply_timed does.
+    pub fn open_row_safe_timed(&mut self, y: usize, clock: &impl Clock) -> Result<Vec<Event>> {
+        let row = self.row(y)?;
+        self.timed(clock, |game, at| game.open_safe(row, at))
+    }
+
+    // Like open_col_safe, keeping the game timer as apply_timed does.
+    pub fn open_col_safe_timed(&mut self, x: usize, clock: &impl Clock) -> Result<Vec<Event>> {
+        let column = self.column(x)?;
+        self.timed(clock, |game, at| game.open_safe(column, at))
+    }
+
+    fn row(&self, y: usize) -> Result<Vec<Point>> {
+        if y >= self.board.height {
+            return Err(Error::OutOfBounds { x: 0, y: y as i32 });
+        }
+        Ok((0..self.board.width).map(|x| Point::new(x, y)).collect())
+    }
+
+    fn column(&self, x: usize) -> Result<Vec<Point>> {
+        if x >= self.board.width {
+            return Err(Error::OutOfBounds { x: x as i32, y: 0 });
+        }
+        Ok((0..self.board.height).map(|y| Point::new(x, y)).collect())
+    }
+
+    fn open_safe(&mut self, line: Vec<Point>, at: Duration) -> Result<Vec<Event>> {
+        if !matches!(self.board.state, BoardState::Ready | BoardState::Playing) {
+            return Err(Error::GameOver);
+        }
+        // Players who can't count the mines get no help that does.
+        let hidden = self.hides_mine_count();
+        let safe: Vec<Point> = deductions_with(&self.board, &Adjacency::for_board(&self.board))
+            .into_iter()
+            .filter(|d| !d.mine && line.contains(&d.point))
+            .filter(|d| !hidden || !d.uses_mine_count)
+            .map(|d| d.point)
+            .collect();
+        let mut events = vec![];
+        for p in safe {
+            let closed = matches!(self.board.at(&p), Some(Number { state: Closed, .. }));
+            let over = matches!(self.board.state, BoardState::Won | BoardState::Failed);
+            if !closed || over {
+                continue;
+            }
+            if let Ok(opened) = self.play(&Move::Open(p), at) {
+                events.extend(opened.into_iter().map(|event| match event {
+                    Event::Opened {
+                        point,
+                        count,
+                        source: Source::User,
+                    } => Event::Opened {
+                        point,
+                        count,
+                        source: Source::Assisted,
+                    },
+                    event => event,
+                }));
+            }
+        }
+        Ok(events)
+    }
+
+    pub(crate) fn play(&mut self, m: &Move, at: Duration) -> Result<Vec<Event>> {
+        if self.options.no_flag && matches!(m, Move::Flag(_)) {
+            return Err(Error::FlagsDisabled);
+        }
+        let (Move::Open(p) | Move::Flag(p) | Move::Power(_, p)) = m;
+        if self.board.at(p).is_some() && !self.in_reach(p) {
+            return Err(Error::OutOfReach { x: p.x, y: p.y });
+        }
+        let mut relocated = None;
+        let first_open = matches!(m, Move::Open(_))
+            && !self
+                .moves
+                .iter()
+                .any(|l| l.changed && matches!(l.action, Move::Open(_)));
+        if let Move::Open(p) | Move::Power(_, p) = m {
+            self.materialize(p)?;
+        }
+        if let Move::Open(p) = m {
+            if self.options.cruel {
+                if self.spring_trap(p) {
+                    relocated = Some(Event::Rearranged { point: *p });
+                }
+            } else if self.options.kind {
+                relocated = self
+                    .relocate_fifty_fifty(p)
+                    .map(|to| Event::Relocated { from: *p, to });
+            }
+        }
+        if let Move::Power(PowerUp::DefuseKit, p) = m {
+            if matches!(self.board.at(p), Some(Mine { .. })) {
+                self.keep_dealt();
+            }
+        }
+        let result = match m {
+            Move::Power(power, p) if self.options.no_flag => {
+                self.board.use_power_with(*power, p, false)
+            }
+            _ => self.board.apply_move(m),
+        };
+        let board = match result {
+            Ok(board) => board,
+            Err(e) => {
+                if matches!(e, Error::CellAlreadyOpen { .. } | Error::CellFlagged { .. }) {
+                    self.moves.push(LoggedMove {
+                        action: *m,
+                        changed: false,
+                        at,
+                    });
+                }
+                return Err(e);
+            }
+        };
+        self.moves.push(LoggedMove {
+            action: *m,
+            changed: true,
+            at,
+        });
+        self.unsaved_moves += 1;
+        if matches!(m, Move::Flag(_)) {
+            self.user_flags += 1;
+        }
+        let mut events: Vec<Event> = relocated.into_iter().collect();
+        if let Move::Power(power, point) = m {
+            events.push(Event::PowerUsed {
+                power: *power,
+                point: *point,
+            });
+        }
+        events.extend(move_events(&self.board, &board, m));
+        let before = core::mem::replace(&mut self.board, board);
+        if first_open {
+            events.extend(self.handicap_reveals());
+        }
+        events.extend(self.assist(matches!(m, Move::Open(_))));
+        if let Some(coach) = &self.coach {
+            events.extend(
+                advise(coach, &before, m, &self.board)
+                    .into_iter()
+                    .map(Event::Advice),
+            );
+        }
+        let solved = solved_three_bv(&self.board);
+        if self.solved.last().map_or(0, |(_, s)| *s) != solved {
+            self.solved.push((at, solved));
+        }
+        events.extend(
+            self.board
+                .treasures_found()
+                .into_iter()
+                .filter(|p| !before.treasures_found().contains(p))
+                .map(|point| Event::TreasureFound { point }),
+        );
+        let changed = self.moves.iter().filter(|m| m.changed).count();
+        let playing = self.board.state == BoardState::Playing;
+        match self.options.moving_mines {
+            Some(every) if playing && every > 0 && changed % every == 0 => {
+                let mines = self.shift_mines();
+                if mines > 0 {
+                    events.push(Event::Shifted { mines });
+                }
+            }
+            _ => (),
+        }
+        events.extend(self.reach_splits(at));
+        match self.board.state {
+            BoardState::Won => events.push(Event::Won),
+            BoardState::Failed => events.push(Event::Lost),
+            _ => (),
+        }
+        Ok(events)
+    }
+
+    fn handicap_reveals(&mut self) -> Vec<Event> {
+        let reveals = self.options.handicap.as_ref().map_or(0, |h| h.reveals);
+        let seed = self
+            .board
+            .seed
+            .or(self.rng_audit.as_ref().and_then(|a| a.seed));
+        let mut source = SplitMix64::new(seed.unwrap_or(0));
+        let mut events = vec![];
+        for _ in 0..reveals {
+            let closed: Vec<Point> = (0..self.board.height)
+                .flat_map(|y| (0..self.board.width).map(move |x| Point::new(x, y)))
+                .filter(|p| matches!(self.board.at(p), Some(Number { state: Closed, .. })))
+                .collect();
+            if closed.is_empty() || self.board.state != BoardState::Playing {
+                break;
+            }
+            let p = closed[source.gen_range(0, closed.len())];
+            if let Ok(board) = self.board.cascade_open_item(&p) {
+                events.extend(diff(&self.board, &board, Source::Auto));
+                self.board = board;
+            }
+        }
+        events
+    }
+
+    fn reach_splits(&mut self, at: Duration) -> Vec<Event> {
+        let definition = match &self.splits {
+            Some(definition) => definition,
+            None => return vec![],
+        };
+        let solved = self.solved.last().map_or(0, |(_, s)| *s);
+        let reached = definition.reached(solved, three_bv(&self.board));
+        let events = definition.percents()[self.splits_reached.min(reached)..reached]
+            .iter()
+            .map(|&percent| Event::Split(Split { percent, at }))
+            .collect();
+        self.splits_reached = self.splits_reached.max(reached);
+        events
+    }
+
+    // Each round runs the solver once, so events come out in deduction order:
+    // everything in a round follows from the board left by the previous one.
+    fn assist(&mut self, opened: bool) -> Vec<Event> {
+        let mut events = vec![];
+        let mut opened = opened;
+        let adjacency = Adjacency::for_board(&self.board);
+        while self.board.state == BoardState::Playing {
+            // Players who can't count the mines get no help that does.
+            let probabilities = if self.hides_mine_count() {
+                let density = self.density().unwrap_or(0.5);
+                probabilities_with_density(&self.board, &adjacency, density)
+            } else {
+                probabilities_with(&self.board, &adjacency)
+            };
+            if !probabilities.exact {
+                break;
+            }
+            let mut progressed = false;
+            for y in 0..self.board.height {
+                for x in 0..self.board.width {
+                    let p = Point::new(x, y);
+                    let closed = matches!(
+                        self.board.at(&p),
+                        Some(Mine { state: Closed }) | Some(Number { state: Closed, .. })
+                    );
+                    if !closed || self.board.state != BoardState::Playing {
+                        continue;
+                    }
+                    match probabilities.at(&p) {
+                        Some(p_mine)
+                            if p_mine == 1.0
+                                && opened
+                                && self.options.auto_flag
+                                && !self.options.no_flag =>
+                        {
+                            if let Ok(board) = self.board.flag_item(&p) {
+                                self.board = board;
+                                self.auto_flags += 1;
+                                events.push(Event::Flagged {
+                                    point: p,
+                                    source: Source::Auto,
+                                });
+                            }
+                        }
+                        Some(p_mine) if p_mine == 0.0 && self.options.auto_open_safe => {
+                            if let Ok(board) = self.board.cascade_open_item(&p) {
+                                events.extend(diff(&self.board, &board, Source::Auto));
+                                self.board = board;
+                                progressed = true;
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            if !progressed {
+                break;
+            }
+            opened = true;
+        }
+        events
+    }
+}
+
+// A sandbox game forked from a real one, see Game::branch.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Branch {
+    game: Game,
+    // How many moves the game had when it was branched.
+    from: usize,
+}
+
+impl Branch {
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn apply(&mut self, m: &Move) -> Result<Vec<Event>> {
+        self.game.apply(m)
+    }
+
+    // The moves tried on the branch, without the ones it started with.
+    pub fn moves(&self) -> &[LoggedMove] {
+        &self.game.moves[self.from..]
+    }
+
+    // Makes the branch's line the game's own, as if its moves had been
+    // played there, replay included. Fails when `game` isn't where the
+    // branch started, because it has moved on or is another game; the
+    // branch is then of no use and dropped either way.
+    pub fn promote(self, game: &mut Game) -> Result<()> {
+        if game.moves[..] != self.game.moves[..self.from] {
+            return Err(Error::InvalidConfig(String::from(
+                "the game has moved on since it was branched",
+            )));
+        }
+        let autosave = game.autosave;
+        *game = Game {
+            autosave,
+            ..self.game
+        };
+        Ok(())
+    }
+}
+
+fn cell_state(el: &MapElement) -> &MapElementCellState {
+    match el {
+        Mine { state } | Number { state, .. } => state,
+    }
+}
+
+// The events of a single user move, before any assists.
+pub(crate) fn move_events(before: &Board, after: &Board, m: &Move) -> Vec<Event> {
+    let mut events = diff(before, after, Source::User);
+    if let (Move::Open(p), BoardState::Failed) = (m, &after.state) {
+        events.push(Event::Exploded {
+            point: Point { x: p.x, y: p.y },
+        });
+    }
+    events
+}
+
+// Events for every cell whose state changed between two boards, in row-major
+// order.
+pub(crate) fn diff(before: &Board, after: &Board, source: Source) -> Vec<Event> {
+    let mut events = vec![];
+    for y in 0..after.height {
+        for x in 0..after.width {
+            let p = Point::new(x, y);
+            let (old, new) = match (before.at(&p), after.at(&p)) {
+                (Some(old), Some(new)) => (old, new),
+                _ => continue,
+            };
+            match (cell_state(old), cell_state(new), new) {
+                // Numbers only change under open cells when a mine is
+                // defused.
+                (Open, Open, Number { count, .. }) if old != new => events.push(Event::Opened {
+                    point: p,
+                    count: *count,
+                    source,
+                }),
+                (Open, Open, _) | (Closed, Closed, _) | (Flagged, Flagged, _) => (),
+                (_, Open, Number { count, .. }) => events.push(Event::Opened {
+                    point: p,
+                    count: *count,
+                    source,
+                }),
+                (_, Flagged, _) => events.push(Event::Flagged { point: p, source }),
+                (Flagged, Closed, _) => events.push(Event::Unflagged { point: p }),
+                _ => (),
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::scenario::Scenario;
+    use crate::tests::{five_by_two_board, make_map};
+    use crate::BoardBuilder;
+    use alloc::string::String;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_user_events() {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        let events = game.apply(&Move::Flag(Point::new(0, 0))).unwrap();
+        assert_eq!(
+            events,
+            vec![Event::Flagged {
+                point: Point::new(0, 0),
+                source: Source::User
+            }]
+        );
+        let events = game.apply(&Move::Open(Point::new(1, 1))).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Exploded {
+                    point: Point::new(1, 1)
+                },
+                Event::Lost
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_row_and_column_safe() {
+        let board = Scenario::from_ascii("*1 .\n11 .\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        // A1 is the only closed cell of the column, and a mine.
+        assert_eq!(game.open_col_safe(0).unwrap(), vec![]);
+        assert_eq!(
+            game.open_row_safe(0).unwrap(),
+            vec![
+                Event::Opened {
+                    point: Point::new(3, 0),
+                    count: 0,
+                    source: Source::Assisted,
+                },
+                Event::Opened {
+                    point: Point::new(3, 1),
+                    count: 0,
+                    source: Source::Assisted,
+                },
+                Event::Won,
+            ]
+        );
+        assert_eq!(game.moves().len(), 1);
+        assert_eq!(
+            game.open_row_safe(2),
+            Err(Error::OutOfBounds { x: 0, y: 2 })
+        );
+        assert_eq!(game.open_col_safe(3), Err(Error::GameOver));
+
+        let clock = MockClock::new();
+        let board = Scenario::from_ascii("*1 .\n11 .\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        game.apply_timed(&Move::Flag(Point::new(0, 0)), &clock)
+            .unwrap();
+        clock.advance(Duration::from_secs(7));
+        game.open_col_safe_timed(3, &clock).unwrap();
+        assert_eq!(game.moves()[1].at, Duration::from_secs(7));
+        assert_eq!(game.board().state, BoardState::Won);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(game.elapsed(&clock), Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_branch() {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        )
+        .autosave_policy(AutosaveEvery(1));
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        game.mark_saved();
+
+        let mut lost = game.branch();
+        lost.apply(&Move::Open(Point::new(1, 1))).unwrap();
+        assert_eq!(lost.game().board().state, BoardState::Failed);
+        assert!(!lost.game().autosave_due());
+        assert_eq!(game.board().state, BoardState::Playing);
+        assert_eq!(game.moves().len(), 1);
+
+        let mut won = game.branch();
+        won.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        won.apply(&Move::Open(Point::new(0, 1))).unwrap();
+        assert_eq!(won.moves().len(), 2);
+        won.promote(&mut game).unwrap();
+        assert_eq!(game.board().state, BoardState::Won);
+        assert_eq!(game.replay().moves.len(), 3);
+        assert!(game.autosave_due());
+        assert!(lost.promote(&mut game).is_err());
+    }
+
+    fn six_by_three_board() -> Board {
+        let board = Board::new(make_map(
+            vec![
+                String::from("X10000"),
+                String::from("110111"),
+                String::from("0001X1"),
+            ],
+            vec![
+                String::from("CCCCCC"),
+                String::from("CCCCCC"),
+                String::from("CCCCCC"),
+            ],
+        ))
+        .unwrap();
+        numbers_on_board(board)
+    }
+
+    #[test]
+    fn test_auto_flag() {
+        let options = GameOptions {
+            auto_flag: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(six_by_three_board(), options);
+        let events = game.apply(&Move::Open(Point::new(5, 0))).unwrap();
+        // The cascade leaves (0, 0) and (4, 2) pinned as mines by the 1s next
+        // to them, while (5, 2) stays closed.
+        let auto_flags: Vec<&Event> = events
+            .iter()
+            .filter(|e| matches!(e, Event::Flagged { .. }))
+            .collect();
+        assert_eq!(
+            auto_flags,
+            vec![
+                &Event::Flagged {
+                    point: Point::new(0, 0),
+                    source: Source::Auto
+                },
+                &Event::Flagged {
+                    point: Point::new(4, 2),
+                    source: Source::Auto
+                }
+            ]
+        );
+        assert_eq!(game.auto_flags(), 2);
+        assert_eq!(game.board().state, BoardState::Playing);
+    }
+
+    #[test]
+    fn test_auto_open_safe() {
+        let options = GameOptions {
+            auto_open_safe: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(six_by_three_board(), options);
+        let events = game.apply(&Move::Open(Point::new(5, 0))).unwrap();
+        let user_opens = events
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e,
+                    Event::Opened {
+                        source: Source::User,
+                        ..
+                    }
+                )
+            })
+            .count();
+        assert_eq!(user_opens, 15);
+        assert_eq!(
+            &events[15..],
+            &[
+                Event::Opened {
+                    point: Point::new(5, 2),
+                    count: 1,
+                    source: Source::Auto
+                },
+                Event::Won
+            ]
+        );
+        assert_eq!(game.board().state, BoardState::Won);
+    }
+
+    #[test]
+    fn test_mine_density() {
+        let board = BoardBuilder::new(10, 10, 0)
+            .seed(5)
+            .density(0.15)
+            .build()
+            .map(numbers_on_board)
+            .unwrap();
+        let mut game = Game::new(
+            board,
+            GameOptions {
+                mine_density: Some(150),
+                ..GameOptions::default()
+            },
+        );
+        assert!(game.hides_mine_count());
+        assert_eq!(game.mines_left(), Some(15));
+        let p = (0..100)
+            .map(|i| Point::new(i % 10, i / 10))
+            .find(|p| matches!(game.board().at(p), Some(Mine { .. })))
+            .unwrap();
+        game.apply(&Move::Flag(p)).unwrap();
+        assert_eq!(game.mines_left(), Some(14));
+    }
+
+    #[test]
+    fn test_hidden_mine_count() {
+        // The 1 has its mine on either side; only the mine count says the
+        // last cell is safe.
+        let board = Scenario::from_ascii("*...\n").into_board();
+        let options = GameOptions {
+            auto_open_safe: true,
+            ..GameOptions::default()
+        };
+        let mut counted = Game::new(board.clone(), options.clone());
+        assert_eq!(counted.mines_left(), Some(1));
+        counted.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        assert_eq!(counted.board().state, BoardState::Won);
+
+        let mut hidden = Game::new(
+            board,
+            GameOptions {
+                hidden_mine_count: true,
+                ..options
+            },
+        );
+        assert_eq!(hidden.mines_left(), None);
+        let events = hidden.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        assert_eq!(events.len(), 1);
+        hidden.apply(&Move::Open(Point::new(3, 0))).unwrap();
+        assert_eq!(hidden.board().state, BoardState::Won);
+    }
+
+    #[test]
+    fn test_coaching() {
+        let mut game =
+            Game::new(six_by_three_board(), GameOptions::default()).coaching(CoachOptions::all());
+        let events = game.apply(&Move::Open(Point::new(5, 0))).unwrap();
+        assert_eq!(
+            events.last(),
+            Some(&Event::Advice(Advice::SafeCellsAvailable {
+                points: vec![Point::new(5, 2)]
+            }))
+        );
+
+        game.set_coaching(None);
+        let events = game.apply(&Move::Flag(Point::new(5, 2))).unwrap();
+        assert!(!events.iter().any(|e| matches!(e, Event::Advice(_))));
+    }
+
+    #[test]
+    fn test_no_flag() {
+        let options = GameOptions {
+            auto_flag: true,
+            no_flag: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(six_by_three_board(), options);
+        assert_eq!(
+            game.apply(&Move::Flag(Point::new(0, 0))),
+            Err(Error::FlagsDisabled)
+        );
+        game.apply(&Move::Open(Point::new(5, 0))).unwrap();
+        assert_eq!(game.auto_flags(), 0);
+        assert!(game.is_no_flag());
+    }
+
+    #[test]
+    fn test_fog_of_war() {
+        let board = Scenario::from_ascii("..*.....\n..*.....\n").into_board();
+        let options = GameOptions {
+            fog: Some(1),
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(board, options);
+        assert!(game.is_legal(&Move::Open(Point::new(7, 1))));
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        assert_eq!(crate::render::game_text(&game), " 2.#####\n 2.#####\n");
+        assert_eq!(game.cell_view(&Point::new(2, 0)), Some(CellView::Hidden));
+        assert!(!game.is_legal(&Move::Open(Point::new(3, 0))));
+        assert_eq!(
+            game.apply(&Move::Open(Point::new(3, 0))),
+            Err(Error::OutOfReach { x: 3, y: 0 })
+        );
+        assert!(game.is_legal(&Move::Flag(Point::new(2, 0))));
+        assert!(!game.is_legal(&Move::Open(Point::new(0, 0))));
+        assert!(!game.is_legal(&Move::Open(Point::new(8, 0))));
+
+        // A radius past the board's size reaches all of it.
+        let board = Scenario::from_ascii("..*.....\n..*.....\n").into_board();
+        let options = GameOptions {
+            fog: Some(usize::MAX),
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(board, options);
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        assert!(game.is_legal(&Move::Open(Point::new(7, 1))));
+    }
+
+    #[test]
+    fn test_moving_mines() {
+        let ascii = "..........\n.......*..\n..........\n........*.\n..........\n\
+                     ......*...\n..........\n.........*\n..........\n.......*..\n";
+        let options = GameOptions {
+            moving_mines: Some(1),
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(Scenario::from_ascii(ascii).into_board(), options);
+        let mut shifted = 0;
+        // Numbers next to a mine, so the opens don't cascade up to every
+        // mine.
+        for p in [Point::new(6, 0), Point::new(9, 6)] {
+            for event in game.apply(&Move::Open(p)).unwrap_or_default() {
+                if let Event::Shifted { mines } = event {
+                    shifted += mines;
+                }
+            }
+        }
+        assert!(shifted > 0);
+        let board = game.board();
+        assert_eq!(board.mines, 5);
+        assert_eq!(&numbers_on_board(board.clone()).map, &board.map);
+        // The replay starts from the mines as dealt and moves them the same.
+        assert_eq!(game.replay().play().board(), game.board());
+    }
+
+    #[test]
+    fn test_timed_moves_skip_pauses() {
+        let clock = MockClock::new();
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        clock.advance(Duration::from_secs(10));
+        game.apply_timed(&Move::Open(Point::new(4, 0)), &clock)
+            .unwrap();
+        clock.advance(Duration::from_secs(2));
+        game.pause(&clock);
+        clock.advance(Duration::from_secs(100));
+        game.resume(&clock);
+        clock.advance(Duration::from_secs(1));
+        game.apply_timed(&Move::Open(Point::new(1, 0)), &clock)
+            .unwrap();
+        clock.advance(Duration::from_secs(1));
+        game.apply_timed(&Move::Open(Point::new(0, 1)), &clock)
+            .unwrap();
+        clock.advance(Duration::from_secs(30));
+
+        let at: Vec<Duration> = game.moves().iter().map(|m| m.at).collect();
+        assert_eq!(
+            at,
+            vec![
+                Duration::ZERO,
+                Duration::from_secs(3),
+                Duration::from_secs(4)
+            ]
+        );
+        assert_eq!(game.board().state, BoardState::Won);
+        assert_eq!(game.elapsed(&clock), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_deferred_game() {
+        let config = GameConfig {
+            width: 9,
+            height: 9,
+            mines: 10,
+            seed: 3,
+            options: GameOptions::default(),
+        };
+        for (x, y) in &[(0, 0), (4, 4), (8, 3)] {
+            let mut game = Game::new_deferred(config.clone()).unwrap();
+            assert!(!game.is_materialized());
+            assert_eq!(game.board().mines, 10);
+            game.apply(&Move::Flag(Point::new(8, 8))).unwrap();
+            game.apply(&Move::Open(Point::new(*x, *y))).unwrap();
+            assert!(game.is_materialized());
+            let board = game.board();
+            assert_eq!(
+                board.at(&Point::new(*x, *y)),
+                Some(&Number {
+                    state: Open,
+                    count: 0
+                })
+            );
+            assert!(matches!(
+                board.at(&Point::new(8, 8)),
+                Some(Mine { state: Flagged }) | Some(Number { state: Flagged, .. })
+            ));
+            let mines = board.map.iter().flatten();
+            assert_eq!(mines.filter(|el| matches!(el, Mine { .. })).count(), 10);
+        }
+
+        // Only the clicked cell is spared when the neighborhood can't be.
+        let mut game = Game::new_deferred(GameConfig {
+            width: 3,
+            height: 3,
+            mines: 8,
+            ..config.clone()
+        })
+        .unwrap();
+        game.apply(&Move::Open(Point::new(1, 1))).unwrap();
+        assert_eq!(game.board().state, BoardState::Won);
+        assert!(Game::new_deferred(GameConfig {
+            mines: 81,
+            ..config
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_border_free_deferred_game() {
+        let config = GameConfig {
+            width: 6,
+            height: 5,
+            mines: 11,
+            seed: 3,
+            options: GameOptions {
+                border_free: true,
+                ..GameOptions::default()
+            },
+        };
+        // The 4x3 inside holds the 11 mines and the first open.
+        let mut game = Game::new_deferred(config.clone()).unwrap();
+        game.apply(&Move::Open(Point::new(2, 2))).unwrap();
+        for p in border_cells(6, 5) {
+            assert!(matches!(game.board().at(&p), Some(Number { .. })));
+        }
+        assert_eq!(game.board().mines, 11);
+        // As many as BoardBuilder::border_free takes: the inside is all mines
+        // and the border is left to open.
+        let full = GameConfig {
+            mines: 12,
+            ..config.clone()
+        };
+        assert!(BoardBuilder::new(6, 5, 12)
+            .seed(3)
+            .border_free()
+            .build()
+            .is_ok());
+        let mut game = Game::new_deferred(full).unwrap();
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        assert_eq!(game.board().mines, 12);
+        assert!(Game::new_deferred(GameConfig {
+            mines: 13,
+            ..config
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_border_free_cruel_game() {
+        let board = numbers_on_board(
+            BoardBuilder::new(8, 8, 6)
+                .seed(1)
+                .border_free()
+                .build()
+                .unwrap(),
+        );
+        let options = GameOptions {
+            cruel: true,
+            border_free: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(board, options);
+        game.apply(&Move::Open(Point::new(3, 3))).unwrap();
+        // H1 could hold a mine if it weren't on the border.
+        let events = game.apply(&Move::Open(Point::new(7, 0))).unwrap();
+        assert!(!events.contains(&Event::Lost));
+        for p in border_cells(8, 8) {
+            assert!(matches!(game.board().at(&p), Some(Number { .. })));
+        }
+    }
+
+    #[test]
+    fn test_border_free_mines_stay_inside() {
+        // The only other cell for the coin flip's mine is on the border.
+        let board = Scenario::from_ascii("*.\n11\n").into_board();
+        let options = GameOptions {
+            kind: true,
+            border_free: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(board, options);
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        assert_eq!(game.board().state, BoardState::Failed);
+
+        let ascii = "..........\n.......*..\n..........\n........*.\n..........\n\
+                     ......*...\n..........\n........*.\n.......*..\n..........\n";
+        let options = GameOptions {
+            moving_mines: Some(1),
+            border_free: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(Scenario::from_ascii(ascii).into_board(), options);
+        let mut shifted = 0;
+        for p in [Point::new(0, 0), Point::new(0, 9), Point::new(9, 0)] {
+            for event in game.apply(&Move::Open(p)).unwrap_or_default() {
+                if let Event::Shifted { mines } = event {
+                    shifted += mines;
+                }
+            }
+            for q in border_cells(10, 10) {
+                assert!(!matches!(game.board().at(&q), Some(Mine { .. })), "{:?}", q);
+            }
+        }
+        assert!(shifted > 0);
+    }
+
+    #[test]
+    fn test_kind_mode_relocates_fifty_fifty() {
+        // Both closed cells touch the same two 1s.
+        let board = Scenario::from_ascii("*.\n11\n").into_board();
+        let kind = GameOptions {
+            kind: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(board.clone(), kind.clone());
+        assert_eq!(
+            game.apply(&Move::Open(Point::new(0, 0))).unwrap(),
+            vec![
+                Event::Relocated {
+                    from: Point::new(0, 0),
+                    to: Point::new(1, 0)
+                },
+                Event::Opened {
+                    point: Point::new(0, 0),
+                    count: 1,
+                    source: Source::User
+                },
+                Event::Won
+            ]
+        );
+
+        let mut game = Game::new(board, GameOptions::default());
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        assert_eq!(game.board().state, BoardState::Failed);
+
+        // A guess that isn't a coin flip is left alone.
+        let board = Scenario::from_ascii("*..\n...\n").into_board();
+        let mut game = Game::new(board, kind);
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        assert_eq!(game.board().state, BoardState::Failed);
+    }
+
+    #[test]
+    fn test_cruel_mode() {
+        let cruel = GameOptions {
+            cruel: true,
+            ..GameOptions::default()
+        };
+        // *2100
+        // 2*100
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(board, cruel.clone());
+        // The first open is spared.
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        // B1 is actually safe, but so could B2 be.
+        let events = game.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Rearranged {
+                    point: Point::new(1, 0)
+                },
+                Event::Exploded {
+                    point: Point::new(1, 0)
+                },
+                Event::Lost
+            ]
+        );
+        let mines: Vec<Point> = (0..2)
+            .flat_map(|y| (0..5).map(move |x| Point::new(x, y)))
+            .filter(|p| matches!(game.board().at(p), Some(Mine { .. })))
+            .collect();
+        assert_eq!(mines, vec![Point::new(0, 0), Point::new(1, 0)]);
+
+        // Only the mine count makes D1 safe, which is enough. Its 0 then
+        // opens C1 too.
+        let board = Scenario::from_ascii("*1..\n").into_board();
+        let mut game = Game::new(board, cruel);
+        game.apply(&Move::Open(Point::new(3, 0))).unwrap();
+        assert_eq!(game.board().state, BoardState::Won);
+    }
+}
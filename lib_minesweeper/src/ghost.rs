@@ -0,0 +1,159 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::game::{Game, LoggedMove};
+use crate::replay::Replay;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Open;
+use crate::{Board, Error, Move, Point, Result};
+
+// Where the ghost is at some point of the race.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GhostPosition {
+    // The cell of the ghost's last move, None before its first.
+    pub cursor: Option<Point>,
+    pub last_move: Option<Move>,
+    // Moves the ghost has made so far.
+    pub moves: usize,
+    // Fraction of the safe cells the ghost has opened, from 0 to 1.
+    pub progress: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Frame {
+    at: Duration,
+    action: Move,
+    progress: f64,
+}
+
+// A recorded game raced against a live one on the same board, for a "race
+// the ghost" overlay: ask where the ghost is at the live game's elapsed time.
+// Rejected moves are skipped, they didn't change anything.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ghost {
+    frames: Vec<Frame>,
+}
+
+fn mines(board: &Board) -> Vec<Point> {
+    (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+        .filter(|p| matches!(board.at(p), Some(Mine { .. })))
+        .collect()
+}
+
+fn progress(board: &Board) -> f64 {
+    let safe = board.width * board.height - board.mines;
+    let open = (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+        .filter(|p| matches!(board.at(p), Some(Number { state: Open, .. })))
+        .count();
+    if safe == 0 {
+        1.0
+    } else {
+        open as f64 / safe as f64
+    }
+}
+
+impl Ghost {
+    // Fails when the replay is of another board than the game's.
+    pub fn new(replay: &Replay, game: &Game) -> Result<Ghost> {
+        let board = game.board();
+        if (replay.board.width, replay.board.height) != (board.width, board.height)
+            || mines(&replay.board) != mines(board)
+        {
+            return Err(Error::InvalidConfig(String::from(
+                "the replay is of another board",
+            )));
+        }
+        let mut ghost = Game::new(replay.board.clone(), replay.options.clone());
+        let mut frames = Vec::new();
+        for LoggedMove {
+            action,
+            changed,
+            at,
+        } in &replay.moves
+        {
+            if *changed && ghost.play(action, *at).is_ok() {
+                frames.push(Frame {
+                    at: *at,
+                    action: *action,
+                    progress: progress(ghost.board()),
+                });
+            }
+        }
+        Ok(Ghost { frames })
+    }
+
+    pub fn position(&self, elapsed: Duration) -> GhostPosition {
+        let moves = self.frames.partition_point(|f| f.at <= elapsed);
+        let last = moves.checked_sub(1).map(|i| &self.frames[i]);
+        GhostPosition {
+            cursor: last.map(|f| match &f.action {
+                Move::Open(p) | Move::Flag(p) | Move::Power(_, p) => *p,
+            }),
+            last_move: last.map(|f| f.action),
+            moves,
+            progress: last.map_or(0.0, |f| f.progress),
+        }
+    }
+
+    // How far ahead of the ghost the live game is, as a fraction of the safe
+    // cells: negative when the ghost leads.
+    pub fn lead(&self, game: &Game, elapsed: Duration) -> f64 {
+        progress(game.board()) - self.position(elapsed).progress
+    }
+
+    // When the ghost made its last move.
+    pub fn finish_time(&self) -> Option<Duration> {
+        self.frames.last().map(|f| f.at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::game::GameOptions;
+    use crate::scenario::Scenario;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_race_the_ghost() {
+        // *2100
+        // 2*100
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let clock = MockClock::new();
+        let mut recorded = Game::new(board.clone(), GameOptions::default());
+        // The timer starts with the first move, so these land at 0s, 2s and
+        // 4s of game time.
+        for (wait, m) in &[
+            (0, Move::Open(Point::new(4, 0))),
+            (2, Move::Open(Point::new(1, 0))),
+            (2, Move::Open(Point::new(0, 1))),
+        ] {
+            clock.advance(Duration::from_secs(*wait));
+            recorded.apply_timed(m, &clock).unwrap();
+        }
+        let replay = recorded.replay();
+
+        let mut live = Game::new(board, GameOptions::default());
+        let ghost = Ghost::new(&replay, &live).unwrap();
+        assert_eq!(ghost.position(Duration::ZERO).moves, 1);
+        let position = ghost.position(Duration::from_secs(3));
+        assert_eq!(position.cursor, Some(Point::new(1, 0)));
+        assert_eq!(position.moves, 2);
+        assert!((position.progress - 7.0 / 8.0).abs() < 1e-9);
+        assert_eq!(ghost.finish_time(), Some(Duration::from_secs(4)));
+
+        live.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        assert!(ghost.lead(&live, Duration::from_secs(3)) < 0.0);
+
+        let other = Game::new(
+            Scenario::from_ascii("....*\n.*...\n").into_board(),
+            GameOptions::default(),
+        );
+        assert!(Ghost::new(&replay, &other).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::solver::{exact_probabilities, Constraint};
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{BoardState, Error, MapElement, Result};
+
+// A board whose cells are the nodes of any undirected graph, numbered from 0,
+// and whose counts are the mines on adjacent nodes. Mazes, maps and puzzle
+// shapes that no grid can describe all fit.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct GraphBoard {
+    cells: Vec<MapElement>,
+    neighbors: Vec<Vec<usize>>,
+    missing_points: usize,
+    pub mines: usize,
+    pub state: BoardState,
+}
+
+fn out_of_bounds(node: usize) -> Error {
+    Error::OutOfBounds {
+        x: node as i32,
+        y: 0,
+    }
+}
+
+// One "a b" edge per line. Blank lines and lines starting with '#' are
+// skipped.
+pub fn parse_edge_list(text: &str) -> Result<Vec<(usize, usize)>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let nodes: Vec<usize> = line
+                .split_whitespace()
+                .map(|n| n.parse().ok())
+                .collect::<Option<_>>()
+                .ok_or_else(|| Error::ParseError(format!("bad edge {:?}", line)))?;
+            match nodes[..] {
+                [a, b] => Ok((a, b)),
+                _ => Err(Error::ParseError(format!("bad edge {:?}", line))),
+            }
+        })
+        .collect()
+}
+
+impl GraphBoard {
+    // Places the mines with rand like create_board. Self loops and repeated
+    // edges are ignored.
+    pub fn new(
+        nodes: usize,
+        edges: &[(usize, usize)],
+        mines: usize,
+        mut rand: impl FnMut(usize, usize) -> usize,
+    ) -> Result<GraphBoard> {
+        if nodes == 0 {
+            return Err(Error::InvalidConfig(String::from(
+                "board must have at least one cell",
+            )));
+        }
+        if mines >= nodes {
+            return Err(Error::InvalidConfig(format!(
+                "{} mines do not fit in a board of {} cells",
+                mines, nodes
+            )));
+        }
+        let mut neighbors = vec![vec![]; nodes];
+        for &(a, b) in edges {
+            if a >= nodes || b >= nodes {
+                return Err(Error::InvalidConfig(format!(
+                    "edge {}-{} is outside the {} cells",
+                    a, b, nodes
+                )));
+            }
+            if a != b && !neighbors[a].contains(&b) {
+                neighbors[a].push(b);
+                neighbors[b].push(a);
+            }
+        }
+        let mut cells = vec![
+            Number {
+                state: Closed,
+                count: 0
+            };
+            nodes
+        ];
+        for _ in 0..mines {
+            loop {
+                let node = rand(0, nodes);
+                if !matches!(cells[node], Mine { .. }) {
+                    cells[node] = Mine { state: Closed };
+                    break;
+                }
+            }
+        }
+        for node in 0..nodes {
+            let around = neighbors[node]
+                .iter()
+                .filter(|&&n| matches!(cells[n], Mine { .. }))
+                .count() as i32;
+            if let Number { count, .. } = &mut cells[node] {
+                *count = around;
+            }
+        }
+        Ok(GraphBoard {
+            cells,
+            neighbors,
+            missing_points: nodes - mines,
+            mines,
+            state: BoardState::Ready,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn at(&self, node: usize) -> Option<&MapElement> {
+        self.cells.get(node)
+    }
+
+    pub fn neighbors(&self, node: usize) -> &[usize] {
+        self.neighbors.get(node).map_or(&[], |n| &n[..])
+    }
+
+    fn checked_at(&self, node: usize) -> Result<&MapElement> {
+        if matches!(self.state, BoardState::Won | BoardState::Failed) {
+            return Err(Error::GameOver);
+        }
+        self.at(node).ok_or_else(|| out_of_bounds(node))
+    }
+
+    pub fn flag_item(&self, node: usize) -> Result<GraphBoard> {
+        if let Number { state: Open, .. } | Mine { state: Open } = self.checked_at(node)? {
+            return Err(Error::CellAlreadyOpen {
+                x: node as i32,
+                y: 0,
+            });
+        }
+        let mut board = self.clone();
+        let (Mine { state } | Number { state, .. }) = &mut board.cells[node];
+        *state = if *state == Flagged { Closed } else { Flagged };
+        Ok(board)
+    }
+
+    // Zeros open their neighbors, as on a grid.
+    pub fn cascade_open_item(&self, node: usize) -> Result<GraphBoard> {
+        match self.checked_at(node)? {
+            Number { state: Open, .. } => {
+                return Err(Error::CellAlreadyOpen {
+                    x: node as i32,
+                    y: 0,
+                })
+            }
+            Mine { state: Flagged } | Number { state: Flagged, .. } => {
+                return Err(Error::CellFlagged {
+                    x: node as i32,
+                    y: 0,
+                })
+            }
+            _ => (),
+        }
+        let mut board = self.clone();
+        if let Mine { .. } = board.cells[node] {
+            board.cells[node] = Mine { state: Open };
+            board.state = BoardState::Failed;
+            return Ok(board);
+        }
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            if let Number { state, count } = &mut board.cells[node] {
+                if *state == Closed {
+                    *state = Open;
+                    board.missing_points -= 1;
+                    if *count == 0 {
+                        stack.extend(&board.neighbors[node]);
+                    }
+                }
+            }
+        }
+        board.state = if board.missing_points == 0 {
+            BoardState::Won
+        } else {
+            BoardState::Playing
+        };
+        Ok(board)
+    }
+
+    // Mine probability of every closed node, None for open ones. None
+    // altogether when the frontier is too large to solve exactly.
+    pub fn probabilities(&self) -> Option<Vec<Option<f64>>> {
+        let unknown: Vec<bool> = self
+            .cells
+            .iter()
+            .map(|el| !matches!(el, Number { state: Open, .. }))
+            .collect();
+        let constraints: Vec<Constraint> = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter_map(|(node, el)| match el {
+                Number { state: Open, count } => {
+                    let cells: Vec<usize> = self.neighbors[node]
+                        .iter()
+                        .copied()
+                        .filter(|&n| unknown[n])
+                        .collect();
+                    if cells.is_empty() {
+                        None
+                    } else {
+                        Some(Constraint {
+                            cells,
+                            mines: *count as usize,
+                        })
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+        exact_probabilities(&unknown, &constraints, self.mines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // A ring of six nodes, 0-1-2-3-4-5-0, with a mine on node 3.
+    fn ring() -> GraphBoard {
+        let edges = parse_edge_list("# ring\n0 1\n1 2\n2 3\n3 4\n4 5\n\n5 0\n").unwrap();
+        GraphBoard::new(6, &edges, 1, |_, _| 3).unwrap()
+    }
+
+    #[test]
+    fn test_parse_edge_list() {
+        assert_eq!(parse_edge_list("0 1\n 2 3 \n"), Ok(vec![(0, 1), (2, 3)]));
+        assert!(parse_edge_list("0 1 2\n").is_err());
+        assert!(parse_edge_list("0 x\n").is_err());
+        assert!(GraphBoard::new(2, &[(0, 2)], 0, |_, _| 0).is_err());
+    }
+
+    #[test]
+    fn test_play_ring() {
+        let board = ring();
+        assert_eq!(board.neighbors(0), &[1, 5]);
+        let board = board.cascade_open_item(0).unwrap();
+        // The 0 cascades both ways round the ring, up to the 1s by the mine.
+        let open: Vec<usize> = (0..6)
+            .filter(|&n| matches!(board.at(n), Some(Number { state: Open, .. })))
+            .collect();
+        assert_eq!(open, vec![0, 1, 2, 4, 5]);
+        assert_eq!(board.state, BoardState::Won);
+
+        let board = ring().cascade_open_item(2).unwrap();
+        assert_eq!(board.state, BoardState::Playing);
+        // The 1 has the mine on either side, and then the rest are safe.
+        let probabilities = board.probabilities().unwrap();
+        for (node, expected) in &[(1, 0.5), (3, 0.5), (5, 0.0)] {
+            assert!((probabilities[*node].unwrap() - expected).abs() < 1e-9);
+        }
+        assert_eq!(probabilities[2], None);
+        let board = board.flag_item(3).unwrap();
+        assert_eq!(
+            board.cascade_open_item(3),
+            Err(Error::CellFlagged { x: 3, y: 0 })
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, Move, Point};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Space,
+    Escape,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+// What a frontend reads from the terminal or window. Clicks carry the cell
+// under the pointer, see layout::cell_at_pixel.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key(Key),
+    Click { button: MouseButton, cell: Point },
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Action {
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    Open,
+    Flag,
+}
+
+// Which key or button does what. Loadable with serde, so players can keep
+// their own in a config file; anything unbound is ignored.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Bindings {
+    pub keys: Vec<(Key, Action)>,
+    pub buttons: Vec<(MouseButton, Action)>,
+}
+
+// Arrows and vi keys move, space opens and f flags. Left click opens and
+// right click flags.
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings {
+            keys: vec![
+                (Key::Up, Action::CursorUp),
+                (Key::Down, Action::CursorDown),
+                (Key::Left, Action::CursorLeft),
+                (Key::Right, Action::CursorRight),
+                (Key::Char('k'), Action::CursorUp),
+                (Key::Char('j'), Action::CursorDown),
+                (Key::Char('h'), Action::CursorLeft),
+                (Key::Char('l'), Action::CursorRight),
+                (Key::Space, Action::Open),
+                (Key::Enter, Action::Open),
+                (Key::Char('f'), Action::Flag),
+            ],
+            buttons: vec![
+                (MouseButton::Left, Action::Open),
+                (MouseButton::Right, Action::Flag),
+            ],
+        }
+    }
+}
+
+impl Bindings {
+    // Binds the key to the action, replacing what it did before.
+    pub fn bind_key(&mut self, key: Key, action: Action) {
+        self.keys.retain(|(k, _)| *k != key);
+        self.keys.push((key, action));
+    }
+
+    pub fn bind_button(&mut self, button: MouseButton, action: Action) {
+        self.buttons.retain(|(b, _)| *b != button);
+        self.buttons.push((button, action));
+    }
+
+    pub fn unbind_key(&mut self, key: Key) {
+        self.keys.retain(|(k, _)| *k != key);
+    }
+
+    pub fn action(&self, event: &InputEvent) -> Option<Action> {
+        match event {
+            InputEvent::Key(key) => self.keys.iter().find(|(k, _)| k == key).map(|(_, a)| *a),
+            InputEvent::Click { button, .. } => self
+                .buttons
+                .iter()
+                .find(|(b, _)| b == button)
+                .map(|(_, a)| *a),
+        }
+    }
+}
+
+// Turns input into moves: keys move a cursor over the board and act on the
+// cell under it, clicks act where they land. The button bound to opening is
+// the primary gesture and the one bound to flagging the secondary, resolved
+// as resolve_gesture does, so the interaction mode swaps them.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Input {
+    pub bindings: Bindings,
+    // None for InteractionConfig's default.
+    pub interaction: Option<InteractionConfig>,
+    cursor: Point,
+}
+
+impl Input {
+    pub fn new(bindings: Bindings) -> Input {
+        Input {
+            bindings,
+            interaction: None,
+            cursor: Point::new(0, 0),
+        }
+    }
+
+    pub fn cursor(&self) -> &Point {
+        &self.cursor
+    }
+
+    // The move the event asks for, if any. The cursor stays on the board;
+    // clicks outside of it do nothing, and neither do clicks resolve_gesture
+    // turns down.
+    pub fn handle(&mut self, event: &InputEvent, board: &Board) -> Option<Move> {
+        if let InputEvent::Click { cell, .. } = event {
+            board.at(cell)?;
+            self.cursor = *cell;
+        }
+        let (max_x, max_y) = (board.width as i32 - 1, board.height as i32 - 1);
+        let Point { x, y } = self.cursor;
+        let action = self.bindings.action(event)?;
+        if let (InputEvent::Click { cell, .. }, Action::Open | Action::Flag) = (event, action) {
+            let gesture = match action {
+                Action::Open => Gesture::Primary,
+                _ => Gesture::Secondary,
+            };
+            let config = self.interaction.unwrap_or_default();
+            return resolve_gesture(&config, gesture, cell, board);
+        }
+        match action {
+            Action::CursorUp => self.cursor.y = (y - 1).max(0),
+            Action::CursorDown => self.cursor.y = (y + 1).min(max_y),
+            Action::CursorLeft => self.cursor.x = (x - 1).max(0),
+            Action::CursorRight => self.cursor.x = (x + 1).min(max_x),
+            Action::Open => return Some(Move::Open(self.cursor)),
+            Action::Flag => return Some(Move::Flag(self.cursor)),
+        }
+        None
+    }
+}
+
+impl Default for Input {
+    fn default() -> Input {
+        Input::new(Bindings::default())
+    }
+}
+
+// What a pointer or touch frontend recognized, before it means anything.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Gesture {
+    // A left click or a tap.
+    Primary,
+    // A right click. Touch screens have none.
+    Secondary,
+    LongPress,
+    DoubleTap,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum GestureMode {
+    // The primary gesture opens, as on desktops.
+    OpenFirst,
+    // The primary gesture flags, as mobile games offer for flag-heavy play.
+    FlagFirst,
+}
+
+// How gestures map to moves, the same way in every frontend, see
+// resolve_gesture. The primary gesture does what the mode says and the
+// secondary one the other thing; a long press and a double tap do the other
+// thing too when they're on, and nothing otherwise.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct InteractionConfig {
+    pub mode: GestureMode,
+    pub long_press: bool,
+    pub double_tap: bool,
+}
+
+// Open first, with long presses for flags: what touch screens without a
+// mode toggle need.
+impl Default for InteractionConfig {
+    fn default() -> InteractionConfig {
+        InteractionConfig {
+            mode: GestureMode::OpenFirst,
+            long_press: true,
+            double_tap: false,
+        }
+    }
+}
+
+impl InteractionConfig {
+    // For the mode button mobile frontends show.
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            GestureMode::OpenFirst => GestureMode::FlagFirst,
+            GestureMode::FlagFirst => GestureMode::OpenFirst,
+        };
+    }
+
+    pub fn action(&self, gesture: Gesture) -> Option<Action> {
+        let (first, other) = match self.mode {
+            GestureMode::OpenFirst => (Action::Open, Action::Flag),
+            GestureMode::FlagFirst => (Action::Flag, Action::Open),
+        };
+        match gesture {
+            Gesture::Primary => Some(first),
+            Gesture::Secondary => Some(other),
+            Gesture::LongPress if self.long_press => Some(other),
+            Gesture::DoubleTap if self.double_tap => Some(other),
+            _ => None,
+        }
+    }
+}
+
+// The move a gesture on the cell asks for. None off the board, on open
+// cells, and for opening a flagged cell: gestures never ask for a move the
+// board would refuse.
+pub fn resolve_gesture(
+    config: &InteractionConfig,
+    gesture: Gesture,
+    cell: &Point,
+    board: &Board,
+) -> Option<Move> {
+    let state = match board.at(cell)? {
+        Mine { state } | Number { state, .. } => *state,
+    };
+    match (config.action(gesture)?, state) {
+        (_, Open) | (Action::Open, Flagged) => None,
+        (Action::Open, _) => Some(Move::Open(*cell)),
+        (Action::Flag, _) => Some(Move::Flag(*cell)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_remapped_input() {
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut input = Input::default();
+        assert_eq!(input.handle(&InputEvent::Key(Key::Up), &board), None);
+        input.handle(&InputEvent::Key(Key::Char('l')), &board);
+        input.handle(&InputEvent::Key(Key::Down), &board);
+        input.handle(&InputEvent::Key(Key::Down), &board);
+        assert_eq!(input.cursor(), &Point::new(1, 1));
+        assert_eq!(
+            input.handle(&InputEvent::Key(Key::Space), &board),
+            Some(Move::Open(Point::new(1, 1)))
+        );
+
+        input.bindings.bind_key(Key::Space, Action::Flag);
+        input.bindings.unbind_key(Key::Char('f'));
+        assert_eq!(
+            input.handle(&InputEvent::Key(Key::Space), &board),
+            Some(Move::Flag(Point::new(1, 1)))
+        );
+        assert_eq!(input.handle(&InputEvent::Key(Key::Char('f')), &board), None);
+
+        let click = |cell| InputEvent::Click {
+            button: MouseButton::Right,
+            cell,
+        };
+        assert_eq!(
+            input.handle(&click(Point::new(4, 0)), &board),
+            Some(Move::Flag(Point::new(4, 0)))
+        );
+        assert_eq!(input.handle(&click(Point::new(5, 0)), &board), None);
+        assert_eq!(input.cursor(), &Point::new(4, 0));
+    }
+
+    #[test]
+    fn test_clicks_resolve_as_gestures() {
+        let board = Scenario::from_ascii("*1..\n").into_board();
+        let board = board.flag_item(&Point::new(2, 0)).unwrap();
+        let mut input = Input::default();
+        let click = |button, x| InputEvent::Click {
+            button,
+            cell: Point::new(x, 0),
+        };
+        assert_eq!(
+            input.handle(&click(MouseButton::Left, 3), &board),
+            Some(Move::Open(Point::new(3, 0)))
+        );
+        // Open cells, and opening a flagged one.
+        assert_eq!(input.handle(&click(MouseButton::Left, 1), &board), None);
+        assert_eq!(input.handle(&click(MouseButton::Left, 2), &board), None);
+
+        let mut flag_first = InteractionConfig::default();
+        flag_first.toggle_mode();
+        input.interaction = Some(flag_first);
+        assert_eq!(
+            input.handle(&click(MouseButton::Left, 3), &board),
+            Some(Move::Flag(Point::new(3, 0)))
+        );
+        assert_eq!(
+            input.handle(&click(MouseButton::Right, 3), &board),
+            Some(Move::Open(Point::new(3, 0)))
+        );
+        // Keys act on the cursor as bound, whatever the mode.
+        assert_eq!(
+            input.handle(&InputEvent::Key(Key::Space), &board),
+            Some(Move::Open(Point::new(3, 0)))
+        );
+    }
+
+    #[test]
+    fn test_resolve_gesture() {
+        let board = Scenario::from_ascii("*1..\n").into_board();
+        let board = board.flag_item(&Point::new(2, 0)).unwrap();
+        let mut config = InteractionConfig::default();
+        let resolve = |config: &InteractionConfig, gesture, x| {
+            resolve_gesture(config, gesture, &Point::new(x, 0), &board)
+        };
+        assert_eq!(
+            resolve(&config, Gesture::Primary, 3),
+            Some(Move::Open(Point::new(3, 0)))
+        );
+        assert_eq!(
+            resolve(&config, Gesture::LongPress, 0),
+            Some(Move::Flag(Point::new(0, 0)))
+        );
+        assert_eq!(resolve(&config, Gesture::DoubleTap, 0), None);
+        // Open cells and off the board.
+        assert_eq!(resolve(&config, Gesture::Primary, 1), None);
+        assert_eq!(resolve(&config, Gesture::Primary, 4), None);
+        // A flagged cell can only be unflagged.
+        assert_eq!(resolve(&config, Gesture::Primary, 2), None);
+
+        config.toggle_mode();
+        config.double_tap = true;
+        assert_eq!(
+            resolve(&config, Gesture::Primary, 2),
+            Some(Move::Flag(Point::new(2, 0)))
+        );
+        assert_eq!(
+            resolve(&config, Gesture::DoubleTap, 3),
+            Some(Move::Open(Point::new(3, 0)))
+        );
+        assert_eq!(
+            resolve(&config, Gesture::Secondary, 3),
+            Some(Move::Open(Point::new(3, 0)))
+        );
+    }
+}
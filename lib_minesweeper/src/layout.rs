@@ -0,0 +1,301 @@
+use crate::{Board, Point};
+
+// Pixel maths shared by frontends that draw the board as a grid of square
+// cells. `origin` is where the top-left corner of cell (0, 0) is drawn; with
+// a scrolled board it's off screen, to the top left.
+
+// The cell under a pixel, or None off the board. Cell sizes of 0 count as 1.
+pub fn cell_at_pixel(
+    x: i32,
+    y: i32,
+    cell_size: usize,
+    origin: (i32, i32),
+    board: &Board,
+) -> Option<Point> {
+    let size = cell_size.max(1) as i32;
+    let p = Point {
+        x: (x - origin.0).div_euclid(size),
+        y: (y - origin.1).div_euclid(size),
+    };
+    board.at(&p).map(|_| p)
+}
+
+// The top-left pixel of a cell.
+pub fn cell_origin(p: &Point, cell_size: usize, origin: (i32, i32)) -> (i32, i32) {
+    let size = cell_size as i32;
+    (origin.0 + p.x * size, origin.1 + p.y * size)
+}
+
+// The middle pixel of a cell, rounded up and left.
+pub fn cell_center(p: &Point, cell_size: usize, origin: (i32, i32)) -> (i32, i32) {
+    let (x, y) = cell_origin(p, cell_size, origin);
+    let half = cell_size as i32 / 2;
+    (x + half, y + half)
+}
+
+// Scrolling over a board larger than the screen, in cells: the offset is
+// the first cell shown, and `visible` how many fit across and down. Offsets
+// are kept so the screen never shows past the board's edges.
+pub fn clamp_offset(offset: (i32, i32), visible: (usize, usize), board: &Board) -> (i32, i32) {
+    clamp(offset, visible, (board.width, board.height))
+}
+
+fn clamp(offset: (i32, i32), visible: (usize, usize), size: (usize, usize)) -> (i32, i32) {
+    let max_x = size.0.saturating_sub(visible.0) as i32;
+    let max_y = size.1.saturating_sub(visible.1) as i32;
+    (offset.0.max(0).min(max_x), offset.1.max(0).min(max_y))
+}
+
+fn follow(at: i32, offset: i32, visible: usize) -> i32 {
+    let visible = visible.max(1) as i32;
+    if at < offset {
+        at
+    } else if at >= offset + visible {
+        at - visible + 1
+    } else {
+        offset
+    }
+}
+
+pub fn pan(
+    offset: (i32, i32),
+    by: (i32, i32),
+    visible: (usize, usize),
+    board: &Board,
+) -> (i32, i32) {
+    clamp_offset((offset.0 + by.0, offset.1 + by.1), visible, board)
+}
+
+// The least scrolling that brings a cell, say the keyboard cursor, on
+// screen.
+pub fn scroll_to(
+    p: &Point,
+    offset: (i32, i32),
+    visible: (usize, usize),
+    board: &Board,
+) -> (i32, i32) {
+    clamp_offset(
+        (
+            follow(p.x, offset.0, visible.0),
+            follow(p.y, offset.1, visible.1),
+        ),
+        visible,
+        board,
+    )
+}
+
+// The origin to draw at so the cell at `offset` lands on the screen's
+// top-left pixel `screen`.
+pub fn scrolled_origin(offset: (i32, i32), cell_size: usize, screen: (i32, i32)) -> (i32, i32) {
+    let size = cell_size as i32;
+    (screen.0 - offset.0 * size, screen.1 - offset.1 * size)
+}
+
+// What part of a board a screen shows: an offset in cells, and a zoom given
+// as the cell size in pixels. Boards without edges, like a board that grows
+// as it's explored, have no bounds and can be panned anywhere; bounded ones
+// keep the screen on the board.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Viewport {
+    offset: (i32, i32),
+    cell_size: usize,
+    // In pixels.
+    screen: (usize, usize),
+    bounds: Option<(usize, usize)>,
+}
+
+impl Viewport {
+    // Unbounded, looking at cell (0, 0).
+    pub fn new(screen: (usize, usize), cell_size: usize) -> Viewport {
+        Viewport {
+            offset: (0, 0),
+            cell_size: cell_size.max(1),
+            screen,
+            bounds: None,
+        }
+    }
+
+    pub fn for_board(board: &Board, screen: (usize, usize), cell_size: usize) -> Viewport {
+        Viewport {
+            bounds: Some((board.width, board.height)),
+            ..Viewport::new(screen, cell_size)
+        }
+    }
+
+    // The first cell shown, top left.
+    pub fn offset(&self) -> (i32, i32) {
+        self.offset
+    }
+
+    pub fn cell_size(&self) -> usize {
+        self.cell_size
+    }
+
+    // Cells that fit on screen whole, across and down.
+    pub fn visible(&self) -> (usize, usize) {
+        (
+            self.screen.0 / self.cell_size,
+            self.screen.1 / self.cell_size,
+        )
+    }
+
+    // Cells at least partly on screen, across and down.
+    pub fn span(&self) -> (usize, usize) {
+        (
+            self.screen.0.div_ceil(self.cell_size),
+            self.screen.1.div_ceil(self.cell_size),
+        )
+    }
+
+    fn clamped(&self, offset: (i32, i32)) -> (i32, i32) {
+        match self.bounds {
+            Some(size) => clamp(offset, self.visible(), size),
+            None => offset,
+        }
+    }
+
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        self.offset = self.clamped((self.offset.0 + dx, self.offset.1 + dy));
+    }
+
+    pub fn scroll_to(&mut self, p: &Point) {
+        let visible = self.visible();
+        self.offset = self.clamped((
+            follow(p.x, self.offset.0, visible.0),
+            follow(p.y, self.offset.1, visible.1),
+        ));
+    }
+
+    pub fn resize(&mut self, screen: (usize, usize)) {
+        self.screen = screen;
+        self.offset = self.clamped(self.offset);
+    }
+
+    // Changes the cell size, keeping the cell under the `anchor` pixel (the
+    // mouse, say) where it is as far as whole cells allow.
+    pub fn zoom(&mut self, cell_size: usize, anchor: (i32, i32)) {
+        let under = self.cell_at_pixel(anchor.0, anchor.1);
+        self.cell_size = cell_size.max(1);
+        let size = self.cell_size as i32;
+        self.offset = self.clamped((
+            under.x - anchor.0.div_euclid(size),
+            under.y - anchor.1.div_euclid(size),
+        ));
+    }
+
+    // Screen pixels are counted from the screen's top-left corner.
+    pub fn cell_at_pixel(&self, x: i32, y: i32) -> Point {
+        let size = self.cell_size as i32;
+        Point {
+            x: self.offset.0 + x.div_euclid(size),
+            y: self.offset.1 + y.div_euclid(size),
+        }
+    }
+
+    pub fn cell_origin(&self, p: &Point) -> (i32, i32) {
+        cell_origin(
+            p,
+            self.cell_size,
+            scrolled_origin(self.offset, self.cell_size, (0, 0)),
+        )
+    }
+
+    // Every cell at least partly on screen, row by row, leaving out cells
+    // past a bounded board's edges.
+    pub fn visible_cells(&self) -> impl Iterator<Item = Point> {
+        let (across, down) = self.span();
+        let (x0, y0) = self.offset;
+        let bounds = self.bounds;
+        (y0..y0 + down as i32)
+            .flat_map(move |y| (x0..x0 + across as i32).map(move |x| Point { x, y }))
+            .filter(move |p| match bounds {
+                Some((w, h)) => p.x >= 0 && p.y >= 0 && (p.x as usize) < w && (p.y as usize) < h,
+                None => true,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use alloc::format;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_hit_testing() {
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let origin = (10, 20);
+        assert_eq!(
+            cell_at_pixel(10, 20, 16, origin, &board),
+            Some(Point::new(0, 0))
+        );
+        assert_eq!(
+            cell_at_pixel(10 + 16 * 4 + 15, 20 + 17, 16, origin, &board),
+            Some(Point::new(4, 1))
+        );
+        assert_eq!(cell_at_pixel(9, 20, 16, origin, &board), None);
+        assert_eq!(cell_at_pixel(10 + 16 * 5, 20, 16, origin, &board), None);
+        let p = Point::new(3, 1);
+        let (x, y) = cell_center(&p, 16, origin);
+        assert_eq!(cell_at_pixel(x, y, 16, origin, &board), Some(p));
+    }
+
+    #[test]
+    fn test_scrolling() {
+        let ascii = format!("*.........\n{}", "..........\n".repeat(9));
+        let board = Scenario::from_ascii(&ascii).into_board();
+        let visible = (4, 3);
+        assert_eq!(pan((0, 0), (-2, 1), visible, &board), (0, 1));
+        assert_eq!(pan((5, 5), (3, 3), visible, &board), (6, 7));
+        assert_eq!(
+            scroll_to(&Point::new(8, 2), (0, 0), visible, &board),
+            (5, 0)
+        );
+        assert_eq!(
+            scroll_to(&Point::new(1, 1), (5, 0), visible, &board),
+            (1, 0)
+        );
+
+        let origin = scrolled_origin((2, 1), 16, (0, 0));
+        assert_eq!(
+            cell_at_pixel(0, 0, 16, origin, &board),
+            Some(Point::new(2, 1))
+        );
+    }
+
+    #[test]
+    fn test_viewport() {
+        let ascii = format!("*.........\n{}", "..........\n".repeat(9));
+        let board = Scenario::from_ascii(&ascii).into_board();
+        let mut viewport = Viewport::for_board(&board, (70, 48), 16);
+        assert_eq!(viewport.visible(), (4, 3));
+        // 70 pixels show 4 cells and a bit of a fifth.
+        assert_eq!(viewport.visible_cells().count(), 15);
+        viewport.pan(100, 1);
+        assert_eq!(viewport.offset(), (6, 1));
+        assert_eq!(viewport.visible_cells().count(), 12);
+        assert_eq!(viewport.cell_at_pixel(17, 0), Point::new(7, 1));
+        assert_eq!(viewport.cell_origin(&Point::new(7, 1)), (16, 0));
+
+        viewport.zoom(32, (17, 0));
+        assert_eq!(viewport.cell_size(), 32);
+        assert_eq!(viewport.offset(), (7, 1));
+        viewport.scroll_to(&Point::new(0, 9));
+        assert_eq!(viewport.offset(), (0, 9));
+
+        let mut endless = Viewport::new((32, 32), 16);
+        endless.pan(-5, -5);
+        assert_eq!(
+            endless.visible_cells().collect::<Vec<_>>(),
+            vec![
+                Point { x: -5, y: -5 },
+                Point { x: -4, y: -5 },
+                Point { x: -5, y: -4 },
+                Point { x: -4, y: -4 }
+            ]
+        );
+    }
+}
@@ -1,6 +1,86 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+mod adjacency;
+pub mod analysis;
+pub mod annotations;
+mod backend;
+pub mod benchmark;
+pub mod board3d;
+pub mod chunks;
+pub mod clock;
+pub mod coach;
+pub mod coop;
+pub mod editor;
+pub mod env;
+mod error;
+pub mod feedback;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+pub mod frontier;
+pub mod game;
+pub mod ghost;
+pub mod graph;
+pub mod input;
+pub mod layout;
+pub mod lod;
+#[cfg(feature = "storage")]
+pub mod migrations;
+#[cfg(feature = "std")]
+pub mod noise;
+#[cfg(feature = "std")]
+pub mod opening;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub mod power;
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod quantum;
+pub mod random;
+#[cfg(feature = "std")]
+pub mod rating;
+#[cfg(feature = "storage")]
+pub mod recovery;
+pub mod render;
+pub mod replay;
+pub mod ruleset;
+#[cfg(feature = "sat")]
+pub mod sat;
+pub mod scenario;
+pub mod session;
+mod share;
+#[cfg(feature = "std")]
+pub mod simulate;
+pub mod solver;
+pub mod spectator;
+pub mod speculate;
+pub mod splits;
+pub mod stats;
+#[cfg(feature = "storage")]
+pub mod storage;
+pub mod symmetry;
+pub mod tablebase;
+pub mod topology;
+pub mod tournament;
+pub mod transaction;
+pub mod treasure;
+pub mod versus;
+pub mod view;
+
+pub use adjacency::Adjacency;
+pub use backend::{BoardRead, BoardWrite};
+pub use error::{Error, Result};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum MapElement {
     Mine {
         state: MapElementCellState,
@@ -10,7 +90,7 @@ pub enum MapElement {
         count: i32,
     },
 }
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum MapElementCellState {
     Closed,
     Open,
@@ -23,7 +103,9 @@ use MapElementCellState::Closed;
 use MapElementCellState::Flagged;
 use MapElementCellState::Open;
 
-#[derive(Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[derive(
+    Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default, Serialize, Deserialize,
+)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
@@ -35,9 +117,24 @@ impl Point {
         let y = y as i32;
         Point { x, y }
     }
+
+    // None when (x, y) is off a board of bounds = (width, height).
+    pub fn checked(x: usize, y: usize, bounds: (usize, usize)) -> Option<Point> {
+        if x >= bounds.0 || y >= bounds.1 || x > i32::MAX as usize || y > i32::MAX as usize {
+            return None;
+        }
+        Some(Point::new(x, y))
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Move {
+    Open(Point),
+    Flag(Point),
+    Power(power::PowerUp, Point),
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum BoardState {
     NotReady,
     Ready,
@@ -46,7 +143,7 @@ pub enum BoardState {
     Failed,
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct Board {
     map: Vec<Vec<MapElement>>,
     missing_points: i32,
@@ -54,28 +151,44 @@ pub struct Board {
     pub height: usize,
     pub mines: usize,
     pub state: BoardState,
+    // Set when the mines were placed from a seed, so the board can be
+    // recreated from a share code.
+    pub seed: Option<u64>,
+    // Safe cells worth a bonus once open, on boards of the treasure variant,
+    // see BoardBuilder::treasures. None on classic boards.
+    pub treasures: Option<Vec<Point>>,
 }
 
 impl Board {
-    pub fn new(map: Vec<Vec<MapElement>>) -> Board {
+    pub fn new(map: Vec<Vec<MapElement>>) -> Result<Board> {
+        let width = match map.first() {
+            Some(row) if !row.is_empty() => row.len(),
+            _ => return Err(Error::InvalidConfig(String::from("map is empty"))),
+        };
+        if map.iter().any(|row| row.len() != width) {
+            return Err(Error::InvalidConfig(String::from(
+                "map rows have different widths",
+            )));
+        }
         let mines = map
             .iter()
             .flat_map(|x| x.iter())
-            .filter(|x| matches!(x, Mine{..}))
+            .filter(|x| matches!(x, Mine { .. }))
             .count();
-        let width = map.iter().next().unwrap().len();
         let height = map.len();
-        Board {
+        Ok(Board {
             width,
             height,
             mines,
             missing_points: (width as i32) * (height as i32) - (mines as i32),
             state: BoardState::NotReady,
+            seed: None,
+            treasures: None,
             map,
-        }
+        })
     }
 
-    pub fn at(self: &Self, p: &Point) -> Option<&MapElement> {
+    pub fn at(&self, p: &Point) -> Option<&MapElement> {
         let width = self.width as i32;
         let height = self.height as i32;
         if p.x < 0 || p.x >= width || p.y < 0 || p.y >= height {
@@ -87,16 +200,39 @@ impl Board {
         }
     }
 
-    fn replace(self: &Self, p: &Point, el: MapElement) -> Board {
+    // Where the cell is when the board is laid out row by row, as flat
+    // storage, bitboards and the solvers number cells. None off the board.
+    pub fn index_of(&self, p: &Point) -> Option<usize> {
+        self.at(p)?;
+        Some(p.y as usize * self.width + p.x as usize)
+    }
+
+    // The other way around from index_of.
+    pub fn point_of(&self, index: usize) -> Option<Point> {
+        Point::checked(
+            index % self.width,
+            index / self.width,
+            (self.width, self.height),
+        )
+    }
+
+    fn checked_at(&self, p: &Point) -> Result<&MapElement> {
+        if matches!(self.state, BoardState::Won | BoardState::Failed) {
+            return Err(Error::GameOver);
+        }
+        self.at(p).ok_or(Error::OutOfBounds { x: p.x, y: p.y })
+    }
+
+    fn replace(&self, p: &Point, el: MapElement) -> Board {
         let was_closed = matches!(self.at(p), Some(Number { state: Closed, .. }));
         let map = (0..self.height)
             .map(|y| {
                 (0..self.width)
                     .map(|x| {
                         if Point::new(x, y) == *p {
-                            el.clone()
+                            el
                         } else {
-                            self.at(&Point::new(x, y)).unwrap().clone()
+                            self.map[y][x]
                         }
                     })
                     .collect()
@@ -111,19 +247,24 @@ impl Board {
             width: self.width,
             height: self.height,
             mines: self.mines,
+            seed: self.seed,
+            treasures: self.treasures.clone(),
             missing_points,
             map,
             state: match (missing_points, &self.state) {
                 (0, _) => BoardState::Won,
                 (_, BoardState::Ready) => BoardState::Playing,
-                _ => self.state.clone(),
+                _ => self.state,
             },
         }
     }
 
-    pub fn flag_item(self: &Self, p: &Point) -> Board {
-        match self.at(p) {
-            Some(Mine { state }) => self.replace(
+    pub fn flag_item(&self, p: &Point) -> Result<Board> {
+        match self.checked_at(p)? {
+            Number { state: Open, .. } | Mine { state: Open } => {
+                Err(Error::CellAlreadyOpen { x: p.x, y: p.y })
+            }
+            Mine { state } => Ok(self.replace(
                 p,
                 Mine {
                     state: match *state {
@@ -132,8 +273,8 @@ impl Board {
                         Open => Open,
                     },
                 },
-            ),
-            Some(Number { state, count }) => self.replace(
+            )),
+            Number { state, count } => Ok(self.replace(
                 p,
                 Number {
                     state: match *state {
@@ -143,140 +284,731 @@ impl Board {
                     },
                     count: *count,
                 },
-            ),
-            None => unreachable!(),
+            )),
         }
     }
 
-    pub fn cascade_open_item(self: &Self, p: &Point) -> Option<Board> {
-        match self.at(p).unwrap() {
-            Number { state: Open, .. }
-            | Mine { state: Flagged, .. }
-            | Number { state: Flagged, .. } => None,
+    pub fn cascade_open_item(&self, p: &Point) -> Result<Board> {
+        self.open_with(p, |board, p| board.flood_open(p, |q| board.neighbors(q)))
+    }
+
+    // Like cascade_open_item for boards of another topology, whose counts
+    // came from numbers_on_board_with the same adjacency.
+    pub fn cascade_open_with(&self, p: &Point, adjacency: &Adjacency) -> Result<Board> {
+        self.open_with(p, |board, p| {
+            board.flood_open(p, |q| {
+                adjacency
+                    .neighbors(adjacency.cell(q))
+                    .iter()
+                    .map(|&n| adjacency.point(n))
+            })
+        })
+    }
+
+    fn open_with(&self, p: &Point, flood: impl FnOnce(&Board, &Point) -> Board) -> Result<Board> {
+        match self.checked_at(p)? {
+            Number { state: Open, .. } => Err(Error::CellAlreadyOpen { x: p.x, y: p.y }),
+            Mine { state: Flagged, .. } | Number { state: Flagged, .. } => {
+                Err(Error::CellFlagged { x: p.x, y: p.y })
+            }
+            Number {
+                state: Closed,
+                count: 0,
+            } => Ok(flood(self, p)),
             Number {
                 state: Closed,
                 count,
-            } => {
-                let board = self.replace(
-                    p,
-                    Number {
-                        state: Open,
-                        count: *count,
-                    },
-                );
-                if *count == 0 {
-                    Some(
-                        board
-                            .surrounding_points(&p)
-                            .iter()
-                            .fold(board, |b: Board, p| b.cascade_open_item(&p).unwrap_or(b)),
-                    )
-                } else {
-                    Some(board)
-                }
-            }
-            Mine { state: Open } | Mine { state: Closed } => Some(Board {
-                map: self.map.clone(),
-                width: self.width,
-                height: self.height,
-                mines: self.mines,
-                missing_points: self.missing_points,
+            } => Ok(self.replace(
+                p,
+                Number {
+                    state: Open,
+                    count: *count,
+                },
+            )),
+            // The mine that went off is left open so views can tell it apart.
+            Mine { state: Open } | Mine { state: Closed } => Ok(Board {
                 state: BoardState::Failed,
+                ..self.replace(p, Mine { state: Open })
             }),
         }
     }
 
-    pub fn surrounding_points(self: &Self, p: &Point) -> Vec<Point> {
-        [p.x - 1, p.x, p.x + 1]
-            .iter()
-            .flat_map(|&x| {
-                [p.y - 1, p.y, p.y + 1]
-                    .iter()
-                    .map(|&y| Point { x, y })
-                    .filter(|&Point { x, y }| p.x != x || p.y != y)
-                    .filter(|p| self.at(p).is_some())
-                    .collect::<Vec<Point>>()
-            })
+    // Opens the zero cell at p and everything reachable from it through other
+    // zero cells, the same as opening each of them one by one would. Flagged
+    // cells are left alone.
+    fn flood_open<N: Iterator<Item = Point>>(
+        &self,
+        p: &Point,
+        neighbors: impl Fn(&Point) -> N,
+    ) -> Board {
+        let mut map = self.map.clone();
+        let mut opened = 0;
+        let mut stack = vec![*p];
+        while let Some(p) = stack.pop() {
+            if let Number { state, count } = &mut map[p.y as usize][p.x as usize] {
+                if *state == Closed {
+                    *state = Open;
+                    opened += 1;
+                    if *count == 0 {
+                        stack.extend(neighbors(&p));
+                    }
+                }
+            }
+        }
+        let missing_points = self.missing_points - opened;
+        Board {
+            width: self.width,
+            height: self.height,
+            mines: self.mines,
+            seed: self.seed,
+            treasures: self.treasures.clone(),
+            missing_points,
+            map,
+            state: match (missing_points, &self.state) {
+                (0, _) => BoardState::Won,
+                (_, BoardState::Ready) => BoardState::Playing,
+                _ => self.state,
+            },
+        }
+    }
+
+    pub fn apply_move(&self, m: &Move) -> Result<Board> {
+        match m {
+            Move::Open(p) => self.cascade_open_item(p),
+            Move::Flag(p) => self.flag_item(p),
+            Move::Power(power, p) => self.use_power(*power, p),
+        }
+    }
+
+    pub fn surrounding_points(&self, p: &Point) -> Vec<Point> {
+        self.neighbors(p).collect()
+    }
+
+    // The in-bounds neighbors of a point, without allocating.
+    pub fn neighbors(&self, p: &Point) -> Neighbors {
+        Neighbors {
+            inner: p.neighbors(),
+            width: self.width as i32,
+            height: self.height as i32,
+        }
+    }
+
+    fn mines_around(&self, p: &Point) -> i32 {
+        self.neighbors(p)
+            .filter(|n| matches!(self.at(n), Some(Mine { .. })))
+            .count() as i32
+    }
+
+    // Brings the counts up to date after the cells at `points` were edited,
+    // touching only their 3x3 neighborhoods instead of the whole board like
+    // numbers_on_board does.
+    pub fn recompute_numbers_around(&mut self, points: &[Point]) {
+        for p in points {
+            for q in core::iter::once(*p).chain(self.neighbors(p)) {
+                let mines = self.mines_around(&q);
+                if let Some(Number { count, .. }) = self
+                    .map
+                    .get_mut(q.y as usize)
+                    .and_then(|row| row.get_mut(q.x as usize))
+                {
+                    *count = mines;
+                }
+            }
+        }
+    }
+
+    fn open_neighbors(&self, p: &Point) -> Vec<Point> {
+        self.neighbors(p)
+            .filter(|n| matches!(self.at(n), Some(Number { state: Open, .. })))
             .collect()
     }
+
+    // Whether the closed mine at `from` can move to the closed safe cell
+    // `to` with every open number staying right: both must touch the same
+    // open cells. Treasures never take a mine.
+    pub(crate) fn can_move_mine(&self, from: &Point, to: &Point) -> bool {
+        matches!(self.at(from), Some(Mine { state: Closed }))
+            && matches!(self.at(to), Some(Number { state: Closed, .. }))
+            && !self.is_treasure(to)
+            && self.open_neighbors(from) == self.open_neighbors(to)
+    }
+
+    // Moves a mine where can_move_mine allows it to.
+    pub(crate) fn move_mine(&mut self, from: &Point, to: &Point) {
+        self.map[from.y as usize][from.x as usize] = Number {
+            state: Closed,
+            count: 0,
+        };
+        self.map[to.y as usize][to.x as usize] = Mine { state: Closed };
+        self.recompute_numbers_around(&[*from, *to]);
+    }
+
+    // Moves the closed mine at `from` to the closed safe cell `to`, for kind,
+    // cruel and moving mine modes and editors. Every open number must stay
+    // right, so both cells have to touch the same open cells: when they
+    // don't, the error names an open number that would change. Treasures
+    // never take a mine. The board loses its seed, as the seed no longer
+    // gives it.
+    pub fn relocate_mine(&self, from: &Point, to: &Point) -> Result<Board> {
+        for p in [from, to] {
+            match self.checked_at(p)? {
+                Number { state: Open, .. } | Mine { state: Open } => {
+                    return Err(Error::CellAlreadyOpen { x: p.x, y: p.y })
+                }
+                Number { state: Flagged, .. } | Mine { state: Flagged } => {
+                    return Err(Error::CellFlagged { x: p.x, y: p.y })
+                }
+                _ => (),
+            }
+        }
+        if !matches!(self.at(from), Some(Mine { .. })) || matches!(self.at(to), Some(Mine { .. })) {
+            return Err(Error::InvalidConfig(format!(
+                "a mine moves from a mine to a safe cell, not ({}, {}) to ({}, {})",
+                from.x, from.y, to.x, to.y
+            )));
+        }
+        if self.is_treasure(to) {
+            return Err(Error::InvalidConfig(format!(
+                "({}, {}) holds a treasure and can't take a mine",
+                to.x, to.y
+            )));
+        }
+        let (around_from, around_to) = (self.open_neighbors(from), self.open_neighbors(to));
+        if let Some(p) = around_from
+            .iter()
+            .chain(&around_to)
+            .find(|p| !around_from.contains(p) || !around_to.contains(p))
+        {
+            return Err(Error::InconsistentWithRevealed { x: p.x, y: p.y });
+        }
+        let mut board = Board {
+            seed: None,
+            ..self.clone()
+        };
+        board.move_mine(from, to);
+        Ok(board)
+    }
+
+    // Adds mines to the closed part of a board in play, for modes that get
+    // harder as a run goes on. Only closed cells with no open neighbor take
+    // them, so every number shown stays right, and never treasures. The
+    // board loses its seed, as the seed no longer gives it.
+    pub fn densify(&self, extra_mines: usize, rng: &mut dyn random::RandomSource) -> Result<Board> {
+        if matches!(self.state, BoardState::Won | BoardState::Failed) {
+            return Err(Error::GameOver);
+        }
+        let is_open = |p: &Point| {
+            matches!(
+                self.at(p),
+                Some(Number { state: Open, .. } | Mine { state: Open })
+            )
+        };
+        let mut candidates: Vec<Point> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point::new(x, y)))
+            .filter(|p| matches!(self.at(p), Some(Number { state: Closed, .. })))
+            .filter(|p| !self.is_treasure(p))
+            .filter(|p| !self.neighbors(p).any(|n| is_open(&n)))
+            .collect();
+        // A cell must be left to open.
+        if extra_mines > candidates.len() || extra_mines as i32 >= self.missing_points {
+            return Err(Error::InvalidConfig(format!(
+                "{} more mines don't fit in the closed part of the board",
+                extra_mines
+            )));
+        }
+        for i in 0..extra_mines {
+            let j = rng.gen_range(i, candidates.len());
+            candidates.swap(i, j);
+        }
+        candidates.truncate(extra_mines);
+        let mut board = self.clone();
+        for p in &candidates {
+            board.map[p.y as usize][p.x as usize] = Mine { state: Closed };
+        }
+        board.recompute_numbers_around(&candidates);
+        board.mines += extra_mines;
+        board.missing_points -= extra_mines as i32;
+        board.seed = None;
+        Ok(board)
+    }
+
+    // Bytes the board holds, on the heap included: what it costs to keep,
+    // say, every position of a game around. Counts capacity, not length,
+    // like the allocator does, but not the allocator's own overhead.
+    pub fn memory_footprint(&self) -> usize {
+        use core::mem::size_of;
+        let rows = self.map.capacity() * size_of::<Vec<MapElement>>();
+        let cells: usize = self
+            .map
+            .iter()
+            .map(|row| row.capacity() * size_of::<MapElement>())
+            .sum();
+        let treasures = self
+            .treasures
+            .as_ref()
+            .map_or(0, |t| t.capacity() * size_of::<Point>());
+        size_of::<Board>() + rows + cells + treasures
+    }
+}
+
+// Column by column, top to bottom, like surrounding_points always returned
+// them.
+const NEIGHBOR_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+impl Point {
+    // All eight neighbors, including the ones off the board.
+    pub fn neighbors(&self) -> PointNeighbors {
+        PointNeighbors {
+            x: self.x,
+            y: self.y,
+            next: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PointNeighbors {
+    x: i32,
+    y: i32,
+    next: usize,
+}
+
+impl Iterator for PointNeighbors {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        let (dx, dy) = NEIGHBOR_OFFSETS.get(self.next)?;
+        self.next += 1;
+        Some(Point {
+            x: self.x + dx,
+            y: self.y + dy,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = NEIGHBOR_OFFSETS.len() - self.next;
+        (left, Some(left))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Neighbors {
+    inner: PointNeighbors,
+    width: i32,
+    height: i32,
+}
+
+impl Iterator for Neighbors {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        let (width, height) = (self.width, self.height);
+        self.inner
+            .by_ref()
+            .find(|p| p.x >= 0 && p.x < width && p.y >= 0 && p.y < height)
+    }
 }
 
 pub fn create_board(
+    width: usize,
+    height: usize,
+    mines: usize,
+    rand: impl FnMut(usize, usize) -> usize,
+) -> Result<Board> {
+    create_board_avoiding(width, height, mines, rand, &[])
+}
+
+// Like create_board, but no mine is placed on the safe points. With no safe
+// points the same rand gives the same board as create_board.
+pub fn create_board_avoiding(
     width: usize,
     height: usize,
     mines: usize,
     mut rand: impl FnMut(usize, usize) -> usize,
-) -> Board {
+    safe: &[Point],
+) -> Result<Board> {
+    check_mines_fit(width, height, mines, safe)?;
+    let mut taken = vec![false; width * height];
+    for p in safe {
+        if p.x >= 0 && p.y >= 0 && (p.x as usize) < width && (p.y as usize) < height {
+            taken[p.y as usize * width + p.x as usize] = true;
+        }
+    }
     let mut points: Vec<Point> = Vec::with_capacity(mines);
     for _ in 0..mines {
         loop {
             let x = rand(0, width);
             let y = rand(0, height);
-            let p = Point::new(x, y);
-            if points.contains(&p) {
+            if taken[y * width + x] {
                 continue;
             }
-            points.push(p);
+            taken[y * width + x] = true;
+            points.push(Point::new(x, y));
             break;
         }
     }
+    board_with_mines(width, height, &points)
+}
 
-    let map = (0..height)
-        .map(|y| {
-            (0..width)
-                .map(|x| {
-                    if points.contains(&Point::new(x, y)) {
-                        Mine { state: Closed }
-                    } else {
-                        Number {
-                            state: Closed,
-                            count: 0,
-                        }
-                    }
-                })
-                .collect()
-        })
+// Every cell but the safe ones is a mine with the chance `density`, on its
+// own, so the number of mines isn't set: see GameOptions::mine_density. Fails
+// in the rare draw that leaves no cell to open.
+pub fn create_density_board(
+    width: usize,
+    height: usize,
+    density: f64,
+    source: &mut dyn random::RandomSource,
+    safe: &[Point],
+) -> Result<Board> {
+    if !(0.0..1.0).contains(&density) {
+        return Err(Error::InvalidConfig(format!(
+            "mine density {} is not at least 0 and under 1",
+            density
+        )));
+    }
+    check_mines_fit(width, height, 0, safe)?;
+    let points: Vec<Point> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| Point::new(x, y)))
+        .filter(|p| source.next_f64() < density && !safe.contains(p))
         .collect();
+    check_mines_fit(width, height, points.len(), safe)?;
+    board_with_mines(width, height, &points)
+}
+
+// The outer ring of cells, which border-free boards keep mine-free, see
+// BoardBuilder::border_free.
+pub fn border_cells(width: usize, height: usize) -> Vec<Point> {
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| Point::new(x, y)))
+        .filter(|p| p.x == 0 || p.y == 0 || p.x as usize == width - 1 || p.y as usize == height - 1)
+        .collect()
+}
+
+// The most cells a board may have: far more than any screen shows, and few
+// enough for the board to fit in memory.
+pub const MAX_CELLS: usize = 1 << 24;
+
+// The mines must leave at least one cell free to open, besides the safe
+// ones.
+pub(crate) fn check_mines_fit(
+    width: usize,
+    height: usize,
+    mines: usize,
+    safe: &[Point],
+) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(Error::InvalidConfig(String::from(
+            "board must have at least one cell",
+        )));
+    }
+    let cells = width
+        .checked_mul(height)
+        .filter(|&cells| cells <= MAX_CELLS)
+        .ok_or_else(|| {
+            Error::InvalidConfig(format!("a {}x{} board is too large", width, height))
+        })?;
+    let mut safe_cells: Vec<&Point> = safe
+        .iter()
+        .filter(|p| p.x >= 0 && p.y >= 0 && (p.x as usize) < width && (p.y as usize) < height)
+        .collect();
+    safe_cells.sort_unstable();
+    safe_cells.dedup();
+    if mines.saturating_add(safe_cells.len().max(1)) > cells {
+        return Err(Error::InvalidConfig(format!(
+            "{} mines do not fit in a {}x{} board",
+            mines, width, height
+        )));
+    }
+    Ok(())
+}
+
+// A closed board with mines on the points and no numbers yet.
+pub(crate) fn board_with_mines(width: usize, height: usize, points: &[Point]) -> Result<Board> {
+    let empty = Number {
+        state: Closed,
+        count: 0,
+    };
+    let mut map = vec![vec![empty; width]; height];
+    for p in points {
+        map[p.y as usize][p.x as usize] = Mine { state: Closed };
+    }
     Board::new(map)
 }
 
-pub fn numbers_on_board(board: Board) -> Board {
-    let map = (0..board.height)
-        .map(|y| {
-            (0..board.width)
-                .map(|x| {
-                    let point = Point::new(x, y);
-                    match board.at(&point).unwrap() {
-                        Mine { state } => Mine {
-                            state: state.clone(),
-                        },
-                        Number { count: 0, state } => {
-                            let count = board
-                                .surrounding_points(&point)
-                                .iter()
-                                .filter(|p| matches!(board.at(p), Some(Mine { .. })))
-                                .count() as i32;
-                            Number {
-                                state: state.clone(),
-                                count,
-                            }
-                        }
-                        _ => unreachable!(),
+// Same as create_board with seeded_rand, but the board keeps the seed.
+pub fn create_seeded_board(width: usize, height: usize, mines: usize, seed: u64) -> Result<Board> {
+    let board = create_board(width, height, mines, random::seeded_rand(seed))?;
+    Ok(Board {
+        seed: Some(seed),
+        ..board
+    })
+}
+
+// Builds the same boards as create_board_avoiding, from one of the sources in
+// random instead of a rand closure. Without a source, boards come from the
+// operating system's entropy; no_std builds have none and need one.
+pub struct BoardBuilder {
+    width: usize,
+    height: usize,
+    mines: usize,
+    safe: Vec<Point>,
+    seed: Option<u64>,
+    source: Option<Box<dyn random::RandomSource>>,
+    #[cfg(feature = "std")]
+    noise: Option<noise::NoiseOptions>,
+    symmetry: Option<symmetry::Symmetry>,
+    constraints: Option<stats::BoardConstraints>,
+    treasures: usize,
+    density: Option<f64>,
+    border_free: bool,
+}
+
+impl BoardBuilder {
+    pub fn new(width: usize, height: usize, mines: usize) -> BoardBuilder {
+        BoardBuilder {
+            width,
+            height,
+            mines,
+            safe: Vec::new(),
+            seed: None,
+            source: None,
+            #[cfg(feature = "std")]
+            noise: None,
+            symmetry: None,
+            constraints: None,
+            treasures: 0,
+            density: None,
+            border_free: false,
+        }
+    }
+
+    pub fn source(mut self, source: impl random::RandomSource + 'static) -> BoardBuilder {
+        self.source = Some(Box::new(source));
+        self.seed = None;
+        self
+    }
+
+    // SplitMix64 from the seed, as create_seeded_board: the board keeps the
+    // seed.
+    pub fn seed(self, seed: u64) -> BoardBuilder {
+        BoardBuilder {
+            seed: Some(seed),
+            ..self.source(random::SplitMix64::new(seed))
+        }
+    }
+
+    // Cells no mine is placed on.
+    pub fn safe(mut self, safe: &[Point]) -> BoardBuilder {
+        self.safe = safe.to_vec();
+        self
+    }
+
+    // Mines in clusters following noise, see noise::create_noise_board,
+    // instead of spread evenly.
+    #[cfg(feature = "std")]
+    pub fn noise(mut self, options: noise::NoiseOptions) -> BoardBuilder {
+        self.noise = Some(options);
+        self
+    }
+
+    // Mines placed symmetrically, see symmetry::create_symmetric_board. It
+    // can't be combined with noise.
+    pub fn symmetry(mut self, symmetry: symmetry::Symmetry) -> BoardBuilder {
+        self.symmetry = Some(symmetry);
+        self
+    }
+
+    // Boards are drawn until one meets the constraints, see
+    // stats::BoardConstraints.
+    pub fn constraints(mut self, constraints: stats::BoardConstraints) -> BoardBuilder {
+        self.constraints = Some(constraints);
+        self
+    }
+
+    // No mine on the outer ring of cells, whatever places them. Fails when
+    // the mines don't fit inside it.
+    pub fn border_free(mut self) -> BoardBuilder {
+        self.border_free = true;
+        self
+    }
+
+    // Each cell is a mine with this chance instead, and the mine count is
+    // ignored, see create_density_board. It can't be combined with noise or
+    // symmetry.
+    pub fn density(mut self, density: f64) -> BoardBuilder {
+        self.density = Some(density);
+        self
+    }
+
+    // Hides treasures on that many safe cells, for the treasure variant, see
+    // Board::treasures.
+    pub fn treasures(mut self, count: usize) -> BoardBuilder {
+        self.treasures = count;
+        self
+    }
+
+    // Whether the mines are placed as create_seeded_board would, so the seed
+    // alone brings the board back.
+    fn is_plain(&self) -> bool {
+        #[cfg(feature = "std")]
+        let noise = self.noise.is_some();
+        #[cfg(not(feature = "std"))]
+        let noise = false;
+        self.safe.is_empty()
+            && !noise
+            && self.symmetry.is_none()
+            && self.constraints.is_none()
+            && self.treasures == 0
+            && self.density.is_none()
+            && !self.border_free
+    }
+
+    // One board, before the constraints are checked.
+    fn generate(&self, source: &mut dyn random::RandomSource) -> Result<Board> {
+        let mut safe = self.safe.clone();
+        if self.border_free {
+            safe.extend(border_cells(self.width, self.height));
+        }
+        if let Some(density) = self.density {
+            #[cfg(feature = "std")]
+            let noise = self.noise.is_some();
+            #[cfg(not(feature = "std"))]
+            let noise = false;
+            if noise || self.symmetry.is_some() {
+                return Err(Error::InvalidConfig(String::from(
+                    "a mine density can't be combined with noise or symmetry",
+                )));
+            }
+            return create_density_board(self.width, self.height, density, source, &safe);
+        }
+        #[cfg(feature = "std")]
+        if let Some(options) = &self.noise {
+            if self.symmetry.is_some() {
+                return Err(Error::InvalidConfig(String::from(
+                    "noise and symmetry can't be combined",
+                )));
+            }
+            return noise::create_noise_board(
+                self.width,
+                self.height,
+                self.mines,
+                options,
+                source,
+                &safe,
+            );
+        }
+        match self.symmetry {
+            Some(symmetry) => symmetry::create_symmetric_board(
+                self.width,
+                self.height,
+                self.mines,
+                symmetry,
+                source,
+                &safe,
+            ),
+            None => create_board_avoiding(
+                self.width,
+                self.height,
+                self.mines,
+                random::rand_from(source),
+                &safe,
+            ),
+        }
+    }
+
+    pub fn build(mut self) -> Result<Board> {
+        let mut source = match self.source.take() {
+            Some(source) => source,
+            #[cfg(feature = "std")]
+            None => Box::new(random::OsEntropy),
+            #[cfg(not(feature = "std"))]
+            None => {
+                return Err(Error::InvalidConfig(String::from(
+                    "no random source, and no operating system to ask for one",
+                )))
+            }
+        };
+        let board = match &self.constraints {
+            None => self.generate(source.as_mut())?,
+            Some(constraints) => {
+                constraints.check()?;
+                let mut attempts = 0;
+                loop {
+                    let board = self.generate(source.as_mut())?;
+                    if constraints.accepts(&numbers_on_board(board.clone())) {
+                        break board;
                     }
-                })
-                .collect()
+                    attempts += 1;
+                    if attempts >= constraints.max_attempts {
+                        return Err(Error::InvalidConfig(format!(
+                            "no board met the constraints in {} attempts",
+                            attempts
+                        )));
+                    }
+                }
+            }
+        };
+        let board = match self.treasures {
+            0 => board,
+            count => board.place_treasures(count, source.as_mut(), &self.safe)?,
+        };
+        // Any other option changes the layout, and the board is shared cell by
+        // cell instead.
+        Ok(Board {
+            seed: self.seed.filter(|_| self.is_plain()),
+            ..board
         })
-        .collect();
-    Board {
-        map,
-        state: BoardState::Ready,
-        ..board
     }
 }
 
+// Like numbers_on_board, counting the neighbors the adjacency gives instead
+// of the eight around each cell.
+pub fn numbers_on_board_with(board: Board, adjacency: &Adjacency) -> Board {
+    let board = numbers_on_board(board);
+    let mut map = board.map.clone();
+    for (cell, el) in map.iter_mut().flatten().enumerate() {
+        if let Number { count, .. } = el {
+            *count = adjacency
+                .neighbors(cell)
+                .iter()
+                .filter(|&&n| matches!(board.map[n / board.width][n % board.width], Mine { .. }))
+                .count() as i32;
+        }
+    }
+    Board { map, ..board }
+}
+
+// Works on any board kept in a way BoardWrite covers, FixedBoard too.
+pub fn numbers_on_board<B: BoardWrite>(mut board: B) -> B {
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let point = Point::new(x, y);
+            if let Some(Number { .. }) = board.cell(&point) {
+                let count = point
+                    .neighbors()
+                    .filter(|n| matches!(board.cell(n), Some(Mine { .. })))
+                    .count();
+                let _ = board.set_count(&point, count as i32);
+            }
+        }
+    }
+    board.set_state(BoardState::Ready);
+    board
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use alloc::vec;
     use pretty_assertions::assert_eq;
 
     fn state_from_bytes(state: u8) -> MapElementCellState {
@@ -292,7 +1024,7 @@ pub mod tests {
         (c as i32) - (b'0' as i32)
     }
 
-    fn make_map(map: Vec<String>, state: Vec<String>) -> Vec<Vec<MapElement>> {
+    pub fn make_map(map: Vec<String>, state: Vec<String>) -> Vec<Vec<MapElement>> {
         map.iter()
             .zip(state)
             .map(|(map_row, state_row)| {
@@ -362,6 +1094,7 @@ pub mod tests {
                 String::from("CCCCC"),
             ],
         ))
+        .unwrap()
     }
 
     pub fn five_by_two_board() -> Board {
@@ -369,6 +1102,7 @@ pub mod tests {
             vec![String::from("X0000"), String::from("0X000")],
             vec![String::from("CCCCC"), String::from("CCCCC")],
         ))
+        .unwrap()
     }
 
     #[test]
@@ -377,25 +1111,175 @@ pub mod tests {
         let height = 4;
         let mines = 4;
         let mut v = vec![3, 3, 2, 2, 1, 1, 0, 0];
-        let rand = move |_start: usize, _end: usize| -> usize {
-            return v.pop().unwrap();
-        };
-        let board = create_board(width, height, mines, rand);
+        let rand = move |_start: usize, _end: usize| -> usize { v.pop().unwrap() };
+        let board = create_board(width, height, mines, rand).unwrap();
         let expected_map = five_by_four_board().map;
         assert_eq!(board.map, expected_map);
         assert_eq!(board.state, BoardState::NotReady);
     }
 
+    #[test]
+    fn test_board_builder() {
+        let mock = random::MockSequence::new(vec![0, 0, 1, 1, 2, 2, 3, 3]).unwrap();
+        let board = BoardBuilder::new(5, 4, 4).source(mock).build().unwrap();
+        assert_eq!(board.map, five_by_four_board().map);
+        assert_eq!(board.seed, None);
+
+        assert_eq!(
+            BoardBuilder::new(6, 5, 8).seed(3).build(),
+            create_seeded_board(6, 5, 8, 3)
+        );
+        let safe = [Point::new(0, 0), Point::new(1, 0)];
+        let board = BoardBuilder::new(2, 2, 2)
+            .source(random::Xoshiro256StarStar::new(1))
+            .safe(&safe)
+            .build()
+            .unwrap();
+        assert!(matches!(board.at(&safe[0]), Some(Number { .. })));
+        assert!(matches!(board.at(&safe[1]), Some(Number { .. })));
+
+        let board = BoardBuilder::new(6, 5, 8)
+            .seed(3)
+            .symmetry(symmetry::Symmetry::Rotation2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            symmetry::asymmetric_mines(&board, symmetry::Symmetry::Rotation2),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_density_board() {
+        let safe = [Point::new(0, 0)];
+        let board = BoardBuilder::new(30, 30, 0)
+            .seed(1)
+            .density(0.2)
+            .safe(&safe)
+            .build()
+            .unwrap();
+        assert!((140..220).contains(&board.mines), "{} mines", board.mines);
+        assert!(matches!(board.at(&safe[0]), Some(Number { .. })));
+        assert!(BoardBuilder::new(3, 3, 0)
+            .seed(1)
+            .density(1.0)
+            .build()
+            .is_err());
+        assert!(BoardBuilder::new(3, 3, 0)
+            .seed(1)
+            .density(0.2)
+            .symmetry(symmetry::Symmetry::Rotation2)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_border_free_board() {
+        let board = BoardBuilder::new(6, 5, 12)
+            .seed(3)
+            .border_free()
+            .build()
+            .unwrap();
+        for p in border_cells(6, 5) {
+            assert!(matches!(board.at(&p), Some(Number { .. })));
+        }
+        assert!(BoardBuilder::new(6, 5, 12)
+            .seed(3)
+            .border_free()
+            .symmetry(symmetry::Symmetry::Rotation2)
+            .build()
+            .is_ok());
+        assert!(BoardBuilder::new(6, 5, 13)
+            .seed(3)
+            .border_free()
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_relocate_mine() {
+        // X2100
+        // 2X100
+        let board = numbers_on_board(five_by_two_board())
+            .cascade_open_item(&Point::new(4, 0))
+            .unwrap();
+        let moved = board
+            .relocate_mine(&Point::new(0, 0), &Point::new(0, 1))
+            .unwrap();
+        assert_eq!(
+            moved.at(&Point::new(0, 0)),
+            Some(&Number {
+                state: Closed,
+                count: 2
+            })
+        );
+        assert_eq!(moved.at(&Point::new(0, 1)), Some(&Mine { state: Closed }));
+        assert_eq!(moved.mines, 2);
+        // The 1s in the middle would lose their mine.
+        assert!(matches!(
+            board.relocate_mine(&Point::new(1, 1), &Point::new(0, 1)),
+            Err(Error::InconsistentWithRevealed { x: 2, .. })
+        ));
+        assert!(matches!(
+            board.relocate_mine(&Point::new(0, 0), &Point::new(1, 1)),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert_eq!(
+            board.relocate_mine(&Point::new(0, 0), &Point::new(2, 0)),
+            Err(Error::CellAlreadyOpen { x: 2, y: 0 })
+        );
+        let with_treasure = Board {
+            treasures: Some(vec![Point::new(0, 1)]),
+            ..board
+        };
+        assert!(matches!(
+            with_treasure.relocate_mine(&Point::new(0, 0), &Point::new(0, 1)),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_densify() {
+        // X2100
+        // 2X100
+        let board = numbers_on_board(five_by_two_board())
+            .cascade_open_item(&Point::new(4, 0))
+            .unwrap();
+        let mut rng = random::MockSequence::new(vec![0]).unwrap();
+        // (0, 1) is the only closed cell away from the open ones.
+        let denser = board.densify(1, &mut rng).unwrap();
+        assert_eq!(denser.mines, 3);
+        assert_eq!(denser.at(&Point::new(0, 1)), Some(&Mine { state: Closed }));
+        assert_eq!(
+            denser.at(&Point::new(1, 0)),
+            Some(&Number {
+                state: Closed,
+                count: 3
+            })
+        );
+        assert_eq!(denser.at(&Point::new(2, 0)), board.at(&Point::new(2, 0)));
+        // Opening the last safe cell wins.
+        assert_eq!(
+            denser.cascade_open_item(&Point::new(1, 0)).unwrap().state,
+            BoardState::Won
+        );
+        assert!(board.densify(2, &mut rng).is_err());
+
+        let treasure = Board {
+            treasures: Some(vec![Point::new(0, 1)]),
+            ..board
+        };
+        assert!(treasure.densify(1, &mut rng).is_err());
+    }
+
     #[test]
     fn test_create_board_without_repeated_mines() {
         let width = 5;
         let height = 4;
         let mines = 4;
         let mut v = vec![3, 3, 2, 2, 0, 0, 1, 1, 0, 0];
-        let rand = move |_start: usize, _end: usize| -> usize {
-            return v.pop().unwrap();
-        };
-        let board = create_board(width, height, mines, rand);
+        let rand = move |_start: usize, _end: usize| -> usize { v.pop().unwrap() };
+        let board = create_board(width, height, mines, rand).unwrap();
         let expected_map = five_by_four_board().map;
         assert_eq!(board.map, expected_map);
         assert_eq!(board.state, BoardState::NotReady);
@@ -422,6 +1306,26 @@ pub mod tests {
         assert_eq!(board.state, BoardState::Ready);
     }
 
+    #[test]
+    fn test_recompute_numbers_around() {
+        let mut board = numbers_on_board(five_by_four_board());
+        board.map[0][4] = Mine { state: Closed };
+        board.map[1][1] = Number {
+            state: Closed,
+            count: 0,
+        };
+        board.recompute_numbers_around(&[Point::new(4, 0), Point::new(1, 1)]);
+        let expected = numbers_on_board(board.clone());
+        assert_eq!(board.map, expected.map);
+        assert_eq!(
+            board.at(&Point::new(3, 0)),
+            Some(&Number {
+                state: Closed,
+                count: 1
+            })
+        );
+    }
+
     #[test]
     fn test_surrounding_points() {
         assert_eq!(
@@ -436,6 +1340,21 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_neighbors() {
+        assert_eq!(Point::new(3, 3).neighbors().count(), 8);
+        let board = five_by_two_board();
+        let corner: Vec<Point> = board.neighbors(&Point::new(4, 1)).collect();
+        assert_eq!(
+            corner,
+            vec![
+                Point { x: 3, y: 0 },
+                Point { x: 3, y: 1 },
+                Point { x: 4, y: 0 },
+            ]
+        );
+    }
+
     #[test]
     fn test_cascade_open_item() {
         let board = numbers_on_board(five_by_two_board());
@@ -465,7 +1384,7 @@ pub mod tests {
     #[test]
     fn test_flag() {
         let board = numbers_on_board(five_by_two_board());
-        let board = board.flag_item(&Point::new(3, 1));
+        let board = board.flag_item(&Point::new(3, 1)).unwrap();
         let expected_map = make_map(
             vec![String::from("X2100"), String::from("2X100")],
             vec![String::from("CCCCC"), String::from("CCCFC")],
@@ -477,8 +1396,8 @@ pub mod tests {
     #[test]
     fn test_flagging_again_unflags() {
         let board = numbers_on_board(five_by_two_board());
-        let board = board.flag_item(&Point::new(3, 1));
-        let board = board.flag_item(&Point::new(3, 1));
+        let board = board.flag_item(&Point::new(3, 1)).unwrap();
+        let board = board.flag_item(&Point::new(3, 1)).unwrap();
         let expected_map = make_map(
             vec![String::from("X2100"), String::from("2X100")],
             vec![String::from("CCCCC"), String::from("CCCCC")],
@@ -488,15 +1407,98 @@ pub mod tests {
     }
 
     #[test]
-    fn test_flagging_open_does_noting() {
+    fn test_flagging_open_fails() {
         let board = numbers_on_board(five_by_two_board());
         let board = board.cascade_open_item(&Point::new(2, 0)).unwrap();
-        let board = board.flag_item(&Point::new(2, 0));
-        let expected_map = make_map(
-            vec![String::from("X2100"), String::from("2X100")],
-            vec![String::from("CCOCC"), String::from("CCCCC")],
+        assert_eq!(
+            board.flag_item(&Point::new(2, 0)),
+            Err(Error::CellAlreadyOpen { x: 2, y: 0 })
         );
-        assert_eq!(board.map, expected_map);
-        assert_eq!(board.state, BoardState::Playing);
+    }
+
+    #[test]
+    fn test_opening_open_or_flagged_fails() {
+        let board = numbers_on_board(five_by_two_board());
+        let board = board.cascade_open_item(&Point::new(2, 0)).unwrap();
+        assert_eq!(
+            board.cascade_open_item(&Point::new(2, 0)),
+            Err(Error::CellAlreadyOpen { x: 2, y: 0 })
+        );
+        let board = board.flag_item(&Point::new(0, 0)).unwrap();
+        assert_eq!(
+            board.cascade_open_item(&Point::new(0, 0)),
+            Err(Error::CellFlagged { x: 0, y: 0 })
+        );
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let board = numbers_on_board(five_by_two_board());
+        assert_eq!(
+            board.cascade_open_item(&Point { x: 5, y: 0 }),
+            Err(Error::OutOfBounds { x: 5, y: 0 })
+        );
+        assert_eq!(
+            board.flag_item(&Point { x: -1, y: 1 }),
+            Err(Error::OutOfBounds { x: -1, y: 1 })
+        );
+    }
+
+    #[test]
+    fn test_linear_indices() {
+        let board = five_by_two_board();
+        assert_eq!(board.index_of(&Point::new(3, 1)), Some(8));
+        assert_eq!(board.point_of(8), Some(Point::new(3, 1)));
+        assert_eq!(board.index_of(&Point { x: 5, y: 0 }), None);
+        assert_eq!(board.index_of(&Point { x: -1, y: 1 }), None);
+        assert_eq!(board.point_of(10), None);
+        for i in 0..10 {
+            assert_eq!(board.point_of(i).and_then(|p| board.index_of(&p)), Some(i));
+        }
+        assert_eq!(Point::checked(4, 1, (5, 2)), Some(Point::new(4, 1)));
+        assert_eq!(Point::checked(5, 1, (5, 2)), None);
+    }
+
+    #[test]
+    fn test_moves_after_game_over_fail() {
+        let board = numbers_on_board(five_by_two_board());
+        let board = board.cascade_open_item(&Point::new(0, 0)).unwrap();
+        assert_eq!(board.state, BoardState::Failed);
+        assert_eq!(
+            board.cascade_open_item(&Point::new(3, 1)),
+            Err(Error::GameOver)
+        );
+        assert_eq!(board.flag_item(&Point::new(3, 1)), Err(Error::GameOver));
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        use core::mem::size_of;
+        let small = numbers_on_board(five_by_two_board());
+        let large = create_seeded_board(100, 100, 10, 1).unwrap();
+        assert!(small.memory_footprint() >= size_of::<Board>() + 10 * size_of::<MapElement>());
+        assert!(large.memory_footprint() > 100 * small.memory_footprint());
+        let opened = small.cascade_open_item(&Point::new(4, 0)).unwrap();
+        assert_eq!(opened.memory_footprint(), small.memory_footprint());
+    }
+
+    #[test]
+    fn test_invalid_configs() {
+        assert!(matches!(Board::new(vec![]), Err(Error::InvalidConfig(_))));
+        assert!(matches!(
+            Board::new(make_map(
+                vec![String::from("00"), String::from("0")],
+                vec![String::from("CC"), String::from("C")],
+            )),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            create_board(2, 2, 4, |_, _| 0),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            create_board(0, 2, 0, |_, _| 0),
+            Err(Error::InvalidConfig(_))
+        ));
     }
 }
@@ -0,0 +1,191 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, Error, MapElement, Result};
+
+// What a block of cells adds up to, for drawing it as one.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct BlockSummary {
+    pub cells: usize,
+    pub opened: usize,
+    pub flagged: usize,
+    pub exploded: bool,
+}
+
+impl BlockSummary {
+    fn of<'a>(cells: impl Iterator<Item = &'a MapElement>) -> BlockSummary {
+        cells.fold(BlockSummary::default(), |mut summary, el| {
+            summary.cells += 1;
+            match el {
+                Number { state: Open, .. } => summary.opened += 1,
+                Mine { state: Open } => summary.exploded = true,
+                Mine { state: Flagged } | Number { state: Flagged, .. } => summary.flagged += 1,
+                _ => (),
+            }
+            summary
+        })
+    }
+
+    fn merge(self, other: BlockSummary) -> BlockSummary {
+        BlockSummary {
+            cells: self.cells + other.cells,
+            opened: self.opened + other.opened,
+            flagged: self.flagged + other.flagged,
+            exploded: self.exploded || other.exploded,
+        }
+    }
+
+    pub fn opened_fraction(&self) -> f64 {
+        self.opened as f64 / self.cells.max(1) as f64
+    }
+}
+
+// Summaries of a board at every zoom level: level 0 has one per chunk, see
+// chunks::CHUNK_SIZE, and each level up one per 2x2 blocks of the level
+// below, up to a single one for the whole board. A zoomed out frontend draws
+// a level without looking at any cell, and update only reads the chunks a
+// move changed.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct LevelOfDetail {
+    levels: Vec<Vec<Vec<BlockSummary>>>,
+}
+
+impl LevelOfDetail {
+    pub fn new(board: &Board) -> LevelOfDetail {
+        let across = board.chunk_grid().map(|(x, _)| x + 1).max().unwrap_or(0);
+        let mut chunks = Vec::new();
+        for (x, y) in board.chunk_grid() {
+            if x == 0 {
+                chunks.push(Vec::with_capacity(across));
+            }
+            chunks[y].push(chunk_summary(board, x, y));
+        }
+        let mut lod = LevelOfDetail {
+            levels: vec![chunks],
+        };
+        lod.build_upper_levels();
+        lod
+    }
+
+    fn build_upper_levels(&mut self) {
+        self.levels.truncate(1);
+        loop {
+            let below = &self.levels[self.levels.len() - 1];
+            if below.len() <= 1 && below.first().map_or(0, Vec::len) <= 1 {
+                break;
+            }
+            let block = |x: usize, y: usize| {
+                below
+                    .get(y)
+                    .and_then(|row| row.get(x))
+                    .copied()
+                    .unwrap_or_default()
+            };
+            let level = (0..below.len().div_ceil(2))
+                .map(|y| {
+                    (0..below[0].len().div_ceil(2))
+                        .map(|x| {
+                            block(2 * x, 2 * y)
+                                .merge(block(2 * x + 1, 2 * y))
+                                .merge(block(2 * x, 2 * y + 1))
+                                .merge(block(2 * x + 1, 2 * y + 1))
+                        })
+                        .collect()
+                })
+                .collect();
+            self.levels.push(level);
+        }
+    }
+
+    // For `after`, a board of the same size that was `before` when these
+    // summaries were last brought up to date. Fails when the sizes differ.
+    pub fn update(&mut self, before: &Board, after: &Board) -> Result<()> {
+        if (before.width, before.height) != (after.width, after.height) {
+            return Err(Error::InvalidConfig(format!(
+                "a {}x{} board can't be compared with a {}x{} one",
+                after.width, after.height, before.width, before.height
+            )));
+        }
+        let (across, down) = after
+            .chunk_grid()
+            .last()
+            .map_or((0, 0), |(x, y)| (x + 1, y + 1));
+        if self.levels[0].len() != down || self.levels[0].iter().any(|row| row.len() != across) {
+            return Err(Error::InvalidConfig(String::from(
+                "the summaries are of a board of another size",
+            )));
+        }
+        for (x, y) in after.chunk_grid() {
+            if after.chunk_differs(before, x, y) {
+                self.levels[0][y][x] = chunk_summary(after, x, y);
+            }
+        }
+        self.build_upper_levels();
+        Ok(())
+    }
+
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    // Row by row. Levels past the last are the last, the whole board.
+    pub fn level(&self, zoom: usize) -> &[Vec<BlockSummary>] {
+        &self.levels[zoom.min(self.levels.len() - 1)]
+    }
+}
+
+fn chunk_summary(board: &Board, x: usize, y: usize) -> BlockSummary {
+    let (xs, ys) = board.chunk_bounds(x, y);
+    BlockSummary::of(ys.flat_map(|row| board.map[row][xs.clone()].iter()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_seeded_board, numbers_on_board, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_level_of_detail() {
+        let board = numbers_on_board(create_seeded_board(70, 40, 100, 4).unwrap());
+        let mut lod = LevelOfDetail::new(&board);
+        // 3x2 chunks, then 2x1, then the whole board.
+        assert_eq!(lod.levels(), 3);
+        assert_eq!(lod.level(0).len(), 2);
+        assert_eq!(lod.level(0)[0].len(), 3);
+        assert_eq!(lod.level(1)[0].len(), 2);
+        assert_eq!(lod.level(9), lod.level(2));
+        assert_eq!(lod.level(2)[0][0].cells, 70 * 40);
+
+        let zero = (0..70 * 40)
+            .map(|i| Point::new(i % 70, i / 70))
+            .find(|p| matches!(board.at(p), Some(Number { count: 0, .. })))
+            .unwrap();
+        let mine = (0..70 * 40)
+            .map(|i| Point::new(i % 70, i / 70))
+            .rfind(|p| matches!(board.at(p), Some(Mine { .. })))
+            .unwrap();
+        let opened = board
+            .cascade_open_item(&zero)
+            .unwrap()
+            .flag_item(&mine)
+            .unwrap();
+        lod.update(&board, &opened).unwrap();
+        assert_eq!(lod, LevelOfDetail::new(&opened));
+        let whole = lod.level(2)[0][0];
+        assert_eq!(whole.flagged, 1);
+        assert!(whole.opened > 0 && whole.opened_fraction() < 1.0);
+
+        let small = numbers_on_board(create_seeded_board(20, 20, 10, 4).unwrap());
+        assert!(lod.update(&board, &small).is_err());
+        assert!(LevelOfDetail::new(&small).update(&board, &opened).is_err());
+        assert_eq!(lod, LevelOfDetail::new(&opened));
+    }
+}
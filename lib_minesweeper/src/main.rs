@@ -1,16 +1,12 @@
 use colored::Colorize;
-use rand::Rng;
 use std::io;
 
-use lib_minesweeper::create_board;
 use lib_minesweeper::numbers_on_board;
+use lib_minesweeper::view::CellView;
 use lib_minesweeper::Board;
+use lib_minesweeper::BoardBuilder;
 use lib_minesweeper::BoardState;
-use lib_minesweeper::MapElement::Mine;
-use lib_minesweeper::MapElement::Number;
-use lib_minesweeper::MapElementCellState::Closed;
-use lib_minesweeper::MapElementCellState::Flagged;
-use lib_minesweeper::MapElementCellState::Open;
+use lib_minesweeper::Error;
 use lib_minesweeper::Point;
 
 fn main() {
@@ -21,9 +17,9 @@ fn main() {
     let height = 8;
     let mines = 10;
 
-    let board = create_board(width, height, mines, |x, y| {
-        rand::thread_rng().gen_range(x, y)
-    });
+    let board = BoardBuilder::new(width, height, mines)
+        .build()
+        .expect("invalid board configuration");
 
     let mut board = numbers_on_board(board);
 
@@ -38,41 +34,45 @@ fn main() {
         io::stdin()
             .read_line(&mut line)
             .expect("failed to read line");
-        let op = process_line(line, &board);
-        match op {
-            Some(Operation::Open { point }) => {
-                board = board.cascade_open_item(&point).unwrap_or(board)
-            }
-            Some(Operation::Flag { point }) => board = board.flag_item(&point),
-            _ => continue,
+        let result = process_line(line, &board).and_then(|op| match op {
+            Operation::Open { point } => board.cascade_open_item(&point),
+            Operation::Flag { point } => board.flag_item(&point),
+        });
+        match result {
+            Ok(b) => board = b,
+            Err(e) => println!("{}", e),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum Operation {
     Open { point: Point },
     Flag { point: Point },
 }
 
-fn process_line(line: String, board: &Board) -> Option<Operation> {
+fn process_line(line: String, board: &Board) -> Result<Operation, Error> {
     let bytes = line.as_bytes();
     match bytes {
         [op, x, y, b'\n'] => {
             let x = coord_reverse_mapping(*x);
             let y = coord_reverse_mapping(*y);
             let p = Point { x, y };
-            if matches!(board.at(&p), Some(_)) {
-                match op {
-                    b'o' => Some(Operation::Open { point: p }),
-                    b'f' => Some(Operation::Flag { point: p }),
-                    _ => None,
-                }
-            } else {
-                None
+            if board.at(&p).is_none() {
+                return Err(Error::OutOfBounds { x, y });
+            }
+            match op {
+                b'o' => Ok(Operation::Open { point: p }),
+                b'f' => Ok(Operation::Flag { point: p }),
+                _ => Err(Error::ParseError(format!(
+                    "unknown operation '{}'",
+                    char::from(*op)
+                ))),
             }
         }
-        _ => None,
+        _ => Err(Error::ParseError(String::from(
+            "expected an operation followed by a column and a row",
+        ))),
     }
 }
 
@@ -94,15 +94,15 @@ fn print_board_state(board: &Board) {
     print!("Board is currently ");
     match board.state {
         BoardState::Won => print!("{}", "🎉🎉  WON! 🎉🎉".green()),
+        BoardState::NotReady | BoardState::Ready => print!("{}", "ready".green()),
         BoardState::Playing => print!("{}", "in play".green()),
         BoardState::Failed => print!("{}", "☠️  FAILED ☠️".red()),
-        _ => unreachable!(),
     }
     println!();
 }
 
 fn colorized_print_map(board: &Board) {
-    print_board_state(&board);
+    print_board_state(board);
     let mut mapping = vec![];
     mapping.extend((b'0'..=b'9').map(char::from));
     mapping.extend((b'a'..=b'z').map(char::from));
@@ -111,33 +111,24 @@ fn colorized_print_map(board: &Board) {
         print!("{} ", item);
     }
     println!();
-    let is_done = matches!(board.state, BoardState::Failed | BoardState::Won);
-    for y in 0..board.height {
-        print!("{} ", mapping[y]);
+    for (y, row_label) in mapping.iter().enumerate().take(board.height) {
+        print!("{} ", row_label);
         for x in 0..board.width {
-            let x = x as i32;
-            let y = y as i32;
-            let c = match board.at(&Point { x, y }) {
-                Some(Mine { state }) => match (state, is_done) {
-                    (_, true) | (Open, _) => " ".on_red(),
-                    (Flagged, _) => " ".on_bright_green(),
-                    (Closed, _) => " ".on_yellow(),
-                },
-                Some(Number { state, count: 0 }) => match (state, is_done) {
-                    (_, true) | (Open, _) => " ".on_bright_white(),
-                    (Flagged, _) => " ".on_bright_green(),
-                    (Closed, _) => " ".on_yellow(),
-                },
-                Some(Number { state, count }) => match (state, is_done) {
-                    (_, true) | (Open, _) => format!("{}", count).black().on_bright_cyan(),
-                    (Flagged, _) => " ".on_bright_green(),
-                    (Closed, _) => " ".on_yellow(),
-                },
-                _ => unreachable!(),
+            let c = match board.cell_view(&Point::new(x, y)) {
+                Some(CellView::Hidden) | Some(CellView::Question) => " ".on_yellow(),
+                Some(CellView::OutOfReach) => " ".on_bright_black(),
+                Some(CellView::Flagged) => " ".on_bright_green(),
+                Some(CellView::WrongFlag) => "x".black().on_bright_green(),
+                Some(CellView::Open(0)) => " ".on_bright_white(),
+                Some(CellView::Open(count)) => format!("{}", count).black().on_bright_cyan(),
+                Some(CellView::Treasure(count)) => format!("{}", count).black().on_bright_yellow(),
+                Some(CellView::Mine) => " ".on_red(),
+                Some(CellView::ExplodedMine) => "*".black().on_red(),
+                None => continue,
             };
             print!("{} ", c);
         }
-        print!("{}", mapping[y]);
+        print!("{}", row_label);
         println!();
     }
 
@@ -151,6 +142,10 @@ fn colorized_print_map(board: &Board) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lib_minesweeper::MapElement::Mine;
+    use lib_minesweeper::MapElement::Number;
+    use lib_minesweeper::MapElementCellState::Closed;
+    use lib_minesweeper::MapElementCellState::Open;
     use lib_minesweeper::*;
     use pretty_assertions::assert_eq;
 
@@ -178,6 +173,7 @@ mod tests {
             vec![(false, -1), (false, 0), (false, 0), (false, 0), (false, 0)],
             vec![(false, 0), (false, -1), (false, 0), (false, 0), (false, 0)],
         ]))
+        .unwrap()
     }
 
     #[test]
@@ -185,7 +181,7 @@ mod tests {
         let o = process_line(String::from("o01\n"), &tests::five_by_two_board());
         assert_eq!(
             o,
-            Some(Operation::Open {
+            Ok(Operation::Open {
                 point: Point { x: 0, y: 1 }
             })
         );
@@ -194,12 +190,12 @@ mod tests {
     #[test]
     fn test_process_line_out_of_bounds_argument() {
         let o = process_line(String::from("o34\n"), &tests::five_by_two_board());
-        assert_eq!(o, None);
+        assert_eq!(o, Err(Error::OutOfBounds { x: 3, y: 4 }));
     }
 
     #[test]
     fn test_process_line_bad_arguments() {
         let o = process_line(String::from("o\n"), &tests::five_by_two_board());
-        assert_eq!(o, None);
+        assert!(matches!(o, Err(Error::ParseError(_))));
     }
 }
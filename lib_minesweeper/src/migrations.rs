@@ -0,0 +1,204 @@
+use serde_json::{json, Map, Value};
+
+use crate::game::Game;
+use crate::replay::Replay;
+use crate::{Error, Result};
+
+// The version of the save and replay formats written now. Bump it with every
+// change to Game or Replay that older files can't be read with, and add the
+// step that upgrades the files written before.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Document {
+    Save,
+    Replay,
+}
+
+impl Document {
+    fn key(self) -> &'static str {
+        match self {
+            Document::Save => "game",
+            Document::Replay => "replay",
+        }
+    }
+}
+
+// Upgrades a document from version `from` to the next one. Steps only see
+// the game or replay, not the envelope around it.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(Document, &mut Value) -> core::result::Result<(), String>,
+}
+
+// In order, one per version.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "fill in the fields added before files had versions",
+    apply: unversioned,
+}];
+
+fn object<'a>(
+    value: &'a mut Value,
+    what: &str,
+) -> core::result::Result<&'a mut Map<String, Value>, String> {
+    value
+        .as_object_mut()
+        .ok_or_else(|| format!("{} is not an object", what))
+}
+
+fn fill(object: &mut Map<String, Value>, defaults: Value) {
+    if let Value::Object(defaults) = defaults {
+        for (key, value) in defaults {
+            object.entry(key).or_insert(value);
+        }
+    }
+}
+
+// Files from before versions were written: the bare game or replay, missing
+// any of the fields added since saves exist. Optional fields read as None
+// already; the others get the value a new game starts with.
+fn unversioned(document: Document, value: &mut Value) -> core::result::Result<(), String> {
+    let top = object(value, document.key())?;
+    let options = top
+        .get_mut("options")
+        .ok_or_else(|| String::from("options are missing"))?;
+    fill(
+        object(options, "options")?,
+        json!({"kind": false, "cruel": false}),
+    );
+    if document == Document::Save {
+        fill(
+            top,
+            json!({"unsaved_moves": 0, "splits_reached": 0, "solved": []}),
+        );
+    }
+    Ok(())
+}
+
+fn parse_error(e: impl core::fmt::Display) -> Error {
+    Error::ParseError(e.to_string())
+}
+
+// Takes a file of any version and returns its game or replay at
+// FORMAT_VERSION, ready to deserialize.
+pub fn upgrade(document: Document, mut file: Value) -> Result<Value> {
+    let (mut version, mut value) = match file.get("version").and_then(Value::as_u64) {
+        Some(version) => {
+            let value = file
+                .get_mut(document.key())
+                .map(Value::take)
+                .ok_or_else(|| parse_error(format!("no {} in the file", document.key())))?;
+            (version as u32, value)
+        }
+        None => (0, file),
+    };
+    if version > FORMAT_VERSION {
+        return Err(parse_error(format!(
+            "written by a newer version, format {} (this reads up to {})",
+            version, FORMAT_VERSION
+        )));
+    }
+    while version < FORMAT_VERSION {
+        let step = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| parse_error(format!("no migration from format {}", version)))?;
+        (step.apply)(document, &mut value)
+            .map_err(|e| parse_error(format!("{} failed: {}", step.description, e)))?;
+        version += 1;
+    }
+    Ok(value)
+}
+
+fn to_json(document: Document, value: impl serde::Serialize) -> Result<Vec<u8>> {
+    let value = serde_json::to_value(value).map_err(|e| Error::Storage(e.to_string()))?;
+    serde_json::to_vec(&json!({"version": FORMAT_VERSION, document.key(): value}))
+        .map_err(|e| Error::Storage(e.to_string()))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(document: Document, bytes: &[u8]) -> Result<T> {
+    let file = serde_json::from_slice(bytes).map_err(parse_error)?;
+    serde_json::from_value(upgrade(document, file)?).map_err(parse_error)
+}
+
+pub fn save_to_json(game: &Game) -> Result<Vec<u8>> {
+    to_json(Document::Save, game)
+}
+
+pub fn save_from_json(bytes: &[u8]) -> Result<Game> {
+    from_json(Document::Save, bytes)
+}
+
+pub fn replay_to_json(replay: &Replay) -> Result<Vec<u8>> {
+    to_json(Document::Replay, replay)
+}
+
+pub fn replay_from_json(bytes: &[u8]) -> Result<Replay> {
+    from_json(Document::Replay, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameOptions;
+    use crate::tests::five_by_two_board;
+    use crate::{numbers_on_board, Move, Point};
+    use pretty_assertions::assert_eq;
+
+    // The fixtures are files written by earlier versions of the crate, from
+    // the same three moves.
+    fn game() -> Game {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        game.apply(&Move::Flag(Point::new(0, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap_err();
+        game
+    }
+
+    #[test]
+    fn test_every_save_format_loads() {
+        let fixtures: &[&[u8]] = &[
+            // The first saves, unversioned.
+            include_bytes!("../fixtures/save-v0-storage.json"),
+            // The last unversioned saves.
+            include_bytes!("../fixtures/save-v0-progress.json"),
+            include_bytes!("../fixtures/save-v1.json"),
+        ];
+        let expected = game();
+        for fixture in fixtures {
+            let game = save_from_json(fixture).unwrap();
+            assert_eq!(game.board(), expected.board());
+            assert_eq!(game.moves(), expected.moves());
+            assert_eq!(game.options, expected.options);
+        }
+        assert_eq!(
+            save_from_json(&save_to_json(&expected).unwrap()),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn test_every_replay_format_loads() {
+        let fixtures: &[&[u8]] = &[
+            include_bytes!("../fixtures/replay-v0.json"),
+            include_bytes!("../fixtures/replay-v1.json"),
+        ];
+        for fixture in fixtures {
+            assert_eq!(replay_from_json(fixture), Ok(game().replay()));
+        }
+    }
+
+    #[test]
+    fn test_newer_formats_are_refused() {
+        let newer = format!(r#"{{"version": {}, "game": {{}}}}"#, FORMAT_VERSION + 1);
+        assert!(matches!(
+            save_from_json(newer.as_bytes()),
+            Err(Error::ParseError(_))
+        ));
+    }
+}
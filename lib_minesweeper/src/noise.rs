@@ -0,0 +1,180 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::random::RandomSource;
+use crate::{board_with_mines, check_mines_fit, Board, Error, Point, Result};
+
+// Mines in clusters instead of spread evenly, for large boards meant to be
+// explored: smooth noise is laid over the board and mines go where it's
+// high, leaving corridors where it's low.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct NoiseOptions {
+    // Roughly how many cells across a cluster is.
+    pub scale: usize,
+    // From 0 to 1. Cells whose noise is under it only get mines when the
+    // count can't be reached otherwise; higher thresholds leave wider
+    // corridors.
+    pub threshold: f64,
+}
+
+impl Default for NoiseOptions {
+    fn default() -> NoiseOptions {
+        NoiseOptions {
+            scale: 8,
+            threshold: 0.4,
+        }
+    }
+}
+
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Value noise from 0 to 1, indexed as `noise[y][x]`: random values on a grid
+// of `scale` cells, smoothly interpolated in between.
+pub fn value_noise(
+    width: usize,
+    height: usize,
+    scale: usize,
+    source: &mut dyn RandomSource,
+) -> Vec<Vec<f64>> {
+    let scale = scale.max(1);
+    let lattice: Vec<Vec<f64>> = (0..height / scale + 2)
+        .map(|_| (0..width / scale + 2).map(|_| source.next_f64()).collect())
+        .collect();
+    (0..height)
+        .map(|y| {
+            let (iy, ty) = (y / scale, smoothstep((y % scale) as f64 / scale as f64));
+            (0..width)
+                .map(|x| {
+                    let (ix, tx) = (x / scale, smoothstep((x % scale) as f64 / scale as f64));
+                    let top = lattice[iy][ix] + (lattice[iy][ix + 1] - lattice[iy][ix]) * tx;
+                    let bottom =
+                        lattice[iy + 1][ix] + (lattice[iy + 1][ix + 1] - lattice[iy + 1][ix]) * tx;
+                    top + (bottom - top) * ty
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Places exactly `mines` mines, none on the safe points. Each mine is drawn
+// among the cells left with a chance that grows with how far the cell's
+// noise is over the threshold, so the count is always met while the mines
+// stay in the clusters. The noise takes the first numbers from the source.
+pub fn create_noise_board(
+    width: usize,
+    height: usize,
+    mines: usize,
+    options: &NoiseOptions,
+    source: &mut dyn RandomSource,
+    safe: &[Point],
+) -> Result<Board> {
+    if options.scale == 0 || !(0.0..1.0).contains(&options.threshold) {
+        return Err(Error::InvalidConfig(format!(
+            "bad noise scale {} or threshold {}",
+            options.scale, options.threshold
+        )));
+    }
+    check_mines_fit(width, height, mines, safe)?;
+    let noise = value_noise(width, height, options.scale, source);
+    // Weighted sampling without replacement: each cell gets the key
+    // -ln(u) / weight and the smallest keys win. Cells under the threshold
+    // come after all others, the noisiest first.
+    let mut keyed: Vec<(bool, f64, Point)> = Vec::with_capacity(width * height);
+    for (y, row) in noise.iter().enumerate() {
+        for (x, &n) in row.iter().enumerate() {
+            let u = 1.0 - source.next_f64();
+            let p = Point::new(x, y);
+            if safe.contains(&p) {
+                continue;
+            }
+            let weight = n - options.threshold;
+            keyed.push(if weight > 0.0 {
+                (false, -u.ln() / weight, p)
+            } else {
+                (true, -n, p)
+            });
+        }
+    }
+    keyed.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    let points: Vec<Point> = keyed.into_iter().take(mines).map(|(_, _, p)| p).collect();
+    board_with_mines(width, height, &points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Xoshiro256StarStar;
+    use crate::MapElement::Mine;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_value_noise() {
+        let noise = value_noise(20, 10, 4, &mut Xoshiro256StarStar::new(3));
+        assert_eq!((noise.len(), noise[0].len()), (10, 20));
+        assert!(noise.iter().flatten().all(|n| (0.0..1.0).contains(n)));
+        // Neighbors differ by little, unlike independent draws.
+        let step = noise[0]
+            .windows(2)
+            .map(|w| (w[0] - w[1]).abs())
+            .fold(0.0, f64::max);
+        assert!(step < 0.5);
+    }
+
+    #[test]
+    fn test_noise_board() {
+        let options = NoiseOptions {
+            scale: 6,
+            threshold: 0.5,
+        };
+        let safe = [Point::new(0, 0)];
+        let board = create_noise_board(
+            40,
+            40,
+            200,
+            &options,
+            &mut Xoshiro256StarStar::new(1),
+            &safe,
+        )
+        .unwrap();
+        assert_eq!(board.mines, 200);
+        assert!(!matches!(board.at(&safe[0]), Some(Mine { .. })));
+        // The noise comes first from the source, so it can be rebuilt.
+        let noise = value_noise(40, 40, 6, &mut Xoshiro256StarStar::new(1));
+        for (y, row) in board.map.iter().enumerate() {
+            for (x, el) in row.iter().enumerate() {
+                if matches!(el, Mine { .. }) {
+                    assert!(noise[y][x] > options.threshold);
+                }
+            }
+        }
+
+        // With too little room over the threshold the count is still met.
+        let strict = NoiseOptions {
+            threshold: 0.99,
+            ..options.clone()
+        };
+        let board =
+            create_noise_board(10, 10, 60, &strict, &mut Xoshiro256StarStar::new(1), &[]).unwrap();
+        assert_eq!(board.mines, 60);
+        let built = crate::BoardBuilder::new(10, 10, 60)
+            .seed(4)
+            .noise(strict.clone())
+            .build()
+            .unwrap();
+        // The seed alone doesn't bring a noise board back.
+        assert_eq!((built.mines, built.seed), (60, None));
+        assert!(create_noise_board(
+            10,
+            10,
+            5,
+            &NoiseOptions {
+                scale: 0,
+                ..options
+            },
+            &mut Xoshiro256StarStar::new(1),
+            &[]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use crate::random::SplitMix64;
+use crate::simulate::{simulate_batch, BasicSolverPolicy, BatchConfig, Outcome, Policy};
+use crate::{Board, BoardState, Move, Point, Result};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OpeningConfig {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub games_per_cell: usize,
+    pub seed: u64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OpeningCell {
+    pub win_rate: f64,
+    // Fraction of games lost on the very first click.
+    pub immediate_loss_rate: f64,
+}
+
+// Indexed as `cells[y][x]`, like the board itself.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OpeningHeatmap {
+    pub width: usize,
+    pub height: usize,
+    pub games_per_cell: usize,
+    pub cells: Vec<Vec<OpeningCell>>,
+}
+
+impl OpeningHeatmap {
+    pub fn best(&self) -> Option<Point> {
+        let mut best: Option<(Point, f64)> = None;
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if best
+                    .as_ref()
+                    .map_or(true, |(_, rate)| cell.win_rate > *rate)
+                {
+                    best = Some((Point::new(x, y), cell.win_rate));
+                }
+            }
+        }
+        best.map(|(p, _)| p)
+    }
+}
+
+struct FirstClick<'a> {
+    x: i32,
+    y: i32,
+    then: &'a dyn Policy,
+}
+
+impl<'a> Policy for FirstClick<'a> {
+    fn next_move(&self, board: &Board, rng: &mut SplitMix64) -> Option<Move> {
+        if board.state == BoardState::Ready {
+            Some(Move::Open(Point {
+                x: self.x,
+                y: self.y,
+            }))
+        } else {
+            self.then.next_move(board, rng)
+        }
+    }
+}
+
+fn opening_cell(
+    batch: &BatchConfig,
+    x: usize,
+    y: usize,
+    policy: &dyn Policy,
+    parallelism: usize,
+) -> Result<OpeningCell> {
+    let first_click = FirstClick {
+        x: x as i32,
+        y: y as i32,
+        then: policy,
+    };
+    let report = simulate_batch(std::slice::from_ref(batch), &first_click, parallelism)?.remove(0);
+    let immediate_losses = report
+        .games
+        .iter()
+        .filter(|g| g.outcome == Outcome::Failed && g.moves == 1)
+        .count();
+    Ok(OpeningCell {
+        win_rate: report.win_rate(),
+        immediate_loss_rate: if report.games.is_empty() {
+            0.0
+        } else {
+            immediate_losses as f64 / report.games.len() as f64
+        },
+    })
+}
+
+// Every cell is played on the same set of seeds, so differences between cells
+// come from the click location and not from the boards that were drawn.
+pub fn analyze_openings(
+    config: &OpeningConfig,
+    policy: &dyn Policy,
+    parallelism: usize,
+) -> Result<OpeningHeatmap> {
+    let batch = BatchConfig {
+        width: config.width,
+        height: config.height,
+        mines: config.mines,
+        games: config.games_per_cell,
+        seed: config.seed,
+    };
+    let cells = (0..config.height)
+        .map(|y| {
+            (0..config.width)
+                .map(|x| opening_cell(&batch, x, y, policy, parallelism))
+                .collect::<Result<Vec<OpeningCell>>>()
+        })
+        .collect::<Result<Vec<Vec<OpeningCell>>>>()?;
+    Ok(OpeningHeatmap {
+        width: config.width,
+        height: config.height,
+        games_per_cell: config.games_per_cell,
+        cells,
+    })
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Expert,
+}
+
+impl Difficulty {
+    pub fn width(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 30,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate | Difficulty::Expert => 16,
+        }
+    }
+
+    pub fn mines(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 10,
+            Difficulty::Intermediate => 40,
+            Difficulty::Expert => 99,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BookEntry {
+    pub point: Point,
+    // With BasicSolverPolicy playing on after the first click.
+    pub win_rate: f64,
+}
+
+// What the opening book below was computed with.
+pub const BOOK_GAMES_PER_CELL: usize = 2000;
+pub const BOOK_SEED: u64 = 1;
+
+// Plays BOOK_GAMES_PER_CELL games from each cell of the top left quarter of
+// the board (the rest mirrors it) and returns the one that wins most. This is
+// slow on expert boards; best_first_click has the results precomputed.
+pub fn compute_book_entry(difficulty: Difficulty, parallelism: usize) -> Result<BookEntry> {
+    let batch = BatchConfig {
+        width: difficulty.width(),
+        height: difficulty.height(),
+        mines: difficulty.mines(),
+        games: BOOK_GAMES_PER_CELL,
+        seed: BOOK_SEED,
+    };
+    let mut best: Option<BookEntry> = None;
+    for y in 0..difficulty.height().div_ceil(2) {
+        for x in 0..difficulty.width().div_ceil(2) {
+            let cell = opening_cell(&batch, x, y, &BasicSolverPolicy, parallelism)?;
+            if best.as_ref().map_or(true, |b| cell.win_rate > b.win_rate) {
+                best = Some(BookEntry {
+                    point: Point::new(x, y),
+                    win_rate: cell.win_rate,
+                });
+            }
+        }
+    }
+    Ok(best.expect("boards have at least one cell"))
+}
+
+// The first click that wins most often for each standard difficulty, as
+// computed by compute_book_entry. Regenerate with
+// `cargo test --release opening_book -- --ignored` after changing the
+// simulator or the policy.
+pub fn best_first_click(difficulty: Difficulty) -> BookEntry {
+    let (x, y, win_rate) = match difficulty {
+        Difficulty::Beginner => (4, 0, 0.5685),
+        Difficulty::Intermediate => (0, 1, 0.222),
+        // BasicSolverPolicy rarely wins expert games, so this one is the
+        // least reliable.
+        Difficulty::Expert => (9, 5, 0.0025),
+    };
+    BookEntry {
+        point: Point::new(x, y),
+        win_rate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulate::RandomPolicy;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_analyze_openings() {
+        let config = OpeningConfig {
+            width: 4,
+            height: 3,
+            mines: 2,
+            games_per_cell: 40,
+            seed: 5,
+        };
+        let heatmap = analyze_openings(&config, &RandomPolicy, 2).unwrap();
+        assert_eq!(heatmap.cells.len(), 3);
+        assert_eq!(heatmap.cells[0].len(), 4);
+        for cell in heatmap.cells.iter().flatten() {
+            assert!(cell.win_rate >= 0.0 && cell.win_rate <= 1.0);
+            assert!(cell.immediate_loss_rate <= 1.0 - cell.win_rate);
+        }
+        assert!(heatmap.best().is_some());
+        assert_eq!(
+            heatmap,
+            analyze_openings(&config, &RandomPolicy, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_best_first_click_is_on_the_board() {
+        for &difficulty in &[
+            Difficulty::Beginner,
+            Difficulty::Intermediate,
+            Difficulty::Expert,
+        ] {
+            let entry = best_first_click(difficulty);
+            assert!((entry.point.x as usize) < difficulty.width());
+            assert!((entry.point.y as usize) < difficulty.height());
+        }
+    }
+
+    // Takes minutes; see best_first_click.
+    #[test]
+    #[ignore]
+    fn test_opening_book_is_up_to_date() {
+        for &difficulty in &[
+            Difficulty::Beginner,
+            Difficulty::Intermediate,
+            Difficulty::Expert,
+        ] {
+            assert_eq!(
+                best_first_click(difficulty),
+                compute_book_entry(difficulty, 1).unwrap()
+            );
+        }
+    }
+}
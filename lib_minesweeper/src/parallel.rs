@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::vec::Vec;
+
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardState, Point, Result};
+
+// Cells of a frontier a thread takes at a time.
+const CHUNK: usize = 256;
+
+impl Board {
+    // Like cascade_open_item, with the flood spread over `threads` threads,
+    // for boards where one cascade opens millions of cells. The board and
+    // the events worked out from it are the same as cascade_open_item's.
+    pub fn cascade_open_parallel(&self, p: &Point, threads: usize) -> Result<Board> {
+        self.open_with(p, |board, p| board.parallel_flood_open(p, threads.max(1)))
+    }
+
+    // Goes through the flood a ring at a time. Threads take chunks of the
+    // ring as they finish the last, and claim each closed cell they reach,
+    // so every cell joins the next ring once whoever got there first. Which
+    // thread that was changes nothing: the cells opened are the zeros
+    // reachable from p and their neighbors, as for flood_open.
+    fn parallel_flood_open(&self, p: &Point, threads: usize) -> Board {
+        let index = |q: &Point| q.y as usize * self.width + q.x as usize;
+        let claimed: Vec<AtomicBool> = (0..self.width * self.height)
+            .map(|_| AtomicBool::new(false))
+            .collect();
+        claimed[index(p)].store(true, Ordering::Relaxed);
+        let mut ring = vec![*p];
+        while !ring.is_empty() {
+            let next_chunk = AtomicUsize::new(0);
+            let expand = || {
+                let mut reached = vec![];
+                loop {
+                    let start = next_chunk.fetch_add(CHUNK, Ordering::Relaxed);
+                    if start >= ring.len() {
+                        return reached;
+                    }
+                    for q in &ring[start..(start + CHUNK).min(ring.len())] {
+                        if !matches!(self.at(q), Some(Number { count: 0, .. })) {
+                            continue;
+                        }
+                        for n in self.neighbors(q) {
+                            let closed = matches!(self.at(&n), Some(Number { state: Closed, .. }));
+                            if closed && !claimed[index(&n)].swap(true, Ordering::Relaxed) {
+                                reached.push(n);
+                            }
+                        }
+                    }
+                }
+            };
+            ring = if ring.len() <= CHUNK || threads == 1 {
+                expand()
+            } else {
+                thread::scope(|scope| {
+                    let workers: Vec<_> = (0..threads).map(|_| scope.spawn(expand)).collect();
+                    workers
+                        .into_iter()
+                        .flat_map(|w| w.join().expect("flood fill thread panicked"))
+                        .collect()
+                })
+            };
+        }
+
+        let mut map = self.map.clone();
+        let mut opened = 0;
+        for (cell, el) in map.iter_mut().flatten().enumerate() {
+            if let Number { state, .. } = el {
+                if *state == Closed && claimed[cell].load(Ordering::Relaxed) {
+                    *state = Open;
+                    opened += 1;
+                }
+            }
+        }
+        let missing_points = self.missing_points - opened;
+        Board {
+            map,
+            missing_points,
+            state: match (missing_points, &self.state) {
+                (0, _) => BoardState::Won,
+                (_, BoardState::Ready) => BoardState::Playing,
+                _ => self.state,
+            },
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{create_seeded_board, numbers_on_board, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parallel_flood_matches_sequential() {
+        let board = numbers_on_board(create_seeded_board(300, 300, 900, 7).unwrap());
+        let zero = (0..300 * 300)
+            .map(|i| Point::new(i % 300, i / 300))
+            .find(|p| {
+                matches!(
+                    board.at(p),
+                    Some(crate::MapElement::Number { count: 0, .. })
+                )
+            })
+            .unwrap();
+        let sequential = board.cascade_open_item(&zero).unwrap();
+        for threads in [1, 4] {
+            assert_eq!(
+                board.cascade_open_parallel(&zero, threads).unwrap(),
+                sequential
+            );
+        }
+        assert_eq!(
+            board.cascade_open_parallel(&Point::new(300, 0), 4),
+            board.cascade_open_item(&Point::new(300, 0))
+        );
+    }
+}
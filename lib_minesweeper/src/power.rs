@@ -0,0 +1,167 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, Error, Point, Result};
+
+// Items an arcade mode can hand out, used on a cell with Game::use_power.
+// They're moves like any other, so replays keep them.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum PowerUp {
+    // Opens every safe cell and flags every mine within this many cells.
+    RevealRadar(usize),
+    // Opens the cell, or flags it if it's a mine.
+    SafeStep,
+    // Removes the cell's mine, if any, and opens it. The numbers around it
+    // count one mine less.
+    DefuseKit,
+}
+
+impl Board {
+    pub fn use_power(&self, power: PowerUp, p: &Point) -> Result<Board> {
+        self.use_power_with(power, p, true)
+    }
+
+    // Without flags, for no-flag games: mines the power finds are left
+    // closed, and a safe step on a mine changes nothing.
+    pub fn use_power_with(&self, power: PowerUp, p: &Point, flags: bool) -> Result<Board> {
+        let el = self.checked_at(p)?;
+        match (power, el) {
+            (PowerUp::RevealRadar(radius), _) => Ok(self.radar(p, radius, flags)),
+            (_, Number { state: Open, .. } | Mine { state: Open }) => {
+                Err(Error::CellAlreadyOpen { x: p.x, y: p.y })
+            }
+            (_, Number { state: Flagged, .. }) => Err(Error::CellFlagged { x: p.x, y: p.y }),
+            (PowerUp::SafeStep, Mine { state: Closed }) if flags => self.flag_item(p),
+            (PowerUp::SafeStep, Mine { state: Closed }) => Ok(self.clone()),
+            (PowerUp::SafeStep, Mine { .. }) => Err(Error::CellFlagged { x: p.x, y: p.y }),
+            (PowerUp::DefuseKit, Mine { .. }) => {
+                let mut board = self.clone();
+                board.map[p.y as usize][p.x as usize] = Number {
+                    state: Closed,
+                    count: 0,
+                };
+                board.recompute_numbers_around(core::slice::from_ref(p));
+                board.mines -= 1;
+                board.missing_points += 1;
+                board.cascade_open_item(p)
+            }
+            (_, Number { .. }) => self.cascade_open_item(p),
+        }
+    }
+
+    // p is on the board.
+    fn radar(&self, p: &Point, radius: usize, flags: bool) -> Board {
+        let span = |c: i32, len: usize| {
+            let c = c as usize;
+            c.saturating_sub(radius)..=c.saturating_add(radius).min(len - 1)
+        };
+        let mut board = self.clone();
+        for y in span(p.y, self.height) {
+            for x in span(p.x, self.width) {
+                let q = Point::new(x, y);
+                board = match board.at(&q) {
+                    Some(Mine { state: Closed }) if flags => board.flag_item(&q),
+                    Some(Number { state: Closed, .. }) => board.cascade_open_item(&q),
+                    _ => continue,
+                }
+                .unwrap_or(board);
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Event, Game, GameOptions};
+    use crate::scenario::Scenario;
+    use crate::{BoardState, Move};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_power_ups() {
+        // *2100
+        // 2*100
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        game.use_power(PowerUp::SafeStep, Point::new(0, 0)).unwrap();
+        assert_eq!(
+            game.board().at(&Point::new(0, 0)),
+            Some(&Mine { state: Flagged })
+        );
+        assert_eq!(game.board().state, BoardState::Playing);
+
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        let events = game
+            .use_power(PowerUp::DefuseKit, Point::new(1, 1))
+            .unwrap();
+        assert_eq!(
+            events[0],
+            Event::PowerUsed {
+                power: PowerUp::DefuseKit,
+                point: Point::new(1, 1)
+            }
+        );
+        // The open 1 next to the defused mine is a 0 now.
+        assert!(events.contains(&Event::Opened {
+            point: Point::new(2, 1),
+            count: 0,
+            source: crate::game::Source::User
+        }));
+        assert_eq!(game.board().mines, 1);
+
+        let events = game
+            .use_power(PowerUp::RevealRadar(1), Point::new(1, 0))
+            .unwrap();
+        assert_eq!(events.last(), Some(&Event::Won));
+        assert_eq!(
+            game.replay().moves[2].action,
+            Move::Power(PowerUp::DefuseKit, Point::new(1, 1))
+        );
+        assert_eq!(
+            game.replay()
+                .splits(&crate::splits::SplitDefinition::every(100).unwrap())[0]
+                .percent,
+            100
+        );
+    }
+
+    #[test]
+    fn test_radar_reaches_past_the_board() {
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let board = board
+            .use_power(PowerUp::RevealRadar(usize::MAX), &Point::new(2, 1))
+            .unwrap();
+        assert_eq!(board.state, BoardState::Won);
+    }
+
+    #[test]
+    fn test_power_ups_without_flags() {
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(
+            board,
+            GameOptions {
+                no_flag: true,
+                ..GameOptions::default()
+            },
+        );
+        game.use_power(PowerUp::SafeStep, Point::new(0, 0)).unwrap();
+        assert_eq!(
+            game.board().at(&Point::new(0, 0)),
+            Some(&Mine { state: Closed })
+        );
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        game.use_power(PowerUp::RevealRadar(1), Point::new(0, 1))
+            .unwrap();
+        assert_eq!(
+            game.board().at(&Point::new(1, 1)),
+            Some(&Mine { state: Closed })
+        );
+        assert_eq!(game.board().state, BoardState::Won);
+    }
+}
@@ -0,0 +1,80 @@
+use png::{BitDepth, ColorType, Encoder};
+use qrcode::render::svg;
+use qrcode::{Color, QrCode};
+
+use crate::{Error, Result};
+
+// Modules of blank border around the code, as the QR spec asks for.
+const QUIET_ZONE: usize = 4;
+
+fn qr_code(code: &str) -> Result<QrCode> {
+    // Share codes are upper case so the QR code can use the denser
+    // alphanumeric mode.
+    QrCode::new(code.to_uppercase()).map_err(|e| Error::InvalidConfig(e.to_string()))
+}
+
+pub fn share_code_svg(code: &str) -> Result<String> {
+    Ok(qr_code(code)?
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build())
+}
+
+// A greyscale PNG with every module drawn as a `scale` pixels wide square.
+pub fn share_code_png(code: &str, scale: usize) -> Result<Vec<u8>> {
+    let qr = qr_code(code)?;
+    let scale = scale.max(1);
+    let modules = qr.width();
+    let colors = qr.to_colors();
+    let size = (modules + 2 * QUIET_ZONE) * scale;
+    let pixels: Vec<u8> = (0..size * size)
+        .map(|i| {
+            let x = (i % size) / scale;
+            let y = (i / size) / scale;
+            let dark = x >= QUIET_ZONE
+                && y >= QUIET_ZONE
+                && x < modules + QUIET_ZONE
+                && y < modules + QUIET_ZONE
+                && colors[(y - QUIET_ZONE) * modules + x - QUIET_ZONE] == Color::Dark;
+            if dark {
+                0
+            } else {
+                255
+            }
+        })
+        .collect();
+
+    let mut out = vec![];
+    let png_error = |e: png::EncodingError| Error::InvalidConfig(e.to_string());
+    let mut encoder = Encoder::new(&mut out, size as u32, size as u32);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&pixels).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_seeded_board;
+    use crate::game::GameOptions;
+
+    #[test]
+    fn test_share_code_qr() {
+        let board = create_seeded_board(16, 16, 40, 3).unwrap();
+        let code = board.share_code(&GameOptions::default()).unwrap();
+
+        let svg = share_code_svg(&code).unwrap();
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.contains("<svg"));
+
+        let png = share_code_png(&code, 3).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        let decoder = png::Decoder::new(&png[..]);
+        let reader = decoder.read_info().unwrap();
+        // A version 2 code is 25 modules wide.
+        assert_eq!(reader.info().width, (25 + 8) * 3);
+    }
+}
@@ -0,0 +1,196 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::random::SplitMix64;
+use crate::view::CellView;
+use crate::{BoardState, Error, Point, Result};
+
+// A board where no cell is a mine until it's opened. Each cell has its own
+// chance of being one, and opening it rolls for it. Open cells show the
+// expected number of mines around them, rounded to the nearest whole number
+// with halves rounded up; cells that already collapsed count as 0 or 1.
+//
+// There are no cascades, a 0 can still have a mine next to it. The game is
+// won once every closed cell is more likely a mine than not: everything
+// worth opening has been opened.
+#[derive(Debug, PartialEq, Clone)]
+pub struct QuantumBoard {
+    pub width: usize,
+    pub height: usize,
+    pub state: BoardState,
+    // Row by row. Collapsed cells have a probability of exactly 0 or 1.
+    probabilities: Vec<f64>,
+    open: Vec<bool>,
+    flagged: Vec<bool>,
+    rng: SplitMix64,
+}
+
+// Uniform in [0, 1), from the top 53 bits.
+fn unit(rng: &mut SplitMix64) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl QuantumBoard {
+    // `mines` is the expected number of mines: each cell's chance is drawn
+    // uniformly from 0 to twice the board's density, capped at 1.
+    pub fn new(width: usize, height: usize, mines: usize, seed: u64) -> Result<QuantumBoard> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidConfig(String::from(
+                "board must have at least one cell",
+            )));
+        }
+        if mines >= width * height {
+            return Err(Error::InvalidConfig(format!(
+                "{} mines do not fit in a {}x{} board",
+                mines, width, height
+            )));
+        }
+        let mut rng = SplitMix64::new(seed);
+        let density = mines as f64 / (width * height) as f64;
+        let probabilities = (0..width * height)
+            .map(|_| (unit(&mut rng) * 2.0 * density).min(1.0))
+            .collect();
+        Ok(QuantumBoard {
+            width,
+            height,
+            state: BoardState::Ready,
+            probabilities,
+            open: vec![false; width * height],
+            flagged: vec![false; width * height],
+            rng,
+        })
+    }
+
+    fn index(&self, p: &Point) -> Option<usize> {
+        if p.x < 0 || p.y < 0 || p.x as usize >= self.width || p.y as usize >= self.height {
+            return None;
+        }
+        Some(p.y as usize * self.width + p.x as usize)
+    }
+
+    fn neighbors(&self, p: &Point) -> impl Iterator<Item = usize> + '_ {
+        p.neighbors().filter_map(move |n| self.index(&n))
+    }
+
+    pub fn probability(&self, p: &Point) -> Option<f64> {
+        self.index(p).map(|i| self.probabilities[i])
+    }
+
+    pub fn expected_count(&self, p: &Point) -> Option<f64> {
+        self.index(p)?;
+        Some(self.neighbors(p).map(|i| self.probabilities[i]).sum())
+    }
+
+    // What an open cell shows. It can change as its neighbors are opened.
+    pub fn number(&self, p: &Point) -> Option<i32> {
+        let i = self.index(p)?;
+        if !self.open[i] {
+            return None;
+        }
+        self.expected_count(p).map(|e| (e + 0.5) as i32)
+    }
+
+    fn check_move(&self, p: &Point) -> Result<usize> {
+        if matches!(self.state, BoardState::Won | BoardState::Failed) {
+            return Err(Error::GameOver);
+        }
+        let i = self.index(p).ok_or(Error::OutOfBounds { x: p.x, y: p.y })?;
+        if self.open[i] {
+            return Err(Error::CellAlreadyOpen { x: p.x, y: p.y });
+        }
+        Ok(i)
+    }
+
+    pub fn open(&self, p: &Point) -> Result<QuantumBoard> {
+        let i = self.check_move(p)?;
+        if self.flagged[i] {
+            return Err(Error::CellFlagged { x: p.x, y: p.y });
+        }
+        let mut board = self.clone();
+        let mine = unit(&mut board.rng) < board.probabilities[i];
+        board.probabilities[i] = if mine { 1.0 } else { 0.0 };
+        board.open[i] = true;
+        board.state = if mine {
+            BoardState::Failed
+        } else if board.is_cleared() {
+            BoardState::Won
+        } else {
+            BoardState::Playing
+        };
+        Ok(board)
+    }
+
+    pub fn flag(&self, p: &Point) -> Result<QuantumBoard> {
+        let i = self.check_move(p)?;
+        let mut board = self.clone();
+        board.flagged[i] = !board.flagged[i];
+        Ok(board)
+    }
+
+    fn is_cleared(&self) -> bool {
+        self.probabilities
+            .iter()
+            .zip(&self.open)
+            .all(|(p, open)| *open || *p > 0.5)
+    }
+
+    // Closed cells stay hidden even after a loss: their mines were never
+    // decided.
+    pub fn cell_view(&self, p: &Point) -> Option<CellView> {
+        let i = self.index(p)?;
+        Some(match (self.open[i], self.flagged[i]) {
+            (true, _) if self.probabilities[i] > 0.5 => CellView::ExplodedMine,
+            (true, _) => CellView::Open(self.number(p)? as u8),
+            (false, true) => CellView::Flagged,
+            (false, false) => CellView::Hidden,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_open_collapses_cells() {
+        let board = QuantumBoard::new(9, 9, 10, 5).unwrap();
+        let total: f64 = board.probabilities.iter().sum();
+        assert!(total > 5.0 && total < 15.0, "{}", total);
+
+        // Open the likeliest safe cells one at a time; each either collapses
+        // to a number or blows up.
+        let mut board = board;
+        while board.state != BoardState::Failed && board.state != BoardState::Won {
+            let i = (0..81)
+                .filter(|&i| !board.open[i])
+                .min_by(|&a, &b| board.probabilities[a].total_cmp(&board.probabilities[b]))
+                .unwrap();
+            let p = Point::new(i % 9, i / 9);
+            board = board.open(&p).unwrap();
+            let collapsed = board.probability(&p).unwrap();
+            assert!(collapsed == 0.0 || collapsed == 1.0);
+            if board.state != BoardState::Failed {
+                let expected = board.expected_count(&p).unwrap();
+                assert_eq!(board.number(&p), Some((expected + 0.5) as i32));
+            }
+        }
+        assert_eq!(board.open(&Point::new(0, 0)), Err(Error::GameOver));
+    }
+
+    #[test]
+    fn test_flags_block_opening() {
+        let board = QuantumBoard::new(3, 3, 1, 0).unwrap();
+        let p = Point::new(1, 1);
+        let flagged = board.flag(&p).unwrap();
+        assert_eq!(flagged.cell_view(&p), Some(CellView::Flagged));
+        assert_eq!(flagged.open(&p), Err(Error::CellFlagged { x: 1, y: 1 }));
+        assert_eq!(
+            flagged.flag(&p).unwrap().cell_view(&p),
+            Some(CellView::Hidden)
+        );
+        assert!(QuantumBoard::new(2, 2, 4, 0).is_err());
+    }
+}
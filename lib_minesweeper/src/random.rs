@@ -0,0 +1,341 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{create_board_avoiding, Board, Error, Point, Result};
+
+// Bumped whenever board generation uses its random numbers differently, so
+// audits recorded by an older version aren't replayed through the new one.
+pub const GENERATOR_VERSION: u32 = 1;
+
+// SplitMix64 is tiny, has no dependencies and gives the same sequence on every
+// platform, which is all we need to turn a seed into a reproducible board.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn gen_range(&mut self, start: usize, end: usize) -> usize {
+        start + (self.next_u64() % ((end - start) as u64)) as usize
+    }
+}
+
+pub fn seeded_rand(seed: u64) -> impl FnMut(usize, usize) -> usize {
+    let mut rng = SplitMix64::new(seed);
+    move |start, end| rng.gen_range(start, end)
+}
+
+// Where board generation gets its numbers from, see BoardBuilder::source.
+// Sources only have to give 64 random bits at a time.
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+
+    fn gen_range(&mut self, start: usize, end: usize) -> usize {
+        start + (self.next_u64() % ((end - start) as u64)) as usize
+    }
+
+    // Uniform in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// A source as the rand closure create_board takes.
+pub fn rand_from(source: &mut dyn RandomSource) -> impl FnMut(usize, usize) -> usize + '_ {
+    move |start, end| source.gen_range(start, end)
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        SplitMix64::next_u64(self)
+    }
+}
+
+// xoshiro256**, for when SplitMix64's 64 bits of state aren't enough, like
+// drawing many boards from one seed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Xoshiro256StarStar {
+    state: [u64; 4],
+}
+
+impl Xoshiro256StarStar {
+    // The state is filled from SplitMix64, as the authors suggest.
+    pub fn new(seed: u64) -> Xoshiro256StarStar {
+        let mut seeder = SplitMix64::new(seed);
+        Xoshiro256StarStar {
+            state: [
+                seeder.next_u64(),
+                seeder.next_u64(),
+                seeder.next_u64(),
+                seeder.next_u64(),
+            ],
+        }
+    }
+
+    // An all zero state only ever gives zeros, and is refused.
+    pub fn from_state(state: [u64; 4]) -> Result<Xoshiro256StarStar> {
+        if state == [0; 4] {
+            return Err(Error::InvalidConfig(String::from(
+                "xoshiro256** can't start from an all zero state",
+            )));
+        }
+        Ok(Xoshiro256StarStar { state })
+    }
+}
+
+impl RandomSource for Xoshiro256StarStar {
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+}
+
+// The operating system's entropy, for boards nobody should be able to
+// predict. Not reproducible, so boards from it have no seed.
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct OsEntropy;
+
+#[cfg(feature = "std")]
+impl RandomSource for OsEntropy {
+    fn next_u64(&mut self) -> u64 {
+        rand::RngCore::next_u64(&mut rand::rngs::OsRng)
+    }
+}
+
+// Gives the numbers it was made with, in order and then over again. For
+// tests that need a board with the mines in known places: ranges take the
+// numbers modulo their length, so numbers inside the range come out as they
+// are.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MockSequence {
+    values: Vec<u64>,
+    next: usize,
+}
+
+impl MockSequence {
+    pub fn new(values: Vec<u64>) -> Result<MockSequence> {
+        if values.is_empty() {
+            return Err(Error::InvalidConfig(String::from(
+                "a mock sequence needs at least one number",
+            )));
+        }
+        Ok(MockSequence { values, next: 0 })
+    }
+}
+
+impl RandomSource for MockSequence {
+    fn next_u64(&mut self) -> u64 {
+        let value = self.values[self.next];
+        self.next = (self.next + 1) % self.values.len();
+        value
+    }
+}
+
+// One number drawn from rand(start, end).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Draw {
+    pub start: usize,
+    pub end: usize,
+    pub value: usize,
+}
+
+// Everything a board's generation drew, so the board can be rebuilt exactly
+// without trusting the seed, or the generator, to give the same numbers.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct RngAudit {
+    pub generator: u32,
+    pub seed: Option<u64>,
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    // Cells kept free of mines, for boards placed by the first open.
+    pub safe: Vec<Point>,
+    pub draws: Vec<Draw>,
+}
+
+// Passes rand's numbers through, keeping a copy of each.
+pub fn recording<'a>(
+    mut rand: impl FnMut(usize, usize) -> usize + 'a,
+    draws: &'a mut Vec<Draw>,
+) -> impl FnMut(usize, usize) -> usize + 'a {
+    move |start, end| {
+        let value = rand(start, end);
+        draws.push(Draw { start, end, value });
+        value
+    }
+}
+
+impl RngAudit {
+    // Generates a board from the seed, keeping every draw.
+    pub fn generate(
+        width: usize,
+        height: usize,
+        mines: usize,
+        seed: u64,
+        safe: &[Point],
+    ) -> Result<(Board, RngAudit)> {
+        let mut draws = Vec::new();
+        let board = create_board_avoiding(
+            width,
+            height,
+            mines,
+            recording(seeded_rand(seed), &mut draws),
+            safe,
+        )?;
+        let audit = RngAudit {
+            generator: GENERATOR_VERSION,
+            seed: Some(seed),
+            width,
+            height,
+            mines,
+            safe: safe.to_vec(),
+            draws,
+        };
+        Ok((board, audit))
+    }
+
+    // Rebuilds the board from the recorded draws alone. Fails when they
+    // aren't the draws generation asks for, or don't follow from the seed.
+    pub fn board(&self) -> Result<Board> {
+        if self.generator != GENERATOR_VERSION {
+            return Err(Error::InvalidConfig(format!(
+                "the board was generated by version {} of the generator, this is {}",
+                self.generator, GENERATOR_VERSION
+            )));
+        }
+        if let Some(seed) = self.seed {
+            let mut rng = SplitMix64::new(seed);
+            if self
+                .draws
+                .iter()
+                .any(|d| d.start >= d.end || rng.gen_range(d.start, d.end) != d.value)
+            {
+                return Err(Error::InvalidConfig(format!(
+                    "the recorded draws don't follow from seed {}",
+                    seed
+                )));
+            }
+        }
+        let matched = Cell::new(true);
+        let mut next = self.draws.iter();
+        // Past the end of the recording, or off it, the numbers only need to
+        // let generation finish: the board is rejected anyway.
+        let mut fallback = SplitMix64::new(0);
+        let board = create_board_avoiding(
+            self.width,
+            self.height,
+            self.mines,
+            |start, end| match next.next() {
+                Some(d) if (d.start, d.end) == (start, end) && d.value < end => d.value,
+                _ => {
+                    matched.set(false);
+                    fallback.gen_range(start, end)
+                }
+            },
+            &self.safe,
+        )?;
+        if !matched.get() || next.next().is_some() {
+            return Err(Error::InvalidConfig(String::from(
+                "the recorded draws don't generate a board",
+            )));
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = seeded_rand(42);
+        let mut b = seeded_rand(42);
+        for _ in 0..100 {
+            let x = a(3, 17);
+            assert_eq!(x, b(3, 17));
+            assert!((3..17).contains(&x));
+        }
+    }
+
+    #[test]
+    fn test_random_sources() {
+        // The first outputs of the reference implementation.
+        let mut xoshiro = Xoshiro256StarStar::from_state([1, 2, 3, 4]).unwrap();
+        let outputs: Vec<u64> = (0..4).map(|_| xoshiro.next_u64()).collect();
+        assert_eq!(outputs, vec![11520, 0, 1509978240, 1215971899390074240]);
+        assert!(Xoshiro256StarStar::from_state([0; 4]).is_err());
+        assert_eq!(
+            Xoshiro256StarStar::new(7).next_u64(),
+            Xoshiro256StarStar::new(7).next_u64()
+        );
+
+        let mut splitmix = SplitMix64::new(42);
+        let mut rand = seeded_rand(42);
+        assert_eq!(RandomSource::gen_range(&mut splitmix, 3, 17), rand(3, 17));
+
+        let mut mock = MockSequence::new(vec![1, 4, 12]).unwrap();
+        let mut rand = rand_from(&mut mock);
+        assert_eq!(
+            [rand(0, 5), rand(0, 5), rand(10, 20), rand(0, 5)],
+            [1, 4, 12, 1]
+        );
+        assert!(MockSequence::new(vec![]).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_os_entropy() {
+        let mut os = OsEntropy;
+        assert!((0..8).map(|_| os.gen_range(0, 10)).all(|x| x < 10));
+    }
+
+    #[test]
+    fn test_rng_audit_rebuilds_the_board() {
+        let safe = [Point::new(0, 0)];
+        let (board, audit) = RngAudit::generate(6, 5, 8, 9, &safe).unwrap();
+        assert_eq!(audit.draws.len() % 2, 0);
+        assert!(audit.draws.len() >= 16);
+        assert_eq!(audit.board(), Ok(board));
+
+        let mut tampered = audit.clone();
+        tampered.draws[0].value = (tampered.draws[0].value + 1) % 6;
+        assert!(tampered.board().is_err());
+        // Without the seed the draws are taken as they are.
+        tampered.seed = None;
+        assert!(tampered.board().is_ok());
+        tampered.draws.pop();
+        assert!(tampered.board().is_err());
+
+        let future = RngAudit {
+            generator: GENERATOR_VERSION + 1,
+            ..audit
+        };
+        assert!(future.board().is_err());
+    }
+}
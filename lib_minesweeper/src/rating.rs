@@ -0,0 +1,161 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::versus::TurnBasedMatch;
+
+pub const INITIAL_RATING: f64 = 1500.0;
+// Glicko's deviation for a player nobody knows anything about yet.
+pub const INITIAL_DEVIATION: f64 = 350.0;
+// The usual K for Elo, how far one game can move a rating.
+pub const ELO_K: f64 = 32.0;
+
+// What a server keeps per player. Elo ignores the deviation; Glicko shrinks
+// it as the player plays, so a newcomer's rating moves faster.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PlayerRating {
+    pub name: String,
+    pub rating: f64,
+    pub deviation: f64,
+    pub games: u32,
+}
+
+impl PlayerRating {
+    pub fn new(name: &str) -> PlayerRating {
+        PlayerRating {
+            name: String::from(name),
+            rating: INITIAL_RATING,
+            deviation: INITIAL_DEVIATION,
+            games: 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum RatingSystem {
+    Elo { k: f64 },
+    Glicko,
+}
+
+// 1 for beating the other player, 0.5 for a tie, 0 for losing.
+fn result(mine: i32, theirs: i32) -> f64 {
+    match mine.cmp(&theirs) {
+        core::cmp::Ordering::Greater => 1.0,
+        core::cmp::Ordering::Equal => 0.5,
+        core::cmp::Ordering::Less => 0.0,
+    }
+}
+
+fn elo_expected(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+const Q: f64 = core::f64::consts::LN_10 / 400.0;
+
+fn glicko_g(deviation: f64) -> f64 {
+    let pi = core::f64::consts::PI;
+    1.0 / (1.0 + 3.0 * Q * Q * deviation * deviation / (pi * pi)).sqrt()
+}
+
+// Rates a match from each player's final score: every pair of players counts
+// as one game between them, won by the higher score. All updates use the
+// ratings from before the match, so the order of players doesn't matter.
+// Glicko treats the match as one rating period.
+pub fn update_ratings(system: RatingSystem, ratings: &mut [PlayerRating], scores: &[i32]) {
+    let before: Vec<PlayerRating> = ratings.to_vec();
+    for (i, player) in ratings.iter_mut().enumerate().take(scores.len()) {
+        let opponents = (0..before.len().min(scores.len())).filter(|&j| j != i);
+        match system {
+            RatingSystem::Elo { k } => {
+                let change: f64 = opponents
+                    .map(|j| {
+                        let s = result(scores[i], scores[j]);
+                        k * (s - elo_expected(before[i].rating, before[j].rating))
+                    })
+                    .sum();
+                player.rating += change;
+            }
+            RatingSystem::Glicko => {
+                let (mut variance, mut improvement) = (0.0, 0.0);
+                for j in opponents {
+                    let g = glicko_g(before[j].deviation);
+                    let e = 1.0
+                        / (1.0 + 10f64.powf(-g * (before[i].rating - before[j].rating) / 400.0));
+                    variance += g * g * e * (1.0 - e);
+                    improvement += g * (result(scores[i], scores[j]) - e);
+                }
+                if variance == 0.0 {
+                    continue;
+                }
+                let d2 = 1.0 / (Q * Q * variance);
+                let precision = 1.0 / (before[i].deviation * before[i].deviation) + 1.0 / d2;
+                player.rating += Q / precision * improvement;
+                player.deviation = (1.0 / precision).sqrt();
+            }
+        }
+        player.games += 1;
+    }
+}
+
+impl TurnBasedMatch {
+    // Rates the finished match, with ratings listed in player order. Does
+    // nothing while the match is still going.
+    pub fn update_ratings(&self, system: RatingSystem, ratings: &mut [PlayerRating]) {
+        if self.is_over() {
+            update_ratings(system, ratings, self.scores());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close_to(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() < tolerance
+    }
+
+    #[test]
+    fn test_elo() {
+        let mut ratings = vec![PlayerRating::new("a"), PlayerRating::new("b")];
+        update_ratings(RatingSystem::Elo { k: ELO_K }, &mut ratings, &[12, 3]);
+        assert!(close_to(ratings[0].rating, 1516.0, 1e-9));
+        assert!(close_to(ratings[1].rating, 1484.0, 1e-9));
+        assert_eq!(ratings[0].games, 1);
+
+        update_ratings(RatingSystem::Elo { k: ELO_K }, &mut ratings, &[5, 5]);
+        assert!(ratings[0].rating < 1516.0);
+    }
+
+    #[test]
+    fn test_glicko_matches_the_paper() {
+        // The example from Glickman's description of the system: a 1500
+        // player beats a 1400 and loses to a 1550 and a 1700.
+        let mut ratings = vec![
+            PlayerRating {
+                deviation: 200.0,
+                ..PlayerRating::new("a")
+            },
+            PlayerRating {
+                rating: 1400.0,
+                deviation: 30.0,
+                ..PlayerRating::new("b")
+            },
+            PlayerRating {
+                rating: 1550.0,
+                deviation: 100.0,
+                ..PlayerRating::new("c")
+            },
+            PlayerRating {
+                rating: 1700.0,
+                deviation: 300.0,
+                ..PlayerRating::new("d")
+            },
+        ];
+        update_ratings(RatingSystem::Glicko, &mut ratings, &[2, 1, 3, 4]);
+        assert!(close_to(ratings[0].rating, 1464.1, 0.1), "{:?}", ratings[0]);
+        assert!(
+            close_to(ratings[0].deviation, 151.4, 0.1),
+            "{:?}",
+            ratings[0]
+        );
+    }
+}
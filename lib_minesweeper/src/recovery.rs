@@ -0,0 +1,292 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::annotations::Annotations;
+use crate::clock::Timer;
+use crate::coach::CoachOptions;
+use crate::game::{AutosaveEvery, Game, GameConfig, GameOptions, LoggedMove};
+use crate::migrations::{upgrade, Document};
+use crate::random::RngAudit;
+use crate::splits::SplitDefinition;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::{numbers_on_board, Board, Error, MapElement, Point, Result};
+
+// What load_recovering had to do to a save to get a game out of it. A save
+// that loaded as it was has an empty report.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RecoveryReport {
+    // The file ended early and was cut back to its last complete value.
+    pub truncated: bool,
+    // Cells that couldn't be read, and were taken as safe.
+    pub bad_cells: Vec<Point>,
+    // Numbers that didn't match the mines around them, rebuilt from those.
+    pub fixed_numbers: Vec<Point>,
+    // Moves that could be read and were played again.
+    pub kept_moves: usize,
+    // Unreadable moves, and every move after the first of them.
+    pub dropped_moves: usize,
+    // Settings that couldn't be read and are back to their defaults.
+    pub reset_fields: Vec<&'static str>,
+}
+
+impl RecoveryReport {
+    pub fn is_clean(&self) -> bool {
+        *self == RecoveryReport::default()
+    }
+}
+
+// Cuts truncated JSON back to its last complete array element or object
+// member, and closes whatever was still open.
+fn close_truncated(text: &str) -> Option<String> {
+    let (mut stack, mut in_string, mut escaped) = (Vec::new(), false, false);
+    let mut cut = None;
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => stack.push('}'),
+            '[' => stack.push(']'),
+            '}' | ']' => {
+                stack.pop();
+                cut = Some((i + 1, stack.clone()));
+            }
+            ',' => cut = Some((i, stack.clone())),
+            _ => (),
+        }
+    }
+    let (at, open) = cut?;
+    let mut out = String::from(&text[..at]);
+    out.extend(open.iter().rev());
+    Some(out)
+}
+
+// A field of the save, None when it's unreadable or, in a truncated save,
+// missing. Older saves may lack fields without being corrupt.
+fn field<T: DeserializeOwned>(
+    game: &Value,
+    name: &'static str,
+    report: &mut RecoveryReport,
+) -> Option<T> {
+    let parsed = match game.get(name) {
+        Some(value) => serde_json::from_value(value.clone()).ok(),
+        None if !report.truncated => return None,
+        None => None,
+    };
+    if parsed.is_none() {
+        report.reset_fields.push(name);
+    }
+    parsed
+}
+
+fn corrupt(reason: &str) -> Error {
+    Error::ParseError(String::from(reason))
+}
+
+// The mines as saved, with numbers rebuilt around them and every cell
+// closed: the moves are played again on it.
+fn recover_board(saved: &Value, report: &mut RecoveryReport) -> Result<Board> {
+    let rows = saved
+        .get("map")
+        .and_then(Value::as_array)
+        .ok_or_else(|| corrupt("the board is missing"))?;
+    let dimension = |name| saved.get(name).and_then(Value::as_u64).map(|n| n as usize);
+    let width = dimension("width")
+        .or_else(|| rows.first().and_then(Value::as_array).map(Vec::len))
+        .ok_or_else(|| corrupt("the board's width is missing"))?;
+    let height = dimension("height").unwrap_or(rows.len());
+    if width == 0 || height == 0 {
+        return Err(corrupt("the board is empty"));
+    }
+    let mut saved_counts = vec![];
+    let map: Vec<Vec<MapElement>> = (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let cell = rows
+                        .get(y)
+                        .and_then(|row| row.get(x))
+                        .and_then(|cell| serde_json::from_value(cell.clone()).ok());
+                    match cell {
+                        Some(Mine { .. }) => Mine { state: Closed },
+                        Some(Number { count, .. }) => {
+                            saved_counts.push((Point::new(x, y), count));
+                            Number {
+                                state: Closed,
+                                count: 0,
+                            }
+                        }
+                        None => {
+                            report.bad_cells.push(Point::new(x, y));
+                            Number {
+                                state: Closed,
+                                count: 0,
+                            }
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let board = numbers_on_board(Board::new(map)?);
+    for (p, count) in saved_counts {
+        if !matches!(board.at(&p), Some(Number { count: c, .. }) if *c == count) {
+            report.fixed_numbers.push(p);
+        }
+    }
+    Ok(Board {
+        seed: saved.get("seed").and_then(Value::as_u64),
+        ..board
+    })
+}
+
+// Gets what it can out of a save that won't load: the board is rebuilt from
+// its mines and the readable moves are played on it again, up to the first
+// one that isn't. Fails only when there's no board to rebuild.
+pub fn recover_save(bytes: &[u8]) -> Result<(Game, RecoveryReport)> {
+    let mut report = RecoveryReport::default();
+    let text = String::from_utf8_lossy(bytes);
+    let file: Value = match serde_json::from_str(&text) {
+        Ok(file) => file,
+        Err(_) => {
+            report.truncated = true;
+            close_truncated(&text)
+                .and_then(|text| serde_json::from_str(&text).ok())
+                .ok_or_else(|| corrupt("nothing in the file can be read"))?
+        }
+    };
+    // A truncated envelope may have lost its version, but not the game.
+    let game = match upgrade(Document::Save, file.clone()) {
+        Ok(game) => game,
+        Err(_) => file.get("game").cloned().unwrap_or(file),
+    };
+
+    // Games whose mines moved replay from where they were dealt.
+    let dealt = game.get("dealt").filter(|dealt| !dealt.is_null());
+    let board = recover_board(
+        dealt
+            .or_else(|| game.get("board"))
+            .ok_or_else(|| corrupt("the board is missing"))?,
+        &mut report,
+    )?;
+    let options: GameOptions = field(&game, "options", &mut report).unwrap_or_default();
+    let deferred_seed = field::<Option<u64>>(&game, "deferred_seed", &mut report).flatten();
+    let mut recovered = match deferred_seed {
+        Some(seed) => Game::new_deferred(GameConfig {
+            width: board.width,
+            height: board.height,
+            mines: game["board"]["mines"]
+                .as_u64()
+                .map_or(board.mines, |n| n as usize),
+            seed,
+            options,
+        })?,
+        None => Game::new(board, options),
+    };
+    if let Some(policy) = field::<Option<AutosaveEvery>>(&game, "autosave", &mut report).flatten() {
+        recovered = recovered.autosave_policy(policy);
+    }
+    recovered.set_coaching(field::<Option<CoachOptions>>(&game, "coach", &mut report).flatten());
+    if let Some(splits) = field::<Option<SplitDefinition>>(&game, "splits", &mut report).flatten() {
+        recovered = recovered.splits(splits);
+    }
+    recovered.annotations =
+        field::<Option<Annotations>>(&game, "annotations", &mut report).flatten();
+
+    let moves = game.get("moves").and_then(Value::as_array);
+    for m in moves.into_iter().flatten() {
+        match serde_json::from_value::<LoggedMove>(m.clone()) {
+            Ok(m) if report.dropped_moves == 0 => {
+                let _ = recovered.play(&m.action, m.at);
+                report.kept_moves += 1;
+            }
+            _ => report.dropped_moves += 1,
+        }
+    }
+    recovered.restore(
+        field::<Timer>(&game, "timer", &mut report).unwrap_or_default(),
+        field::<Option<RngAudit>>(&game, "rng_audit", &mut report).flatten(),
+    );
+    Ok((recovered, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::{save_from_json, save_to_json};
+    use crate::tests::five_by_two_board;
+    use crate::{BoardState, Move};
+    use pretty_assertions::assert_eq;
+
+    fn game() -> Game {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        game.apply(&Move::Flag(Point::new(0, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        game
+    }
+
+    #[test]
+    fn test_close_truncated() {
+        assert_eq!(
+            close_truncated(r#"{"a": [1, 2, {"b": "x,]"#).as_deref(),
+            Some(r#"{"a": [1, 2]}"#)
+        );
+        assert_eq!(close_truncated(r#"{"a"#), None);
+    }
+
+    #[test]
+    fn test_recover_truncated_save() {
+        let json = save_to_json(&game()).unwrap();
+        let text = String::from_utf8(json).unwrap();
+        // Cut in the middle of the last move.
+        let cut = text.rfind(r#"{"action""#).unwrap() + 12;
+        let (recovered, report) = recover_save(&text.as_bytes()[..cut]).unwrap();
+        assert!(report.truncated);
+        assert_eq!(report.kept_moves, 2);
+        assert_eq!(report.bad_cells, vec![]);
+        assert_eq!(recovered.moves(), &game().moves()[..2]);
+        assert_eq!(recovered.board().state, BoardState::Playing);
+    }
+
+    #[test]
+    fn test_recover_bad_cells_and_moves() {
+        let mut save: Value = serde_json::from_slice(&save_to_json(&game()).unwrap()).unwrap();
+        let board = &mut save["game"]["board"];
+        board["map"][0][2] = Value::from("garbage");
+        board["map"][1][3]["Number"]["count"] = Value::from(7);
+        save["game"]["moves"][1] = Value::from(42);
+        save["game"]["coach"] = Value::from("loud");
+        let bytes = serde_json::to_vec(&save).unwrap();
+        assert!(save_from_json(&bytes).is_err());
+
+        let (recovered, report) = recover_save(&bytes).unwrap();
+        assert_eq!(
+            report,
+            RecoveryReport {
+                truncated: false,
+                bad_cells: vec![Point::new(2, 0)],
+                fixed_numbers: vec![Point::new(3, 1)],
+                kept_moves: 1,
+                dropped_moves: 2,
+                reset_fields: vec!["coach"],
+            }
+        );
+        // Only the first open, on a board with the same mines.
+        assert_eq!(recovered.moves(), &game().moves()[..1]);
+        assert_eq!(recovered.board().mines, 2);
+        assert!(!report.is_clean());
+    }
+}
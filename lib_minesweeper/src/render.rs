@@ -0,0 +1,641 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::{Event, Game};
+use crate::layout::Viewport;
+use crate::view::CellView;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardRead, MapElement, Point};
+
+// Bumped whenever the output of any renderer here changes for the same input,
+// so consumers that store renders know when to redo them. The snapshot tests
+// below pin every format; a change to one of them needs a bump here too.
+pub fn stable_format_version() -> u32 {
+    2
+}
+
+pub(crate) fn cell_char(cell: CellView) -> char {
+    match cell {
+        CellView::Hidden => '.',
+        CellView::Flagged => 'F',
+        CellView::Question => '?',
+        CellView::Open(0) => ' ',
+        // 3D boards count up to 26, from 10 on as letters.
+        CellView::Open(count) => core::char::from_digit(count as u32, 36).unwrap_or('?'),
+        CellView::Mine => '*',
+        CellView::ExplodedMine => 'X',
+        CellView::WrongFlag => 'x',
+        CellView::OutOfReach => '#',
+        CellView::Treasure(_) => '$',
+    }
+}
+
+fn view_text(view: Vec<Vec<CellView>>) -> String {
+    let mut out = String::new();
+    for row in view {
+        out.extend(row.into_iter().map(cell_char));
+        out.push('\n');
+    }
+    out
+}
+
+// One character per cell, as the player sees the board.
+pub fn board_text<B: BoardRead>(board: &B) -> String {
+    view_text(board.view())
+}
+
+// Like board_text, with the cells a game with fog of war keeps out of reach.
+pub fn game_text(game: &Game) -> String {
+    view_text(game.view())
+}
+
+// Like board_text, but only the cells the viewport shows, one character per
+// cell whatever the zoom. Cells past the board's edges are blank.
+pub fn board_text_in<B: BoardRead>(board: &B, viewport: &Viewport) -> String {
+    let mut out = String::new();
+    let (x0, y0) = viewport.offset();
+    let (across, down) = viewport.span();
+    for y in y0..y0 + down as i32 {
+        for x in x0..x0 + across as i32 {
+            let cell = board.cell_view(&Point { x, y });
+            out.push(cell.map_or(' ', cell_char));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// How board_text_with writes counts. Boards where a cell can count more
+// than 9 mines, like 3D ones, need letters to stay one character per cell,
+// or wider cells.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum CountFormat {
+    // 1 to 9, then a for 10 up to z for 35, as board_text does.
+    #[default]
+    Letters,
+    // Every cell as wide as the widest count on the board.
+    Hex,
+    Decimal,
+}
+
+impl CountFormat {
+    fn width(self, count: u8) -> usize {
+        match self {
+            CountFormat::Letters => 1,
+            CountFormat::Hex => format!("{:x}", count).len(),
+            CountFormat::Decimal => format!("{}", count).len(),
+        }
+    }
+}
+
+// The characters board_text_with draws cells with, for frontends with
+// their own conventions or scripts. The default draws like board_text.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct GlyphTable {
+    pub hidden: char,
+    pub flagged: char,
+    pub question: char,
+    pub zero: char,
+    pub mine: char,
+    pub exploded: char,
+    pub wrong_flag: char,
+    pub out_of_reach: char,
+    pub treasure: char,
+    pub counts: CountFormat,
+}
+
+impl Default for GlyphTable {
+    fn default() -> GlyphTable {
+        GlyphTable {
+            hidden: '.',
+            flagged: 'F',
+            question: '?',
+            zero: ' ',
+            mine: '*',
+            exploded: 'X',
+            wrong_flag: 'x',
+            out_of_reach: '#',
+            treasure: '$',
+            counts: CountFormat::Letters,
+        }
+    }
+}
+
+impl GlyphTable {
+    fn width(&self, cell: CellView) -> usize {
+        match cell {
+            CellView::Open(count) if count > 0 => self.counts.width(count),
+            _ => 1,
+        }
+    }
+
+    // Right-aligned in `width` characters.
+    fn write(&self, out: &mut String, cell: CellView, width: usize) {
+        let glyph = match cell {
+            CellView::Hidden => self.hidden,
+            CellView::Flagged => self.flagged,
+            CellView::Question => self.question,
+            CellView::Open(0) => self.zero,
+            CellView::Open(count) => {
+                let _ = match self.counts {
+                    CountFormat::Letters => write!(
+                        out,
+                        "{:>1$}",
+                        core::char::from_digit(count as u32, 36).unwrap_or('?'),
+                        width
+                    ),
+                    CountFormat::Hex => write!(out, "{:>1$x}", count, width),
+                    CountFormat::Decimal => write!(out, "{:>1$}", count, width),
+                };
+                return;
+            }
+            CellView::Mine => self.mine,
+            CellView::ExplodedMine => self.exploded,
+            CellView::WrongFlag => self.wrong_flag,
+            CellView::OutOfReach => self.out_of_reach,
+            CellView::Treasure(_) => self.treasure,
+        };
+        let _ = write!(out, "{:>1$}", glyph, width);
+    }
+}
+
+// Like board_text, drawn with `glyphs`. When some count needs more than one
+// character every cell gets as wide, so the columns stay lined up.
+pub fn board_text_with<B: BoardRead>(board: &B, glyphs: &GlyphTable) -> String {
+    let view = board.view();
+    let width = view
+        .iter()
+        .flatten()
+        .map(|&cell| glyphs.width(cell))
+        .max()
+        .unwrap_or(1);
+    let mut out = String::new();
+    for row in view {
+        for cell in row {
+            glyphs.write(&mut out, cell, width);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum Palette {
+    // The CLI's colours: yellow for hidden, green for flags, cyan for numbers
+    // and red for mines.
+    #[default]
+    Classic,
+    // Black, white and saturated colours with light text on dark cells.
+    HighContrast,
+    // Blue and orange instead of green and red, after the Okabe-Ito palette.
+    DeuteranopiaSafe,
+}
+
+// How to draw one kind of cell: an ANSI SGR style, and the fill and text
+// colours for SVG.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CellColors {
+    pub ansi: &'static str,
+    pub fill: &'static str,
+    pub text: &'static str,
+}
+
+const fn colors(ansi: &'static str, fill: &'static str, text: &'static str) -> CellColors {
+    CellColors { ansi, fill, text }
+}
+
+impl Palette {
+    pub fn colors(&self, cell: CellView) -> CellColors {
+        use CellView::*;
+        match (self, cell) {
+            (Palette::Classic, Hidden | Question) => colors("43", "#c8b400", "#000000"),
+            (Palette::Classic, Flagged | WrongFlag) => colors("30;102", "#5fd75f", "#000000"),
+            (Palette::Classic, Open(0)) => colors("107", "#ffffff", "#000000"),
+            (Palette::Classic, Open(_)) => colors("30;106", "#87d7d7", "#000000"),
+            (Palette::Classic, Mine | ExplodedMine) => colors("30;41", "#d70000", "#000000"),
+            (Palette::Classic, OutOfReach) => colors("100", "#767676", "#000000"),
+            (Palette::Classic, Treasure(_)) => colors("30;103", "#ffd700", "#000000"),
+            (Palette::HighContrast, Hidden | Question) => colors("97;40", "#000000", "#ffffff"),
+            (Palette::HighContrast, Flagged | WrongFlag) => colors("30;103", "#ffff00", "#000000"),
+            (Palette::HighContrast, Open(0)) => colors("30;107", "#ffffff", "#000000"),
+            (Palette::HighContrast, Open(_)) => colors("97;44", "#0000c0", "#ffffff"),
+            (Palette::HighContrast, Mine | ExplodedMine) => colors("97;41", "#c00000", "#ffffff"),
+            (Palette::HighContrast, OutOfReach) => colors("90;40", "#303030", "#ffffff"),
+            (Palette::HighContrast, Treasure(_)) => colors("30;105", "#ff00ff", "#000000"),
+            (Palette::DeuteranopiaSafe, Hidden | Question) => colors("30;47", "#b0b0b0", "#000000"),
+            (Palette::DeuteranopiaSafe, Flagged | WrongFlag) => {
+                colors("97;44", "#0072b2", "#ffffff")
+            }
+            (Palette::DeuteranopiaSafe, Open(0)) => colors("107", "#ffffff", "#000000"),
+            (Palette::DeuteranopiaSafe, Open(_)) => colors("30;104", "#56b4e9", "#000000"),
+            (Palette::DeuteranopiaSafe, Mine | ExplodedMine) => {
+                colors("30;43", "#e69f00", "#000000")
+            }
+            (Palette::DeuteranopiaSafe, OutOfReach) => colors("100", "#606060", "#000000"),
+            (Palette::DeuteranopiaSafe, Treasure(_)) => colors("30;103", "#f0e442", "#000000"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Animation {
+    Reveal,
+    Explosion,
+    Celebration,
+}
+
+// Presets frontends can offer for accessibility.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct RenderStyle {
+    pub palette: Palette,
+    // No animations at all, for players who set their system to reduce motion.
+    pub reduced_motion: bool,
+}
+
+impl RenderStyle {
+    // The animation a frontend may play for a game event. Always None with
+    // reduced motion, so frontends just skip whatever they'd have played.
+    pub fn animation(&self, event: &Event) -> Option<Animation> {
+        if self.reduced_motion {
+            return None;
+        }
+        match event {
+            Event::Opened { .. } => Some(Animation::Reveal),
+            Event::Exploded { .. } => Some(Animation::Explosion),
+            Event::Won => Some(Animation::Celebration),
+            _ => None,
+        }
+    }
+}
+
+pub fn board_ansi<B: BoardRead>(board: &B) -> String {
+    board_ansi_styled(board, &RenderStyle::default())
+}
+
+pub fn board_ansi_styled<B: BoardRead>(board: &B, style: &RenderStyle) -> String {
+    let mut out = String::new();
+    for row in board.view() {
+        for cell in row {
+            let _ = write!(
+                out,
+                "\x1b[{}m{}\x1b[0m",
+                style.palette.colors(cell).ansi,
+                cell_char(cell)
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// A standalone SVG with `cell_size` pixels per cell.
+pub fn board_svg<B: BoardRead>(board: &B, cell_size: usize) -> String {
+    board_svg_styled(board, cell_size, &RenderStyle::default())
+}
+
+pub fn board_svg_styled<B: BoardRead>(board: &B, cell_size: usize, style: &RenderStyle) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="{}" text-anchor="middle">"#,
+        board.width() * cell_size,
+        board.height() * cell_size,
+        cell_size * 3 / 4
+    );
+    for (y, row) in board.view().into_iter().enumerate() {
+        for (x, cell) in row.into_iter().enumerate() {
+            let colors = style.palette.colors(cell);
+            let _ = writeln!(
+                out,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="#808080"/>"##,
+                x * cell_size,
+                y * cell_size,
+                cell_size,
+                cell_size,
+                colors.fill
+            );
+            let label = cell_char(cell);
+            if !matches!(label, ' ' | '.' | '#') {
+                let _ = writeln!(
+                    out,
+                    r#"<text x="{}" y="{}" fill="{}">{}</text>"#,
+                    x * cell_size + cell_size / 2,
+                    y * cell_size + cell_size * 3 / 4,
+                    colors.text,
+                    label
+                );
+            }
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+// One character per cell: ' ' for open cells, '.' for certainly safe, '*' for
+// certainly a mine and the rounded tenth of the probability in between.
+pub fn heatmap_text(grid: &[Vec<Option<f64>>]) -> String {
+    let mut out = String::new();
+    for row in grid {
+        for p in row {
+            out.push(match p {
+                None => ' ',
+                Some(p) if *p <= 0.0 => '.',
+                Some(p) if *p >= 1.0 => '*',
+                Some(p) => {
+                    let tenth = ((p * 10.0) + 0.5) as u32;
+                    core::char::from_digit(tenth.clamp(1, 9), 10).unwrap_or('?')
+                }
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// Green (safe) to red (mine) background using the xterm 256 colour cube, with
+// the percentage printed on top.
+pub fn heatmap_ansi(grid: &[Vec<Option<f64>>]) -> String {
+    let mut out = String::new();
+    for row in grid {
+        for p in row {
+            match p {
+                None => out.push_str("   "),
+                Some(p) => {
+                    let p = p.clamp(0.0, 1.0);
+                    let red = ((p * 5.0) + 0.5) as u32;
+                    let green = 5 - red;
+                    let color = 16 + 36 * red + 6 * green;
+                    let percent = ((p * 100.0) + 0.5) as u32;
+                    let _ = write!(out, "\x1b[30;48;5;{}m{:>3}\x1b[0m", color, percent);
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn truth_char(el: &MapElement) -> char {
+    match el {
+        Mine { .. } => '*',
+        Number { count, .. } => core::char::from_digit(*count as u32, 10).unwrap_or('?'),
+    }
+}
+
+// Dumps of the board's internals, for debugging and logs rather than players:
+// unlike the renderers above they show what's under closed cells, and their
+// format may change without a bump of stable_format_version.
+impl Board {
+    // Every cell with its mine or number and its state, under column and row
+    // indices:
+    //
+    //     5x2, 2 mines, Playing, no seed
+    //        0  1  2  3  4
+    //     0 [*] 2 (1)(0)(0)
+    //     1  2  * (1)(0)(0)
+    //     legend: * mine, digits count mines around; (x) open, [x] flagged
+    pub fn debug_dump(&self) -> String {
+        let digits = |n: usize| n.saturating_sub(1).to_string().len();
+        let (label, cell) = (digits(self.height), digits(self.width).max(2) + 1);
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{}x{}, {} mines, {:?}, ",
+            self.width, self.height, self.mines, self.state
+        );
+        let _ = match self.seed {
+            Some(seed) => writeln!(out, "seed {}", seed),
+            None => writeln!(out, "no seed"),
+        };
+        let _ = write!(out, "{:label$} ", "", label = label);
+        for x in 0..self.width {
+            let _ = write!(out, "{:>width$} ", x, width = cell - 1);
+        }
+        out.push('\n');
+        for (y, row) in self.map.iter().enumerate() {
+            let _ = write!(out, "{:>label$} ", y, label = label);
+            for el in row {
+                let c = truth_char(el);
+                let token = match el {
+                    Mine { state: Open } | Number { state: Open, .. } => format!("({})", c),
+                    Mine { state: Flagged } | Number { state: Flagged, .. } => format!("[{}]", c),
+                    _ => format!(" {} ", c),
+                };
+                let _ = write!(out, "{:>cell$}", token, cell = cell);
+            }
+            out.push('\n');
+        }
+        out.push_str("legend: * mine, digits count mines around; (x) open, [x] flagged\n");
+        out
+    }
+
+    // The same on one line: the mines and numbers row by row, then the board
+    // as the player sees it, as board_text draws it.
+    //
+    //     "5x2 Playing mines=2 map=*2100/2*100 seen=F.1  /..1  "
+    pub fn debug_line(&self) -> String {
+        let map: Vec<String> = self
+            .map
+            .iter()
+            .map(|row| row.iter().map(truth_char).collect())
+            .collect();
+        let seen: Vec<String> = board_text(self).lines().map(String::from).collect();
+        format!(
+            "{}x{} {:?} mines={} map={} seen={}",
+            self.width,
+            self.height,
+            self.state,
+            self.mines,
+            map.join("/"),
+            seen.join("/")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use crate::MapElementCellState::Closed;
+    use alloc::vec;
+    use insta::assert_snapshot;
+    use pretty_assertions::assert_eq;
+
+    // X2100
+    // 2X100, lost by opening B2 with A2 wrongly flagged.
+    fn lost_board() -> Board {
+        Scenario::from_ascii("*....\n.*...\n")
+            .open("E1")
+            .flag("A2")
+            .open("B2")
+            .into_board()
+    }
+
+    fn playing_board() -> Board {
+        Scenario::from_ascii("*....\n.*...\n")
+            .open("E1")
+            .flag("A1")
+            .into_board()
+    }
+
+    #[test]
+    fn test_board_text() {
+        assert_eq!(board_text(&lost_board()), "*21  \nxX1  \n");
+    }
+
+    #[test]
+    fn test_debug_dump() {
+        let board = playing_board();
+        assert_eq!(
+            board.debug_dump(),
+            "5x2, 2 mines, Playing, no seed\n   \
+             0  1  2  3  4 \n\
+             0 [*] 2 (1)(0)(0)\n\
+             1  2  * (1)(0)(0)\n\
+             legend: * mine, digits count mines around; (x) open, [x] flagged\n"
+        );
+        assert_eq!(
+            board.debug_line(),
+            "5x2 Playing mines=2 map=*2100/2*100 seen=F.1  /..1  "
+        );
+    }
+
+    #[test]
+    fn test_board_text_with_large_counts() {
+        let open = |count| Number { state: Open, count };
+        let board = Board::new(vec![
+            vec![
+                open(12),
+                open(3),
+                Number {
+                    state: Closed,
+                    count: 0,
+                },
+            ],
+            vec![Mine { state: Flagged }, open(0), open(26)],
+        ])
+        .unwrap();
+        let glyphs = |counts| GlyphTable {
+            counts,
+            ..GlyphTable::default()
+        };
+        assert_eq!(
+            board_text_with(&board, &GlyphTable::default()),
+            board_text(&board)
+        );
+        assert_eq!(board_text(&board), "c3.\nF q\n");
+        assert_eq!(
+            board_text_with(&board, &glyphs(CountFormat::Decimal)),
+            "12 3 .\n F  26\n"
+        );
+        assert_eq!(
+            board_text_with(&board, &glyphs(CountFormat::Hex)),
+            " c 3 .\n F  1a\n"
+        );
+        let boxes = GlyphTable {
+            hidden: '\u{25a0}',
+            zero: '\u{b7}',
+            ..GlyphTable::default()
+        };
+        assert_eq!(board_text_with(&board, &boxes), "c3\u{25a0}\nF\u{b7}q\n");
+        let lost = lost_board();
+        assert_eq!(
+            board_text_with(&lost, &glyphs(CountFormat::Decimal)),
+            board_text(&lost)
+        );
+    }
+
+    #[test]
+    fn test_board_text_in_viewport() {
+        let board = lost_board();
+        let mut viewport = Viewport::for_board(&board, (3, 3), 1);
+        viewport.pan(3, 0);
+        // Clamped to the board's right edge, and the row below it is blank.
+        assert_eq!(board_text_in(&board, &viewport), "1  \n1  \n   \n");
+    }
+
+    #[test]
+    fn test_board_text_snapshot() {
+        assert_snapshot!("board_text_playing", board_text(&playing_board()));
+        assert_snapshot!("board_text_lost", board_text(&lost_board()));
+    }
+
+    #[test]
+    fn test_board_ansi_snapshot() {
+        assert_snapshot!("board_ansi_playing", board_ansi(&playing_board()));
+        assert_snapshot!("board_ansi_lost", board_ansi(&lost_board()));
+    }
+
+    #[test]
+    fn test_board_svg_snapshot() {
+        assert_snapshot!("board_svg_lost", board_svg(&lost_board(), 16));
+    }
+
+    #[test]
+    fn test_palette_snapshots() {
+        let style = |palette| RenderStyle {
+            palette,
+            reduced_motion: false,
+        };
+        assert_snapshot!(
+            "board_ansi_high_contrast",
+            board_ansi_styled(&lost_board(), &style(Palette::HighContrast))
+        );
+        assert_snapshot!(
+            "board_svg_deuteranopia_safe",
+            board_svg_styled(&lost_board(), 16, &style(Palette::DeuteranopiaSafe))
+        );
+    }
+
+    #[test]
+    fn test_reduced_motion() {
+        let event = Event::Exploded {
+            point: crate::Point::new(0, 0),
+        };
+        let style = RenderStyle::default();
+        assert_eq!(style.animation(&event), Some(Animation::Explosion));
+        let style = RenderStyle {
+            reduced_motion: true,
+            ..style
+        };
+        assert_eq!(style.animation(&event), None);
+        assert_eq!(style.animation(&Event::Won), None);
+    }
+
+    #[test]
+    fn test_heatmap_snapshot() {
+        let probabilities = crate::solver::probabilities(&playing_board());
+        let grid: Vec<Vec<Option<f64>>> = probabilities
+            .cells
+            .chunks(probabilities.width)
+            .map(|row| row.to_vec())
+            .collect();
+        assert_snapshot!("heatmap_text", heatmap_text(&grid));
+        assert_snapshot!("heatmap_ansi", heatmap_ansi(&grid));
+    }
+
+    #[test]
+    fn test_heatmap_text() {
+        let grid = vec![
+            vec![None, Some(0.0), Some(1.0)],
+            vec![Some(0.25), Some(0.5), Some(0.01)],
+        ];
+        assert_eq!(heatmap_text(&grid), " .*\n351\n");
+    }
+
+    #[test]
+    fn test_heatmap_ansi() {
+        let grid = vec![vec![None, Some(0.0), Some(1.0)]];
+        assert_eq!(
+            heatmap_ansi(&grid),
+            "   \x1b[30;48;5;46m  0\x1b[0m\x1b[30;48;5;196m100\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::time::Duration;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::{Game, GameOptions, LoggedMove};
+use crate::random::RngAudit;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::{numbers_on_board, Board, Error, MapElement, Move, Point, Result};
+
+// Cell size in pixels assumed for the mouse positions in RAW files.
+const RAW_CELL_PIXELS: i32 = 16;
+
+// Everything needed to play a game back: the board before the first move and
+// the moves the player made. Boards generated from a seed also keep the
+// random numbers that placed their mines, see verify_replay.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub board: Board,
+    pub options: GameOptions,
+    pub moves: Vec<LoggedMove>,
+    pub rng: Option<RngAudit>,
+}
+
+fn closed_board(board: &Board) -> Board {
+    let map = (0..board.height)
+        .map(|y| {
+            (0..board.width)
+                .map(|x| match board.at(&Point::new(x, y)) {
+                    Some(Mine { .. }) => Mine { state: Closed },
+                    _ => Number {
+                        state: Closed,
+                        count: 0,
+                    },
+                })
+                .collect()
+        })
+        .collect();
+    numbers_on_board(Board {
+        map,
+        missing_points: (board.width * board.height - board.mines) as i32,
+        ..board.clone()
+    })
+}
+
+fn raw_level(board: &Board) -> &'static str {
+    match (board.width, board.height, board.mines) {
+        (8, 8, 10) | (9, 9, 10) => "Beginner",
+        (16, 16, 40) => "Intermediate",
+        (30, 16, 99) => "Expert",
+        _ => "Custom",
+    }
+}
+
+fn parse_error(line: &str) -> Error {
+    Error::ParseError(format!("bad RAW line {:?}", line))
+}
+
+fn header<'a>(headers: &[(&'a str, &'a str)], key: &str) -> Result<usize> {
+    headers
+        .iter()
+        .find(|(k, _)| *k == key)
+        .and_then(|(_, v)| v.parse().ok())
+        .ok_or_else(|| Error::ParseError(format!("missing or bad RAW header {:?}", key)))
+}
+
+// "12.34 lr 3 5 (40 72)": time, event and 1-based cell. Mouse positions and
+// anything after them are ignored.
+fn parse_event(line: &str, board: &Board) -> Result<Option<LoggedMove>> {
+    let mut parts = line.split_whitespace();
+    let time: f64 = parts
+        .next()
+        .and_then(|t| t.parse().ok())
+        .ok_or_else(|| parse_error(line))?;
+    let action = match parts.next() {
+        Some("lr") => Move::Open,
+        Some("rc") => Move::Flag,
+        _ => return Ok(None),
+    };
+    let mut coord = || -> Result<usize> {
+        match parts.next().and_then(|c| c.parse::<usize>().ok()) {
+            Some(c) if c > 0 => Ok(c - 1),
+            _ => Err(parse_error(line)),
+        }
+    };
+    let p = Point::new(coord()?, coord()?);
+    if board.at(&p).is_none() {
+        // Releasing the button outside of the board does nothing.
+        return Ok(None);
+    }
+    Ok(Some(LoggedMove {
+        action: action(p),
+        changed: true,
+        at: Duration::from_millis((time.max(0.0) * 1000.0 + 0.5) as u64),
+    }))
+}
+
+impl Replay {
+    pub fn from_game(game: &Game) -> Replay {
+        let board = game.dealt();
+        let rng = game.rng_audit().cloned().or_else(|| {
+            let seed = board.seed?;
+            RngAudit::generate(board.width, board.height, board.mines, seed, &[])
+                .ok()
+                .map(|(_, audit)| audit)
+        });
+        Replay {
+            board: closed_board(board),
+            options: game.options.clone(),
+            moves: game.moves().to_vec(),
+            rng,
+        }
+    }
+
+    // Plays every move again on a fresh game. Moves keep their timestamps and
+    // rejected ones are logged as such, as they were in the original game.
+    pub fn play(&self) -> Game {
+        let mut game = Game::new(self.board.clone(), self.options.clone());
+        for m in &self.moves {
+            let _ = game.play(&m.action, m.at);
+        }
+        game
+    }
+
+    // The RAW text format (RawVF Rev2) read by Minesweeper Arbiter tools and
+    // ranking sites. Opens become a left press and release, flags a right
+    // press and release.
+    pub fn to_raw(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "RawVF_Version: Rev2");
+        let _ = writeln!(out, "Program: lib_minesweeper");
+        let _ = writeln!(out, "Version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(out, "Player: Anonymous");
+        let _ = writeln!(out, "Level: {}", raw_level(&self.board));
+        let _ = writeln!(out, "Width: {}", self.board.width);
+        let _ = writeln!(out, "Height: {}", self.board.height);
+        let _ = writeln!(out, "Mines: {}", self.board.mines);
+        let _ = writeln!(out, "Marks: Off");
+        let _ = writeln!(out, "Mode: Classic");
+        let _ = writeln!(out, "Board:");
+        for y in 0..self.board.height {
+            for x in 0..self.board.width {
+                out.push(match self.board.at(&Point::new(x, y)) {
+                    Some(Mine { .. }) => '*',
+                    _ => '0',
+                });
+            }
+            out.push('\n');
+        }
+        let _ = writeln!(out, "Events:");
+        let _ = writeln!(out, "0.000 start");
+        for m in &self.moves {
+            let (press, release, p) = match &m.action {
+                Move::Open(p) => ("lc", "lr", p),
+                Move::Flag(p) => ("rc", "rr", p),
+                // RMV has no power-ups, so they're left out.
+                Move::Power(..) => continue,
+            };
+            let time = format!("{}.{:03}", m.at.as_secs(), m.at.subsec_millis());
+            let px = p.x * RAW_CELL_PIXELS + RAW_CELL_PIXELS / 2;
+            let py = p.y * RAW_CELL_PIXELS + RAW_CELL_PIXELS / 2;
+            for event in &[press, release] {
+                let _ = writeln!(
+                    out,
+                    "{} {} {} {} ({} {})",
+                    time,
+                    event,
+                    p.x + 1,
+                    p.y + 1,
+                    px,
+                    py
+                );
+            }
+        }
+        out
+    }
+
+    // Reads the board and the opening and flagging clicks of a RAW file. The
+    // options are the defaults, as RAW has no notion of assists.
+    pub fn from_raw(raw: &str) -> Result<Replay> {
+        let mut lines = raw.lines().map(str::trim).filter(|l| !l.is_empty());
+        let mut headers = vec![];
+        for line in &mut lines {
+            if line == "Board:" {
+                break;
+            }
+            let (key, value) = line.split_once(':').ok_or_else(|| parse_error(line))?;
+            headers.push((key.trim(), value.trim()));
+        }
+        let width = header(&headers, "Width")?;
+        let height = header(&headers, "Height")?;
+        let mines = header(&headers, "Mines")?;
+
+        let mut map: Vec<Vec<MapElement>> = vec![];
+        for line in (&mut lines).take(height) {
+            if line.chars().count() != width {
+                return Err(parse_error(line));
+            }
+            map.push(
+                line.chars()
+                    .map(|c| match c {
+                        '*' => Mine { state: Closed },
+                        _ => Number {
+                            state: Closed,
+                            count: 0,
+                        },
+                    })
+                    .collect(),
+            );
+        }
+        if map.len() != height {
+            return Err(Error::ParseError(String::from("RAW board is too short")));
+        }
+        let board = numbers_on_board(Board::new(map)?);
+        if board.mines != mines {
+            return Err(Error::ParseError(format!(
+                "RAW board has {} mines, header says {}",
+                board.mines, mines
+            )));
+        }
+
+        match lines.next() {
+            Some("Events:") => (),
+            Some(line) => return Err(parse_error(line)),
+            None => return Err(Error::ParseError(String::from("RAW events are missing"))),
+        }
+        let mut moves = vec![];
+        for line in lines {
+            if let Some(m) = parse_event(line, &board)? {
+                moves.push(m);
+            }
+        }
+        let mut replay = Replay {
+            board,
+            options: GameOptions::default(),
+            moves,
+            rng: None,
+        };
+        // Which clicks were wasted only shows when playing them.
+        replay.moves = replay.play().moves().to_vec();
+        Ok(replay)
+    }
+}
+
+// Checks that the replay is what it claims to be: its board is the one its
+// recorded random numbers generate, and playing it back accepts and rejects
+// the same moves it logged.
+pub fn verify_replay(replay: &Replay) -> Result<()> {
+    if let Some(audit) = &replay.rng {
+        let mines = |board: &Board| -> Vec<Point> {
+            (0..board.height)
+                .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+                .filter(|p| matches!(board.at(p), Some(Mine { .. })))
+                .collect()
+        };
+        let generated = audit.board()?;
+        if (generated.width, generated.height) != (replay.board.width, replay.board.height)
+            || mines(&generated) != mines(&replay.board)
+        {
+            return Err(Error::InvalidConfig(String::from(
+                "the replay's board isn't the one its random numbers generate",
+            )));
+        }
+    }
+    let played = replay.play();
+    let changed = |moves: &[LoggedMove]| -> Vec<bool> { moves.iter().map(|m| m.changed).collect() };
+    if changed(played.moves()) != changed(&replay.moves) {
+        return Err(Error::InvalidConfig(String::from(
+            "the replay's moves don't play back as logged",
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::five_by_two_board;
+    use crate::BoardState;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_raw_round_trip() {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        game.play(&Move::Open(Point::new(4, 0)), Duration::from_millis(250))
+            .unwrap();
+        game.play(&Move::Open(Point::new(4, 0)), Duration::from_millis(900))
+            .unwrap_err();
+        game.play(&Move::Flag(Point::new(0, 0)), Duration::from_millis(1500))
+            .unwrap();
+        let replay = game.replay();
+        let raw = replay.to_raw();
+        assert!(raw.contains("Board:\n*0000\n0*000\nEvents:\n"));
+        assert!(raw.contains("0.250 lc 5 1 (72 8)\n0.250 lr 5 1 (72 8)\n"));
+
+        let imported = Replay::from_raw(&raw).unwrap();
+        assert_eq!(imported, replay);
+        assert_eq!(imported.play(), game);
+    }
+
+    #[test]
+    fn test_from_arbiter_raw() {
+        let raw = "RawVF_Version: Rev2\n\
+                   Program: Arbiter\n\
+                   Level: Custom\n\
+                   Width: 5\n\
+                   Height: 2\n\
+                   Mines: 2\n\
+                   Board:\n\
+                   *0000\n\
+                   0*000\n\
+                   Events:\n\
+                   0.00 start\n\
+                   0.10 lc 5 2 (72 24) (l)\n\
+                   0.12 lr 5 2 (72 24)\n\
+                   0.50 mv 2 1 (24 8)\n\
+                   0.60 lr 2 1 (24 8)\n\
+                   1.01 lr 1 2 (8 24)\n";
+        let replay = Replay::from_raw(raw).unwrap();
+        assert_eq!(replay.moves.len(), 3);
+        assert_eq!(replay.moves[2].at, Duration::from_millis(1010));
+        assert_eq!(replay.play().board().state, BoardState::Won);
+
+        assert!(matches!(
+            Replay::from_raw(&raw.replace("Mines: 2", "Mines: 3")),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_replay() {
+        let mut game = Game::new(
+            numbers_on_board(crate::create_seeded_board(6, 5, 6, 11).unwrap()),
+            GameOptions::default(),
+        );
+        game.apply(&Move::Flag(Point::new(0, 0))).unwrap();
+        game.apply(&Move::Flag(Point::new(0, 0))).unwrap();
+        let replay = game.replay();
+        assert!(replay.rng.is_some());
+        assert_eq!(verify_replay(&replay), Ok(()));
+
+        let mut moved = replay.clone();
+        moved.board = closed_board(&numbers_on_board(
+            crate::create_seeded_board(6, 5, 6, 12).unwrap(),
+        ));
+        assert!(verify_replay(&moved).is_err());
+
+        let mut forged = replay;
+        forged.moves[1].changed = false;
+        assert!(verify_replay(&forged).is_err());
+
+        let config = crate::game::GameConfig {
+            width: 6,
+            height: 5,
+            mines: 6,
+            seed: 3,
+            options: GameOptions::default(),
+        };
+        let mut deferred = Game::new_deferred(config).unwrap();
+        deferred.apply(&Move::Open(Point::new(2, 2))).unwrap();
+        let replay = deferred.replay();
+        assert_eq!(replay.rng.as_ref().unwrap().safe.len(), 9);
+        assert_eq!(verify_replay(&replay), Ok(()));
+    }
+}
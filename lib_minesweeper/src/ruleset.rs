@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::clock::Clock;
+use crate::game::{Event, Game};
+use crate::stats::{GameSummary, ScoreAttackSummary};
+use crate::MapElement::Number;
+use crate::MapElementCellState::Flagged;
+use crate::{Board, Move, Result};
+
+// Scoring kept on top of the usual rules, fed every move the game takes by
+// Game::apply_ruled. The board is the one after the move, `at` the game's
+// time when it was made.
+pub trait Ruleset {
+    fn on_move(&mut self, board: &Board, events: &[Event], at: Duration);
+    fn score(&self) -> i64;
+
+    // The summary with the ruleset's own fields filled in.
+    fn summarize(&self, summary: GameSummary) -> GameSummary {
+        summary
+    }
+}
+
+// The combo never multiplies a move's cells by more than this.
+pub const MAX_MULTIPLIER: u64 = 8;
+
+// Arcade scoring: every cell an open reveals scores the combo multiplier.
+// Each safe open made within the window of the one before builds the combo
+// up, a slower one starts it over, and flagging a safe cell breaks it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScoreAttack {
+    window: Duration,
+    combo: u64,
+    best_combo: u64,
+    broken_combos: usize,
+    score: i64,
+    last_open: Option<Duration>,
+}
+
+impl ScoreAttack {
+    pub fn new(window: Duration) -> ScoreAttack {
+        ScoreAttack {
+            window,
+            combo: 0,
+            best_combo: 0,
+            broken_combos: 0,
+            score: 0,
+            last_open: None,
+        }
+    }
+
+    pub fn combo(&self) -> u64 {
+        self.combo
+    }
+
+    pub fn multiplier(&self) -> u64 {
+        self.combo.clamp(1, MAX_MULTIPLIER)
+    }
+
+    fn break_combo(&mut self) {
+        if self.combo > 0 {
+            self.broken_combos += 1;
+        }
+        self.combo = 0;
+        self.last_open = None;
+    }
+}
+
+impl Ruleset for ScoreAttack {
+    fn on_move(&mut self, board: &Board, events: &[Event], at: Duration) {
+        let mut opened = 0;
+        for event in events {
+            match event {
+                Event::Opened { .. } => opened += 1,
+                Event::Exploded { .. } => return self.break_combo(),
+                Event::Flagged { point, .. } => {
+                    if let Some(Number { state: Flagged, .. }) = board.at(point) {
+                        self.break_combo();
+                    }
+                }
+                _ => (),
+            }
+        }
+        if opened == 0 {
+            return;
+        }
+        self.combo = match self.last_open {
+            Some(last) if at.saturating_sub(last) <= self.window => self.combo + 1,
+            _ => 1,
+        };
+        self.best_combo = self.best_combo.max(self.combo);
+        self.last_open = Some(at);
+        self.score += (opened * self.multiplier()) as i64;
+    }
+
+    fn score(&self) -> i64 {
+        self.score
+    }
+
+    fn summarize(&self, summary: GameSummary) -> GameSummary {
+        GameSummary {
+            score_attack: Some(ScoreAttackSummary {
+                score: self.score,
+                best_combo: self.best_combo,
+                broken_combos: self.broken_combos,
+            }),
+            ..summary
+        }
+    }
+}
+
+impl Game {
+    // Like apply_timed, then tells the ruleset what the move did. Refused
+    // moves don't reach it.
+    pub fn apply_ruled(
+        &mut self,
+        m: &Move,
+        ruleset: &mut dyn Ruleset,
+        clock: &impl Clock,
+    ) -> Result<Vec<Event>> {
+        let events = self.apply_timed(m, clock)?;
+        ruleset.on_move(self.board(), &events, self.elapsed(clock));
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::game::GameOptions;
+    use crate::scenario::Scenario;
+    use crate::Point;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_score_attack_combos() {
+        let mut game = Game::new(
+            Scenario::from_ascii("*.*.*.*.\n").into_board(),
+            GameOptions::default(),
+        );
+        let mut rules = ScoreAttack::new(Duration::from_secs(2));
+        let clock = MockClock::new();
+        for (m, wait) in [
+            (Move::Open(Point::new(1, 0)), 0),
+            (Move::Open(Point::new(3, 0)), 1),
+            // Too slow: the combo starts over.
+            (Move::Open(Point::new(5, 0)), 3),
+            (Move::Flag(Point::new(0, 0)), 0),
+        ] {
+            clock.advance(Duration::from_secs(wait));
+            game.apply_ruled(&m, &mut rules, &clock).unwrap();
+        }
+        assert_eq!((rules.score(), rules.combo()), (4, 1));
+        game.apply_ruled(&Move::Flag(Point::new(7, 0)), &mut rules, &clock)
+            .unwrap();
+        assert_eq!(rules.combo(), 0);
+
+        let summary = rules.summarize(game.summary(4.0));
+        assert_eq!(
+            summary.score_attack,
+            Some(ScoreAttackSummary {
+                score: 4,
+                best_combo: 2,
+                broken_combos: 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::solver::{interior_weights, is_unknown, Probabilities};
+use crate::{Board, Point};
+
+// An exact alternative to the solver module: the frontier is encoded as CNF
+// and a small DPLL solver proves cells safe or mined, and counts models to get
+// exact probabilities. It never falls back to estimates, so it can take a long
+// time on large open boards; it's meant for research, not for the assists.
+
+// DIMACS style literals: variable i (1-based) or its negation -i.
+pub type Lit = i32;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cnf {
+    pub vars: usize,
+    pub clauses: Vec<Vec<Lit>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Certainty {
+    Safe,
+    Mine,
+}
+
+impl Cnf {
+    // Variable i + 1 is whether the i-th returned cell holds a mine. Each
+    // "exactly k of these cells" constraint is spelled out without auxiliary
+    // variables (a number has at most 8 neighbors), so models of the CNF are
+    // exactly the mine layouts of the frontier.
+    pub fn from_board(board: &Board) -> (Cnf, Vec<Point>) {
+        let frontier = board.frontier();
+        let cells = frontier.cells();
+        let var = |p: &Point| cells.iter().position(|c| c == p).unwrap() as Lit + 1;
+        let mut clauses = vec![];
+        for c in &frontier.constraints {
+            let lits: Vec<Lit> = c.cells.iter().map(var).collect();
+            // At most `mines`: no `mines + 1` of them are all mines.
+            for subset in subsets(lits.len(), c.mines + 1) {
+                clauses.push(subset.iter().map(|&i| -lits[i]).collect());
+            }
+            // At least `mines`: no `len - mines + 1` of them are all safe.
+            if c.mines > lits.len() {
+                clauses.push(vec![]);
+            } else if c.mines > 0 {
+                for subset in subsets(lits.len(), lits.len() - c.mines + 1) {
+                    clauses.push(subset.iter().map(|&i| lits[i]).collect());
+                }
+            }
+        }
+        let cnf = Cnf {
+            vars: cells.len(),
+            clauses,
+        };
+        (cnf, cells)
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        let mut out = format!("p cnf {} {}\n", self.vars, self.clauses.len());
+        for clause in &self.clauses {
+            for lit in clause {
+                let _ = write!(out, "{} ", lit);
+            }
+            out.push_str("0\n");
+        }
+        out
+    }
+}
+
+// Index sets of size `r` out of `0..n`.
+fn subsets(n: usize, r: usize) -> Vec<Vec<usize>> {
+    if r > n {
+        return vec![];
+    }
+    (0u32..1 << n)
+        .filter(|mask| mask.count_ones() as usize == r)
+        .map(|mask| (0..n).filter(|i| mask & (1 << i) != 0).collect())
+        .collect()
+}
+
+fn var(lit: Lit) -> usize {
+    lit.unsigned_abs() as usize
+}
+
+// The clauses left once `lit` is true, or None if one of them can't be
+// satisfied any more.
+fn assign(clauses: &[Vec<Lit>], lit: Lit) -> Option<Vec<Vec<Lit>>> {
+    let mut out = Vec::with_capacity(clauses.len());
+    for clause in clauses {
+        if clause.contains(&lit) {
+            continue;
+        }
+        let rest: Vec<Lit> = clause.iter().copied().filter(|&l| l != -lit).collect();
+        if rest.is_empty() {
+            return None;
+        }
+        out.push(rest);
+    }
+    Some(out)
+}
+
+// Applies unit clauses until there are none, pushing the forced literals.
+fn propagate(mut clauses: Vec<Vec<Lit>>, forced: &mut Vec<Lit>) -> Option<Vec<Vec<Lit>>> {
+    if clauses.iter().any(|c| c.is_empty()) {
+        return None;
+    }
+    while let Some(unit) = clauses.iter().find(|c| c.len() == 1).map(|c| c[0]) {
+        forced.push(unit);
+        clauses = assign(&clauses, unit)?;
+    }
+    Some(clauses)
+}
+
+fn dpll(clauses: Vec<Vec<Lit>>, model: &mut Vec<Lit>) -> bool {
+    let mark = model.len();
+    let clauses = match propagate(clauses, model) {
+        Some(c) => c,
+        None => {
+            model.truncate(mark);
+            return false;
+        }
+    };
+    let lit = match clauses.first() {
+        Some(clause) => clause[0],
+        None => return true,
+    };
+    for &choice in &[lit, -lit] {
+        let branch = model.len();
+        if let Some(rest) = assign(&clauses, choice) {
+            model.push(choice);
+            if dpll(rest, model) {
+                return true;
+            }
+        }
+        model.truncate(branch);
+    }
+    model.truncate(mark);
+    false
+}
+
+// A satisfying assignment under the assumptions, variable i at index i - 1.
+pub fn solve(cnf: &Cnf, assumptions: &[Lit]) -> Option<Vec<bool>> {
+    let mut clauses = cnf.clauses.clone();
+    clauses.extend(assumptions.iter().map(|&a| vec![a]));
+    let mut model = vec![];
+    if !dpll(clauses, &mut model) {
+        return None;
+    }
+    let mut values = vec![false; cnf.vars];
+    for lit in model {
+        values[var(lit) - 1] = lit > 0;
+    }
+    Some(values)
+}
+
+// Polynomials in the number of true variables: `p[k]` models have k of them.
+fn add(a: &mut Vec<f64>, b: &[f64]) {
+    if a.len() < b.len() {
+        a.resize(b.len(), 0.0);
+    }
+    a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+}
+
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+// (1 + x)^n: n unconstrained variables.
+fn free(n: usize) -> Vec<f64> {
+    let mut p = vec![1.0];
+    for _ in 0..n {
+        p = multiply(&p, &[1.0, 1.0]);
+    }
+    p
+}
+
+fn count(clauses: Vec<Vec<Lit>>, mut scope: Vec<usize>) -> Vec<f64> {
+    let mut forced = vec![];
+    let clauses = match propagate(clauses, &mut forced) {
+        Some(c) => c,
+        None => return vec![],
+    };
+    scope.retain(|v| !forced.iter().any(|&l| var(l) == *v));
+    let mut result = vec![0.0; forced.iter().filter(|&&l| l > 0).count()];
+    result.push(1.0);
+
+    // Clauses that share no variables are counted on their own and multiplied.
+    let mut components: Vec<(Vec<usize>, Vec<Vec<Lit>>)> = vec![];
+    for clause in clauses {
+        let (mut vars, mut group): (Vec<usize>, Vec<Vec<Lit>>) =
+            (clause.iter().map(|&l| var(l)).collect(), vec![clause]);
+        let mut i = 0;
+        while i < components.len() {
+            if components[i].0.iter().any(|v| vars.contains(v)) {
+                let (v, g) = components.swap_remove(i);
+                vars.extend(v);
+                group.extend(g);
+            } else {
+                i += 1;
+            }
+        }
+        vars.sort_unstable();
+        vars.dedup();
+        components.push((vars, group));
+    }
+    let constrained: usize = components.iter().map(|(v, _)| v.len()).sum();
+    result = multiply(&result, &free(scope.len() - constrained));
+    for (vars, group) in components {
+        let v = vars[0] as Lit;
+        let rest: Vec<usize> = vars[1..].to_vec();
+        let mut total = vec![];
+        if let Some(c) = assign(&group, v) {
+            add(&mut total, &multiply(&[0.0, 1.0], &count(c, rest.clone())));
+        }
+        if let Some(c) = assign(&group, -v) {
+            add(&mut total, &count(c, rest));
+        }
+        result = multiply(&result, &total);
+    }
+    result
+}
+
+// `counts[k]` is the number of models, under the assumptions, with k variables
+// set to true.
+pub fn count_models(cnf: &Cnf, assumptions: &[Lit]) -> Vec<f64> {
+    let mut clauses = cnf.clauses.clone();
+    clauses.extend(assumptions.iter().map(|&a| vec![a]));
+    count(clauses, (1..=cnf.vars).collect())
+}
+
+// Frontier cells the numbers alone prove safe or mined. The total mine count
+// is left out, so a few cells the probabilities pin to 0 or 1 may be missing.
+pub fn certain(board: &Board) -> Vec<(Point, Certainty)> {
+    let (cnf, cells) = Cnf::from_board(board);
+    cells
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, p)| {
+            let v = i as Lit + 1;
+            if solve(&cnf, &[v]).is_none() {
+                Some((p, Certainty::Safe))
+            } else if solve(&cnf, &[-v]).is_none() {
+                Some((p, Certainty::Mine))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn probabilities(board: &Board) -> Probabilities {
+    let frontier = board.frontier();
+    let (cnf, cells) = Cnf::from_board(board);
+    let weights = interior_weights(frontier.interior.len(), frontier.mines_left, cnf.vars);
+    let weighted = |counts: &[f64]| -> f64 {
+        counts
+            .iter()
+            .zip(&weights)
+            .map(|(count, weight)| count * weight)
+            .sum()
+    };
+    let all = count_models(&cnf, &[]);
+    let total = weighted(&all);
+    let ratio = |x: f64| if total > 0.0 { x / total } else { 0.0 };
+
+    let mut probabilities: Vec<Option<f64>> = (0..board.width * board.height)
+        .map(|i| {
+            if is_unknown(board, i % board.width, i / board.width) {
+                Some(0.0)
+            } else {
+                None
+            }
+        })
+        .collect();
+    for (i, p) in cells.iter().enumerate() {
+        let mined = count_models(&cnf, &[i as Lit + 1]);
+        probabilities[p.y as usize * board.width + p.x as usize] = Some(ratio(weighted(&mined)));
+    }
+    if !frontier.interior.is_empty() {
+        let interior_mines: f64 = all
+            .iter()
+            .zip(&weights)
+            .enumerate()
+            .map(|(s, (count, weight))| {
+                count * weight * frontier.mines_left.saturating_sub(s) as f64
+            })
+            .sum();
+        let p = ratio(interior_mines) / frontier.interior.len() as f64;
+        for cell in &frontier.interior {
+            probabilities[cell.y as usize * board.width + cell.x as usize] = Some(p);
+        }
+    }
+    Probabilities {
+        width: board.width,
+        height: board.height,
+        cells: probabilities,
+        exact: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use crate::{create_seeded_board, numbers_on_board, solver, BoardState};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_count_models() {
+        // Exactly one of A1 and C1.
+        let (cnf, cells) = Cnf::from_board(&Scenario::from_ascii("*1.\n").into_board());
+        assert_eq!(cells, vec![Point::new(0, 0), Point::new(2, 0)]);
+        assert_eq!(count_models(&cnf, &[]), vec![0.0, 2.0]);
+        assert_eq!(count_models(&cnf, &[-1]), vec![0.0, 1.0]);
+        assert_eq!(solve(&cnf, &[2]), Some(vec![false, true]));
+        assert_eq!(solve(&cnf, &[-1, -2]), None);
+        assert_eq!(cnf.to_dimacs(), "p cnf 2 2\n-1 -2 0\n1 2 0\n");
+    }
+
+    #[test]
+    fn test_certain() {
+        // Opening D1 opens columns C and D, which pin down all of column B.
+        // Column A is next to no number, so nothing is known there.
+        let board = Scenario::from_ascii("*...\n.*..\n....\n.*..\n")
+            .open("D1")
+            .into_board();
+        assert_eq!(
+            certain(&board),
+            vec![
+                (Point::new(1, 0), Certainty::Safe),
+                (Point::new(1, 1), Certainty::Mine),
+                (Point::new(1, 2), Certainty::Safe),
+                (Point::new(1, 3), Certainty::Mine),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matches_heuristic_solver() {
+        let board = numbers_on_board(create_seeded_board(9, 9, 10, 3).unwrap());
+        let board = (0..81)
+            .filter_map(|i| board.cascade_open_item(&Point::new(i % 9, i / 9)).ok())
+            .find(|b| b.state == BoardState::Playing)
+            .unwrap();
+        let exact = probabilities(&board);
+        let heuristic = solver::probabilities(&board);
+        assert!(heuristic.exact);
+        for (a, b) in exact.cells.iter().zip(&heuristic.cells) {
+            match (a, b) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9, "{} != {}", a, b),
+                _ => assert_eq!(a, b),
+            }
+        }
+    }
+}
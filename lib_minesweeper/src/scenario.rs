@@ -0,0 +1,238 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::editor::EditorBoard;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardState, Error, Point, Result};
+
+// A board and a few moves, written down the way a tutorial would:
+//
+//     Scenario::from_ascii("*..\n...\n..*")
+//         .open("C1")
+//         .expect_revealed(&["B1", "C1"])
+//         .expect_state(BoardState::Playing);
+//
+// Cells are named like spreadsheet cells, column letters then a 1-based row.
+// The board is drawn as for EditorBoard::from_ascii. Every step panics with
+// the board drawn out when it fails, so it's meant for tests.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    board: Board,
+}
+
+// "A1" is the top left corner, "B3" the second column of the third row.
+pub fn parse_cell(name: &str) -> Result<Point> {
+    let invalid = || Error::ParseError(format!("invalid cell name {:?}", name));
+    let split = name
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .ok_or_else(invalid)?;
+    let (column, row) = name.split_at(split);
+    if column.is_empty() {
+        return Err(invalid());
+    }
+    let x = column
+        .chars()
+        .try_fold(0usize, |x, c| {
+            x.checked_mul(26)?
+                .checked_add(c.to_ascii_uppercase() as usize - 'A' as usize + 1)
+        })
+        .ok_or_else(invalid)?;
+    let y: usize = row.parse().map_err(|_| invalid())?;
+    if y == 0 {
+        return Err(invalid());
+    }
+    // Columns and rows past i32::MAX can't be a Point.
+    Point::checked(x - 1, y - 1, (usize::MAX, usize::MAX)).ok_or_else(invalid)
+}
+
+impl Scenario {
+    #[track_caller]
+    pub fn from_ascii(ascii: &str) -> Scenario {
+        let board = EditorBoard::from_ascii(ascii)
+            .and_then(|editor| editor.to_board())
+            .unwrap_or_else(|e| panic!("invalid scenario board: {}", e));
+        Scenario { board }
+    }
+
+    pub fn from_board(board: Board) -> Scenario {
+        Scenario { board }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn into_board(self) -> Board {
+        self.board
+    }
+
+    #[track_caller]
+    fn point(&self, name: &str) -> Point {
+        let p = parse_cell(name).unwrap_or_else(|e| panic!("{}", e));
+        if self.board.at(&p).is_none() {
+            panic!("{} is outside the board\n{}", name, self.drawing());
+        }
+        p
+    }
+
+    fn drawing(&self) -> String {
+        EditorBoard::from_board(&self.board).to_ascii()
+    }
+
+    #[track_caller]
+    fn fail(&self, message: String) -> ! {
+        panic!("{}\n{}", message, self.drawing())
+    }
+
+    #[track_caller]
+    pub fn open(self, name: &str) -> Scenario {
+        let p = self.point(name);
+        match self.board.cascade_open_item(&p) {
+            Ok(board) => Scenario { board },
+            Err(e) => self.fail(format!("opening {} failed: {}", name, e)),
+        }
+    }
+
+    #[track_caller]
+    pub fn flag(self, name: &str) -> Scenario {
+        let p = self.point(name);
+        match self.board.flag_item(&p) {
+            Ok(board) => Scenario { board },
+            Err(e) => self.fail(format!("flagging {} failed: {}", name, e)),
+        }
+    }
+
+    // For moves that should be refused; the board is left as it was.
+    #[track_caller]
+    pub fn expect_open_error(self, name: &str, expected: Error) -> Scenario {
+        let p = self.point(name);
+        match self.board.cascade_open_item(&p) {
+            Err(e) if e == expected => self,
+            other => self.fail(format!(
+                "opening {}: expected {:?}, got {:?}",
+                name,
+                expected,
+                other.map(|b| b.state)
+            )),
+        }
+    }
+
+    #[track_caller]
+    pub fn expect_state(self, state: BoardState) -> Scenario {
+        if self.board.state != state {
+            self.fail(format!(
+                "expected the board to be {:?}, it is {:?}",
+                state, self.board.state
+            ));
+        }
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_revealed(self, names: &[&str]) -> Scenario {
+        self.expect_all(names, "open", |board, p| {
+            matches!(board.at(p), Some(Number { state: Open, .. }))
+        })
+    }
+
+    #[track_caller]
+    pub fn expect_hidden(self, names: &[&str]) -> Scenario {
+        self.expect_all(names, "hidden", |board, p| {
+            matches!(
+                board.at(p),
+                Some(Mine { state }) | Some(Number { state, .. }) if *state != Open && *state != Flagged
+            )
+        })
+    }
+
+    #[track_caller]
+    pub fn expect_flagged(self, names: &[&str]) -> Scenario {
+        self.expect_all(names, "flagged", |board, p| {
+            matches!(
+                board.at(p),
+                Some(Mine { state: Flagged }) | Some(Number { state: Flagged, .. })
+            )
+        })
+    }
+
+    // The whole board, drawn as for EditorBoard::to_ascii.
+    #[track_caller]
+    pub fn expect_ascii(self, ascii: &str) -> Scenario {
+        let drawing = self.drawing();
+        if drawing.trim_end_matches('\n') != ascii.trim_end_matches('\n') {
+            self.fail(format!("expected the board to be\n{}\nbut it is", ascii));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn expect_all(
+        self,
+        names: &[&str],
+        what: &str,
+        check: impl Fn(&Board, &Point) -> bool,
+    ) -> Scenario {
+        let wrong: Vec<&str> = names
+            .iter()
+            .filter(|name| !check(&self.board, &self.point(name)))
+            .copied()
+            .collect();
+        if !wrong.is_empty() {
+            self.fail(format!("expected {} to be {}", wrong.join(", "), what));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_cell() {
+        assert_eq!(parse_cell("A1"), Ok(Point::new(0, 0)));
+        assert_eq!(parse_cell("c12"), Ok(Point::new(2, 11)));
+        assert_eq!(parse_cell("AA3"), Ok(Point::new(26, 2)));
+        for name in &[
+            "",
+            "A",
+            "3",
+            "A0",
+            "A1B",
+            "ZZZZZZZZZZZZZZZZZZZZ1",
+            "ZZZZZZZ1",
+            "A99999999999",
+        ] {
+            assert!(parse_cell(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_scenario() {
+        Scenario::from_ascii(
+            "*....\n\
+             .*...\n",
+        )
+        .open("E1")
+        .expect_revealed(&["C1", "D2", "E2"])
+        .expect_hidden(&["A1", "B1", "A2"])
+        .expect_ascii("*.1  \n.*1  \n")
+        .expect_open_error("E1", Error::CellAlreadyOpen { x: 4, y: 0 })
+        .flag("A1")
+        .expect_flagged(&["A1"])
+        .open("B1")
+        .open("A2")
+        .expect_state(BoardState::Won);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected A1 to be open")]
+    fn test_failed_expectation_panics() {
+        Scenario::from_ascii("*.\n..\n").expect_revealed(&["A1"]);
+    }
+}
@@ -0,0 +1,154 @@
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::MapElement::Mine;
+use crate::{Board, Error, Result};
+
+// Boards drawn before SessionRegistry::deal gives up.
+pub const MAX_REROLLS: u64 = 1000;
+
+// The mines' cells once the board is turned or mirrored `t` ways, as the
+// board's width and height after it and the cells row by row, sorted.
+fn transformed(
+    mines: &[(usize, usize)],
+    width: usize,
+    height: usize,
+    t: u8,
+) -> (usize, usize, Vec<usize>) {
+    let (w, h) = (width - 1, height - 1);
+    let (tw, th) = if t < 4 {
+        (width, height)
+    } else {
+        (height, width)
+    };
+    let mut cells: Vec<usize> = mines
+        .iter()
+        .map(|&(x, y)| {
+            let (x, y) = match t {
+                0 => (x, y),
+                1 => (w - x, y),
+                2 => (x, h - y),
+                3 => (w - x, h - y),
+                4 => (y, x),
+                5 => (h - y, x),
+                6 => (y, w - x),
+                _ => (h - y, w - x),
+            };
+            y * tw + x
+        })
+        .collect();
+    cells.sort_unstable();
+    (tw, th, cells)
+}
+
+// The same for every board with the same mines, turned or mirrored any way:
+// those look alike to a player. Cell states, numbers and seeds don't count.
+// FNV-1a, so it's the same on every platform and version.
+pub fn canonical_hash(board: &Board) -> u64 {
+    let mines: Vec<(usize, usize)> = (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| matches!(board.map[y][x], Mine { .. }))
+        .collect();
+    let (width, height, cells) = (0..8)
+        .map(|t| transformed(&mines, board.width, board.height, t))
+        .min()
+        .unwrap_or_default();
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for value in [width, height].iter().chain(&cells) {
+        for byte in (*value as u64).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+// Every board a player was dealt in a session or a campaign, by canonical
+// hash, so none is dealt to them twice. Kept with the campaign's save.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct SessionRegistry {
+    seen: BTreeSet<u64>,
+}
+
+impl SessionRegistry {
+    pub fn new() -> SessionRegistry {
+        SessionRegistry::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn contains(&self, board: &Board) -> bool {
+        self.seen.contains(&canonical_hash(board))
+    }
+
+    // False when the board was already dealt.
+    pub fn register(&mut self, board: &Board) -> bool {
+        self.seen.insert(canonical_hash(board))
+    }
+
+    // Draws boards until one wasn't dealt before, and registers it. `draw`
+    // gets the attempt number, from 0, to derive a seed from when the boards
+    // are seeded, as in |attempt| BoardBuilder::new(9, 9, 10).seed(seed +
+    // attempt).build(). Its errors are passed on.
+    pub fn deal(&mut self, mut draw: impl FnMut(u64) -> Result<Board>) -> Result<Board> {
+        for attempt in 0..MAX_REROLLS {
+            let board = draw(attempt)?;
+            if self.register(&board) {
+                return Ok(board);
+            }
+        }
+        Err(Error::InvalidConfig(format!(
+            "every board drawn in {} attempts was already dealt",
+            MAX_REROLLS
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use crate::BoardBuilder;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_canonical_hash() {
+        let board = Scenario::from_ascii("*..\n...\n").into_board();
+        let mirrored = Scenario::from_ascii("...\n..*\n").into_board();
+        let turned = Scenario::from_ascii("*.\n..\n..\n").into_board();
+        let other = Scenario::from_ascii(".*.\n...\n").into_board();
+        assert_eq!(canonical_hash(&board), canonical_hash(&mirrored));
+        assert_eq!(canonical_hash(&board), canonical_hash(&turned));
+        assert_ne!(canonical_hash(&board), canonical_hash(&other));
+        // Opening cells doesn't make it another board.
+        let opened = Scenario::from_ascii("*..\n...\n").open("C2").into_board();
+        assert_eq!(canonical_hash(&board), canonical_hash(&opened));
+    }
+
+    #[test]
+    fn test_deal_rerolls_seen_boards() {
+        let mut registry = SessionRegistry::new();
+        // Three mines in a 2x2 board: four layouts, one up to symmetry.
+        let draw = |attempt| BoardBuilder::new(2, 2, 3).seed(attempt).build();
+        let first = registry.deal(draw).unwrap();
+        assert!(registry.contains(&first));
+        assert!(registry.deal(draw).is_err());
+
+        let mut registry = SessionRegistry::new();
+        let draw = |attempt| BoardBuilder::new(3, 3, 1).seed(attempt).build();
+        // A corner, an edge and the center, in some order.
+        for _ in 0..3 {
+            registry.deal(draw).unwrap();
+        }
+        assert_eq!(registry.len(), 3);
+        assert!(registry.deal(draw).is_err());
+    }
+}
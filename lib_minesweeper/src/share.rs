@@ -0,0 +1,392 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::editor::EditorBoard;
+use crate::game::GameOptions;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{
+    check_mines_fit, create_seeded_board, numbers_on_board, Board, Error, MapElement, Point,
+    Result, MAX_CELLS,
+};
+
+// Crockford's base32: no I, L, O or U, so codes survive being read aloud or
+// retyped from a screenshot.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+const VERSION: u8 = 1;
+const VERSION_LAYOUT: u8 = 2;
+// Bits of the mode byte. Assists like auto flagging are left to whoever
+// opens the code.
+const NO_FLAG: u8 = 1;
+const CRUEL: u8 = 1 << 1;
+const KIND: u8 = 1 << 2;
+const HIDDEN_MINE_COUNT: u8 = 1 << 3;
+const BORDER_FREE: u8 = 1 << 4;
+// The fog radius takes the top three bits, 0 for no fog.
+const FOG_SHIFT: u8 = 5;
+const MAX_FOG: usize = 7;
+
+// version, width, height, mines, mode, seed and a checksum byte.
+const PAYLOAD_LEN: usize = 1 + 2 + 2 + 4 + 1 + 8 + 1;
+// version, width, height and mode, then the cells and a checksum byte.
+const LAYOUT_HEADER_LEN: usize = 1 + 2 + 2 + 1;
+
+// Two bits per cell in layout codes.
+const CELL_CLOSED: u8 = 0;
+const CELL_MINE: u8 = 1;
+const CELL_OPEN: u8 = 2;
+const CELL_FLAGGED_MINE: u8 = 3;
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, b| sum.rotate_left(3).wrapping_add(*b))
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for b in bytes {
+        buffer = (buffer << 8) | u32::from(*b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn decode_char(c: char) -> Option<u32> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        c => c,
+    };
+    ALPHABET
+        .iter()
+        .position(|a| *a as char == c)
+        .map(|i| i as u32)
+}
+
+// Dashes and spaces are ignored so codes can be grouped for readability.
+fn decode(code: &str) -> Option<Vec<u8>> {
+    let mut out = vec![];
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in code.chars().filter(|c| *c != '-' && !c.is_whitespace()) {
+        buffer = (buffer << 5) | decode_char(c)?;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn invalid_code(code: &str) -> Error {
+    Error::ParseError(format!("{:?} is not a valid share code", code))
+}
+
+fn mode(options: &GameOptions) -> Result<u8> {
+    let fog = match options.fog {
+        None => 0,
+        Some(radius @ 1..=MAX_FOG) => radius as u8,
+        Some(radius) => {
+            return Err(Error::InvalidConfig(format!(
+                "a fog radius of {} can't be shared, only 1 to {}",
+                radius, MAX_FOG
+            )))
+        }
+    };
+    let bits = [
+        (options.no_flag, NO_FLAG),
+        (options.cruel, CRUEL),
+        (options.kind, KIND),
+        (options.hidden_mine_count, HIDDEN_MINE_COUNT),
+        (options.border_free, BORDER_FREE),
+    ];
+    Ok(bits
+        .iter()
+        .filter(|(on, _)| *on)
+        .fold(fog << FOG_SHIFT, |mode, (_, bit)| mode | bit))
+}
+
+fn options(mode: u8) -> GameOptions {
+    let fog = usize::from(mode >> FOG_SHIFT);
+    GameOptions {
+        no_flag: mode & NO_FLAG != 0,
+        cruel: mode & CRUEL != 0,
+        kind: mode & KIND != 0,
+        hidden_mine_count: mode & HIDDEN_MINE_COUNT != 0,
+        border_free: mode & BORDER_FREE != 0,
+        fog: Some(fog).filter(|&radius| radius > 0),
+        ..GameOptions::default()
+    }
+}
+
+fn dimension(n: usize) -> Result<[u8; 2]> {
+    u16::try_from(n)
+        .map(u16::to_be_bytes)
+        .map_err(|_| Error::InvalidConfig(String::from("board is too big to share")))
+}
+
+fn layout_cell(el: &MapElement) -> u8 {
+    match el {
+        Mine { state: Flagged } => CELL_FLAGGED_MINE,
+        Mine { .. } => CELL_MINE,
+        Number { state: Open, .. } => CELL_OPEN,
+        Number { .. } => CELL_CLOSED,
+    }
+}
+
+fn with_checksum(mut bytes: Vec<u8>) -> String {
+    bytes.push(checksum(&bytes));
+    encode(&bytes)
+}
+
+impl Board {
+    // A short code from which from_share_code rebuilds this exact board.
+    // Seeded boards (see create_seeded_board) only need their seed and come
+    // back fresh. Other boards, like the ones made in the editor, are shared
+    // cell by cell, open cells and flagged mines included, which makes for a
+    // longer code. Treasures follow the cells, one bit per cell. The game
+    // modes in `options` are kept, but not the assists, and fog only up to a
+    // radius of 7.
+    pub fn share_code(&self, options: &GameOptions) -> Result<String> {
+        let mut bytes = vec![];
+        match self.seed {
+            Some(seed) => {
+                let mines = u32::try_from(self.mines)
+                    .map_err(|_| Error::InvalidConfig(String::from("board is too big to share")))?;
+                bytes.push(VERSION);
+                bytes.extend_from_slice(&dimension(self.width)?);
+                bytes.extend_from_slice(&dimension(self.height)?);
+                bytes.extend_from_slice(&mines.to_be_bytes());
+                bytes.push(mode(options)?);
+                bytes.extend_from_slice(&seed.to_be_bytes());
+            }
+            None => {
+                bytes.push(VERSION_LAYOUT);
+                bytes.extend_from_slice(&dimension(self.width)?);
+                bytes.extend_from_slice(&dimension(self.height)?);
+                bytes.push(mode(options)?);
+                let cells: Vec<u8> = self.map.iter().flatten().map(layout_cell).collect();
+                bytes.extend(cells.chunks(4).map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (i, cell)| byte | cell << (6 - 2 * i))
+                }));
+                if let Some(treasures) = &self.treasures {
+                    let mut bits = vec![0u8; (self.width * self.height).div_ceil(8)];
+                    for p in treasures {
+                        let i = p.y as usize * self.width + p.x as usize;
+                        bits[i / 8] |= 0x80 >> (i % 8);
+                    }
+                    bytes.extend(bits);
+                }
+            }
+        }
+        Ok(with_checksum(bytes))
+    }
+
+    // The board, ready to play, and the options (only the mode is shared, the
+    // assists are left to each player).
+    pub fn from_share_code(code: &str) -> Result<(Board, GameOptions)> {
+        let bytes = decode(code).ok_or_else(|| invalid_code(code))?;
+        let (payload, sum) = match bytes.split_last() {
+            Some((sum, payload)) if payload.len() >= LAYOUT_HEADER_LEN => (payload, *sum),
+            _ => return Err(invalid_code(code)),
+        };
+        if checksum(payload) != sum {
+            return Err(invalid_code(code));
+        }
+        let u16_at = |i: usize| u16::from_be_bytes([payload[i], payload[i + 1]]) as usize;
+        match payload[0] {
+            VERSION if bytes.len() == PAYLOAD_LEN => {
+                let mut mines = [0; 4];
+                mines.copy_from_slice(&payload[5..9]);
+                let mut seed = [0; 8];
+                seed.copy_from_slice(&payload[10..18]);
+                let (width, height) = (u16_at(1), u16_at(3));
+                let mines = u32::from_be_bytes(mines) as usize;
+                // Checked before generating anything, so a hostile code
+                // can't ask for a huge board.
+                check_mines_fit(width, height, mines, &[]).map_err(|_| invalid_code(code))?;
+                let board = create_seeded_board(width, height, mines, u64::from_be_bytes(seed))?;
+                Ok((numbers_on_board(board), options(payload[9])))
+            }
+            VERSION_LAYOUT => {
+                let (width, height) = (u16_at(1), u16_at(3));
+                if width * height > MAX_CELLS {
+                    return Err(invalid_code(code));
+                }
+                let (cells, treasures) = payload[LAYOUT_HEADER_LEN..]
+                    .split_at_checked((width * height).div_ceil(4))
+                    .ok_or_else(|| invalid_code(code))?;
+                if !treasures.is_empty() && treasures.len() != (width * height).div_ceil(8) {
+                    return Err(invalid_code(code));
+                }
+                let mut ascii = String::with_capacity((width + 1) * height);
+                for i in 0..width * height {
+                    ascii.push(match (cells[i / 4] >> (6 - 2 * (i % 4))) & 3 {
+                        CELL_MINE => '*',
+                        CELL_OPEN => '0',
+                        CELL_FLAGGED_MINE => 'F',
+                        _ => '.',
+                    });
+                    if i % width == width - 1 {
+                        ascii.push('\n');
+                    }
+                }
+                let mut board = EditorBoard::from_ascii(&ascii)?.to_board()?;
+                if !treasures.is_empty() {
+                    board.treasures = Some(
+                        (0..width * height)
+                            .filter(|i| treasures[i / 8] & (0x80 >> (i % 8)) != 0)
+                            .map(|i| Point::new(i % width, i / width))
+                            .collect(),
+                    );
+                }
+                Ok((board, options(payload[5])))
+            }
+            _ => Err(invalid_code(code)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::BoardConstraints;
+    use crate::symmetry::Symmetry;
+    use crate::tests::five_by_two_board;
+    use crate::BoardBuilder;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_share_code_round_trip() {
+        let board = numbers_on_board(create_seeded_board(30, 16, 99, 0xdead_beef).unwrap());
+        let options = GameOptions {
+            no_flag: true,
+            auto_flag: true,
+            ..GameOptions::default()
+        };
+        let code = board.share_code(&options).unwrap();
+        assert_eq!(code.len(), 31);
+
+        let (shared, shared_options) = Board::from_share_code(&code).unwrap();
+        assert_eq!(shared, board);
+        assert!(shared_options.no_flag);
+        assert!(!shared_options.auto_flag);
+
+        let modes = GameOptions {
+            cruel: true,
+            kind: true,
+            fog: Some(3),
+            hidden_mine_count: true,
+            border_free: true,
+            ..GameOptions::default()
+        };
+        let code = board.share_code(&modes).unwrap();
+        assert_eq!(Board::from_share_code(&code).unwrap().1, modes);
+        let too_foggy = GameOptions {
+            fog: Some(8),
+            ..GameOptions::default()
+        };
+        assert!(matches!(
+            board.share_code(&too_foggy),
+            Err(Error::InvalidConfig(_))
+        ));
+
+        let lowercase = code.to_lowercase();
+        let grouped = format!("{}-{}", &lowercase[..10], &lowercase[10..]);
+        assert_eq!(Board::from_share_code(&grouped).unwrap().0, board);
+    }
+
+    #[test]
+    fn test_layout_share_code() {
+        let board = numbers_on_board(five_by_two_board())
+            .cascade_open_item(&Point::new(4, 0))
+            .unwrap()
+            .flag_item(&Point::new(0, 0))
+            .unwrap();
+        let code = board.share_code(&GameOptions::default()).unwrap();
+        let (shared, _) = Board::from_share_code(&code).unwrap();
+        assert_eq!(shared, board);
+    }
+
+    #[test]
+    fn test_builder_share_code_round_trip() {
+        let builder = || BoardBuilder::new(9, 8, 10).seed(5);
+        let boards = vec![
+            builder().safe(&[Point::new(0, 0)]),
+            builder().symmetry(Symmetry::MirrorBoth),
+            builder().constraints(BoardConstraints {
+                min_3bv: 5,
+                ..BoardConstraints::default()
+            }),
+            builder().treasures(3),
+            builder().density(0.2),
+            builder().border_free(),
+        ];
+        #[cfg(feature = "std")]
+        let boards = {
+            let mut boards = boards;
+            boards.push(builder().noise(crate::noise::NoiseOptions::default()));
+            boards
+        };
+        for builder in boards {
+            let board = numbers_on_board(builder.build().unwrap());
+            assert_eq!(board.seed, None);
+            let code = board.share_code(&GameOptions::default()).unwrap();
+            assert_eq!(Board::from_share_code(&code).unwrap().0, board);
+        }
+    }
+
+    #[test]
+    fn test_share_code_errors() {
+        let board = create_seeded_board(9, 9, 10, 7).unwrap();
+        let mut code = board.share_code(&GameOptions::default()).unwrap();
+        let typo = if &code[5..6] == "0" { "1" } else { "0" };
+        code.replace_range(5..6, typo);
+        assert!(matches!(
+            Board::from_share_code(&code),
+            Err(Error::ParseError(_))
+        ));
+        assert!(matches!(
+            Board::from_share_code("not a code!"),
+            Err(Error::ParseError(_))
+        ));
+
+        // Well formed, but far too big to build, or with too many mines.
+        let seeded = |width: u16, height: u16, mines: u32| {
+            let mut bytes = vec![VERSION];
+            bytes.extend_from_slice(&width.to_be_bytes());
+            bytes.extend_from_slice(&height.to_be_bytes());
+            bytes.extend_from_slice(&mines.to_be_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&7u64.to_be_bytes());
+            with_checksum(bytes)
+        };
+        for code in [seeded(65535, 65535, 10), seeded(9, 9, u32::MAX)] {
+            assert!(matches!(
+                Board::from_share_code(&code),
+                Err(Error::ParseError(_))
+            ));
+        }
+        assert!(Board::from_share_code(&seeded(9, 9, 10)).is_ok());
+    }
+}
@@ -0,0 +1,317 @@
+use std::sync::Mutex;
+use std::thread;
+
+use crate::random::{seeded_rand, SplitMix64};
+use crate::tablebase::Tablebase;
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Open;
+use crate::{create_board, numbers_on_board, Board, BoardState, Move, Point, Result};
+
+pub trait Policy: Sync {
+    fn next_move(&self, board: &Board, rng: &mut SplitMix64) -> Option<Move>;
+}
+
+impl<F> Policy for F
+where
+    F: Fn(&Board, &mut SplitMix64) -> Option<Move> + Sync,
+{
+    fn next_move(&self, board: &Board, rng: &mut SplitMix64) -> Option<Move> {
+        self(board, rng)
+    }
+}
+
+// Opens a uniformly random closed cell. Useful as a baseline to compare
+// smarter policies against.
+pub struct RandomPolicy;
+
+impl Policy for RandomPolicy {
+    fn next_move(&self, board: &Board, rng: &mut SplitMix64) -> Option<Move> {
+        let closed: Vec<Point> = (0..board.height)
+            .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+            .filter(|p| {
+                matches!(
+                    board.at(p),
+                    Some(Number { state: Closed, .. }) | Some(Mine { state: Closed })
+                )
+            })
+            .collect();
+        if closed.is_empty() {
+            return None;
+        }
+        let i = rng.gen_range(0, closed.len());
+        closed.into_iter().nth(i).map(Move::Open)
+    }
+}
+
+// Opens a cell that a single number proves safe, and guesses at random when
+// there is none. Cheap enough to play thousands of expert games, which is what
+// the opening book needs; use the solver for anything stronger.
+pub struct BasicSolverPolicy;
+
+impl Policy for BasicSolverPolicy {
+    fn next_move(&self, board: &Board, rng: &mut SplitMix64) -> Option<Move> {
+        let closed = |p: &Point| {
+            matches!(
+                board.at(p),
+                Some(Number { state: Closed, .. }) | Some(Mine { state: Closed })
+            )
+        };
+        let numbers: Vec<(Point, i32)> = (0..board.height)
+            .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+            .filter_map(|p| match board.at(&p) {
+                Some(Number { state: Open, count }) if *count > 0 => Some((p, *count)),
+                _ => None,
+            })
+            .collect();
+        let mut mines = vec![false; board.width * board.height];
+        for (p, count) in &numbers {
+            let hidden: Vec<Point> = board.neighbors(p).filter(|n| closed(n)).collect();
+            if hidden.len() as i32 == *count {
+                for n in hidden {
+                    mines[n.y as usize * board.width + n.x as usize] = true;
+                }
+            }
+        }
+        let is_mine = |p: &Point| mines[p.y as usize * board.width + p.x as usize];
+        for (p, count) in &numbers {
+            if board.neighbors(p).filter(|n| is_mine(n)).count() as i32 == *count {
+                if let Some(safe) = board.neighbors(p).find(|n| closed(n) && !is_mine(n)) {
+                    return Some(Move::Open(safe));
+                }
+            }
+        }
+        RandomPolicy.next_move(board, rng)
+    }
+}
+
+// BasicSolverPolicy until the closed cells fit in the tablebase, then
+// perfect play. The tablebase is shared by every game the policy plays.
+#[derive(Debug, Default)]
+pub struct TablebasePolicy {
+    tablebase: Mutex<Tablebase>,
+}
+
+impl TablebasePolicy {
+    pub fn new(max_cells: usize) -> TablebasePolicy {
+        TablebasePolicy {
+            tablebase: Mutex::new(Tablebase::new(max_cells)),
+        }
+    }
+}
+
+impl Policy for TablebasePolicy {
+    fn next_move(&self, board: &Board, rng: &mut SplitMix64) -> Option<Move> {
+        let endgame = self
+            .tablebase
+            .lock()
+            .expect("tablebase lock poisoned")
+            .evaluate(board);
+        match endgame {
+            Some(endgame) => Some(Move::Open(endgame.best)),
+            None => BasicSolverPolicy.next_move(board, rng),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct BatchConfig {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub games: usize,
+    pub seed: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Outcome {
+    Won,
+    Failed,
+    // The policy gave up, made an invalid move or ran out of moves.
+    Stalled,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct GameResult {
+    pub seed: u64,
+    pub outcome: Outcome,
+    pub moves: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BatchReport {
+    pub config: BatchConfig,
+    pub games: Vec<GameResult>,
+    pub wins: usize,
+    pub failures: usize,
+    pub stalls: usize,
+}
+
+impl BatchReport {
+    pub fn win_rate(&self) -> f64 {
+        if self.games.is_empty() {
+            0.0
+        } else {
+            self.wins as f64 / self.games.len() as f64
+        }
+    }
+}
+
+// Each game gets its own seed so the results don't depend on which thread ran
+// which game. SplitMix64 is random-access: the n-th derived seed is simply the
+// n-th output of a generator seeded with the batch seed.
+pub fn derive_seed(seed: u64, index: usize) -> u64 {
+    let offset = (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    SplitMix64::new(seed.wrapping_add(offset)).next_u64()
+}
+
+pub fn simulate_game(config: &BatchConfig, seed: u64, policy: &dyn Policy) -> Result<GameResult> {
+    let board = create_board(config.width, config.height, config.mines, seeded_rand(seed))?;
+    let mut board = numbers_on_board(board);
+    let mut rng = SplitMix64::new(seed);
+    let max_moves = 4 * config.width * config.height;
+    let mut moves = 0;
+    let outcome = loop {
+        match board.state {
+            BoardState::Won => break Outcome::Won,
+            BoardState::Failed => break Outcome::Failed,
+            _ if moves >= max_moves => break Outcome::Stalled,
+            _ => (),
+        }
+        let next = match policy.next_move(&board, &mut rng) {
+            Some(m) => m,
+            None => break Outcome::Stalled,
+        };
+        moves += 1;
+        match board.apply_move(&next) {
+            Ok(b) => board = b,
+            Err(_) => break Outcome::Stalled,
+        }
+    };
+    Ok(GameResult {
+        seed,
+        outcome,
+        moves,
+    })
+}
+
+pub fn simulate_batch(
+    configs: &[BatchConfig],
+    policy: &dyn Policy,
+    parallelism: usize,
+) -> Result<Vec<BatchReport>> {
+    let parallelism = parallelism.max(1);
+    configs
+        .iter()
+        .map(|config| {
+            let mut games: Vec<Option<Result<GameResult>>> = vec![None; config.games];
+            thread::scope(|scope| {
+                let workers: Vec<_> = (0..parallelism)
+                    .map(|worker| {
+                        scope.spawn(move || {
+                            (worker..config.games)
+                                .step_by(parallelism)
+                                .map(|i| {
+                                    (
+                                        i,
+                                        simulate_game(config, derive_seed(config.seed, i), policy),
+                                    )
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                for worker in workers {
+                    for (i, result) in worker.join().expect("simulation thread panicked") {
+                        games[i] = Some(result);
+                    }
+                }
+            });
+            let games = games
+                .into_iter()
+                .flatten()
+                .collect::<Result<Vec<GameResult>>>()?;
+            let count = |outcome: Outcome| games.iter().filter(|g| g.outcome == outcome).count();
+            Ok(BatchReport {
+                config: config.clone(),
+                wins: count(Outcome::Won),
+                failures: count(Outcome::Failed),
+                stalls: count(Outcome::Stalled),
+                games,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn config() -> BatchConfig {
+        BatchConfig {
+            width: 5,
+            height: 5,
+            mines: 3,
+            games: 50,
+            seed: 11,
+        }
+    }
+
+    #[test]
+    fn test_results_do_not_depend_on_parallelism() {
+        let sequential = simulate_batch(&[config()], &RandomPolicy, 1).unwrap();
+        let parallel = simulate_batch(&[config()], &RandomPolicy, 4).unwrap();
+        assert_eq!(sequential, parallel);
+        let report = &sequential[0];
+        assert_eq!(report.games.len(), 50);
+        assert_eq!(report.wins + report.failures + report.stalls, 50);
+        assert!(report.wins > 0 && report.failures > 0);
+    }
+
+    #[test]
+    fn test_basic_solver_beats_random() {
+        let config = BatchConfig {
+            width: 9,
+            height: 9,
+            mines: 10,
+            games: 50,
+            seed: 3,
+        };
+        let random = simulate_batch(std::slice::from_ref(&config), &RandomPolicy, 1).unwrap();
+        let basic = simulate_batch(&[config], &BasicSolverPolicy, 1).unwrap();
+        assert!(basic[0].win_rate() > random[0].win_rate());
+    }
+
+    #[test]
+    fn test_tablebase_endgames() {
+        let config = BatchConfig {
+            width: 4,
+            height: 3,
+            mines: 3,
+            games: 30,
+            seed: 5,
+        };
+        let basic = simulate_batch(std::slice::from_ref(&config), &BasicSolverPolicy, 1).unwrap();
+        let perfect = simulate_batch(&[config], &TablebasePolicy::default(), 2).unwrap();
+        assert!(perfect[0].win_rate() >= basic[0].win_rate());
+        assert_eq!(perfect[0].stalls, 0);
+    }
+
+    #[test]
+    fn test_policy_giving_up_stalls() {
+        let give_up = |_: &Board, _: &mut SplitMix64| None;
+        let report = simulate_batch(&[config()], &give_up, 2).unwrap();
+        assert_eq!(report[0].stalls, 50);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = BatchConfig {
+            mines: 25,
+            ..config()
+        };
+        assert!(simulate_batch(&[config], &RandomPolicy, 2).is_err());
+    }
+}
@@ -0,0 +1,1195 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::time::Duration;
+
+use crate::clock::Clock;
+#[cfg(feature = "std")]
+use crate::clock::SystemClock;
+use crate::random::{RandomSource, SplitMix64};
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Open;
+use crate::{Adjacency, Board, BoardRead, BoardState, Point};
+
+// Upper bound on the backtracking nodes visited per frontier component before
+// giving up on exact probabilities for the whole board.
+const MAX_NODES_PER_COMPONENT: usize = 2_000_000;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Probabilities {
+    pub width: usize,
+    pub height: usize,
+    // Mine probability per cell, `None` for cells the player has opened. Flags
+    // are not trusted, so flagged cells get a probability like closed ones.
+    pub cells: Vec<Option<f64>>,
+    // False when the frontier was too large to enumerate and the numbers are
+    // local estimates instead.
+    pub exact: bool,
+}
+
+impl Probabilities {
+    pub fn at(&self, p: &Point) -> Option<f64> {
+        if p.x < 0 || p.y < 0 || p.x as usize >= self.width || p.y as usize >= self.height {
+            return None;
+        }
+        self.cells[p.y as usize * self.width + p.x as usize]
+    }
+
+    // The exact solution when there is one, the estimate otherwise.
+    fn from_solution<B: BoardRead>(
+        board: &B,
+        exact: Option<Vec<Option<f64>>>,
+        estimate: impl FnOnce() -> Vec<Option<f64>>,
+    ) -> Probabilities {
+        Probabilities {
+            width: board.width(),
+            height: board.height(),
+            exact: exact.is_some(),
+            cells: exact.unwrap_or_else(estimate),
+        }
+    }
+}
+
+pub(crate) struct Constraint {
+    pub(crate) cells: Vec<usize>,
+    pub(crate) mines: usize,
+}
+
+struct Component {
+    cells: Vec<usize>,
+    constraints: Vec<usize>,
+}
+
+// Mine count distribution of a frontier component: `solutions[k]` is the
+// (normalized) number of consistent assignments with `k` mines, and
+// `cell_mines[k][i]` how many of those have a mine on the component's i-th cell.
+struct Distribution {
+    solutions: Vec<f64>,
+    cell_mines: Vec<Vec<f64>>,
+    // Number of consistent assignments before normalizing.
+    total: f64,
+}
+
+pub(crate) fn is_unknown<B: BoardRead>(board: &B, x: usize, y: usize) -> bool {
+    !matches!(
+        board.cell(&Point::new(x, y)),
+        Some(Number { state: Open, .. }) | Some(Mine { state: Open })
+    )
+}
+
+// Mines opened so far, known without any solving.
+fn exploded_mines<B: BoardRead>(board: &B) -> usize {
+    (0..board.width() * board.height())
+        .filter(|&i| {
+            matches!(
+                board.cell(&Point::new(i % board.width(), i / board.width())),
+                Some(Mine { state: Open })
+            )
+        })
+        .count()
+}
+
+pub(crate) fn constraints<B: BoardRead>(board: &B, adjacency: &Adjacency) -> Vec<Constraint> {
+    let mut constraints = vec![];
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let p = Point::new(x, y);
+            if let Some(Number { state: Open, count }) = board.cell(&p) {
+                let mut known_mines = 0;
+                let mut cells = vec![];
+                for &n in adjacency.neighbors(adjacency.cell(&p)) {
+                    match board.cell(&adjacency.point(n)) {
+                        None => (),
+                        Some(Mine { state: Open }) => known_mines += 1,
+                        Some(Number { state: Open, .. }) => (),
+                        Some(_) => cells.push(n),
+                    }
+                }
+                if !cells.is_empty() {
+                    constraints.push(Constraint {
+                        cells,
+                        mines: (count - known_mines).max(0) as usize,
+                    });
+                }
+            }
+        }
+    }
+    constraints
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut i = i;
+    while parent[i] != root {
+        let next = parent[i];
+        parent[i] = root;
+        i = next;
+    }
+    root
+}
+
+fn components(cell_count: usize, constraints: &[Constraint]) -> Vec<Component> {
+    let mut parent: Vec<usize> = (0..cell_count).collect();
+    for c in constraints {
+        for &cell in &c.cells[1..] {
+            let a = find(&mut parent, c.cells[0]);
+            let b = find(&mut parent, cell);
+            parent[a] = b;
+        }
+    }
+    let mut in_frontier = vec![false; cell_count];
+    for c in constraints {
+        for &cell in &c.cells {
+            in_frontier[cell] = true;
+        }
+    }
+    let mut by_root: Vec<Option<usize>> = vec![None; cell_count];
+    let mut components: Vec<Component> = vec![];
+    for cell in (0..cell_count).filter(|&cell| in_frontier[cell]) {
+        let root = find(&mut parent, cell);
+        let i = *by_root[root].get_or_insert_with(|| {
+            components.push(Component {
+                cells: vec![],
+                constraints: vec![],
+            });
+            components.len() - 1
+        });
+        components[i].cells.push(cell);
+    }
+    for (ci, c) in constraints.iter().enumerate() {
+        let root = find(&mut parent, c.cells[0]);
+        if let Some(i) = by_root[root] {
+            components[i].constraints.push(ci);
+        }
+    }
+    components
+}
+
+struct Enumeration<'a> {
+    constraints: Vec<&'a Constraint>,
+    // For each component cell, the indices (into `constraints`) it belongs to.
+    cell_constraints: Vec<Vec<usize>>,
+    assigned_mines: Vec<usize>,
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    nodes: usize,
+    solutions: Vec<f64>,
+    cell_mines: Vec<Vec<f64>>,
+}
+
+impl<'a> Enumeration<'a> {
+    fn fits(&self, constraint: usize) -> bool {
+        let c = self.constraints[constraint];
+        let assigned = self.assigned_mines[constraint];
+        assigned <= c.mines && assigned + self.unassigned[constraint] >= c.mines
+    }
+
+    fn set(&mut self, i: usize, mine: bool) -> bool {
+        self.assignment[i] = mine;
+        let mut ok = true;
+        for k in 0..self.cell_constraints[i].len() {
+            let c = self.cell_constraints[i][k];
+            self.unassigned[c] -= 1;
+            if mine {
+                self.assigned_mines[c] += 1;
+            }
+            ok = ok && self.fits(c);
+        }
+        ok
+    }
+
+    fn unset(&mut self, i: usize, mine: bool) {
+        for k in 0..self.cell_constraints[i].len() {
+            let c = self.cell_constraints[i][k];
+            self.unassigned[c] += 1;
+            if mine {
+                self.assigned_mines[c] -= 1;
+            }
+        }
+    }
+
+    // Stops at the first assignment with between min and max mines, leaving
+    // it in `assignment`. False when there is none or the search gave up.
+    fn find(&mut self, i: usize, mines: usize, min: usize, max: usize) -> bool {
+        self.nodes += 1;
+        if self.nodes > MAX_NODES_PER_COMPONENT || mines > max {
+            return false;
+        }
+        if i == self.assignment.len() {
+            return mines >= min;
+        }
+        for &mine in &[false, true] {
+            if self.set(i, mine) && self.find(i + 1, mines + mine as usize, min, max) {
+                return true;
+            }
+            self.unset(i, mine);
+        }
+        false
+    }
+
+    // Every assignment, counted exactly by its number of mines. Not bounded
+    // by MAX_NODES_PER_COMPONENT: callers bound the cells instead.
+    fn count(&mut self, i: usize, mines: usize, counts: &mut [u128]) {
+        if i == self.assignment.len() {
+            counts[mines] += 1;
+            return;
+        }
+        for &mine in &[false, true] {
+            if self.set(i, mine) {
+                self.count(i + 1, mines + mine as usize, counts);
+            }
+            self.unset(i, mine);
+        }
+    }
+
+    // Every assignment, into `out`. False when there are more than `max` or
+    // the search gave up.
+    fn list(&mut self, i: usize, max: usize, out: &mut Vec<Vec<bool>>) -> bool {
+        self.nodes += 1;
+        if self.nodes > MAX_NODES_PER_COMPONENT {
+            return false;
+        }
+        if i == self.assignment.len() {
+            out.push(self.assignment.clone());
+            return out.len() <= max;
+        }
+        for &mine in &[false, true] {
+            if self.set(i, mine) && !self.list(i + 1, max, out) {
+                return false;
+            }
+            self.unset(i, mine);
+        }
+        true
+    }
+
+    fn run(&mut self, i: usize) -> bool {
+        self.nodes += 1;
+        if self.nodes > MAX_NODES_PER_COMPONENT {
+            return false;
+        }
+        if i == self.assignment.len() {
+            let k = self.assignment.iter().filter(|&&m| m).count();
+            if self.solutions.len() <= k {
+                self.solutions.resize(k + 1, 0.0);
+                self.cell_mines
+                    .resize(k + 1, vec![0.0; self.assignment.len()]);
+            }
+            self.solutions[k] += 1.0;
+            for (j, &m) in self.assignment.iter().enumerate() {
+                if m {
+                    self.cell_mines[k][j] += 1.0;
+                }
+            }
+            return true;
+        }
+        for &mine in &[false, true] {
+            if self.set(i, mine) && !self.run(i + 1) {
+                return false;
+            }
+            self.unset(i, mine);
+        }
+        true
+    }
+}
+
+fn enumeration<'a>(component: &Component, constraints: &'a [Constraint]) -> Enumeration<'a> {
+    let local: Vec<&Constraint> = component
+        .constraints
+        .iter()
+        .map(|&c| &constraints[c])
+        .collect();
+    let cell_constraints = component
+        .cells
+        .iter()
+        .map(|cell| {
+            local
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.cells.contains(cell))
+                .map(|(i, _)| i)
+                .collect()
+        })
+        .collect();
+    Enumeration {
+        unassigned: local.iter().map(|c| c.cells.len()).collect(),
+        assigned_mines: vec![0; local.len()],
+        constraints: local,
+        cell_constraints,
+        assignment: vec![false; component.cells.len()],
+        nodes: 0,
+        solutions: vec![],
+        cell_mines: vec![],
+    }
+}
+
+fn enumerate(component: &Component, constraints: &[Constraint]) -> Option<Distribution> {
+    let mut e = enumeration(component, constraints);
+    if !e.run(0) {
+        return None;
+    }
+    // Normalizing keeps products of many components from overflowing.
+    let total: f64 = e.solutions.iter().sum();
+    if total > 0.0 {
+        e.solutions.iter_mut().for_each(|s| *s /= total);
+        e.cell_mines
+            .iter_mut()
+            .flat_map(|row| row.iter_mut())
+            .for_each(|m| *m /= total);
+    }
+    Some(Distribution {
+        solutions: e.solutions,
+        cell_mines: e.cell_mines,
+        total,
+    })
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    let total: f64 = result.iter().sum();
+    if total > 0.0 {
+        result.iter_mut().for_each(|r| *r /= total);
+    }
+    result
+}
+
+// `weights[s]` is proportional to the number of ways of placing the mines
+// left over when the frontier holds `s` mines, C(interior, remaining - s).
+// Built from ratios of consecutive binomials so it never overflows.
+pub(crate) fn interior_weights(
+    interior: usize,
+    remaining: usize,
+    max_frontier_mines: usize,
+) -> Vec<f64> {
+    let mut weights = vec![0.0; max_frontier_mines + 1];
+    let first = remaining.saturating_sub(interior);
+    if first > max_frontier_mines || first > remaining {
+        return weights;
+    }
+    weights[first] = 1.0;
+    for s in first + 1..=max_frontier_mines.min(remaining) {
+        let k = (remaining - s + 1) as f64;
+        weights[s] = weights[s - 1] * k / (interior + s - remaining) as f64;
+    }
+    let max = weights.iter().cloned().fold(0.0, f64::max);
+    if max > 0.0 {
+        weights.iter_mut().for_each(|w| *w /= max);
+    }
+    weights
+}
+
+// Cells outside every constraint get `density`.
+fn local_estimate(unknown: &[bool], constraints: &[Constraint], density: f64) -> Vec<Option<f64>> {
+    let mut cells: Vec<Option<f64>> = unknown
+        .iter()
+        .map(|&u| if u { Some(density) } else { None })
+        .collect();
+    let mut seen = vec![false; unknown.len()];
+    for c in constraints {
+        let p = c.mines as f64 / c.cells.len() as f64;
+        for &cell in &c.cells {
+            let current = if seen[cell] {
+                cells[cell].unwrap_or(0.0)
+            } else {
+                0.0
+            };
+            seen[cell] = true;
+            cells[cell] = Some(if current > p { current } else { p });
+        }
+    }
+    cells
+}
+
+pub fn probabilities<B: BoardRead>(board: &B) -> Probabilities {
+    probabilities_with(board, &Adjacency::new(board.width(), board.height()))
+}
+
+// For callers solving the same board over and over, so the adjacency is only
+// worked out once.
+pub fn probabilities_with<B: BoardRead>(board: &B, adjacency: &Adjacency) -> Probabilities {
+    let cell_count = board.width() * board.height();
+    let unknown: Vec<bool> = (0..cell_count)
+        .map(|i| is_unknown(board, i % board.width(), i / board.width()))
+        .collect();
+    let constraints = constraints(board, adjacency);
+    let known_mines = exploded_mines(board);
+    let remaining = board.mines().saturating_sub(known_mines);
+    let unknown_count = unknown.iter().filter(|&&u| u).count();
+    let density = if unknown_count == 0 {
+        0.0
+    } else {
+        (board.mines() as f64 / unknown_count as f64).min(1.0)
+    };
+    let exact = exact_probabilities(&unknown, &constraints, remaining);
+    Probabilities::from_solution(board, exact, || {
+        local_estimate(&unknown, &constraints, density)
+    })
+}
+
+// For games that hide the mine count, see GameOptions::hidden_mine_count:
+// instead of weighing layouts by the mines left, every unknown cell is taken
+// to be a mine with the same chance, `density`, on its own. Cells the
+// numbers settle come out the same whatever the density.
+pub fn probabilities_with_density<B: BoardRead>(
+    board: &B,
+    adjacency: &Adjacency,
+    density: f64,
+) -> Probabilities {
+    let density = density.clamp(1e-9, 1.0 - 1e-9);
+    let cell_count = board.width() * board.height();
+    let unknown: Vec<bool> = (0..cell_count)
+        .map(|i| is_unknown(board, i % board.width(), i / board.width()))
+        .collect();
+    let constraints = constraints(board, adjacency);
+    // Each mine on the frontier weighs the odds of a mine, relative to the
+    // likeliest count so the weights stay in range.
+    let odds = density / (1.0 - density);
+    let weights = |frontier_size: usize| {
+        let top = if odds > 1.0 { frontier_size } else { 0 };
+        (0..=frontier_size)
+            .map(|s| odds.powi(s as i32 - top as i32))
+            .collect()
+    };
+    let exact = weighted_probabilities(&unknown, &constraints, weights, |_, interior| {
+        density * interior as f64
+    });
+    Probabilities::from_solution(board, exact, || {
+        local_estimate(&unknown, &constraints, density)
+    })
+}
+
+// The exact mine probability of every unknown cell, whatever the shape of the
+// board: cells are plain indices and the constraints carry the neighborhoods.
+// None when a frontier component is too large to enumerate.
+pub(crate) fn exact_probabilities(
+    unknown: &[bool],
+    constraints: &[Constraint],
+    remaining: usize,
+) -> Option<Vec<Option<f64>>> {
+    weighted_probabilities(
+        unknown,
+        constraints,
+        |frontier_size| {
+            let interior = unknown.iter().filter(|&&u| u).count() - frontier_size;
+            interior_weights(interior, remaining, frontier_size)
+        },
+        |s, _| remaining.saturating_sub(s) as f64,
+    )
+}
+
+// Layouts putting s mines on the frontier weigh weights(frontier size)[s],
+// and leave interior_mines(s, interior cells) mines for the interior, on
+// average.
+fn weighted_probabilities(
+    unknown: &[bool],
+    constraints: &[Constraint],
+    weights: impl FnOnce(usize) -> Vec<f64>,
+    interior_mines: impl Fn(usize, usize) -> f64,
+) -> Option<Vec<Option<f64>>> {
+    let cell_count = unknown.len();
+    let components = components(cell_count, constraints);
+    let distributions: Vec<Distribution> = components
+        .iter()
+        .map(|c| enumerate(c, constraints))
+        .collect::<Option<_>>()?;
+
+    let frontier_size: usize = components.iter().map(|c| c.cells.len()).sum();
+    let interior = unknown.iter().filter(|&&u| u).count() - frontier_size;
+    let weights = weights(frontier_size);
+    let weight = |s: usize| weights.get(s).cloned().unwrap_or(0.0);
+
+    let mut cells: Vec<Option<f64>> = vec![None; cell_count];
+    for (j, (component, dist)) in components.iter().zip(&distributions).enumerate() {
+        let others = distributions
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != j)
+            .fold(vec![1.0], |acc, (_, d)| convolve(&acc, &d.solutions));
+        let expected = |k: usize| -> f64 {
+            others
+                .iter()
+                .enumerate()
+                .map(|(t, d)| d * weight(k + t))
+                .sum()
+        };
+        let total: f64 = dist
+            .solutions
+            .iter()
+            .enumerate()
+            .map(|(k, s)| s * expected(k))
+            .sum();
+        for (i, &cell) in component.cells.iter().enumerate() {
+            let mines: f64 = dist
+                .cell_mines
+                .iter()
+                .enumerate()
+                .map(|(k, m)| m[i] * expected(k))
+                .sum();
+            cells[cell] = Some(if total > 0.0 { mines / total } else { 0.0 });
+        }
+    }
+
+    if interior > 0 {
+        let all = distributions
+            .iter()
+            .fold(vec![1.0], |acc, d| convolve(&acc, &d.solutions));
+        let total: f64 = all.iter().enumerate().map(|(s, d)| d * weight(s)).sum();
+        let mines: f64 = all
+            .iter()
+            .enumerate()
+            .map(|(s, d)| d * weight(s) * interior_mines(s, interior))
+            .sum();
+        let p = if total > 0.0 {
+            mines / total / interior as f64
+        } else {
+            0.0
+        };
+        let mut in_frontier = vec![false; cell_count];
+        components
+            .iter()
+            .flat_map(|c| c.cells.iter())
+            .for_each(|&cell| in_frontier[cell] = true);
+        for cell in 0..cell_count {
+            if unknown[cell] && !in_frontier[cell] {
+                cells[cell] = Some(p);
+            }
+        }
+    }
+
+    Some(cells)
+}
+
+pub fn probabilities_grid<B: BoardRead>(board: &B) -> Vec<Vec<Option<f64>>> {
+    let probabilities = probabilities(board);
+    probabilities
+        .cells
+        .chunks(board.width())
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+// How many mine layouts agree with the board, up to a factor shared by boards
+// with the same unknown cells, frontier and mine count: only the ratios between
+// such boards mean anything. None when the frontier is too large.
+pub(crate) fn relative_layouts<B: BoardRead>(board: &B, adjacency: &Adjacency) -> Option<f64> {
+    let cell_count = board.width() * board.height();
+    let constraints = constraints(board, adjacency);
+    let components = components(cell_count, &constraints);
+    let distributions: Vec<Distribution> = components
+        .iter()
+        .map(|c| enumerate(c, &constraints))
+        .collect::<Option<_>>()?;
+    let scale: f64 = distributions.iter().map(|d| d.total).product();
+    if scale == 0.0 {
+        return Some(0.0);
+    }
+    let all = distributions
+        .iter()
+        .fold(vec![1.0], |acc, d| convolve(&acc, &d.solutions));
+    let unknown = (0..cell_count)
+        .filter(|&i| is_unknown(board, i % board.width(), i / board.width()))
+        .count();
+    let frontier_size: usize = components.iter().map(|c| c.cells.len()).sum();
+    let known_mines = exploded_mines(board);
+    let weights = interior_weights(
+        unknown - frontier_size,
+        board.mines().saturating_sub(known_mines),
+        frontier_size,
+    );
+    let weighted: f64 = all.iter().zip(&weights).map(|(a, w)| a * w).sum();
+    Some(weighted * scale)
+}
+
+fn binomial(n: usize, k: usize) -> Option<u128> {
+    if k > n {
+        return Some(0);
+    }
+    (0..k.min(n - k)).try_fold(1u128, |c, i| {
+        Some(c.checked_mul((n - i) as u128)? / (i + 1) as u128)
+    })
+}
+
+// Exactly how many mine layouts agree with every open number and the mine
+// count, for uniqueness checks in puzzle mode and exact endgame odds. Flags
+// aren't trusted. Every layout of the closed cells next to open numbers is
+// tried, so None when there are more than max_cells of them, or the count
+// doesn't fit.
+pub fn count_consistent_assignments<B: BoardRead>(board: &B, max_cells: usize) -> Option<u128> {
+    let cell_count = board.width() * board.height();
+    let constraints = constraints(board, &Adjacency::new(board.width(), board.height()));
+    let mut frontier: Vec<usize> = constraints
+        .iter()
+        .flat_map(|c| c.cells.iter().copied())
+        .collect();
+    frontier.sort_unstable();
+    frontier.dedup();
+    if frontier.len() > max_cells {
+        return None;
+    }
+    let unknown = (0..cell_count)
+        .filter(|&i| is_unknown(board, i % board.width(), i / board.width()))
+        .count();
+    let interior = unknown - frontier.len();
+    let remaining = match board.mines().checked_sub(exploded_mines(board)) {
+        Some(remaining) => remaining,
+        None => return Some(0),
+    };
+
+    let all = Component {
+        cells: frontier,
+        constraints: (0..constraints.len()).collect(),
+    };
+    let mut counts = vec![0; all.cells.len() + 1];
+    enumeration(&all, &constraints).count(0, 0, &mut counts);
+    counts
+        .iter()
+        .enumerate()
+        .filter(|&(s, _)| s <= remaining)
+        .try_fold(0u128, |total, (s, &layouts)| {
+            total.checked_add(layouts.checked_mul(binomial(interior, remaining - s)?)?)
+        })
+}
+
+// A mine layout agreeing with every open number and the mine count, with a
+// mine on `cell` and none on `safe`: true for each cell that holds one. Mines
+// off the frontier go where the board already has them first. None when there
+// is no such layout, or the frontier is too large to search.
+pub(crate) fn layout_with_mine<B: BoardRead>(
+    board: &B,
+    adjacency: &Adjacency,
+    cell: usize,
+    safe: &[usize],
+) -> Option<Vec<bool>> {
+    let cell_count = board.width() * board.height();
+    let mut constraints = constraints(board, adjacency);
+    constraints.push(Constraint {
+        cells: vec![cell],
+        mines: 1,
+    });
+    for &cell in safe {
+        if is_unknown(board, cell % board.width(), cell / board.width()) {
+            constraints.push(Constraint {
+                cells: vec![cell],
+                mines: 0,
+            });
+        }
+    }
+    let mut frontier: Vec<usize> = constraints
+        .iter()
+        .flat_map(|c| c.cells.iter().copied())
+        .collect();
+    frontier.sort_unstable();
+    frontier.dedup();
+    let unknown: Vec<usize> = (0..cell_count)
+        .filter(|&i| is_unknown(board, i % board.width(), i / board.width()))
+        .collect();
+    let known_mines = exploded_mines(board);
+    let remaining = board.mines().saturating_sub(known_mines);
+    let interior = unknown.len() - frontier.len();
+
+    let all = Component {
+        cells: frontier,
+        constraints: (0..constraints.len()).collect(),
+    };
+    let mut e = enumeration(&all, &constraints);
+    if !e.find(0, 0, remaining.saturating_sub(interior), remaining) {
+        return None;
+    }
+    let mut layout = vec![false; cell_count];
+    for (&cell, &mine) in all.cells.iter().zip(&e.assignment) {
+        layout[cell] = mine;
+    }
+    let placed = e.assignment.iter().filter(|&&m| m).count();
+    let is_mine = |&i: &usize| matches!(board.cell(&adjacency.point(i)), Some(Mine { .. }));
+    let mut interior: Vec<usize> = unknown
+        .into_iter()
+        .filter(|i| all.cells.binary_search(i).is_err())
+        .collect();
+    interior.sort_by_key(|i| !is_mine(i));
+    for cell in interior.into_iter().take(remaining - placed) {
+        layout[cell] = true;
+    }
+    Some(layout)
+}
+
+// LayoutSampler keeps at most this many layouts of the frontier.
+const MAX_SAMPLED_LAYOUTS: usize = 100_000;
+
+// Draws mine layouts agreeing with every open number and the mine count, all
+// of them equally likely. Flags aren't trusted.
+pub(crate) struct LayoutSampler {
+    cell_count: usize,
+    frontier: Vec<usize>,
+    layouts: Vec<Vec<bool>>,
+    // How many layouts of the whole board each frontier layout stands for,
+    // up to a shared factor.
+    weights: Vec<f64>,
+    interior: Vec<usize>,
+    remaining: usize,
+}
+
+impl LayoutSampler {
+    // None when no layout agrees with the board, or the frontier has too
+    // many to list.
+    pub(crate) fn new<B: BoardRead>(board: &B, adjacency: &Adjacency) -> Option<LayoutSampler> {
+        let cell_count = board.width() * board.height();
+        let constraints = constraints(board, adjacency);
+        let mut frontier: Vec<usize> = constraints
+            .iter()
+            .flat_map(|c| c.cells.iter().copied())
+            .collect();
+        frontier.sort_unstable();
+        frontier.dedup();
+        let interior: Vec<usize> = (0..cell_count)
+            .filter(|&i| is_unknown(board, i % board.width(), i / board.width()))
+            .filter(|i| frontier.binary_search(i).is_err())
+            .collect();
+        let remaining = board.mines().checked_sub(exploded_mines(board))?;
+
+        let all = Component {
+            cells: frontier,
+            constraints: (0..constraints.len()).collect(),
+        };
+        let mut layouts = vec![];
+        if !enumeration(&all, &constraints).list(0, MAX_SAMPLED_LAYOUTS, &mut layouts) {
+            return None;
+        }
+        let by_mines = interior_weights(interior.len(), remaining, all.cells.len());
+        let weights: Vec<f64> = layouts
+            .iter()
+            .map(|l| by_mines[l.iter().filter(|&&m| m).count()])
+            .collect();
+        if !weights.iter().any(|&w| w > 0.0) {
+            return None;
+        }
+        Some(LayoutSampler {
+            cell_count,
+            frontier: all.cells,
+            layouts,
+            weights,
+            interior,
+            remaining,
+        })
+    }
+
+    // True for each cell holding a mine. Mines already open aren't drawn.
+    pub(crate) fn sample(&self, rng: &mut SplitMix64) -> Vec<bool> {
+        let mut r = rng.next_f64() * self.weights.iter().sum::<f64>();
+        let chosen = self
+            .weights
+            .iter()
+            .position(|&w| {
+                r -= w;
+                r < 0.0
+            })
+            .or_else(|| self.weights.iter().rposition(|&w| w > 0.0))
+            .unwrap_or(0);
+        let mut mines = vec![false; self.cell_count];
+        for (&cell, &mine) in self.frontier.iter().zip(&self.layouts[chosen]) {
+            mines[cell] = mine;
+        }
+        let placed = self.layouts[chosen].iter().filter(|&&m| m).count();
+        let mut interior = self.interior.clone();
+        for k in 0..self.remaining - placed {
+            let j = rng.gen_range(k, interior.len());
+            interior.swap(k, j);
+            mines[interior[k]] = true;
+        }
+        mines
+    }
+}
+
+// A cell the solver is certain about.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Deduction {
+    pub point: Point,
+    pub mine: bool,
+    // The numbers alone allow either, and only the count of mines left settles
+    // the cell, as in "1 mine left among these 5 isolated cells".
+    pub uses_mine_count: bool,
+}
+
+// Whether the numbers alone, without the mine count, settle each frontier
+// cell: Some(true) for a mine, Some(false) for safe.
+fn local_certainty(cell_count: usize, constraints: &[Constraint]) -> Vec<Option<bool>> {
+    let mut local = vec![None; cell_count];
+    for component in components(cell_count, constraints) {
+        let dist = match enumerate(&component, constraints) {
+            Some(dist) => dist,
+            None => continue,
+        };
+        for (i, &cell) in component.cells.iter().enumerate() {
+            if dist.cell_mines.iter().all(|m| m[i] == 0.0) {
+                local[cell] = Some(false);
+            } else if dist
+                .cell_mines
+                .iter()
+                .zip(&dist.solutions)
+                .all(|(m, s)| m[i] == *s)
+            {
+                local[cell] = Some(true);
+            }
+        }
+    }
+    local
+}
+
+pub fn deductions<B: BoardRead>(board: &B) -> Vec<Deduction> {
+    deductions_with(board, &Adjacency::new(board.width(), board.height()))
+}
+
+// Every closed cell that is certainly safe or certainly a mine, row by row.
+// Empty when the frontier is too large for exact probabilities.
+pub fn deductions_with<B: BoardRead>(board: &B, adjacency: &Adjacency) -> Vec<Deduction> {
+    let probabilities = probabilities_with(board, adjacency);
+    if !probabilities.exact {
+        return vec![];
+    }
+    let cell_count = board.width() * board.height();
+    let local = local_certainty(cell_count, &constraints(board, adjacency));
+    (0..cell_count)
+        .filter_map(|cell| {
+            let mine = match probabilities.cells[cell] {
+                Some(p) if p < 1e-9 => false,
+                Some(p) if p > 1.0 - 1e-9 => true,
+                _ => return None,
+            };
+            Some(Deduction {
+                point: adjacency.point(cell),
+                mine,
+                uses_mine_count: local[cell] != Some(mine),
+            })
+        })
+        .collect()
+}
+
+// The ways bench settles cells, from the cheapest up. Each is only tried
+// when the ones before it found nothing.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Technique {
+    // A number whose closed cells are all mines, or all safe.
+    SingleNumber,
+    // Several numbers worked out together.
+    LocalGroup,
+    // Everything, the count of mines left included.
+    MineCount,
+    // Nothing is certain: the cell least likely to be a mine is opened.
+    Guess,
+}
+
+const TECHNIQUES: [Technique; 4] = [
+    Technique::SingleNumber,
+    Technique::LocalGroup,
+    Technique::MineCount,
+    Technique::Guess,
+];
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct TechniqueStats {
+    pub technique: Technique,
+    // Rounds where this technique settled something.
+    pub uses: usize,
+    pub cells: usize,
+    // Spent running it, the rounds where it found nothing included.
+    pub time: Duration,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct SolverReport {
+    pub boards: usize,
+    pub won: usize,
+    // Won without a single guess.
+    pub solved: usize,
+    // In the order of Technique.
+    pub techniques: Vec<TechniqueStats>,
+}
+
+impl SolverReport {
+    pub fn technique(&self, technique: Technique) -> &TechniqueStats {
+        &self.techniques[technique as usize]
+    }
+
+    // Boards that needed a guess, won or lost.
+    pub fn unsolved_fraction(&self) -> f64 {
+        (self.boards - self.solved) as f64 / self.boards.max(1) as f64
+    }
+
+    pub fn lost_fraction(&self) -> f64 {
+        (self.boards - self.won) as f64 / self.boards.max(1) as f64
+    }
+}
+
+fn is_closed<B: BoardRead>(board: &B, p: &Point) -> bool {
+    matches!(
+        board.cell(p),
+        Some(Mine { state: Closed }) | Some(Number { state: Closed, .. })
+    )
+}
+
+// What `technique` settles on the board, true for mines. Flagged cells are
+// settled already and left out.
+fn settle(board: &Board, adjacency: &Adjacency, technique: Technique) -> Vec<(Point, bool)> {
+    let mut settled: Vec<(Point, bool)> = match technique {
+        Technique::SingleNumber => constraints(board, adjacency)
+            .iter()
+            .filter(|c| c.mines == 0 || c.mines == c.cells.len())
+            .flat_map(|c| {
+                c.cells
+                    .iter()
+                    .map(move |&n| (adjacency.point(n), c.mines > 0))
+            })
+            .collect(),
+        Technique::LocalGroup => {
+            local_certainty(board.width * board.height, &constraints(board, adjacency))
+                .into_iter()
+                .enumerate()
+                .filter_map(|(cell, mine)| Some((adjacency.point(cell), mine?)))
+                .collect()
+        }
+        Technique::MineCount => deductions_with(board, adjacency)
+            .into_iter()
+            .map(|d| (d.point, d.mine))
+            .collect(),
+        Technique::Guess => {
+            let probabilities = probabilities_with(board, adjacency);
+            (0..board.width * board.height)
+                .map(|cell| adjacency.point(cell))
+                .filter(|p| is_closed(board, p))
+                .filter_map(|p| Some((p, probabilities.at(&p)?)))
+                .fold(None, |best: Option<(Point, f64)>, (p, chance)| match best {
+                    Some((_, b)) if b <= chance => best,
+                    _ => Some((p, chance)),
+                })
+                .map(|(p, _)| (p, false))
+                .into_iter()
+                .collect()
+        }
+    };
+    settled.retain(|(p, _)| is_closed(board, p));
+    settled.sort_unstable_by_key(|(p, _)| (p.y, p.x));
+    settled.dedup();
+    settled
+}
+
+// Plays every board with the techniques above, cheapest first, and tallies
+// what each did and how long it took, to compare solver changes on the same
+// corpus. Play starts on the first zero in row order, as benchmark sets
+// assume, or the first safe cell when there is none.
+pub fn bench_with(boards: impl IntoIterator<Item = Board>, clock: &impl Clock) -> SolverReport {
+    let mut report = SolverReport {
+        boards: 0,
+        won: 0,
+        solved: 0,
+        techniques: TECHNIQUES
+            .iter()
+            .map(|&technique| TechniqueStats {
+                technique,
+                uses: 0,
+                cells: 0,
+                time: Duration::ZERO,
+            })
+            .collect(),
+    };
+    for board in boards {
+        report.boards += 1;
+        let adjacency = Adjacency::for_board(&board);
+        let cells = (0..board.width * board.height).map(|cell| adjacency.point(cell));
+        let mut safe = cells.filter(|p| matches!(board.at(p), Some(Number { .. })));
+        let start = safe
+            .clone()
+            .find(|p| matches!(board.at(p), Some(Number { count: 0, .. })))
+            .or_else(|| safe.next());
+        let mut board = match start.map(|p| board.cascade_open_with(&p, &adjacency)) {
+            Some(Ok(board)) => board,
+            // Nothing to open.
+            _ => {
+                report.won += 1;
+                report.solved += 1;
+                continue;
+            }
+        };
+        let mut guessed = false;
+        while board.state == BoardState::Playing {
+            for stats in &mut report.techniques {
+                let started = clock.now();
+                let settled = settle(&board, &adjacency, stats.technique);
+                stats.time += clock.now().saturating_sub(started);
+                if settled.is_empty() {
+                    continue;
+                }
+                stats.uses += 1;
+                stats.cells += settled.len();
+                guessed |= stats.technique == Technique::Guess;
+                for (p, mine) in settled {
+                    let played = match mine {
+                        true => board.flag_item(&p),
+                        false => board.cascade_open_with(&p, &adjacency),
+                    };
+                    match played {
+                        Ok(next) => board = next,
+                        Err(_) => break,
+                    }
+                }
+                break;
+            }
+        }
+        if board.state == BoardState::Won {
+            report.won += 1;
+            report.solved += usize::from(!guessed);
+        }
+    }
+    report
+}
+
+#[cfg(feature = "std")]
+pub fn bench(boards: impl IntoIterator<Item = Board>) -> SolverReport {
+    bench_with(boards, &SystemClock::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{five_by_two_board, make_map};
+    use crate::{numbers_on_board, Board};
+    use alloc::string::String;
+    use pretty_assertions::assert_eq;
+
+    fn close_to(a: Option<f64>, b: f64) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn test_fresh_board_is_uniform() {
+        let board = numbers_on_board(five_by_two_board());
+        let grid = probabilities_grid(&board);
+        assert_eq!(grid.len(), 2);
+        for p in grid.iter().flatten() {
+            assert!(close_to(*p, 0.2));
+        }
+    }
+
+    #[test]
+    fn test_deductions() {
+        // The open 1 in the corner has a single closed neighbour: a mine.
+        let board = Board::new(make_map(
+            vec![String::from("X1000"), String::from("11000")],
+            vec![String::from("COOOO"), String::from("OOOOO")],
+        ))
+        .unwrap();
+        let probabilities = probabilities(&board);
+        assert!(probabilities.exact);
+        assert!(close_to(probabilities.at(&Point::new(0, 0)), 1.0));
+        assert_eq!(probabilities.at(&Point::new(1, 0)), None);
+    }
+
+    #[test]
+    fn test_global_mine_count() {
+        // Two closed cells next to a 1, one mine in total: either is equally
+        // likely, and the far closed cell must be safe.
+        let board = Board::new(make_map(
+            vec![String::from("X10"), String::from("010")],
+            vec![String::from("COO"), String::from("COC")],
+        ))
+        .unwrap();
+        let grid = probabilities_grid(&board);
+        assert!(close_to(grid[0][0], 0.5));
+        assert!(close_to(grid[1][0], 0.5));
+        assert!(close_to(grid[1][2], 0.0));
+    }
+
+    #[test]
+    fn test_deductions_using_mine_count() {
+        // The 1 has its mine on either side, and there's only one mine: the
+        // isolated cell on the right can only be safe.
+        let board = Board::new(make_map(
+            vec![String::from("X100")],
+            vec![String::from("COCC")],
+        ))
+        .unwrap();
+        assert_eq!(
+            deductions(&board),
+            vec![Deduction {
+                point: Point::new(3, 0),
+                mine: false,
+                uses_mine_count: true,
+            }]
+        );
+
+        let board = Board::new(make_map(
+            vec![String::from("X1000"), String::from("11000")],
+            vec![String::from("COOOO"), String::from("OOOOO")],
+        ))
+        .unwrap();
+        assert_eq!(
+            deductions(&board),
+            vec![Deduction {
+                point: Point::new(0, 0),
+                mine: true,
+                uses_mine_count: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_probabilities_with_density() {
+        // Without the mine count the isolated cell is only as likely a mine
+        // as any other.
+        let board = Board::new(make_map(
+            vec![String::from("X100")],
+            vec![String::from("COCC")],
+        ))
+        .unwrap();
+        let probabilities = probabilities_with_density(&board, &Adjacency::new(4, 1), 0.2);
+        assert!(probabilities.exact);
+        assert!(close_to(probabilities.at(&Point::new(0, 0)), 0.5));
+        assert!(close_to(probabilities.at(&Point::new(2, 0)), 0.5));
+        assert!(close_to(probabilities.at(&Point::new(3, 0)), 0.2));
+    }
+
+    #[test]
+    fn test_count_consistent_assignments() {
+        // 2 mines anywhere in 10 closed cells.
+        let board = numbers_on_board(five_by_two_board());
+        assert_eq!(count_consistent_assignments(&board, 0), Some(45));
+        // The 1's mine on either side, and the far cell safe.
+        let board = Board::new(make_map(
+            vec![String::from("X100")],
+            vec![String::from("COCC")],
+        ))
+        .unwrap();
+        assert_eq!(count_consistent_assignments(&board, 2), Some(2));
+        assert_eq!(count_consistent_assignments(&board, 1), None);
+        // Exploding mines leaves fewer to place.
+        let board = board.cascade_open_item(&Point::new(0, 0)).unwrap();
+        assert_eq!(count_consistent_assignments(&board, 2), Some(1));
+    }
+
+    #[test]
+    fn test_bench() {
+        use crate::clock::MockClock;
+        use crate::scenario::Scenario;
+
+        let solvable = Scenario::from_ascii("*...\n....\n...*\n").into_board();
+        // Opening the 0 leaves two 50/50s, and the guess picks a mine.
+        let report = bench_with(
+            vec![numbers_on_board(five_by_two_board()), solvable],
+            &MockClock::new(),
+        );
+        assert_eq!(report.boards, 2);
+        assert_eq!(report.won, 1);
+        assert_eq!(report.solved, 1);
+        assert_eq!(report.technique(Technique::Guess).uses, 1);
+        assert_eq!(report.technique(Technique::LocalGroup).cells, 4);
+        assert_eq!(report.unsolved_fraction(), 0.5);
+        assert_eq!(report.lost_fraction(), 0.5);
+    }
+}
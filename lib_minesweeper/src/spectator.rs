@@ -0,0 +1,242 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::{Event, Game};
+use crate::view::CellView;
+use crate::{BoardState, Point};
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct SpectatorOptions {
+    // Don't tell spectators how many mines are left unflagged.
+    pub hide_mine_count: bool,
+    // How far behind the game spectators are, in game time.
+    pub delay: Duration,
+}
+
+// What a stream shows of a live game. It's rebuilt from the game's events
+// only, never from its board, and those reach it `delay` late: a spectator
+// relaying what they see can't help the player. Events that would give mines
+// away before they're found, like advice or kind and cruel mode moving mines,
+// are dropped.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpectatorView {
+    options: SpectatorOptions,
+    view: Vec<Vec<CellView>>,
+    mines: usize,
+    flags: usize,
+    state: BoardState,
+    pending: VecDeque<(Duration, Event)>,
+}
+
+fn is_public(event: &Event) -> bool {
+    !matches!(
+        event,
+        Event::Advice(_) | Event::Relocated { .. } | Event::Rearranged { .. }
+    )
+}
+
+impl SpectatorView {
+    // Starts from the board as it was dealt, with every move made so far
+    // replayed into the queue at the game time it was made: joining late
+    // shows no more than a spectator who was there all along. Games that
+    // hide the mine count from the player hide it from spectators too.
+    pub fn new(game: &Game, options: SpectatorOptions) -> SpectatorView {
+        let options = SpectatorOptions {
+            hide_mine_count: options.hide_mine_count || game.hides_mine_count(),
+            ..options
+        };
+        let replay = game.replay();
+        let mut replayed = Game::new(replay.board, replay.options);
+        let board = replayed.board();
+        let mut spectator = SpectatorView {
+            options,
+            view: board.view(),
+            mines: board.mines,
+            flags: 0,
+            state: board.state,
+            pending: VecDeque::new(),
+        };
+        for m in &replay.moves {
+            if let Ok(events) = replayed.play(&m.action, m.at) {
+                spectator.push(m.at, &events);
+            }
+        }
+        spectator
+    }
+
+    // Queues the events of one move, made at game time `at`.
+    pub fn push(&mut self, at: Duration, events: &[Event]) {
+        self.pending.extend(
+            events
+                .iter()
+                .filter(|e| is_public(e))
+                .map(|e| (at, e.clone())),
+        );
+    }
+
+    // Shows spectators everything at least `delay` old at game time `now`,
+    // and returns it.
+    pub fn advance(&mut self, now: Duration) -> Vec<Event> {
+        let mut released = Vec::new();
+        while let Some((at, _)) = self.pending.front() {
+            if *at + self.options.delay > now {
+                break;
+            }
+            if let Some((_, event)) = self.pending.pop_front() {
+                self.apply(&event);
+                released.push(event);
+            }
+        }
+        released
+    }
+
+    fn set(&mut self, p: &Point, cell: CellView) {
+        if let Some(c) = self
+            .view
+            .get_mut(p.y as usize)
+            .and_then(|row| row.get_mut(p.x as usize))
+        {
+            *c = cell;
+        }
+    }
+
+    fn apply(&mut self, event: &Event) {
+        match event {
+            Event::Opened { point, count, .. } => self.set(point, CellView::Open(*count as u8)),
+            Event::Flagged { point, .. } => {
+                self.flags += 1;
+                self.set(point, CellView::Flagged);
+            }
+            Event::Unflagged { point } => {
+                self.flags = self.flags.saturating_sub(1);
+                self.set(point, CellView::Hidden);
+            }
+            Event::Exploded { point } => self.set(point, CellView::ExplodedMine),
+            Event::TreasureFound { point } => {
+                let cell = self
+                    .view
+                    .get(point.y as usize)
+                    .and_then(|row| row.get(point.x as usize));
+                if let Some(&CellView::Open(count)) = cell {
+                    self.set(point, CellView::Treasure(count));
+                }
+            }
+            Event::Won => self.state = BoardState::Won,
+            Event::Lost => self.state = BoardState::Failed,
+            Event::Relocated { .. }
+            | Event::Rearranged { .. }
+            | Event::Advice(_)
+            | Event::Split(_)
+            | Event::Shifted { .. }
+            | Event::PowerUsed { .. } => (),
+        }
+        if self.state == BoardState::Ready {
+            self.state = BoardState::Playing;
+        }
+    }
+
+    // Row by row, like Board::view.
+    pub fn view(&self) -> &[Vec<CellView>] {
+        &self.view
+    }
+
+    pub fn state(&self) -> &BoardState {
+        &self.state
+    }
+
+    // Mines minus flags, like the counter players see, unless hidden.
+    pub fn mines_left(&self) -> Option<i64> {
+        if self.options.hide_mine_count {
+            None
+        } else {
+            Some(self.mines as i64 - self.flags as i64)
+        }
+    }
+
+    // Events not shown yet.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::game::GameOptions;
+    use crate::scenario::Scenario;
+    use crate::Move;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_delayed_spectator() {
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        let mut spectator = SpectatorView::new(
+            &game,
+            SpectatorOptions {
+                hide_mine_count: false,
+                delay: Duration::from_secs(5),
+            },
+        );
+        let events = game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        spectator.push(Duration::from_secs(1), &events);
+        let events = game.apply(&Move::Flag(Point::new(0, 0))).unwrap();
+        spectator.push(Duration::from_secs(3), &events);
+
+        assert_eq!(spectator.advance(Duration::from_secs(5)), vec![]);
+        assert_eq!(spectator.view()[0][4], CellView::Hidden);
+        assert_eq!(spectator.advance(Duration::from_secs(6)).len(), 6);
+        assert_eq!(spectator.view()[0][4], CellView::Open(0));
+        assert_eq!(spectator.mines_left(), Some(2));
+        assert_eq!(spectator.pending(), 1);
+        spectator.advance(Duration::from_secs(8));
+        assert_eq!(spectator.view()[0][0], CellView::Flagged);
+        assert_eq!(spectator.mines_left(), Some(1));
+        assert_eq!(spectator.state(), &BoardState::Playing);
+
+        let hidden = SpectatorView::new(
+            &game,
+            SpectatorOptions {
+                hide_mine_count: true,
+                ..SpectatorOptions::default()
+            },
+        );
+        assert_eq!(hidden.mines_left(), None);
+    }
+
+    #[test]
+    fn test_late_spectator() {
+        let clock = MockClock::new();
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut game = Game::new(board, GameOptions::default());
+        game.apply_timed(&Move::Open(Point::new(4, 0)), &clock)
+            .unwrap();
+        clock.advance(Duration::from_secs(4));
+        game.apply_timed(&Move::Flag(Point::new(0, 0)), &clock)
+            .unwrap();
+
+        let mut spectator = SpectatorView::new(
+            &game,
+            SpectatorOptions {
+                hide_mine_count: false,
+                delay: Duration::from_secs(5),
+            },
+        );
+        // Nothing the game shows now is shown before it's old enough.
+        assert_eq!(spectator.view()[0][4], CellView::Hidden);
+        assert_eq!(spectator.state(), &BoardState::Ready);
+        assert_eq!(spectator.pending(), 7);
+        spectator.advance(game.elapsed(&clock));
+        assert_eq!(spectator.view()[0][4], CellView::Hidden);
+        spectator.advance(Duration::from_secs(5));
+        assert_eq!(spectator.view()[0][4], CellView::Open(0));
+        assert_eq!(spectator.view()[0][0], CellView::Hidden);
+        assert_eq!(spectator.mines_left(), Some(2));
+        spectator.advance(Duration::from_secs(9));
+        assert_eq!(spectator.view()[0][0], CellView::Flagged);
+        assert_eq!(spectator.mines_left(), Some(1));
+    }
+}
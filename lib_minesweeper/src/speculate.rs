@@ -0,0 +1,164 @@
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardRead, BoardState, MapElement, Point};
+
+// One changed cell, and the changes made before it.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Change {
+    point: Point,
+    el: MapElement,
+    earlier: Option<Rc<Change>>,
+}
+
+// A board with a few cells changed to try out a hypothesis, sharing
+// everything else with the board it came from: making one costs a single
+// cell, not a copy of the map. Branches of the same speculation share their
+// common changes too. Solvers take it like any board, see BoardRead.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Speculation<'a> {
+    base: &'a Board,
+    // Newest first.
+    changes: Option<Rc<Change>>,
+}
+
+impl Board {
+    // A speculation with nothing changed yet, to branch hypotheses from.
+    pub fn speculate(&self) -> Speculation<'_> {
+        Speculation {
+            base: self,
+            changes: None,
+        }
+    }
+}
+
+impl<'a> Speculation<'a> {
+    pub fn base(&self) -> &'a Board {
+        self.base
+    }
+
+    // A child with `p` shown as `el`. This speculation is left as it was,
+    // so siblings can try other hypotheses from it.
+    pub fn with(&self, p: &Point, el: MapElement) -> Speculation<'a> {
+        Speculation {
+            base: self.base,
+            changes: Some(Rc::new(Change {
+                point: *p,
+                el,
+                earlier: self.changes.clone(),
+            })),
+        }
+    }
+
+    // "What if this cell is a mine": it's shown as an open mine, which the
+    // solvers count as known.
+    pub fn assume_mine(&self, p: &Point) -> Speculation<'a> {
+        self.with(p, Mine { state: Open })
+    }
+
+    // "What if this cell is safe and shows `count`".
+    pub fn assume_safe(&self, p: &Point, count: i32) -> Speculation<'a> {
+        self.with(p, Number { state: Open, count })
+    }
+
+    // A board with the changes made, for what needs a real one. Copies the
+    // whole map, and only the map: the mine count and state are the base's.
+    pub fn to_board(&self) -> Board {
+        let mut changes = Vec::new();
+        let mut change = self.changes.as_deref();
+        while let Some(c) = change {
+            changes.push(c);
+            change = c.earlier.as_deref();
+        }
+        let mut board = self.base.clone();
+        for c in changes.into_iter().rev() {
+            let (x, y) = (c.point.x as usize, c.point.y as usize);
+            if let Some(el) = board.map.get_mut(y).and_then(|row| row.get_mut(x)) {
+                *el = c.el;
+            }
+        }
+        board
+    }
+}
+
+impl BoardRead for Speculation<'_> {
+    fn width(&self) -> usize {
+        self.base.width
+    }
+
+    fn height(&self) -> usize {
+        self.base.height
+    }
+
+    fn mines(&self) -> usize {
+        self.base.mines
+    }
+
+    fn state(&self) -> BoardState {
+        self.base.state
+    }
+
+    fn cell(&self, p: &Point) -> Option<MapElement> {
+        let base = self.base.at(p).copied()?;
+        let mut change = self.changes.as_deref();
+        while let Some(c) = change {
+            if c.point == *p {
+                return Some(c.el);
+            }
+            change = c.earlier.as_deref();
+        }
+        Some(base)
+    }
+
+    fn is_treasure(&self, p: &Point) -> bool {
+        self.base.is_treasure(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers_on_board;
+    use crate::solver::deductions;
+    use crate::tests::five_by_two_board;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_speculate() {
+        // *2100
+        // 2*100
+        let board = numbers_on_board(five_by_two_board())
+            .cascade_open_item(&Point::new(4, 0))
+            .unwrap();
+        let root = board.speculate();
+        assert_eq!(root.to_board(), board);
+        let safe = |s: &Speculation| -> Vec<Point> {
+            deductions(s)
+                .into_iter()
+                .filter(|d| !d.mine)
+                .map(|d| d.point)
+                .collect()
+        };
+        assert_eq!(safe(&root), vec![]);
+
+        let lower = root.assume_mine(&Point::new(1, 1));
+        let upper = root.assume_mine(&Point::new(1, 0));
+        assert_eq!(safe(&lower), vec![Point::new(1, 0)]);
+        assert_eq!(safe(&upper), vec![Point::new(1, 1)]);
+        // A 1 on (1, 0) would leave the corner clear.
+        let deeper = lower.assume_safe(&Point::new(1, 0), 1);
+        assert_eq!(safe(&deeper), vec![Point::new(0, 0), Point::new(0, 1)]);
+        assert_eq!(root.cell(&Point::new(1, 1)), board.cell(&Point::new(1, 1)));
+
+        let mut expected = board.clone();
+        expected.map[1][1] = Mine { state: Open };
+        expected.map[0][1] = Number {
+            state: Open,
+            count: 1,
+        };
+        assert_eq!(deeper.to_board(), expected);
+    }
+}
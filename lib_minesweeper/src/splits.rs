@@ -0,0 +1,175 @@
+use alloc::format;
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::{Event, Game};
+use crate::replay::Replay;
+use crate::{Error, Result};
+
+// Where a speedrun is split, as percentages of the board's 3BV solved.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SplitDefinition {
+    percents: Vec<u32>,
+}
+
+// A split reached at game time `at`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Split {
+    pub percent: u32,
+    pub at: Duration,
+}
+
+// One split of a run next to the same split of the personal best. Either
+// time is None when that run didn't get there.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SplitComparison {
+    pub percent: u32,
+    pub at: Option<Duration>,
+    pub best: Option<Duration>,
+}
+
+impl SplitComparison {
+    // Milliseconds behind the personal best, negative when ahead.
+    pub fn delta_millis(&self) -> Option<i64> {
+        match (self.at, self.best) {
+            (Some(at), Some(best)) => Some(at.as_millis() as i64 - best.as_millis() as i64),
+            _ => None,
+        }
+    }
+}
+
+impl SplitDefinition {
+    // Percents must go up strictly, from 1 to 100.
+    pub fn new(percents: Vec<u32>) -> Result<SplitDefinition> {
+        let increasing = percents.windows(2).all(|w| w[0] < w[1]);
+        match (percents.first(), percents.last()) {
+            (Some(&first), Some(&last)) if first > 0 && last <= 100 && increasing => {
+                Ok(SplitDefinition { percents })
+            }
+            _ => Err(Error::InvalidConfig(format!("bad splits {:?}", percents))),
+        }
+    }
+
+    // A split every `step` percent, the last one at 100.
+    pub fn every(step: u32) -> Result<SplitDefinition> {
+        if step == 0 {
+            return Err(Error::InvalidConfig(format!("bad split step {}", step)));
+        }
+        let mut percents: Vec<u32> = (1..).map(|i| i * step).take_while(|&p| p < 100).collect();
+        percents.push(100);
+        SplitDefinition::new(percents)
+    }
+
+    pub fn percents(&self) -> &[u32] {
+        &self.percents
+    }
+
+    // How many splits `solved` of `total` 3BV has reached.
+    pub(crate) fn reached(&self, solved: usize, total: usize) -> usize {
+        self.percents
+            .iter()
+            .take_while(|&&p| solved * 100 >= p as usize * total)
+            .count()
+    }
+}
+
+impl Replay {
+    // The splits the replayed game reached.
+    pub fn splits(&self, definition: &SplitDefinition) -> Vec<Split> {
+        let mut game =
+            Game::new(self.board.clone(), self.options.clone()).splits(definition.clone());
+        let mut splits = Vec::new();
+        for m in &self.moves {
+            for event in game.play(&m.action, m.at).unwrap_or_default() {
+                if let Event::Split(split) = event {
+                    splits.push(split);
+                }
+            }
+        }
+        splits
+    }
+
+    // Every split of this run against the same split of `best`.
+    pub fn compare_splits(
+        &self,
+        definition: &SplitDefinition,
+        best: &Replay,
+    ) -> Vec<SplitComparison> {
+        let (mine, theirs) = (self.splits(definition), best.splits(definition));
+        let time = |splits: &[Split], percent| {
+            splits
+                .iter()
+                .find(|s: &&Split| s.percent == percent)
+                .map(|s| s.at)
+        };
+        definition
+            .percents
+            .iter()
+            .map(|&percent| SplitComparison {
+                percent,
+                at: time(&mine, percent),
+                best: time(&theirs, percent),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::game::GameOptions;
+    use crate::scenario::Scenario;
+    use crate::{Move, Point};
+    use pretty_assertions::assert_eq;
+
+    fn run(waits: &[u64]) -> Game {
+        // *2100
+        // 2*100
+        // 3BV 3: the opening and the two 2s.
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let definition = SplitDefinition::every(50).unwrap();
+        let mut game = Game::new(board, GameOptions::default()).splits(definition);
+        let clock = MockClock::new();
+        let moves = [Point::new(4, 0), Point::new(1, 0), Point::new(0, 1)];
+        for (wait, p) in waits.iter().zip(moves.iter()) {
+            clock.advance(Duration::from_secs(*wait));
+            game.apply_timed(&Move::Open(*p), &clock).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_split_definition() {
+        assert_eq!(
+            SplitDefinition::every(25).unwrap().percents(),
+            &[25, 50, 75, 100]
+        );
+        assert_eq!(
+            SplitDefinition::every(30).unwrap().percents(),
+            &[30, 60, 90, 100]
+        );
+        assert!(SplitDefinition::every(0).is_err());
+        assert!(SplitDefinition::new(vec![50, 50]).is_err());
+        assert!(SplitDefinition::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_splits_against_personal_best() {
+        let best = run(&[0, 3, 1]).replay();
+        let mut game = run(&[0, 2]);
+        // The second open solves 2 of the 3BV, past the first split only.
+        let events = game.apply(&Move::Open(Point::new(0, 1))).unwrap();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::Split(Split { percent: 100, .. }))));
+
+        let definition = SplitDefinition::every(50).unwrap();
+        let comparison = run(&[0, 2]).replay().compare_splits(&definition, &best);
+        assert_eq!(comparison[0].delta_millis(), Some(-1000));
+        assert_eq!(comparison[1].at, None);
+        assert_eq!(comparison[1].best, Some(Duration::from_secs(4)));
+        assert_eq!(comparison[1].delta_millis(), None);
+    }
+}
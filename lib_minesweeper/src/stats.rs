@@ -0,0 +1,940 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::analysis::{AnalysisReport, MoveKind};
+use crate::game::{Game, GameOptions};
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardState, Error, Point, Result};
+
+fn is_zero(board: &Board, p: &Point) -> bool {
+    matches!(board.at(p), Some(Number { count: 0, .. }))
+}
+
+fn is_open(board: &Board, p: &Point) -> bool {
+    matches!(board.at(p), Some(Number { state: Open, .. }))
+}
+
+// Groups of connected zero cells. Opening any cell of a group opens all of it
+// (and its border), so each group is worth a single click.
+fn openings(board: &Board) -> Vec<Vec<Point>> {
+    let mut seen = vec![vec![false; board.width]; board.height];
+    let mut openings = vec![];
+    for y in 0..board.height {
+        for x in 0..board.width {
+            if seen[y][x] || !is_zero(board, &Point::new(x, y)) {
+                continue;
+            }
+            seen[y][x] = true;
+            let mut opening = vec![];
+            let mut stack = vec![Point::new(x, y)];
+            while let Some(p) = stack.pop() {
+                for n in board.neighbors(&p) {
+                    let (nx, ny) = (n.x as usize, n.y as usize);
+                    if !seen[ny][nx] && is_zero(board, &n) {
+                        seen[ny][nx] = true;
+                        stack.push(n);
+                    }
+                }
+                opening.push(p);
+            }
+            openings.push(opening);
+        }
+    }
+    openings
+}
+
+fn isolated_numbers(board: &Board) -> Vec<Point> {
+    (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+        .filter(|p| matches!(board.at(p), Some(Number { count, .. }) if *count > 0))
+        .filter(|p| !board.neighbors(p).any(|n| is_zero(board, &n)))
+        .collect()
+}
+
+// Bechtel's Board Benchmark Value: the minimum number of clicks needed to
+// clear the board without flagging.
+pub fn three_bv(board: &Board) -> usize {
+    openings(board).len() + isolated_numbers(board).len()
+}
+
+// The part of the 3BV the player has already cleared.
+pub fn solved_three_bv(board: &Board) -> usize {
+    let openings = openings(board)
+        .iter()
+        .filter(|opening| opening.iter().all(|p| is_open(board, p)))
+        .count();
+    let numbers = isolated_numbers(board)
+        .iter()
+        .filter(|p| is_open(board, p))
+        .count();
+    openings + numbers
+}
+
+// Cells the first click at p opens, itself included: none when it's a mine.
+pub fn first_opening(board: &Board, p: &Point) -> usize {
+    match board.cascade_open_item(p) {
+        Ok(opened) if opened.state != BoardState::Failed => (0..board.height)
+            .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+            .filter(|p| is_open(&opened, p))
+            .count(),
+        _ => 0,
+    }
+}
+
+// Limits on how a generated board plays, to tune how boards feel. Boards are
+// drawn until one meets them all, see BoardBuilder::constraints.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct BoardConstraints {
+    // Where the player opens first, needed by the limits on the opening.
+    pub first_click: Option<Point>,
+    pub min_first_opening: usize,
+    pub max_first_opening: Option<usize>,
+    pub min_3bv: usize,
+    pub max_3bv: Option<usize>,
+    // Boards drawn before giving up.
+    pub max_attempts: usize,
+}
+
+impl Default for BoardConstraints {
+    fn default() -> BoardConstraints {
+        BoardConstraints {
+            first_click: None,
+            min_first_opening: 0,
+            max_first_opening: None,
+            min_3bv: 0,
+            max_3bv: None,
+            max_attempts: 1000,
+        }
+    }
+}
+
+impl BoardConstraints {
+    pub fn check(&self) -> Result<()> {
+        let opening_limited = self.min_first_opening > 0 || self.max_first_opening.is_some();
+        if opening_limited && self.first_click.is_none() {
+            return Err(Error::InvalidConfig(String::from(
+                "limits on the first opening need the first click",
+            )));
+        }
+        let empty = |min, max: Option<usize>| max.is_some_and(|max| min > max);
+        if empty(self.min_first_opening, self.max_first_opening)
+            || empty(self.min_3bv, self.max_3bv)
+            || self.max_attempts == 0
+        {
+            return Err(Error::InvalidConfig(format!(
+                "no board can meet {:?}",
+                self
+            )));
+        }
+        Ok(())
+    }
+
+    // Takes boards with their numbers.
+    pub fn accepts(&self, board: &Board) -> bool {
+        let within = |n, min, max: Option<usize>| n >= min && max.map_or(true, |max| n <= max);
+        if let Some(p) = &self.first_click {
+            let opening = first_opening(board, p);
+            if !within(opening, self.min_first_opening, self.max_first_opening) {
+                return false;
+            }
+        }
+        within(three_bv(board), self.min_3bv, self.max_3bv)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub won: bool,
+    pub no_flag: bool,
+    pub clicks: usize,
+    // Clicks that were rejected and didn't change the board.
+    pub wasted_clicks: usize,
+    pub three_bv: usize,
+    pub solved_three_bv: usize,
+    pub elapsed_seconds: f64,
+    pub three_bv_per_second: f64,
+    // Efficiency: solved 3BV per click.
+    pub ioe: f64,
+    // The fields below are None in summaries saved before they were kept.
+    pub lost: Option<bool>,
+    pub config: Option<SummaryConfig>,
+    // Only once the game was analyzed, see GameSummary::analyzed.
+    pub analysis: Option<SummaryAnalysis>,
+    // Only from score attack games, see ruleset::ScoreAttack.
+    pub score_attack: Option<ScoreAttackSummary>,
+}
+
+// The game that was played.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SummaryConfig {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub seed: Option<u64>,
+    pub options: GameOptions,
+}
+
+// What analysis::analyze_replay made of the game.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SummaryAnalysis {
+    pub guesses: usize,
+    // Guesses taken while a certainly safe cell was there to open.
+    pub missed_deductions: usize,
+    pub blunders: usize,
+    pub survival_chance: f64,
+}
+
+// How a score attack game scored.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ScoreAttackSummary {
+    pub score: i64,
+    pub best_combo: u64,
+    // Combos ended by a wrong flag or a mine.
+    pub broken_combos: usize,
+}
+
+// Bumped with every change to the layout GameSummary::to_json writes.
+#[cfg(feature = "json")]
+pub const SUMMARY_SCHEMA_VERSION: u32 = 2;
+
+impl GameSummary {
+    // Handicapped players' time bonus is taken off elapsed_seconds.
+    pub fn new(game: &Game, elapsed_seconds: f64) -> GameSummary {
+        let bonus = game
+            .options
+            .handicap
+            .as_ref()
+            .map_or(0.0, |h| h.time_bonus.as_secs_f64());
+        let elapsed_seconds = (elapsed_seconds - bonus).max(0.0);
+        let board = game.board();
+        let clicks = game.moves().len();
+        let solved_three_bv = solved_three_bv(board);
+        GameSummary {
+            won: board.state == BoardState::Won,
+            no_flag: game.is_no_flag(),
+            clicks,
+            wasted_clicks: game.moves().iter().filter(|m| !m.changed).count(),
+            three_bv: three_bv(board),
+            solved_three_bv,
+            elapsed_seconds,
+            three_bv_per_second: if elapsed_seconds > 0.0 {
+                solved_three_bv as f64 / elapsed_seconds
+            } else {
+                0.0
+            },
+            ioe: if clicks > 0 {
+                solved_three_bv as f64 / clicks as f64
+            } else {
+                0.0
+            },
+            lost: Some(board.state == BoardState::Failed),
+            config: Some(SummaryConfig {
+                width: board.width,
+                height: board.height,
+                mines: board.mines,
+                seed: board.seed,
+                options: game.options.clone(),
+            }),
+            analysis: None,
+            score_attack: None,
+        }
+    }
+
+    // Adds the analysis of the game's replay, which is too slow on large
+    // boards for every summary to carry.
+    pub fn analyzed(self, report: &AnalysisReport) -> GameSummary {
+        GameSummary {
+            analysis: Some(SummaryAnalysis {
+                guesses: report.guesses,
+                missed_deductions: report.missed_deductions().count(),
+                blunders: report
+                    .moves
+                    .iter()
+                    .filter(|m| m.kind == MoveKind::Blunder)
+                    .count(),
+                survival_chance: report.survival_chance,
+            }),
+            ..self
+        }
+    }
+
+    // The summary as one JSON object for frontends to send to their own
+    // analytics:
+    //
+    //     {
+    //       "schema": 2,
+    //       "config": {"width", "height", "mines", "seed", "options"} | null,
+    //       "outcome": "won" | "lost" | "unfinished" | null,
+    //       "timing": {"elapsed_seconds"},
+    //       "efficiency": {"clicks", "wasted_clicks", "three_bv",
+    //                      "solved_three_bv", "three_bv_per_second", "ioe"},
+    //       "flags": {"no_flag", "analyzed", "guesses", "missed_deductions",
+    //                 "blunders", "survival_chance"},
+    //       "score_attack": {"score", "best_combo", "broken_combos"} | null
+    //     }
+    //
+    // The analysis flags are null until the summary is analyzed; outcome is
+    // null only for lost or unfinished games from before it was kept, and
+    // score_attack for games of other rulesets.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        let outcome = match (self.won, self.lost) {
+            (true, _) => Some("won"),
+            (false, Some(true)) => Some("lost"),
+            (false, Some(false)) => Some("unfinished"),
+            (false, None) => None,
+        };
+        let analysis = self.analysis.as_ref();
+        serde_json::json!({
+            "schema": SUMMARY_SCHEMA_VERSION,
+            "config": self.config,
+            "outcome": outcome,
+            "timing": {"elapsed_seconds": self.elapsed_seconds},
+            "efficiency": {
+                "clicks": self.clicks,
+                "wasted_clicks": self.wasted_clicks,
+                "three_bv": self.three_bv,
+                "solved_three_bv": self.solved_three_bv,
+                "three_bv_per_second": self.three_bv_per_second,
+                "ioe": self.ioe,
+            },
+            "flags": {
+                "no_flag": self.no_flag,
+                "analyzed": analysis.is_some(),
+                "guesses": analysis.map(|a| a.guesses),
+                "missed_deductions": analysis.map(|a| a.missed_deductions),
+                "blunders": analysis.map(|a| a.blunders),
+                "survival_chance": analysis.map(|a| a.survival_chance),
+            },
+            "score_attack": self.score_attack,
+        })
+        .to_string()
+    }
+}
+
+impl Board {
+    // Fraction of mines in each block_size by block_size block, row by row.
+    // Blocks cut by the right and bottom edges count only their own cells.
+    pub fn mine_density_map(&self, block_size: usize) -> Vec<Vec<f64>> {
+        let size = block_size.max(1);
+        (0..self.height)
+            .step_by(size)
+            .map(|y0| {
+                (0..self.width)
+                    .step_by(size)
+                    .map(|x0| {
+                        let cells: Vec<Point> = (y0..(y0 + size).min(self.height))
+                            .flat_map(|y| {
+                                (x0..(x0 + size).min(self.width)).map(move |x| Point::new(x, y))
+                            })
+                            .collect();
+                        let mines = cells
+                            .iter()
+                            .filter(|p| matches!(self.at(p), Some(Mine { .. })))
+                            .count();
+                        mines as f64 / cells.len() as f64
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// Pearson's chi-square test of how evenly mines fell over the cells of many
+// boards of one size. With an unbiased generator every cell is as likely a
+// mine, and the statistic stays near the degrees of freedom.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Uniformity {
+    pub statistic: f64,
+    pub degrees_of_freedom: usize,
+    // How many times each cell was a mine, row by row.
+    pub counts: Vec<Vec<usize>>,
+}
+
+// The test is only meaningful with at least five mines expected per cell,
+// so generate a few times more boards than cells per mine.
+pub fn uniformity(boards: &[Board]) -> Result<Uniformity> {
+    let first = boards
+        .first()
+        .ok_or_else(|| Error::InvalidConfig(String::from("no boards to test")))?;
+    let (width, height) = (first.width, first.height);
+    if boards
+        .iter()
+        .any(|b| (b.width, b.height) != (width, height))
+    {
+        return Err(Error::InvalidConfig(String::from(
+            "boards of different sizes can't be compared",
+        )));
+    }
+    let mut counts = vec![vec![0; width]; height];
+    for board in boards {
+        for (y, row) in counts.iter_mut().enumerate() {
+            for (x, count) in row.iter_mut().enumerate() {
+                if let Some(Mine { .. }) = board.at(&Point::new(x, y)) {
+                    *count += 1;
+                }
+            }
+        }
+    }
+    let cells = width * height;
+    let total: usize = counts.iter().flatten().sum();
+    let expected = total as f64 / cells as f64;
+    let statistic = if expected > 0.0 {
+        counts
+            .iter()
+            .flatten()
+            .map(|&c| (c as f64 - expected) * (c as f64 - expected) / expected)
+            .sum()
+    } else {
+        0.0
+    };
+    Ok(Uniformity {
+        statistic,
+        degrees_of_freedom: cells - 1,
+        counts,
+    })
+}
+
+#[cfg(feature = "std")]
+impl Uniformity {
+    // The chance of a statistic at least this large from an unbiased
+    // generator. Tiny values, say under 0.001, mean the mines are biased.
+    pub fn p_value(&self) -> f64 {
+        if self.degrees_of_freedom == 0 {
+            return 1.0;
+        }
+        upper_gamma(self.degrees_of_freedom as f64 / 2.0, self.statistic / 2.0)
+    }
+}
+
+// Lanczos' approximation, as in Numerical Recipes.
+#[cfg(feature = "std")]
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series: f64 = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c / (x + 1.0 + i as f64))
+        .sum();
+    -tmp + (2.506_628_274_631_000_5 * (1.000_000_000_190_015 + series) / x).ln()
+}
+
+// The regularized upper incomplete gamma function Q(a, x): by its series
+// below a + 1, and by its continued fraction above.
+#[cfg(feature = "std")]
+fn upper_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let scale = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        while term.abs() > sum.abs() * 1e-15 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+        }
+        1.0 - sum * scale
+    } else {
+        // Lentz's method.
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        scale * h
+    }
+}
+
+// How far back Progress looks for the player's pace.
+pub const PACE_WINDOW: Duration = Duration::from_secs(10);
+
+// For HUDs and stream overlays.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Progress {
+    // Fraction of the safe cells open, from 0 to 1.
+    pub opened: f64,
+    // 3BV solved per second over the last PACE_WINDOW of game time.
+    pub three_bv_per_second: f64,
+    // The rest of the 3BV at that pace. None while the pace is zero, and
+    // always for untimed games.
+    pub remaining: Option<Duration>,
+}
+
+impl Progress {
+    pub fn new(game: &Game, elapsed: Duration) -> Progress {
+        let board = game.board();
+        let safe = board.width * board.height - board.mines;
+        let opened = (safe as i64 - board.missing_points as i64).max(0) as f64 / safe.max(1) as f64;
+        let history = game.solved_history();
+        let solved = |at: Duration| {
+            history
+                .iter()
+                .take_while(|(t, _)| *t <= at)
+                .last()
+                .map_or(0, |(_, s)| *s)
+        };
+        let since = elapsed.saturating_sub(PACE_WINDOW);
+        let window = (elapsed - since).as_secs_f64();
+        let (now, then) = (solved(elapsed), solved(since));
+        let three_bv_per_second = if window > 0.0 {
+            (now - then) as f64 / window
+        } else {
+            0.0
+        };
+        let left = three_bv(board).saturating_sub(now);
+        let remaining = if left == 0 && board.state == BoardState::Won {
+            Some(Duration::ZERO)
+        } else if three_bv_per_second > 0.0 {
+            Some(Duration::from_secs_f64(left as f64 / three_bv_per_second))
+        } else {
+            None
+        };
+        Progress {
+            opened,
+            three_bv_per_second,
+            remaining,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Category {
+    // Lowest time.
+    Time,
+    // Highest 3BV/s.
+    ThreeBvPerSecond,
+    // Highest IOE.
+    Ioe,
+}
+
+// A won game kept as a personal best, with whatever the frontend uses to
+// find its replay again (a file name, a URL).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub summary: GameSummary,
+    pub replay: Option<String>,
+}
+
+impl Record {
+    fn beats(&self, other: &Record, category: Category) -> bool {
+        let (a, b) = (&self.summary, &other.summary);
+        match category {
+            Category::Time => a.elapsed_seconds < b.elapsed_seconds,
+            Category::ThreeBvPerSecond => a.three_bv_per_second > b.three_bv_per_second,
+            Category::Ioe => a.ioe > b.ioe,
+        }
+    }
+}
+
+// The best game of each category on one board size. A single game can hold
+// all three.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LevelRecords {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub time: Option<Record>,
+    pub three_bv_per_second: Option<Record>,
+    pub ioe: Option<Record>,
+}
+
+impl LevelRecords {
+    fn slot(&mut self, category: Category) -> &mut Option<Record> {
+        match category {
+            Category::Time => &mut self.time,
+            Category::ThreeBvPerSecond => &mut self.three_bv_per_second,
+            Category::Ioe => &mut self.ioe,
+        }
+    }
+
+    // Keeps the record where it beats the current one, ties going to the
+    // current one. Returns the categories it took.
+    fn offer(&mut self, record: &Record) -> Vec<Category> {
+        let mut improved = vec![];
+        for &category in &[Category::Time, Category::ThreeBvPerSecond, Category::Ioe] {
+            let slot = self.slot(category);
+            if slot
+                .as_ref()
+                .map_or(true, |best| record.beats(best, category))
+            {
+                *slot = Some(record.clone());
+                improved.push(category);
+            }
+        }
+        improved
+    }
+}
+
+// Personal bests per board size, for keeping on disk and moving between
+// machines: merging two databases keeps the best of both, so importing the
+// same export twice changes nothing.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct PersonalBests {
+    levels: Vec<LevelRecords>,
+}
+
+impl PersonalBests {
+    pub fn new() -> PersonalBests {
+        PersonalBests::default()
+    }
+
+    pub fn levels(&self) -> &[LevelRecords] {
+        &self.levels
+    }
+
+    pub fn level(&self, width: usize, height: usize, mines: usize) -> Option<&LevelRecords> {
+        self.levels
+            .iter()
+            .find(|l| (l.width, l.height, l.mines) == (width, height, mines))
+    }
+
+    fn level_mut(&mut self, width: usize, height: usize, mines: usize) -> &mut LevelRecords {
+        let index = match self
+            .levels
+            .iter()
+            .position(|l| (l.width, l.height, l.mines) == (width, height, mines))
+        {
+            Some(index) => index,
+            None => {
+                self.levels.push(LevelRecords {
+                    width,
+                    height,
+                    mines,
+                    time: None,
+                    three_bv_per_second: None,
+                    ioe: None,
+                });
+                self.levels.len() - 1
+            }
+        };
+        &mut self.levels[index]
+    }
+
+    // Offers a finished game, returning the categories it's a new best in.
+    // Lost games are never records.
+    pub fn record(
+        &mut self,
+        game: &Game,
+        summary: &GameSummary,
+        replay: Option<&str>,
+    ) -> Vec<Category> {
+        if !summary.won {
+            return vec![];
+        }
+        let board = game.board();
+        self.level_mut(board.width, board.height, board.mines)
+            .offer(&Record {
+                summary: summary.clone(),
+                replay: replay.map(String::from),
+            })
+    }
+
+    pub fn merge(&mut self, other: &PersonalBests) {
+        for theirs in &other.levels {
+            let level = self.level_mut(theirs.width, theirs.height, theirs.mines);
+            for record in [&theirs.time, &theirs.three_bv_per_second, &theirs.ioe]
+                .iter()
+                .filter_map(|r| r.as_ref())
+            {
+                level.offer(record);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_replay;
+    use crate::clock::MockClock;
+    use crate::tests::five_by_two_board;
+    use crate::{numbers_on_board, Move};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_three_bv() {
+        // X2100
+        // 2X100
+        // One opening on the right, plus the two 2s on the left.
+        let board = numbers_on_board(five_by_two_board());
+        assert_eq!(three_bv(&board), 3);
+        assert_eq!(solved_three_bv(&board), 0);
+    }
+
+    #[test]
+    fn test_board_constraints() {
+        let board = numbers_on_board(five_by_two_board());
+        assert_eq!(first_opening(&board, &Point::new(4, 0)), 6);
+        assert_eq!(first_opening(&board, &Point::new(1, 0)), 1);
+        assert_eq!(first_opening(&board, &Point::new(0, 0)), 0);
+
+        let constraints = BoardConstraints {
+            first_click: Some(Point::new(4, 4)),
+            min_first_opening: 20,
+            max_3bv: Some(30),
+            ..BoardConstraints::default()
+        };
+        let board = crate::BoardBuilder::new(9, 9, 10)
+            .seed(1)
+            .constraints(constraints.clone())
+            .build()
+            .unwrap();
+        let board = numbers_on_board(board);
+        assert!(first_opening(&board, &Point::new(4, 4)) >= 20);
+        assert!(three_bv(&board) <= 30);
+
+        let impossible = BoardConstraints {
+            min_first_opening: 81,
+            max_attempts: 3,
+            ..constraints.clone()
+        };
+        let built = crate::BoardBuilder::new(9, 9, 10)
+            .seed(1)
+            .constraints(impossible)
+            .build();
+        assert!(matches!(built, Err(Error::InvalidConfig(_))));
+        let no_click = BoardConstraints {
+            first_click: None,
+            ..constraints
+        };
+        assert!(no_click.check().is_err());
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(4, 1))).unwrap_err();
+        game.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(0, 1))).unwrap();
+        let summary = game.summary(2.0);
+        assert_eq!(
+            summary,
+            GameSummary {
+                won: true,
+                no_flag: true,
+                clicks: 4,
+                wasted_clicks: 1,
+                three_bv: 3,
+                solved_three_bv: 3,
+                elapsed_seconds: 2.0,
+                three_bv_per_second: 1.5,
+                ioe: 0.75,
+                lost: Some(false),
+                config: Some(SummaryConfig {
+                    width: 5,
+                    height: 2,
+                    mines: 2,
+                    seed: None,
+                    options: GameOptions::default(),
+                }),
+                analysis: None,
+                score_attack: None,
+            }
+        );
+        let analyzed = summary.analyzed(&analyze_replay(&game.replay()));
+        assert_eq!(
+            analyzed.analysis,
+            Some(SummaryAnalysis {
+                // The first click, then both 50/50s on the left.
+                guesses: 3,
+                missed_deductions: 0,
+                blunders: 0,
+                survival_chance: 0.2,
+            })
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_summary_json() {
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&game.summary(1.0).to_json()).unwrap();
+        assert_eq!(json["schema"], SUMMARY_SCHEMA_VERSION);
+        assert_eq!(json["outcome"], "lost");
+        assert_eq!(json["config"]["mines"], 2);
+        assert_eq!(json["efficiency"]["clicks"], 1);
+        assert_eq!(json["flags"]["analyzed"], false);
+        assert_eq!(json["flags"]["guesses"], serde_json::Value::Null);
+        assert_eq!(json["score_attack"], serde_json::Value::Null);
+
+        let summary = GameSummary {
+            score_attack: Some(ScoreAttackSummary {
+                score: 120,
+                best_combo: 4,
+                broken_combos: 1,
+            }),
+            ..game.summary(1.0)
+        };
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json()).unwrap();
+        assert_eq!(json["score_attack"]["score"], 120);
+        assert_eq!(json["score_attack"]["broken_combos"], 1);
+    }
+
+    #[test]
+    fn test_mine_density_map() {
+        // X2100
+        // 2X100
+        let board = numbers_on_board(five_by_two_board());
+        assert_eq!(board.mine_density_map(2), vec![vec![0.5, 0.0, 0.0]]);
+        assert_eq!(board.mine_density_map(5), vec![vec![0.2]]);
+    }
+
+    #[test]
+    fn test_uniformity() {
+        let seeded: Vec<Board> = (0..400)
+            .map(|seed| crate::create_seeded_board(4, 4, 4, seed).unwrap())
+            .collect();
+        let fair = uniformity(&seeded).unwrap();
+        assert_eq!(fair.degrees_of_freedom, 15);
+        assert_eq!(fair.counts.iter().flatten().sum::<usize>(), 1600);
+        assert!(fair.p_value() > 0.001, "{:?}", fair);
+
+        // Mines only ever in the top half.
+        let biased: Vec<Board> = (0..400)
+            .map(|seed| {
+                let mut rng = crate::random::SplitMix64::new(seed);
+                let mut draws = 0;
+                crate::create_board(4, 4, 4, |start, end| {
+                    draws += 1;
+                    // Every second draw is a row.
+                    let end = if draws % 2 == 0 { end / 2 } else { end };
+                    rng.gen_range(start, end)
+                })
+                .unwrap()
+            })
+            .collect();
+        assert!(uniformity(&biased).unwrap().p_value() < 1e-6);
+
+        assert!(uniformity(&[]).is_err());
+    }
+
+    #[test]
+    fn test_p_value() {
+        // Critical values from chi-square tables.
+        let p = |statistic, degrees_of_freedom| {
+            Uniformity {
+                statistic,
+                degrees_of_freedom,
+                counts: vec![],
+            }
+            .p_value()
+        };
+        assert!((p(3.841, 1) - 0.05).abs() < 1e-4);
+        assert!((p(18.307, 10) - 0.05).abs() < 1e-4);
+        assert!((p(124.342, 100) - 0.05).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_progress() {
+        // 3BV 3: the opening and the two 2s.
+        let mut game = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        let clock = MockClock::new();
+        assert_eq!(game.progress(&clock).remaining, None);
+        game.apply_timed(&Move::Open(Point::new(4, 0)), &clock)
+            .unwrap();
+        clock.advance(Duration::from_secs(20));
+        game.apply_timed(&Move::Open(Point::new(1, 0)), &clock)
+            .unwrap();
+        // The opening was solved more than PACE_WINDOW ago, so only the 2
+        // counts: one 3BV in ten seconds leaves ten for the last one.
+        let progress = game.progress(&clock);
+        assert_eq!(progress.opened, 7.0 / 8.0);
+        assert_eq!(progress.three_bv_per_second, 0.1);
+        assert_eq!(progress.remaining, Some(Duration::from_secs(10)));
+
+        game.apply_timed(&Move::Open(Point::new(0, 1)), &clock)
+            .unwrap();
+        assert_eq!(game.progress(&clock).remaining, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_personal_bests() {
+        let mut slow = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        slow.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        slow.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        slow.apply(&Move::Open(Point::new(0, 1))).unwrap();
+        let mut sloppy = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        sloppy.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        sloppy.apply(&Move::Open(Point::new(4, 0))).unwrap_err();
+        sloppy.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        sloppy.apply(&Move::Open(Point::new(0, 1))).unwrap();
+
+        let mut bests = PersonalBests::new();
+        let all = vec![Category::Time, Category::ThreeBvPerSecond, Category::Ioe];
+        assert_eq!(bests.record(&slow, &slow.summary(3.0), Some("slow")), all);
+        let mut other = PersonalBests::new();
+        assert_eq!(
+            other.record(&sloppy, &sloppy.summary(2.0), Some("sloppy")),
+            all
+        );
+
+        bests.merge(&other);
+        let level = bests.level(5, 2, 2).unwrap();
+        assert_eq!(
+            level.time.as_ref().unwrap().replay.as_deref(),
+            Some("sloppy")
+        );
+        assert_eq!(level.ioe.as_ref().unwrap().replay.as_deref(), Some("slow"));
+        let merged = bests.clone();
+        bests.merge(&other);
+        assert_eq!(bests, merged);
+        assert_eq!(bests.levels().len(), 1);
+
+        let mut lost = Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        );
+        lost.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        assert_eq!(bests.record(&lost, &lost.summary(0.5), None), vec![]);
+    }
+}
@@ -0,0 +1,395 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use directories::ProjectDirs;
+
+use crate::game::Game;
+use crate::migrations::{save_from_json, save_to_json};
+use crate::recovery::{recover_save, RecoveryReport};
+use crate::stats::PersonalBests;
+use crate::{Error, Result};
+
+// How many autosaves are kept. Older ones are there to fall back on when the
+// newest one can't be read.
+pub const AUTOSAVE_SLOTS: usize = 3;
+
+const EXTENSION: &str = "json";
+// Not a save, so its extension keeps it out of the list.
+const PERSONAL_BESTS: &str = "personal-bests.records";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SaveInfo {
+    pub name: String,
+    pub modified: Option<SystemTime>,
+    // False when the file exists but doesn't hold a game we can load.
+    pub valid: bool,
+}
+
+// A directory of saved games, one JSON file per save.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Storage {
+    root: PathBuf,
+}
+
+pub(crate) fn io_error(path: &Path, e: std::io::Error) -> Error {
+    Error::Storage(format!("{}: {}", path.display(), e))
+}
+
+// Writes to a temporary file first so a crash mid-write leaves the previous
+// contents intact.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes).map_err(|e| io_error(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| io_error(path, e))
+}
+
+fn read_personal_bests(path: &Path) -> Result<PersonalBests> {
+    let bytes = fs::read(path).map_err(|e| io_error(path, e))?;
+    serde_json::from_slice(&bytes).map_err(|e| Error::CorruptSave {
+        name: path.display().to_string(),
+        reason: e.to_string(),
+    })
+}
+
+fn write_personal_bests(path: &Path, bests: &PersonalBests) -> Result<()> {
+    let json = serde_json::to_vec(bests).map_err(|e| Error::Storage(e.to_string()))?;
+    write_atomic(path, &json)
+}
+
+const AUTOSAVE_PREFIX: &str = "autosave-";
+
+fn autosave_name(slot: usize) -> String {
+    format!("{}{}", AUTOSAVE_PREFIX, slot)
+}
+
+// Autosave names are reserved so a slot can't be rotated away.
+fn check_slot(name: &str) -> Result<()> {
+    check_name(name)?;
+    if name.starts_with(AUTOSAVE_PREFIX) {
+        return Err(Error::InvalidConfig(format!(
+            "slot names can't start with {:?}",
+            AUTOSAVE_PREFIX
+        )));
+    }
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidConfig(format!(
+            "save names can only use letters, digits, '-' and '_', got {:?}",
+            name
+        )))
+    }
+}
+
+impl Storage {
+    // The per-user data directory for the game, e.g. ~/.local/share/minesweeper
+    // on Linux.
+    pub fn open_default() -> Result<Storage> {
+        let dirs = ProjectDirs::from("", "", "minesweeper")
+            .ok_or_else(|| Error::Storage(String::from("no home directory found")))?;
+        Storage::open(dirs.data_dir())
+    }
+
+    pub fn open(root: impl Into<PathBuf>) -> Result<Storage> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| io_error(&root, e))?;
+        Ok(Storage { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", name, EXTENSION))
+    }
+
+    pub fn save(&self, name: &str, game: &Game) -> Result<()> {
+        check_name(name)?;
+        write_atomic(&self.path(name), &save_to_json(game)?)
+    }
+
+    // Saves written by older versions are upgraded, see migrations.
+    pub fn load(&self, name: &str) -> Result<Game> {
+        check_name(name)?;
+        let path = self.path(name);
+        let bytes = fs::read(&path).map_err(|e| io_error(&path, e))?;
+        save_from_json(&bytes).map_err(|e| Error::CorruptSave {
+            name: String::from(name),
+            reason: match e {
+                Error::ParseError(reason) => reason,
+                e => e.to_string(),
+            },
+        })
+    }
+
+    // Like load, but a corrupt save gives back what can be recovered from it
+    // instead of an error, with a report of what was lost.
+    pub fn load_recovering(&self, name: &str) -> Result<(Game, RecoveryReport)> {
+        check_name(name)?;
+        let path = self.path(name);
+        let bytes = fs::read(&path).map_err(|e| io_error(&path, e))?;
+        if let Ok(game) = save_from_json(&bytes) {
+            return Ok((game, RecoveryReport::default()));
+        }
+        recover_save(&bytes).map_err(|e| Error::CorruptSave {
+            name: String::from(name),
+            reason: match e {
+                Error::ParseError(reason) => reason,
+                e => e.to_string(),
+            },
+        })
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        check_name(name)?;
+        let path = self.path(name);
+        fs::remove_file(&path).map_err(|e| io_error(&path, e))
+    }
+
+    // Every save in the directory, newest first. Corrupt saves are listed too
+    // so frontends can offer to delete them.
+    pub fn list(&self) -> Result<Vec<SaveInfo>> {
+        let entries = fs::read_dir(&self.root).map_err(|e| io_error(&self.root, e))?;
+        let mut saves = vec![];
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(&self.root, e))?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(name) if check_name(name).is_ok() => String::from(name),
+                _ => continue,
+            };
+            saves.push(SaveInfo {
+                modified: fs::metadata(&path).and_then(|m| m.modified()).ok(),
+                valid: self.load(&name).is_ok(),
+                name,
+            });
+        }
+        saves.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.name.cmp(&b.name)));
+        Ok(saves)
+    }
+
+    // Shifts the older autosaves down a slot, dropping the oldest, and writes
+    // the game to the first slot.
+    pub fn autosave(&self, game: &Game) -> Result<()> {
+        for slot in (1..AUTOSAVE_SLOTS).rev() {
+            let from = self.path(&autosave_name(slot - 1));
+            if from.exists() {
+                let to = self.path(&autosave_name(slot));
+                fs::rename(&from, &to).map_err(|e| io_error(&to, e))?;
+            }
+        }
+        self.save(&autosave_name(0), game)
+    }
+
+    // Saves only when the game's autosave policy says it's time, so it can be
+    // called after every move. Returns whether it saved.
+    pub fn autosave_if_due(&self, game: &mut Game) -> Result<bool> {
+        if !game.autosave_due() {
+            return Ok(false);
+        }
+        // Saved as already saved, or the game loaded back from it would be
+        // due again straight away. A failed save leaves the game due.
+        let mut saved = game.clone();
+        saved.mark_saved();
+        self.autosave(&saved)?;
+        game.mark_saved();
+        Ok(true)
+    }
+
+    pub fn save_slot(&self, name: &str, game: &Game) -> Result<()> {
+        check_slot(name)?;
+        self.save(name, game)
+    }
+
+    pub fn load_slot(&self, name: &str) -> Result<Game> {
+        check_slot(name)?;
+        self.load(name)
+    }
+
+    // Like list, without the autosaves.
+    pub fn list_slots(&self) -> Result<Vec<SaveInfo>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|s| !s.name.starts_with(AUTOSAVE_PREFIX))
+            .collect())
+    }
+
+    // The newest autosave that can still be read, if any.
+    pub fn load_autosave(&self) -> Option<Game> {
+        (0..AUTOSAVE_SLOTS).find_map(|slot| self.load(&autosave_name(slot)).ok())
+    }
+
+    // Empty until the first save.
+    pub fn load_personal_bests(&self) -> Result<PersonalBests> {
+        let path = self.root.join(PERSONAL_BESTS);
+        if !path.exists() {
+            return Ok(PersonalBests::new());
+        }
+        read_personal_bests(&path)
+    }
+
+    pub fn save_personal_bests(&self, bests: &PersonalBests) -> Result<()> {
+        write_personal_bests(&self.root.join(PERSONAL_BESTS), bests)
+    }
+
+    // Writes the stored personal bests to a file anywhere, to carry to
+    // another machine.
+    pub fn export_personal_bests(&self, path: &Path) -> Result<()> {
+        write_personal_bests(path, &self.load_personal_bests()?)
+    }
+
+    // Merges an export into the stored personal bests and returns the result.
+    pub fn import_personal_bests(&self, path: &Path) -> Result<PersonalBests> {
+        let mut bests = self.load_personal_bests()?;
+        bests.merge(&read_personal_bests(path)?);
+        self.save_personal_bests(&bests)?;
+        Ok(bests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{AutosaveEvery, GameOptions};
+    use crate::tests::five_by_two_board;
+    use crate::{numbers_on_board, Move, Point};
+    use pretty_assertions::assert_eq;
+
+    fn scratch_storage(test: &str) -> Storage {
+        let root =
+            std::env::temp_dir().join(format!("lib_minesweeper-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        Storage::open(root).unwrap()
+    }
+
+    fn new_game() -> Game {
+        Game::new(
+            numbers_on_board(five_by_two_board()),
+            GameOptions::default(),
+        )
+    }
+
+    #[test]
+    fn test_save_load_list() {
+        let storage = scratch_storage("save_load_list");
+        let mut game = new_game();
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        storage.save("expert", &game).unwrap();
+        assert_eq!(storage.load("expert").unwrap(), game);
+
+        fs::write(storage.root().join("broken.json"), "{").unwrap();
+        assert!(matches!(
+            storage.load("broken"),
+            Err(Error::CorruptSave { .. })
+        ));
+        assert!(matches!(
+            storage.load_recovering("broken"),
+            Err(Error::CorruptSave { .. })
+        ));
+        let (loaded, report) = storage.load_recovering("expert").unwrap();
+        assert_eq!((&loaded, report.is_clean()), (&game, true));
+        assert!(matches!(
+            storage.save("../escape", &game),
+            Err(Error::InvalidConfig(_))
+        ));
+
+        let mut saves: Vec<(String, bool)> = storage
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.valid))
+            .collect();
+        saves.sort();
+        assert_eq!(
+            saves,
+            vec![
+                (String::from("broken"), false),
+                (String::from("expert"), true)
+            ]
+        );
+        fs::remove_dir_all(storage.root()).unwrap();
+    }
+
+    #[test]
+    fn test_autosave_falls_back_on_corruption() {
+        let storage = scratch_storage("autosave");
+        assert_eq!(storage.load_autosave(), None);
+
+        let older = new_game();
+        let mut newer = new_game();
+        newer.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        storage.autosave(&older).unwrap();
+        storage.autosave(&newer).unwrap();
+        assert_eq!(storage.load_autosave(), Some(newer));
+
+        fs::write(storage.root().join("autosave-0.json"), "garbage").unwrap();
+        assert_eq!(storage.load_autosave(), Some(older));
+        fs::remove_dir_all(storage.root()).unwrap();
+    }
+
+    #[test]
+    fn test_slots_and_autosave_policy() {
+        let storage = scratch_storage("slots");
+        let mut game = new_game().autosave_policy(AutosaveEvery(2));
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap_err();
+        assert_eq!(storage.autosave_if_due(&mut game), Ok(false));
+        game.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        assert_eq!(storage.autosave_if_due(&mut game), Ok(true));
+        assert_eq!(storage.autosave_if_due(&mut game), Ok(false));
+        let loaded = storage.load_autosave().unwrap();
+        assert_eq!(loaded.moves().len(), 3);
+        assert!(!loaded.autosave_due());
+
+        storage.save_slot("slot1", &game).unwrap();
+        assert_eq!(storage.load_slot("slot1").unwrap(), game);
+        assert!(matches!(
+            storage.save_slot("autosave-9", &game),
+            Err(Error::InvalidConfig(_))
+        ));
+        let slots: Vec<String> = storage
+            .list_slots()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(slots, vec![String::from("slot1")]);
+        fs::remove_dir_all(storage.root()).unwrap();
+    }
+
+    #[test]
+    fn test_export_import_personal_bests() {
+        let here = scratch_storage("bests_here");
+        let there = scratch_storage("bests_there");
+        assert_eq!(here.load_personal_bests(), Ok(PersonalBests::new()));
+
+        let mut game = new_game();
+        game.apply(&Move::Open(Point::new(4, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(1, 0))).unwrap();
+        game.apply(&Move::Open(Point::new(0, 1))).unwrap();
+        let mut bests = PersonalBests::new();
+        bests.record(&game, &game.summary(2.0), Some("replay-1"));
+        here.save_personal_bests(&bests).unwrap();
+        assert_eq!(here.list().unwrap(), vec![]);
+
+        let export = here.root().join("export.records");
+        here.export_personal_bests(&export).unwrap();
+        assert_eq!(there.import_personal_bests(&export), Ok(bests.clone()));
+        assert_eq!(there.import_personal_bests(&export), Ok(bests));
+        fs::remove_dir_all(here.root()).unwrap();
+        fs::remove_dir_all(there.root()).unwrap();
+    }
+}
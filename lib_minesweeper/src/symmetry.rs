@@ -0,0 +1,219 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::random::RandomSource;
+use crate::MapElement::Mine;
+use crate::{board_with_mines, check_mines_fit, Board, Error, Point, Result};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Symmetry {
+    // Left to right.
+    Mirror,
+    // Both left to right and top to bottom.
+    MirrorBoth,
+    // Half a turn around the center.
+    Rotation2,
+    // A quarter turn around the center, for square boards only.
+    Rotation4,
+}
+
+impl Symmetry {
+    // Where the symmetry takes p, p included, without repeats.
+    fn images(self, p: &Point, width: usize, height: usize) -> Vec<Point> {
+        let (w, h) = (width as i32 - 1, height as i32 - 1);
+        let (x, y) = (p.x, p.y);
+        let mut images = match self {
+            Symmetry::Mirror => vec![Point { x, y }, Point { x: w - x, y }],
+            Symmetry::MirrorBoth => vec![
+                Point { x, y },
+                Point { x: w - x, y },
+                Point { x, y: h - y },
+                Point { x: w - x, y: h - y },
+            ],
+            Symmetry::Rotation2 => vec![Point { x, y }, Point { x: w - x, y: h - y }],
+            Symmetry::Rotation4 => vec![
+                Point { x, y },
+                Point { x: w - y, y: x },
+                Point { x: w - x, y: h - y },
+                Point { x: y, y: h - x },
+            ],
+        };
+        images.sort_by_key(|p| (p.y, p.x));
+        images.dedup();
+        images
+    }
+}
+
+fn shuffle<T>(items: &mut [T], source: &mut dyn RandomSource) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, source.gen_range(0, i + 1));
+    }
+}
+
+// How many orbits of 1, 2 and 4 cells, out of the ones there are, hold as
+// many of the mines as they can: the sizes any symmetry's orbits come in.
+// Fewer 4-cell orbits are tried until the rest fill the gap exactly.
+fn orbit_counts(available: [usize; 3], mines: usize) -> [usize; 3] {
+    let mut best = [0; 3];
+    let mut best_total = 0;
+    for fours in (0..=available[2].min(mines / 4)).rev() {
+        let rest = mines - 4 * fours;
+        let twos = available[1].min(rest / 2);
+        let ones = available[0].min(rest - 2 * twos);
+        let total = 4 * fours + 2 * twos + ones;
+        if total > best_total {
+            best = [ones, twos, fours];
+            best_total = total;
+        }
+        if total == mines {
+            break;
+        }
+    }
+    best
+}
+
+// Places exactly `mines` mines so the board looks the same under the
+// symmetry, none on the safe points. The mines go in whole orbits, the cells
+// the symmetry maps onto each other, picked so their sizes add up to the
+// count whenever they can. When the count can't be met that way,
+// or a safe point is in the way, the last few mines go on single cells:
+// first the cells of orbits the safe points already broke, then others.
+pub fn create_symmetric_board(
+    width: usize,
+    height: usize,
+    mines: usize,
+    symmetry: Symmetry,
+    source: &mut dyn RandomSource,
+    safe: &[Point],
+) -> Result<Board> {
+    if symmetry == Symmetry::Rotation4 && width != height {
+        return Err(Error::InvalidConfig(format!(
+            "a {}x{} board can't have quarter turn symmetry",
+            width, height
+        )));
+    }
+    check_mines_fit(width, height, mines, safe)?;
+    let (mut whole, mut broken) = (Vec::new(), Vec::new());
+    for y in 0..height {
+        for x in 0..width {
+            let p = Point::new(x, y);
+            let orbit = symmetry.images(&p, width, height);
+            if orbit[0] != p {
+                continue;
+            }
+            if orbit.iter().any(|p| safe.contains(p)) {
+                broken.extend(orbit.into_iter().filter(|p| !safe.contains(p)));
+            } else {
+                whole.push(orbit);
+            }
+        }
+    }
+    shuffle(&mut whole, source);
+    shuffle(&mut broken, source);
+
+    let size = |orbit: &Vec<Point>| match orbit.len() {
+        1 => 0,
+        2 => 1,
+        _ => 2,
+    };
+    let mut available = [0; 3];
+    for orbit in &whole {
+        available[size(orbit)] += 1;
+    }
+    let mut wanted = orbit_counts(available, mines);
+    let mut points = Vec::with_capacity(mines);
+    let mut left = Vec::new();
+    for orbit in whole {
+        if wanted[size(&orbit)] > 0 {
+            wanted[size(&orbit)] -= 1;
+            points.extend(orbit);
+        } else {
+            left.extend(orbit);
+        }
+    }
+    shuffle(&mut left, source);
+    let missing = mines - points.len();
+    points.extend(broken.into_iter().chain(left).take(missing));
+    board_with_mines(width, height, &points)
+}
+
+// Mines whose images under the symmetry aren't all mines.
+pub fn asymmetric_mines(board: &Board, symmetry: Symmetry) -> Vec<Point> {
+    let is_mine = |p: &Point| matches!(board.at(p), Some(Mine { .. }));
+    (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+        .filter(|p| is_mine(p))
+        .filter(|p| {
+            !symmetry
+                .images(p, board.width, board.height)
+                .iter()
+                .all(is_mine)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::SplitMix64;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_symmetric_boards() {
+        let mut source = SplitMix64::new(5);
+        let safe = [Point::new(0, 0)];
+        let board =
+            create_symmetric_board(9, 9, 20, Symmetry::Rotation4, &mut source, &safe).unwrap();
+        assert_eq!(board.mines, 20);
+        assert_eq!(asymmetric_mines(&board, Symmetry::Rotation4), vec![]);
+        assert!(!matches!(board.at(&safe[0]), Some(Mine { .. })));
+
+        for symmetry in [Symmetry::Mirror, Symmetry::MirrorBoth, Symmetry::Rotation2] {
+            let board = create_symmetric_board(8, 6, 12, symmetry, &mut source, &[]).unwrap();
+            assert_eq!(board.mines, 12);
+            assert_eq!(asymmetric_mines(&board, symmetry), vec![]);
+        }
+        assert!(create_symmetric_board(8, 6, 12, Symmetry::Rotation4, &mut source, &[]).is_err());
+    }
+
+    #[test]
+    fn test_symmetric_counts_use_every_orbit_size() {
+        // A 3x3 board mirrored both ways has the center, two 2-cell orbits
+        // and the corners: 7 mines need the corners, one pair and the center.
+        for seed in 0..20 {
+            let mut source = SplitMix64::new(seed);
+            let board =
+                create_symmetric_board(3, 3, 7, Symmetry::MirrorBoth, &mut source, &[]).unwrap();
+            assert_eq!(board.mines, 7);
+            assert_eq!(asymmetric_mines(&board, Symmetry::MirrorBoth), vec![]);
+        }
+        let mut source = SplitMix64::new(1);
+        for mines in 0..25 {
+            let board = create_symmetric_board(5, 5, mines, Symmetry::MirrorBoth, &mut source, &[])
+                .unwrap();
+            assert_eq!(asymmetric_mines(&board, Symmetry::MirrorBoth), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_symmetry_breaks_minimally() {
+        let mut source = SplitMix64::new(5);
+        // Every orbit of a 4x4 board under quarter turns has 4 cells.
+        let board = create_symmetric_board(4, 4, 5, Symmetry::Rotation4, &mut source, &[]).unwrap();
+        assert_eq!(board.mines, 5);
+        assert_eq!(asymmetric_mines(&board, Symmetry::Rotation4).len(), 1);
+
+        // The safe corner breaks the corners' orbit, which takes the extra
+        // mines.
+        let safe = [Point::new(0, 0)];
+        let board =
+            create_symmetric_board(4, 4, 7, Symmetry::Rotation4, &mut source, &safe).unwrap();
+        let broken = asymmetric_mines(&board, Symmetry::Rotation4);
+        assert_eq!(broken.len(), 3);
+        assert!(broken
+            .iter()
+            .all(|p| (p.x == 0 || p.x == 3) && (p.y == 0 || p.y == 3)));
+    }
+}
@@ -0,0 +1,272 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::solver::{constraints, is_unknown};
+use crate::{Adjacency, BoardRead, BoardState, Point};
+
+// Endgames with more closed cells than this aren't looked up by default:
+// the positions to solve grow about as fast as the mine layouts.
+pub const TABLEBASE_CELLS: usize = 12;
+
+// The exact value of an endgame position, playing on perfectly.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Endgame {
+    pub win_probability: f64,
+    // The closed cell to open for it. Flags are ignored.
+    pub best: Point,
+    // The chance of winning after opening each closed cell, row by row.
+    pub moves: Vec<(Point, f64)>,
+}
+
+impl Endgame {
+    // What opening the cell gives up against the best move. None for cells
+    // that aren't closed.
+    pub fn loss(&self, p: &Point) -> Option<f64> {
+        self.moves
+            .iter()
+            .find(|(q, _)| q == p)
+            .map(|(_, chance)| self.win_probability - chance)
+    }
+}
+
+// What one position needs: its closed cells, in the order of the bits of
+// the masks below, and who touches whom among them.
+struct Position {
+    cells: Vec<usize>,
+    neighbors: Vec<u64>,
+}
+
+impl Position {
+    // The closed cells opening `cell` reveals in the layout, the cascade
+    // included, and the numbers they show.
+    fn reveal(&self, cell: usize, closed: u64, layout: u64) -> (u64, Vec<u32>) {
+        let (mut opened, mut counts, mut stack) = (0u64, vec![], vec![cell]);
+        while let Some(i) = stack.pop() {
+            if opened & 1 << i != 0 {
+                continue;
+            }
+            opened |= 1 << i;
+            let count = (layout & self.neighbors[i]).count_ones();
+            counts.push((i as u32) << 8 | count);
+            if count == 0 {
+                let next = self.neighbors[i] & closed;
+                stack.extend((0..self.cells.len()).filter(|&n| next & 1 << n != 0));
+            }
+        }
+        counts.sort_unstable();
+        (opened, counts)
+    }
+}
+
+// Solves endgames exactly and remembers every position it solved. A
+// position's value only depends on the closed cells and the mine layouts
+// that agree with the numbers, so one tablebase serves every game on boards
+// of one size: keep it for the whole game, or a whole batch of them.
+#[derive(Debug, Clone)]
+pub struct Tablebase {
+    max_cells: usize,
+    size: (usize, usize),
+    // Closed cells and layouts, over those cells, to the chance of winning.
+    memo: BTreeMap<(Vec<usize>, Vec<u64>), f64>,
+}
+
+impl Tablebase {
+    // Positions with more than max_cells closed cells, or 64, are left alone.
+    pub fn new(max_cells: usize) -> Tablebase {
+        Tablebase {
+            max_cells: max_cells.min(64),
+            size: (0, 0),
+            memo: BTreeMap::new(),
+        }
+    }
+
+    // Positions solved so far.
+    pub fn len(&self) -> usize {
+        self.memo.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memo.is_empty()
+    }
+
+    // None for games that are over, positions with too many closed cells
+    // and boards no layout agrees with. The mine count is taken as known.
+    pub fn evaluate<B: BoardRead>(&mut self, board: &B) -> Option<Endgame> {
+        if !matches!(board.state(), BoardState::Ready | BoardState::Playing) {
+            return None;
+        }
+        let (width, height) = (board.width(), board.height());
+        let cells: Vec<usize> = (0..width * height)
+            .filter(|&i| is_unknown(board, i % width, i / width))
+            .collect();
+        if cells.is_empty() || cells.len() > self.max_cells {
+            return None;
+        }
+        if self.size != (width, height) {
+            self.size = (width, height);
+            self.memo.clear();
+        }
+        let adjacency = Adjacency::new(width, height);
+        let mask = |neighbors: &[usize]| {
+            neighbors
+                .iter()
+                .filter_map(|n| cells.binary_search(n).ok())
+                .fold(0u64, |m, i| m | 1 << i)
+        };
+        let position = Position {
+            neighbors: cells
+                .iter()
+                .map(|&c| mask(adjacency.neighbors(c)))
+                .collect(),
+            cells: cells.clone(),
+        };
+        let numbers: Vec<(u64, u32)> = constraints(board, &adjacency)
+            .iter()
+            .map(|c| (mask(&c.cells), c.mines as u32))
+            .collect();
+        let mut layouts = vec![];
+        layouts_of(cells.len(), board.mines(), 0, 0, &numbers, &mut layouts);
+        if layouts.is_empty() {
+            return None;
+        }
+
+        let all = u64::MAX >> (64 - cells.len());
+        let moves: Vec<(Point, f64)> = (0..cells.len())
+            .map(|i| {
+                let chance = self.open(&position, all, &layouts, i);
+                (adjacency.point(cells[i]), chance)
+            })
+            .collect();
+        let (best, win_probability) =
+            moves
+                .iter()
+                .fold(moves[0], |best, &m| if m.1 > best.1 { m } else { best });
+        Some(Endgame {
+            win_probability,
+            best,
+            moves,
+        })
+    }
+
+    // The chance of winning from the position, playing on perfectly.
+    fn solve(&mut self, position: &Position, closed: u64, layouts: &[u64]) -> f64 {
+        if layouts.is_empty() {
+            return 0.0;
+        }
+        // Every closed cell is a mine.
+        if layouts[0].count_ones() == closed.count_ones() {
+            return 1.0;
+        }
+        let key = (
+            (0..position.cells.len())
+                .filter(|&i| closed & 1 << i != 0)
+                .map(|i| position.cells[i])
+                .collect(),
+            layouts.iter().map(|&l| compress(l, closed)).collect(),
+        );
+        if let Some(&chance) = self.memo.get(&key) {
+            return chance;
+        }
+        let mut best = 0.0;
+        for i in (0..position.cells.len()).filter(|&i| closed & 1 << i != 0) {
+            let chance = self.open(position, closed, layouts, i);
+            if chance > best {
+                best = chance;
+            }
+            if best >= 1.0 {
+                break;
+            }
+        }
+        self.memo.insert(key, best);
+        best
+    }
+
+    // The chance of winning after opening cell i, each reveal weighed by the
+    // layouts that give it.
+    fn open(&mut self, position: &Position, closed: u64, layouts: &[u64], i: usize) -> f64 {
+        let mut reveals: BTreeMap<(u64, Vec<u32>), Vec<u64>> = BTreeMap::new();
+        for &layout in layouts.iter().filter(|&&l| l & 1 << i == 0) {
+            reveals
+                .entry(position.reveal(i, closed, layout))
+                .or_default()
+                .push(layout);
+        }
+        reveals
+            .into_iter()
+            .map(|((opened, _), group)| {
+                group.len() as f64 * self.solve(position, closed & !opened, &group)
+            })
+            .sum::<f64>()
+            / layouts.len() as f64
+    }
+}
+
+impl Default for Tablebase {
+    fn default() -> Tablebase {
+        Tablebase::new(TABLEBASE_CELLS)
+    }
+}
+
+// The bits of `value` under the bits of `mask`, packed together.
+fn compress(value: u64, mask: u64) -> u64 {
+    (0..64)
+        .filter(|&i| mask & 1 << i != 0)
+        .enumerate()
+        .fold(0, |out, (j, i)| out | (value >> i & 1) << j)
+}
+
+// Every way of putting `mines` mines on the first n cells that agrees with
+// the numbers, each a mask of cells and the mines it must touch.
+fn layouts_of(
+    n: usize,
+    mines: usize,
+    i: usize,
+    layout: u64,
+    numbers: &[(u64, u32)],
+    out: &mut Vec<u64>,
+) {
+    let placed = layout.count_ones() as usize;
+    if placed > mines || mines - placed > n - i {
+        return;
+    }
+    if i == n {
+        if numbers.iter().all(|&(m, c)| (layout & m).count_ones() == c) {
+            out.push(layout);
+        }
+        return;
+    }
+    layouts_of(n, mines, i + 1, layout, numbers, out);
+    layouts_of(n, mines, i + 1, layout | 1 << i, numbers, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+
+    fn close_to(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_corner_beats_middle() {
+        // One mine in three cells: an end wins two times in three, as the
+        // number it shows settles the rest, while the middle leaves a 50/50.
+        let board = Scenario::from_ascii("..*\n").into_board();
+        let mut tablebase = Tablebase::default();
+        let endgame = tablebase.evaluate(&board).unwrap();
+        assert!(close_to(endgame.win_probability, 2.0 / 3.0));
+        assert_eq!(endgame.best, Point::new(0, 0));
+        assert!(close_to(
+            endgame.loss(&Point::new(1, 0)).unwrap(),
+            1.0 / 3.0
+        ));
+        assert!(!tablebase.is_empty());
+
+        let fifty_fifty = Scenario::from_ascii("*.\n11\n").into_board();
+        let endgame = tablebase.evaluate(&fifty_fifty).unwrap();
+        assert!(close_to(endgame.win_probability, 0.5));
+        assert_eq!(Tablebase::new(1).evaluate(&board), None);
+    }
+}
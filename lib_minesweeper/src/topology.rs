@@ -0,0 +1,163 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::render::cell_char;
+use crate::{Adjacency, Board, Point};
+
+// How the cells of a width x height grid touch each other. Boards always store
+// cells row by row; a topology only changes which of them are neighbors. Use
+// it through Adjacency::for_topology, numbers_on_board_with and
+// Board::cascade_open_with.
+pub trait Topology {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    // The in-bounds neighbors of p.
+    fn neighbors(&self, p: &Point) -> Vec<Point>;
+
+    fn contains(&self, p: &Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width() && (p.y as usize) < self.height()
+    }
+}
+
+// The classic grid, eight neighbors per cell.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Square {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Topology for Square {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn neighbors(&self, p: &Point) -> Vec<Point> {
+        p.neighbors().filter(|n| self.contains(n)).collect()
+    }
+}
+
+// Rows of triangles alternately pointing up and down; (0, 0) points up, and
+// so does every cell whose x + y is even. Each triangle touches twelve others,
+// three by an edge and nine by a corner only.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Triangular {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Triangular {
+    pub fn points_up(p: &Point) -> bool {
+        (p.x + p.y) % 2 == 0
+    }
+}
+
+impl Topology for Triangular {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    // The row on a triangle's flat side has five neighbors, the row on its tip
+    // side three.
+    fn neighbors(&self, p: &Point) -> Vec<Point> {
+        let (above, below) = if Triangular::points_up(p) {
+            (1, 2)
+        } else {
+            (2, 1)
+        };
+        let mut neighbors = Vec::with_capacity(12);
+        for (dy, reach) in &[(-1, above), (0, 2), (1, below)] {
+            for dx in -reach..=*reach {
+                let n = Point {
+                    x: p.x + dx,
+                    y: p.y + dy,
+                };
+                if (dx, *dy) != (0, 0) && self.contains(&n) {
+                    neighbors.push(n);
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+// A triangular board as text: each row alternates edges and cells, so
+// "/1\ /" is an up triangle showing a 1 next to a down one. Cells are drawn
+// as for render::board_text.
+pub fn triangular_text(board: &Board) -> String {
+    let mut out = String::new();
+    for (y, row) in board.view().into_iter().enumerate() {
+        for (x, cell) in row.into_iter().enumerate() {
+            let up = Triangular::points_up(&Point::new(x, y));
+            out.push(if up { '/' } else { '\\' });
+            out.push(cell_char(cell));
+        }
+        let last = Point::new(board.width - 1, y);
+        out.push(if Triangular::points_up(&last) {
+            '\\'
+        } else {
+            '/'
+        });
+        out.push('\n');
+    }
+    out
+}
+
+// For solvers and counts on a triangular board.
+pub fn triangular_adjacency(width: usize, height: usize) -> Adjacency {
+    Adjacency::for_topology(&Triangular { width, height })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_board, numbers_on_board_with, BoardState};
+    use alloc::vec;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_triangular_neighbors() {
+        let grid = Triangular {
+            width: 9,
+            height: 5,
+        };
+        assert_eq!(grid.neighbors(&Point::new(4, 2)).len(), 12);
+        assert_eq!(grid.neighbors(&Point::new(3, 2)).len(), 12);
+        assert_eq!(grid.neighbors(&Point::new(0, 0)).len(), 5);
+        // Neighborhoods are symmetric.
+        for y in 0..5 {
+            for x in 0..9 {
+                let p = Point::new(x, y);
+                for n in grid.neighbors(&p) {
+                    assert!(grid.neighbors(&n).contains(&p));
+                }
+            }
+        }
+        let square = Adjacency::for_topology(&Square {
+            width: 5,
+            height: 2,
+        });
+        assert_eq!(square, Adjacency::new(5, 2));
+    }
+
+    #[test]
+    fn test_play_triangular_board() {
+        // A single mine in the bottom right corner.
+        let mut rand = vec![4, 1].into_iter();
+        let board = create_board(5, 2, 1, |_, _| rand.next().unwrap()).unwrap();
+        let adjacency = triangular_adjacency(5, 2);
+        let board = numbers_on_board_with(board, &adjacency);
+        let board = board
+            .cascade_open_with(&Point::new(0, 0), &adjacency)
+            .unwrap();
+        assert_eq!(board.state, BoardState::Playing);
+        assert_eq!(triangular_text(&board), "/ \\ /1\\1/.\\\n\\ / \\1/1\\./\n");
+    }
+}
@@ -0,0 +1,392 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::game::{Game, GameConfig, GameOptions, Handicap};
+use crate::random::SplitMix64;
+use crate::stats::GameSummary;
+use crate::{Error, Result};
+
+// Points for winning a match or sitting a round out, and for a draw.
+pub const WIN_POINTS: u32 = 2;
+pub const DRAW_POINTS: u32 = 1;
+
+// How one player did on a match's board.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Outcome {
+    pub won: bool,
+    pub solved_three_bv: usize,
+    pub elapsed_seconds: f64,
+}
+
+impl Outcome {
+    pub fn from_summary(summary: &GameSummary) -> Outcome {
+        Outcome {
+            won: summary.won,
+            solved_three_bv: summary.solved_three_bv,
+            elapsed_seconds: summary.elapsed_seconds,
+        }
+    }
+
+    // A win beats a loss, then more of the board solved, then the faster.
+    fn beats(&self, other: &Outcome) -> Ordering {
+        (self.won, self.solved_three_bv)
+            .cmp(&(other.won, other.solved_three_bv))
+            .then(
+                other
+                    .elapsed_seconds
+                    .partial_cmp(&self.elapsed_seconds)
+                    .unwrap_or(Ordering::Equal),
+            )
+    }
+}
+
+// Two players on boards dealt from the same config, or one player sitting
+// the round out when the count is odd.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Pairing {
+    pub first: usize,
+    pub second: Option<usize>,
+    pub config: GameConfig,
+    pub outcomes: Option<(Outcome, Outcome)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Standing {
+    pub player: usize,
+    pub points: u32,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    // The first tiebreaker: the points of every opponent met.
+    pub opponents_points: u32,
+    // The second: the 3BV solved over every match.
+    pub solved_three_bv: usize,
+}
+
+// A round robin where both players of a match get the same board. The seed
+// draws the order players meet in and every match's board, so a server can
+// announce the seed and anyone can check the schedule.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Tournament {
+    players: Vec<String>,
+    rounds: Vec<Vec<Pairing>>,
+    handicaps: Vec<Handicap>,
+}
+
+impl Tournament {
+    // The config's seed is replaced by one per match.
+    pub fn new(players: Vec<String>, seed: u64, config: GameConfig) -> Result<Tournament> {
+        if players.len() < 2 {
+            return Err(Error::InvalidConfig(String::from(
+                "a tournament needs at least two players",
+            )));
+        }
+        Game::new_deferred(config.clone())?;
+        let mut source = SplitMix64::new(seed);
+        // The circle method: the first seat stays, the others turn one seat
+        // each round. An odd count gets an empty seat, the bye.
+        let mut seats: Vec<Option<usize>> = (0..players.len()).map(Some).collect();
+        for i in (1..seats.len()).rev() {
+            seats.swap(i, source.gen_range(0, i + 1));
+        }
+        if seats.len() % 2 == 1 {
+            seats.push(None);
+        }
+        let n = seats.len();
+        let mut rounds = vec![];
+        for _ in 0..n - 1 {
+            let mut round = vec![];
+            for i in 0..n / 2 {
+                let (first, second) = match (seats[i], seats[n - 1 - i]) {
+                    (Some(a), b) | (b, Some(a)) => (a, b),
+                    (None, None) => continue,
+                };
+                round.push(Pairing {
+                    first,
+                    second,
+                    config: GameConfig {
+                        seed: source.next_u64(),
+                        ..config.clone()
+                    },
+                    outcomes: None,
+                });
+            }
+            rounds.push(round);
+            seats[1..].rotate_right(1);
+        }
+        Ok(Tournament {
+            handicaps: vec![Handicap::default(); players.len()],
+            players,
+            rounds,
+        })
+    }
+
+    // Gives a player the handicap in every match they play. Their boards
+    // are dealt from the same seeds as their opponents', with the handicap
+    // on top, and it's kept in the game's options for replays and
+    // summaries to show.
+    pub fn handicap(mut self, player: usize, handicap: Handicap) -> Result<Tournament> {
+        let mines = self.rounds[0][0].config.mines;
+        if handicap.fewer_mines >= mines {
+            return Err(Error::InvalidConfig(format!(
+                "a handicap of {} mines leaves none of the {}",
+                handicap.fewer_mines, mines
+            )));
+        }
+        *self
+            .handicaps
+            .get_mut(player)
+            .ok_or_else(|| Error::InvalidConfig(format!("there is no player {}", player)))? =
+            handicap;
+        Ok(self)
+    }
+
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    pub fn rounds(&self) -> &[Vec<Pairing>] {
+        &self.rounds
+    }
+
+    // The game a player of the match plays, handicap included.
+    pub fn game(&self, round: usize, pairing: usize, player: usize) -> Result<Game> {
+        let config = &self
+            .rounds
+            .get(round)
+            .and_then(|r| r.get(pairing))
+            .filter(|p| p.first == player || p.second == Some(player))
+            .ok_or_else(|| {
+                Error::InvalidConfig(format!(
+                    "player {} has no match {} in round {}",
+                    player, pairing, round
+                ))
+            })?
+            .config;
+        let handicap = &self.handicaps[player];
+        Game::new_deferred(GameConfig {
+            mines: config.mines - handicap.fewer_mines,
+            options: GameOptions {
+                handicap: Some(handicap.clone()).filter(|h| *h != Handicap::default()),
+                ..config.options.clone()
+            },
+            ..config.clone()
+        })
+    }
+
+    // The outcomes of a match, in the order the pairing names its players.
+    // Results can be corrected by recording them again.
+    pub fn record(
+        &mut self,
+        round: usize,
+        pairing: usize,
+        first: Outcome,
+        second: Outcome,
+    ) -> Result<()> {
+        let pairing = self
+            .rounds
+            .get_mut(round)
+            .and_then(|r| r.get_mut(pairing))
+            .ok_or_else(|| {
+                Error::InvalidConfig(format!("no match {} in round {}", pairing, round))
+            })?;
+        if pairing.second.is_none() {
+            return Err(Error::InvalidConfig(String::from(
+                "a bye has no results to record",
+            )));
+        }
+        pairing.outcomes = Some((first, second));
+        Ok(())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.rounds
+            .iter()
+            .flatten()
+            .all(|p| p.second.is_none() || p.outcomes.is_some())
+    }
+
+    // Best first: by points, then opponents' points, then 3BV solved, then
+    // the order players were listed in. Matches without results don't count
+    // yet; byes count as wins.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (0..self.players.len())
+            .map(|player| Standing {
+                player,
+                points: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                opponents_points: 0,
+                solved_three_bv: 0,
+            })
+            .collect();
+        let mut opponents = vec![vec![]; self.players.len()];
+        for pairing in self.rounds.iter().flatten() {
+            let (second, (a, b)) = match (pairing.second, &pairing.outcomes) {
+                (Some(second), Some(outcomes)) => (second, outcomes),
+                (None, _) => {
+                    standings[pairing.first].points += WIN_POINTS;
+                    standings[pairing.first].wins += 1;
+                    continue;
+                }
+                (Some(_), None) => continue,
+            };
+            for (player, outcome, opponent) in
+                [(pairing.first, a, second), (second, b, pairing.first)]
+            {
+                standings[player].solved_three_bv += outcome.solved_three_bv;
+                opponents[player].push(opponent);
+            }
+            let (winner, loser) = match a.beats(b) {
+                Ordering::Greater => (pairing.first, second),
+                Ordering::Less => (second, pairing.first),
+                Ordering::Equal => {
+                    for player in [pairing.first, second] {
+                        standings[player].points += DRAW_POINTS;
+                        standings[player].draws += 1;
+                    }
+                    continue;
+                }
+            };
+            standings[winner].points += WIN_POINTS;
+            standings[winner].wins += 1;
+            standings[loser].losses += 1;
+        }
+        let points: Vec<u32> = standings.iter().map(|s| s.points).collect();
+        for (standing, met) in standings.iter_mut().zip(opponents) {
+            standing.opponents_points = met.iter().map(|&o| points[o]).sum();
+        }
+        standings.sort_by(|a, b| {
+            (b.points, b.opponents_points, b.solved_three_bv, a.player).cmp(&(
+                a.points,
+                a.opponents_points,
+                a.solved_three_bv,
+                b.player,
+            ))
+        });
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Event;
+    use crate::{Move, Point};
+    use core::time::Duration;
+    use pretty_assertions::assert_eq;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            width: 9,
+            height: 9,
+            mines: 10,
+            seed: 0,
+            options: GameOptions::default(),
+        }
+    }
+
+    fn players(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("player {}", i)).collect()
+    }
+
+    fn outcome(won: bool, solved_three_bv: usize, elapsed_seconds: f64) -> Outcome {
+        Outcome {
+            won,
+            solved_three_bv,
+            elapsed_seconds,
+        }
+    }
+
+    #[test]
+    fn test_schedule() {
+        let tournament = Tournament::new(players(5), 7, config()).unwrap();
+        assert_eq!(tournament.rounds().len(), 5);
+        // Everyone meets everyone once, and sits out once.
+        let mut met = vec![];
+        for round in tournament.rounds() {
+            assert_eq!(round.iter().filter(|p| p.second.is_none()).count(), 1);
+            for p in round {
+                if let Some(second) = p.second {
+                    met.push((p.first.min(second), p.first.max(second)));
+                }
+            }
+        }
+        met.sort();
+        met.dedup();
+        assert_eq!(met.len(), 10);
+        assert_eq!(Tournament::new(players(5), 7, config()), Ok(tournament));
+        assert!(Tournament::new(players(1), 7, config()).is_err());
+    }
+
+    #[test]
+    fn test_standings() {
+        let mut tournament = Tournament::new(players(4), 1, config()).unwrap();
+        for round in 0..3 {
+            for pairing in 0..2 {
+                let p = &tournament.rounds()[round][pairing];
+                let (first, second) = (p.first, p.second.unwrap());
+                // Lower numbers always win, except player 3 draws player 2.
+                let (a, b) = match (first.min(second), first.max(second)) {
+                    (2, 3) => (outcome(false, 5, 9.0), outcome(false, 5, 9.0)),
+                    _ => (outcome(true, 10, 20.0), outcome(true, 10, 30.0)),
+                };
+                let (a, b) = if first < second { (a, b) } else { (b, a) };
+                tournament.record(round, pairing, a, b).unwrap();
+            }
+        }
+        assert!(tournament.is_over());
+        let standings = tournament.standings();
+        let order: Vec<(usize, u32)> = standings.iter().map(|s| (s.player, s.points)).collect();
+        // 2 and 3 tie on points, opponents' points and 3BV: 2 was listed first.
+        assert_eq!(order, vec![(0, 6), (1, 4), (2, 1), (3, 1)]);
+        assert_eq!((standings[2].draws, standings[2].losses), (1, 2));
+        assert!(tournament
+            .record(3, 0, outcome(true, 0, 0.0), outcome(true, 0, 0.0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_handicap() {
+        let handicap = Handicap {
+            reveals: 3,
+            fewer_mines: 0,
+            time_bonus: Duration::from_secs(5),
+        };
+        let tournament = Tournament::new(players(2), 4, config())
+            .unwrap()
+            .handicap(1, handicap.clone())
+            .unwrap();
+        let mut games = [0, 1].map(|player| tournament.game(0, 0, player).unwrap());
+        let opened: Vec<usize> = games
+            .iter_mut()
+            .map(|game| {
+                let events = game.apply(&Move::Open(Point::new(4, 4))).unwrap();
+                events
+                    .iter()
+                    .filter(|e| matches!(e, Event::Opened { .. }))
+                    .count()
+            })
+            .collect();
+        assert!(opened[1] > opened[0]);
+        assert_eq!(games[1].replay().options.handicap, Some(handicap));
+        assert_eq!(games[0].options.handicap, None);
+        assert_eq!(games[1].summary(12.0).elapsed_seconds, 7.0);
+        assert!(tournament.game(0, 0, 2).is_err());
+        assert!(Tournament::new(players(2), 4, config())
+            .unwrap()
+            .handicap(
+                0,
+                Handicap {
+                    fewer_mines: 10,
+                    ..Handicap::default()
+                }
+            )
+            .is_err());
+    }
+}
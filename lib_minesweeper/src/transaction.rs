@@ -0,0 +1,139 @@
+use alloc::vec::Vec;
+
+use crate::game::{diff, Event, Source};
+use crate::{Board, BoardState, Error, Move, Point, Result};
+
+// The moves queued by Board::transaction, played once its closure returns.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Transaction {
+    moves: Vec<Move>,
+}
+
+impl Transaction {
+    pub fn open(&mut self, p: &Point) -> &mut Transaction {
+        self.apply(Move::Open(*p))
+    }
+
+    pub fn flag(&mut self, p: &Point) -> &mut Transaction {
+        self.apply(Move::Flag(*p))
+    }
+
+    pub fn apply(&mut self, m: Move) -> &mut Transaction {
+        self.moves.push(m);
+        self
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+}
+
+impl Board {
+    // Plays the moves queued by `build` as one change: either every move
+    // applies, or the first error comes back and nothing does. The events
+    // are a single batch comparing the board before and after, so a cell
+    // opened by a cascade shows up once, in row-major order, whichever move
+    // opened it, and nothing in between is ever seen.
+    pub fn transaction(&self, build: impl FnOnce(&mut Transaction)) -> Result<(Board, Vec<Event>)> {
+        let mut tx = Transaction::default();
+        build(&mut tx);
+        // The cheap checks go first, so a bad point at the end of the batch
+        // fails before any cascade runs.
+        let over = matches!(self.state, BoardState::Won | BoardState::Failed);
+        for m in &tx.moves {
+            let (Move::Open(p) | Move::Flag(p) | Move::Power(_, p)) = m;
+            if over {
+                return Err(Error::GameOver);
+            }
+            if self.at(p).is_none() {
+                return Err(Error::OutOfBounds { x: p.x, y: p.y });
+            }
+        }
+        let mut board = self.clone();
+        let mut exploded = None;
+        for m in &tx.moves {
+            board = board.apply_move(m)?;
+            if let (Move::Open(p), BoardState::Failed, None) = (m, board.state, exploded) {
+                exploded = Some(*p);
+            }
+        }
+        let mut events = diff(self, &board, Source::User);
+        events.extend(exploded.map(|point| Event::Exploded { point }));
+        match board.state {
+            BoardState::Won => events.push(Event::Won),
+            BoardState::Failed => events.push(Event::Lost),
+            _ => (),
+        }
+        Ok((board, events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers_on_board;
+    use crate::tests::five_by_two_board;
+    use alloc::vec;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_transaction() {
+        // *2100
+        // 2*100
+        let board = numbers_on_board(five_by_two_board());
+        let (after, events) = board
+            .transaction(|tx| {
+                tx.open(&Point::new(4, 0)).flag(&Point::new(0, 0));
+                tx.open(&Point::new(1, 0));
+            })
+            .unwrap();
+        let opened = |x, y, count| Event::Opened {
+            point: Point::new(x, y),
+            count,
+            source: Source::User,
+        };
+        assert_eq!(
+            events,
+            vec![
+                Event::Flagged {
+                    point: Point::new(0, 0),
+                    source: Source::User,
+                },
+                opened(1, 0, 2),
+                opened(2, 0, 1),
+                opened(3, 0, 0),
+                opened(4, 0, 0),
+                opened(2, 1, 1),
+                opened(3, 1, 0),
+                opened(4, 1, 0),
+            ]
+        );
+        assert_eq!(after.state, BoardState::Playing);
+
+        // (3, 0) was opened by the first move's cascade.
+        let failed = board.transaction(|tx| {
+            tx.open(&Point::new(4, 0)).open(&Point::new(3, 0));
+        });
+        assert_eq!(failed, Err(Error::CellAlreadyOpen { x: 3, y: 0 }));
+        let failed = board.transaction(|tx| {
+            tx.open(&Point::new(4, 0)).flag(&Point::new(5, 0));
+        });
+        assert_eq!(failed, Err(Error::OutOfBounds { x: 5, y: 0 }));
+
+        let (lost, events) = after
+            .transaction(|tx| {
+                tx.open(&Point::new(1, 1));
+            })
+            .unwrap();
+        assert_eq!(lost.state, BoardState::Failed);
+        assert_eq!(
+            &events[events.len() - 2..],
+            &[
+                Event::Exploded {
+                    point: Point::new(1, 1)
+                },
+                Event::Lost
+            ]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::random::RandomSource;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Open;
+use crate::{Board, Error, Point, Result};
+
+impl Board {
+    pub fn is_treasure(&self, p: &Point) -> bool {
+        self.treasures.as_ref().is_some_and(|t| t.contains(p))
+    }
+
+    // The treasures opened so far.
+    pub fn treasures_found(&self) -> Vec<Point> {
+        self.treasures
+            .iter()
+            .flatten()
+            .filter(|p| matches!(self.at(p), Some(Number { state: Open, .. })))
+            .cloned()
+            .collect()
+    }
+
+    // Hides treasures on `count` closed safe cells, none of them on the safe
+    // points. Treasures already there are replaced.
+    pub(crate) fn place_treasures(
+        self,
+        count: usize,
+        source: &mut dyn RandomSource,
+        safe: &[Point],
+    ) -> Result<Board> {
+        let mut candidates: Vec<Point> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point::new(x, y)))
+            .filter(|p| matches!(self.at(p), Some(Number { .. })) && !safe.contains(p))
+            .collect();
+        if count > candidates.len() {
+            return Err(Error::InvalidConfig(format!(
+                "{} treasures don't fit in the {} safe cells left",
+                count,
+                candidates.len()
+            )));
+        }
+        for i in 0..count {
+            let j = source.gen_range(i, candidates.len());
+            candidates.swap(i, j);
+        }
+        candidates.truncate(count);
+        // Row by row, as share codes list them.
+        candidates.sort_by_key(|p| (p.y, p.x));
+        Ok(Board {
+            treasures: Some(candidates),
+            ..self
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Event, Game, GameOptions};
+    use crate::render::board_text;
+    use crate::versus::{TurnBasedMatch, TREASURE_BONUS};
+    use crate::view::CellView;
+    use crate::MapElement::Mine;
+    use crate::{numbers_on_board, BoardBuilder, Move, Point};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_treasures() {
+        let board = BoardBuilder::new(6, 5, 8)
+            .seed(3)
+            .treasures(4)
+            .build()
+            .unwrap();
+        let treasures = board.treasures.clone().unwrap();
+        assert_eq!(treasures.len(), 4);
+        assert!(treasures
+            .iter()
+            .all(|p| !matches!(board.at(p), Some(Mine { .. }))));
+        assert!(BoardBuilder::new(2, 2, 1)
+            .seed(3)
+            .treasures(4)
+            .build()
+            .is_err());
+
+        let board = numbers_on_board(board);
+        let t = treasures[0];
+        let mut game = Game::new(board.clone(), GameOptions::default());
+        let events = game.apply(&Move::Open(t)).unwrap();
+        assert!(events.contains(&Event::TreasureFound { point: t }));
+        assert!(matches!(
+            game.board().cell_view(&t),
+            Some(CellView::Treasure(_))
+        ));
+        assert!(board_text(game.board()).contains('$'));
+
+        let mut versus = TurnBasedMatch::new(board, 2).unwrap();
+        let turn = versus.open(&t).unwrap();
+        let revealed = game
+            .board()
+            .view()
+            .iter()
+            .flatten()
+            .filter(|c| matches!(c, CellView::Open(_) | CellView::Treasure(_)))
+            .count() as i32;
+        let found = game.board().treasures_found().len() as i32;
+        assert_eq!(turn.points, revealed + found * TREASURE_BONUS);
+    }
+
+    #[test]
+    fn test_cruel_mode_spares_treasures() {
+        let board = BoardBuilder::new(8, 8, 6)
+            .seed(1)
+            .treasures(4)
+            .build()
+            .unwrap();
+        let t = board.treasures.clone().unwrap()[0];
+        let cruel = GameOptions {
+            cruel: true,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new(numbers_on_board(board), cruel);
+        game.apply(&Move::Open(Point::new(0, 0))).unwrap();
+        // Nothing open tells whether B3 holds a mine, but as a treasure it
+        // can't be made one.
+        assert_eq!(t, Point::new(1, 2));
+        let events = game.apply(&Move::Open(t)).unwrap();
+        assert!(events.contains(&Event::TreasureFound { point: t }));
+    }
+}
@@ -0,0 +1,167 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardState, Error, Point, Result};
+
+// Points lost for opening a mine, unless the match sets its own.
+pub const MINE_PENALTY: i32 = 10;
+// Points won on top of the cell for revealing a treasure, see
+// Board::treasures.
+pub const TREASURE_BONUS: i32 = 5;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Turn {
+    pub player: usize,
+    pub point: Point,
+    // Safe cells the open revealed, cascade included, or minus the penalty.
+    pub points: i32,
+    pub mine: bool,
+}
+
+// Players take turns opening cells on one shared board. Every safe cell a
+// turn reveals scores a point; a mine costs the penalty and is flagged for
+// everyone to see, and the match goes on. It ends when every safe cell is
+// open, and the highest score wins. Treasures revealed score a bonus.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct TurnBasedMatch {
+    board: Board,
+    scores: Vec<i32>,
+    turn: usize,
+    mine_penalty: i32,
+    turns: Vec<Turn>,
+}
+
+fn open_cells(board: &Board) -> usize {
+    (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| Point::new(x, y)))
+        .filter(|p| matches!(board.at(p), Some(Number { state: Open, .. })))
+        .count()
+}
+
+impl TurnBasedMatch {
+    pub fn new(board: Board, players: usize) -> Result<TurnBasedMatch> {
+        if players < 2 {
+            return Err(Error::InvalidConfig(String::from(
+                "a match needs at least two players",
+            )));
+        }
+        Ok(TurnBasedMatch {
+            board,
+            scores: vec![0; players],
+            turn: 0,
+            mine_penalty: MINE_PENALTY,
+            turns: vec![],
+        })
+    }
+
+    pub fn mine_penalty(mut self, penalty: i32) -> TurnBasedMatch {
+        self.mine_penalty = penalty;
+        self
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn scores(&self) -> &[i32] {
+        &self.scores
+    }
+
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.turn
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.board.state == BoardState::Won
+    }
+
+    // The player with the best score once the match is over, None before
+    // that or on a tie for first.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.is_over() {
+            return None;
+        }
+        let best = *self.scores.iter().max()?;
+        let mut leaders = (0..self.scores.len()).filter(|&p| self.scores[p] == best);
+        match (leaders.next(), leaders.next()) {
+            (Some(player), None) => Some(player),
+            _ => None,
+        }
+    }
+
+    // Plays the current player's turn and passes it on. Rejected opens (a
+    // cell already open, say) leave the turn where it was.
+    pub fn open(&mut self, p: &Point) -> Result<Turn> {
+        if self.is_over() {
+            return Err(Error::GameOver);
+        }
+        let (board, points, mine) = match self.board.at(p) {
+            Some(Mine { state: Closed }) => (self.board.flag_item(p)?, -self.mine_penalty, true),
+            // Found by an earlier turn.
+            Some(Mine { .. }) => return Err(Error::CellFlagged { x: p.x, y: p.y }),
+            _ => {
+                let board = self.board.cascade_open_item(p)?;
+                let revealed = open_cells(&board) - open_cells(&self.board);
+                let treasures = board.treasures_found().len() - self.board.treasures_found().len();
+                (
+                    board,
+                    revealed as i32 + treasures as i32 * TREASURE_BONUS,
+                    false,
+                )
+            }
+        };
+        self.board = board;
+        self.scores[self.turn] += points;
+        let turn = Turn {
+            player: self.turn,
+            point: *p,
+            points,
+            mine,
+        };
+        self.turns.push(turn.clone());
+        self.turn = (self.turn + 1) % self.scores.len();
+        Ok(turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_turn_based_match() {
+        // *2100
+        // 2*100
+        let board = Scenario::from_ascii("*....\n.*...\n").into_board();
+        let mut versus = TurnBasedMatch::new(board, 2).unwrap().mine_penalty(3);
+        assert_eq!(versus.open(&Point::new(4, 0)).unwrap().points, 6);
+        assert_eq!(versus.current_player(), 1);
+        let turn = versus.open(&Point::new(0, 0)).unwrap();
+        assert!(turn.mine);
+        assert_eq!(
+            versus.open(&Point::new(0, 0)),
+            Err(Error::CellFlagged { x: 0, y: 0 })
+        );
+        assert_eq!(versus.current_player(), 0);
+        versus.open(&Point::new(1, 0)).unwrap();
+        assert_eq!(versus.winner(), None);
+        versus.open(&Point::new(0, 1)).unwrap();
+        assert!(versus.is_over());
+        assert_eq!(versus.scores(), &[7, -2]);
+        assert_eq!(versus.winner(), Some(0));
+        assert_eq!(versus.turns().len(), 4);
+        assert!(TurnBasedMatch::new(Scenario::from_ascii("*.").into_board(), 1).is_err());
+    }
+}
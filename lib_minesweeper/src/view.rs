@@ -0,0 +1,207 @@
+use alloc::vec::Vec;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::MapElement::Mine;
+use crate::MapElement::Number;
+use crate::MapElementCellState::Closed;
+use crate::MapElementCellState::Flagged;
+use crate::MapElementCellState::Open;
+use crate::{Board, BoardRead, BoardState, Point};
+
+// What a player is allowed to see of a cell. Renderers should only look at
+// this, so the board's internal representation can change under them.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum CellView {
+    Hidden,
+    Flagged,
+    // Reserved for question marks; the board doesn't track them yet.
+    Question,
+    Open(u8),
+    // Mines are only shown once the game is lost.
+    Mine,
+    ExplodedMine,
+    WrongFlag,
+    // An open cell with a treasure, and its count. See Board::treasures.
+    Treasure(u8),
+    // Hidden, and too far from the open cells to be played in a game with
+    // fog of war, see GameOptions::fog.
+    OutOfReach,
+}
+
+// A block of cells on a minimap, from what the player can see of them.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct MinimapCell {
+    // Fraction of the block's cells that are open, from 0 to 1.
+    pub opened: f64,
+    pub flagged: bool,
+    pub exploded: bool,
+}
+
+pub(crate) fn cell_view<B: BoardRead + ?Sized>(board: &B, p: &Point) -> Option<CellView> {
+    let failed = board.state() == BoardState::Failed;
+    let over = failed || board.state() == BoardState::Won;
+    let number = |count: i32| {
+        if board.is_treasure(p) {
+            CellView::Treasure(count as u8)
+        } else {
+            CellView::Open(count as u8)
+        }
+    };
+    let view = match board.cell(p)? {
+        Mine { state: Open } => CellView::ExplodedMine,
+        Mine { state: Closed } if failed => CellView::Mine,
+        // A won board has every mine found, so show them as flags.
+        Mine { state: Closed } if board.state() == BoardState::Won => CellView::Flagged,
+        Number { state: Flagged, .. } if failed => CellView::WrongFlag,
+        Mine { state: Flagged } | Number { state: Flagged, .. } => CellView::Flagged,
+        // Once the game is over every number is shown, as frontends always
+        // revealed the whole board at the end.
+        Number {
+            state: Closed,
+            count,
+        } if over => number(count),
+        Number { state: Open, count } => number(count),
+        Mine { state: Closed } | Number { state: Closed, .. } => CellView::Hidden,
+    };
+    Some(view)
+}
+
+pub(crate) fn view<B: BoardRead + ?Sized>(board: &B) -> Vec<Vec<CellView>> {
+    (0..board.height())
+        .map(|y| {
+            (0..board.width())
+                .filter_map(|x| board.cell_view(&Point::new(x, y)))
+                .collect()
+        })
+        .collect()
+}
+
+impl Board {
+    pub fn cell_view(&self, p: &Point) -> Option<CellView> {
+        cell_view(self, p)
+    }
+
+    // Row by row, like the board's map.
+    pub fn view(&self) -> Vec<Vec<CellView>> {
+        view(self)
+    }
+
+    // The board shrunk to at most target_w by target_h blocks, row by row,
+    // for drawing next to boards too large to show whole. Blocks split the
+    // board as evenly as they can; the board is never stretched.
+    pub fn minimap(&self, target_w: usize, target_h: usize) -> Vec<Vec<MinimapCell>> {
+        let (w, h) = (
+            target_w.clamp(1, self.width),
+            target_h.clamp(1, self.height),
+        );
+        (0..h)
+            .map(|row| {
+                (0..w)
+                    .map(|column| {
+                        let xs = column * self.width / w..(column + 1) * self.width / w;
+                        let ys = row * self.height / h..(row + 1) * self.height / h;
+                        let cells: Vec<CellView> = ys
+                            .flat_map(|y| xs.clone().map(move |x| Point::new(x, y)))
+                            .filter_map(|p| self.cell_view(&p))
+                            .collect();
+                        let opened = cells
+                            .iter()
+                            .filter(|c| matches!(c, CellView::Open(_) | CellView::Treasure(_)))
+                            .count();
+                        MinimapCell {
+                            opened: opened as f64 / cells.len().max(1) as f64,
+                            flagged: cells
+                                .iter()
+                                .any(|c| matches!(c, CellView::Flagged | CellView::WrongFlag)),
+                            exploded: cells.contains(&CellView::ExplodedMine),
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CellView::*;
+    use super::MinimapCell;
+    use crate::tests::five_by_two_board;
+    use crate::{numbers_on_board, BoardState, Point};
+    use alloc::vec;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_view() {
+        // X2100
+        // 2X100
+        let board = numbers_on_board(five_by_two_board())
+            .cascade_open_item(&Point::new(4, 0))
+            .unwrap()
+            .flag_item(&Point::new(0, 1))
+            .unwrap();
+        assert_eq!(
+            board.view(),
+            vec![
+                vec![Hidden, Hidden, Open(1), Open(0), Open(0)],
+                vec![Flagged, Hidden, Open(1), Open(0), Open(0)],
+            ]
+        );
+
+        // The rest of the board is revealed.
+        let lost = board.cascade_open_item(&Point::new(1, 1)).unwrap();
+        assert_eq!(
+            lost.view(),
+            vec![
+                vec![Mine, Open(2), Open(1), Open(0), Open(0)],
+                vec![WrongFlag, ExplodedMine, Open(1), Open(0), Open(0)],
+            ]
+        );
+        assert_eq!(lost.cell_view(&Point::new(5, 0)), None);
+    }
+
+    #[test]
+    fn test_won_view_flags_mines() {
+        let board = numbers_on_board(five_by_two_board())
+            .cascade_open_item(&Point::new(4, 0))
+            .unwrap()
+            .cascade_open_item(&Point::new(1, 0))
+            .unwrap()
+            .cascade_open_item(&Point::new(0, 1))
+            .unwrap();
+        assert_eq!(board.state, BoardState::Won);
+        assert_eq!(
+            board.view(),
+            vec![
+                vec![Flagged, Open(2), Open(1), Open(0), Open(0)],
+                vec![Open(2), Flagged, Open(1), Open(0), Open(0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minimap() {
+        // X2100
+        // 2X100
+        let board = numbers_on_board(five_by_two_board())
+            .cascade_open_item(&Point::new(4, 0))
+            .unwrap()
+            .flag_item(&Point::new(0, 1))
+            .unwrap();
+        let cell = |opened, flagged| MinimapCell {
+            opened,
+            flagged,
+            exploded: false,
+        };
+        // Columns 0, 1-2 and 3-4, over both rows.
+        assert_eq!(
+            board.minimap(3, 1),
+            vec![vec![cell(0.0, true), cell(0.5, false), cell(1.0, false)]]
+        );
+        assert_eq!(board.minimap(50, 0).len(), 1);
+        assert_eq!(board.minimap(50, 0)[0].len(), 5);
+
+        let lost = board.cascade_open_item(&Point::new(1, 1)).unwrap();
+        assert!(lost.minimap(1, 1)[0][0].exploded);
+    }
+}
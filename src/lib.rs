@@ -1,6 +1,11 @@
 #![recursion_limit = "512"]
 
+use lib_minesweeper::clock::{Clock, Timer};
 use lib_minesweeper::create_board;
+use lib_minesweeper::input::resolve_gesture;
+use lib_minesweeper::input::Gesture;
+use lib_minesweeper::input::GestureMode;
+use lib_minesweeper::input::InteractionConfig;
 use lib_minesweeper::numbers_on_board;
 use lib_minesweeper::Board;
 use lib_minesweeper::BoardState;
@@ -12,9 +17,9 @@ use lib_minesweeper::BoardState::Won;
 use lib_minesweeper::MapElement;
 use lib_minesweeper::MapElement::Mine;
 use lib_minesweeper::MapElement::Number;
-use lib_minesweeper::MapElementCellState::Closed;
 use lib_minesweeper::MapElementCellState::Flagged;
 use lib_minesweeper::MapElementCellState::Open;
+use lib_minesweeper::view::CellView;
 use lib_minesweeper::Point;
 
 use std::time::Duration;
@@ -38,7 +43,8 @@ fn small_board() -> Board {
 
     let board = create_board(width, height, mines, |x, y| {
         rand::thread_rng().gen_range(x, y)
-    });
+    })
+    .expect("invalid board configuration");
 
     numbers_on_board(board)
 }
@@ -51,7 +57,8 @@ fn medium_board() -> Board {
 
     let board = create_board(width, height, mines, |x, y| {
         rand::thread_rng().gen_range(x, y)
-    });
+    })
+    .expect("invalid board configuration");
 
     numbers_on_board(board)
 }
@@ -64,17 +71,12 @@ fn large_board() -> Board {
 
     let board = create_board(width, height, mines, |x, y| {
         rand::thread_rng().gen_range(x, y)
-    });
+    })
+    .expect("invalid board configuration");
 
     numbers_on_board(board)
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-enum Mode {
-    Flagging,
-    Digging,
-}
-
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 enum Difficulty {
     Easy,
@@ -97,7 +99,8 @@ enum Msg {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct State {
     difficulty: Difficulty,
-    mode: Mode,
+    // The mode button switches between digging and flagging.
+    interaction: InteractionConfig,
     board: Board,
 }
 
@@ -117,7 +120,7 @@ impl Component for Model {
         //        };
         let state = State {
             difficulty: Difficulty::Easy,
-            mode: Mode::Digging,
+            interaction: InteractionConfig::default(),
             board: small_board(),
         };
         Self {
@@ -184,7 +187,7 @@ impl Component for Model {
                                                                 y={y}
                                                                 board_state={board.state.clone()}
                                                                 board_width={board.width}
-                                                                element={board.at(&Point::new(x,y)).unwrap()}
+                                                                cell={board.cell_view(&Point::new(x,y)).unwrap()}
                                                                 update_signal={self.link.callback(|msg:Msg| msg)}/>
                                                         }
                                                     }
@@ -221,16 +224,7 @@ impl Model {
         if matches!(self.state.board.state, Won | Failed) {
             return;
         }
-        self.state = match self.state.mode {
-            Mode::Digging => State {
-                mode: Mode::Flagging,
-                ..self.state.clone()
-            },
-            Mode::Flagging => State {
-                mode: Mode::Digging,
-                ..self.state.clone()
-            },
-        }
+        self.state.interaction.toggle_mode();
     }
 
     fn render_body_class(&self) -> &str {
@@ -260,9 +254,9 @@ impl Model {
     }
 
     fn render_mode(&self) -> &str {
-        match (&self.state.board.state, self.state.mode.clone()) {
-            (Ready, Mode::Flagging) | (Playing, Mode::Flagging) => "🚩",
-            (Ready, Mode::Digging) | (Playing, Mode::Digging) => "⛏️",
+        match (&self.state.board.state, self.state.interaction.mode) {
+            (Ready, GestureMode::FlagFirst) | (Playing, GestureMode::FlagFirst) => "🚩",
+            (Ready, GestureMode::OpenFirst) | (Playing, GestureMode::OpenFirst) => "⛏️",
             (Won, _) => "🏆",
             (Failed, _) => "☠️",
             _ => unreachable!(),
@@ -285,16 +279,10 @@ impl Model {
     }
 
     fn update_board(&mut self, p: Point) {
-        match self.state.mode {
-            Mode::Digging => {
-                let new_board = self.state.board.cascade_open_item(&p);
-                if let Some(b) = new_board {
-                    self.state.board = b
-                }
-            }
-            Mode::Flagging => {
-                self.state.board = self.state.board.flag_item(&p);
-            }
+        let board = &self.state.board;
+        let m = resolve_gesture(&self.state.interaction, Gesture::Primary, &p, board);
+        if let Some(Ok(b)) = m.map(|m| board.apply_move(&m)) {
+            self.state.board = b
         }
     }
 
@@ -337,14 +325,16 @@ impl Model {
 
                         if *mine_count == unopened_count as i32 && flagged_count < unopened_count {
                             let (p,_el) = unopened.filter(|(_p,el)| !matches!(el, Mine{state:Flagged} | Number{state:Flagged,..})).next().unwrap();
-                            self.state.board = self.state.board.flag_item(&p);
-                            return;
+                            if let Ok(b) = self.state.board.flag_item(&p) {
+                                self.state.board = b;
+                                return;
+                            }
                         }
 
                         if *mine_count == flagged_count as i32 && unopened_count - flagged_count > 0
                         {
                             let (p,_el) = unopened.filter(|(_p,el)| !matches!(el, Mine{state:Flagged} | Number{state:Flagged,..})).next().unwrap();
-                            if let Some(b) = self.state.board.cascade_open_item(&p) {
+                            if let Ok(b) = self.state.board.cascade_open_item(&p) {
                                 self.state.board = b;
                                 return;
                             }
@@ -370,11 +360,18 @@ enum TimeKeeperOp {
 }
 
 struct TimeKeeperState {
-    started_at: Option<Date>,
-    stopped_at: Option<Date>,
+    timer: Timer,
     _handle: yew::services::interval::IntervalTask,
 }
 
+struct DateClock;
+
+impl Clock for DateClock {
+    fn now(&self) -> Duration {
+        Duration::from_millis(Date::now() as u64)
+    }
+}
+
 struct TimeKeeper {
     props: TimeKeeperProps,
     state: TimeKeeperState,
@@ -393,8 +390,7 @@ impl Component for TimeKeeper {
         let _handle = interval_service.spawn(Duration::from_millis(100), callback_tick);
 
         let state = TimeKeeperState {
-            started_at: None,
-            stopped_at: None,
+            timer: Timer::new(),
             _handle,
         };
         Self { state, props }
@@ -404,24 +400,23 @@ impl Component for TimeKeeper {
         let should_render = match (&self.props.op, props.op) {
             (TimeKeeperOp::Counting, TimeKeeperOp::Reset)
             | (TimeKeeperOp::Stopped, TimeKeeperOp::Reset) => {
-                self.state.started_at = None;
-                self.state.stopped_at = None;
+                self.state.timer = Timer::new();
                 true
             }
             (TimeKeeperOp::Reset, TimeKeeperOp::Reset) => false,
             (TimeKeeperOp::Stopped, TimeKeeperOp::Counting)
             | (TimeKeeperOp::Reset, TimeKeeperOp::Counting) => {
-                self.state.started_at = Some(Date::new_0());
+                self.state.timer = Timer::new();
+                self.state.timer.resume(&DateClock);
                 true
             }
             (TimeKeeperOp::Counting, TimeKeeperOp::Counting) => true,
             (TimeKeeperOp::Counting, TimeKeeperOp::Stopped) => {
-                self.state.stopped_at = Some(Date::new_0());
+                self.state.timer.pause(&DateClock);
                 true
             }
             (TimeKeeperOp::Reset, TimeKeeperOp::Stopped) => {
-                self.state.started_at = Some(Date::new_0());
-                self.state.stopped_at = Some(Date::new_0());
+                self.state.timer = Timer::new();
                 true
             }
             (TimeKeeperOp::Stopped, TimeKeeperOp::Stopped) => false,
@@ -448,25 +443,15 @@ impl Component for TimeKeeper {
 
 impl TimeKeeper {
     fn render_timer(&self) -> String {
-        match (&self.state.started_at, &self.state.stopped_at) {
-            (Some(started_at), None) => {
-                let now = Date::new_0();
-                format!(
-                    "{}",
-                    ((now.get_time() - started_at.get_time()) / 1000_f64)
-                        .round()
-                        .min(999_f64) // make sure we don't run out of space
-                )
-            }
-            (Some(started_at), Some(stopped_at)) => format!(
-                "{}",
-                ((stopped_at.get_time() - started_at.get_time()) / 1000_f64)
-                    .round()
-                    .min(999_f64) // make sure we don't run out of space
-            ),
-            (None, None) => String::from("0"),
-            _ => unreachable!(),
-        }
+        format!(
+            "{}",
+            self.state
+                .timer
+                .elapsed(&DateClock)
+                .as_secs_f64()
+                .round()
+                .min(999_f64) // make sure we don't run out of space
+        )
     }
 }
 
@@ -476,7 +461,7 @@ struct BoardItemProps {
     y: usize,
     board_state: BoardState,
     board_width: usize,
-    element: MapElement,
+    cell: CellView,
     update_signal: Callback<Msg>,
 }
 
@@ -497,7 +482,7 @@ impl Component for BoardItem {
             && self.props.y == props.y
             && self.props.board_state == props.board_state
             && self.props.board_width == props.board_width
-            && self.props.element == props.element
+            && self.props.cell == props.cell
         {
             false
         } else {
@@ -520,41 +505,31 @@ impl Component for BoardItem {
         html! {
             <div
              class={
-                 match(&self.props.board_state, &self.props.element) {
-                     (Ready, Number { state: Closed, .. })
-                         | (Ready, Mine { state: Closed, .. })
-                         | (Playing, Number { state: Closed, .. })
-                         | (Playing, Mine { state: Closed, .. }) => {
-                             String::from("item clickable2")
-                         },
-                     (Playing, Number {state: Open, count: count})
-                         | (Won,Number {count: count, ..})
-                         | (Failed,Number {count: count, ..}) => {
-                         format!("item not-clickable2 mines-{}", count)
-                     },
-                     _ => String::from("item not-clickable2")
+                 match (&self.props.board_state, &self.props.cell) {
+                     (Ready, CellView::Hidden) | (Playing, CellView::Hidden) => {
+                         String::from("item clickable2")
+                     }
+                     (_, CellView::Open(count)) => format!("item not-clickable2 mines-{}", count),
+                     (_, CellView::OutOfReach) => String::from("item not-clickable2 out-of-reach"),
+                     (_, CellView::Treasure(count)) => {
+                         format!("item not-clickable2 treasure mines-{}", count)
+                     }
+                     _ => String::from("item not-clickable2"),
              }},
                 style={self.get_item_style()}
                 onclick=self.link.callback(move |_| {Msg::UpdateBoard {point:Point::new(x,y)}}) >
                 <div style="width:100%; text-align:center"> {
-                    match (&self.props.board_state, &self.props.element) {
-                        (Ready, Number { state: Flagged, .. })
-                            | (Ready, Mine { state: Flagged, .. })
-                            | (Playing, Number { state: Flagged, .. })
-                            | (Playing, Mine { state: Flagged, .. }) => {
-                                String::from("🚩")
-                            }
-                        (Ready, Number { state: Closed, .. })
-                            | (Ready, Mine { state: Closed, .. })
-                            | (Playing, Number { state: Closed, .. })
-                            | (Playing, Mine { state: Closed, .. }) => {
-                                String::from("❓")
-                            }
-                        (_, Number { count:0, .. }) => String::from(""),
-                        (_, Number { count, .. }) => format!("{}",count),
-                        (Failed, Mine { .. }) => String::from("💣"),
-                        (Won, Mine { .. }) => String::from("🚩"),
-                        _ => unreachable!(),
+                    match &self.props.cell {
+                        CellView::Flagged => String::from("🚩"),
+                        CellView::Hidden | CellView::Question => String::from("❓"),
+                        CellView::OutOfReach => String::from("🌫️"),
+                        CellView::Open(0) => String::from(""),
+                        CellView::Open(count) => format!("{}", count),
+                        CellView::Treasure(0) => String::from("💎"),
+                        CellView::Treasure(count) => format!("💎{}", count),
+                        CellView::Mine => String::from("💣"),
+                        CellView::ExplodedMine => String::from("💥"),
+                        CellView::WrongFlag => String::from("❌"),
                     }
                 }
             </div>